@@ -315,6 +315,9 @@ impl SymbolicErrorCode {
                     }
                     SymCacheErrorKind::BadSegment => SymbolicErrorCode::SymCacheErrorBadSegment,
                     SymCacheErrorKind::BadCacheFile => SymbolicErrorCode::SymCacheErrorBadCacheFile,
+                    SymCacheErrorKind::OutOfBounds { .. } => {
+                        SymbolicErrorCode::SymCacheErrorBadCacheFile
+                    }
                     SymCacheErrorKind::UnsupportedVersion => {
                         SymbolicErrorCode::SymCacheErrorUnsupportedVersion
                     }
@@ -4,7 +4,7 @@ use std::mem;
 use std::os::raw::c_char;
 use std::slice;
 
-use symbolic::common::{ByteView, InstructionInfo, SelfCell};
+use symbolic::common::{Advice, ByteView, InstructionInfo, SelfCell};
 use symbolic::symcache::{format::SYMCACHE_VERSION, SymCache, SymCacheWriter};
 
 use crate::core::SymbolicStr;
@@ -58,6 +58,9 @@ ffi_fn! {
     /// Creates a symcache from a given path.
     unsafe fn symbolic_symcache_open(path: *const c_char) -> Result<*mut SymbolicSymCache> {
         let byteview = ByteView::open(CStr::from_ptr(path).to_str()?)?;
+        // Lookups only touch a small fraction of the file, so read ahead is wasted. This is just
+        // a hint, so failures are not fatal.
+        byteview.advise(Advice::Random).ok();
         let cell = SelfCell::try_new(byteview, |p| SymCache::parse(&*p))?;
         Ok(SymbolicSymCache::from_rust(cell))
     }
@@ -35,6 +35,62 @@ impl Deref for ByteViewBacking<'_> {
     }
 }
 
+impl ByteViewBacking<'_> {
+    #[cfg(unix)]
+    fn advise(&self, advice: Advice) -> Result<(), io::Error> {
+        let mmap = match *self {
+            ByteViewBacking::Mmap(ref mmap) => mmap,
+            ByteViewBacking::Buf(_) => return Ok(()),
+        };
+
+        if mmap.is_empty() {
+            return Ok(());
+        }
+
+        let advice = match advice {
+            Advice::Normal => libc::MADV_NORMAL,
+            Advice::Sequential => libc::MADV_SEQUENTIAL,
+            Advice::Random => libc::MADV_RANDOM,
+            Advice::WillNeed => libc::MADV_WILLNEED,
+        };
+
+        // SAFETY: the pointer and length are taken from a live mapping, which is page aligned by
+        // construction. `madvise` with these flags does not modify the mapped contents.
+        let result =
+            unsafe { libc::madvise(mmap.as_ptr() as *mut libc::c_void, mmap.len(), advice) };
+
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn advise(&self, _advice: Advice) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+/// Access pattern hints for memory mapped [`ByteView`]s.
+///
+/// See [`ByteView::advise`] for more information.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Advice {
+    /// No special treatment. This is the default for new mappings.
+    Normal,
+    /// Pages will be accessed in sequential order, and can be read ahead aggressively.
+    Sequential,
+    /// Pages will be accessed in random order, and read ahead is of little use.
+    ///
+    /// This is the best fit for lookups in caches, such as SymCaches, which only touch a small
+    /// fraction of the file.
+    Random,
+    /// Pages will be accessed in the near future and should be prefetched.
+    WillNeed,
+}
+
 /// A smart pointer for byte data.
 ///
 /// This type can be used to uniformly access bytes that were created either from mmapping in a
@@ -233,6 +289,31 @@ impl<'a> ByteView<'a> {
     pub fn as_slice(&self) -> &[u8] {
         self.backing.deref()
     }
+
+    /// Advises the operating system about the expected access pattern of this `ByteView`.
+    ///
+    /// For memory mapped files on Unix platforms, this maps to `madvise`. For in-memory buffers
+    /// and on all other platforms, this is a no-op. Since this is only a hint, the contents of the
+    /// `ByteView` are never affected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use symbolic_common::{Advice, ByteView};
+    ///
+    /// fn main() -> Result<(), std::io::Error> {
+    ///     let mut file = tempfile::tempfile()?;
+    ///     file.write_all(b"1234")?;
+    ///
+    ///     let view = ByteView::map_file(file)?;
+    ///     view.advise(Advice::Random)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn advise(&self, advice: Advice) -> Result<(), io::Error> {
+        self.backing.advise(advice)
+    }
 }
 
 impl AsRef<[u8]> for ByteView<'_> {
@@ -308,4 +389,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_advise() -> Result<(), std::io::Error> {
+        let path = symbolic_testutils::fixture("linux/crash.debug");
+        let view = ByteView::open(path)?;
+
+        view.advise(Advice::Random)?;
+        view.advise(Advice::Sequential)?;
+        view.advise(Advice::WillNeed)?;
+        view.advise(Advice::Normal)?;
+
+        ByteView::from_slice(b"1234").advise(Advice::Random)?;
+        ByteView::open(NamedTempFile::new()?.path())?.advise(Advice::Random)?;
+
+        Ok(())
+    }
 }
@@ -58,6 +58,15 @@ static MIPS: &[&str] = &[
     "$f29", "$f30", "$f31", "$fcsr", "$fir",
 ];
 
+/// The byte order of a CPU architecture.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Endian {
+    /// Little endian, with the least significant byte first.
+    Little,
+    /// Big endian, with the most significant byte first.
+    Big,
+}
+
 /// Represents a family of CPUs.
 ///
 /// This is strongly connected to the [`Arch`] type, but reduces the selection to a range of
@@ -123,6 +132,23 @@ impl CpuFamily {
         }
     }
 
+    /// Returns the native pointer size, or `default` if the CPU family is unknown.
+    ///
+    /// This is equivalent to [`pointer_size`](Self::pointer_size) for all known CPU families. Use
+    /// this where a pointer size is required, such as when reading pointers from memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::CpuFamily;
+    ///
+    /// assert_eq!(CpuFamily::Intel32.pointer_size_or(8), 4);
+    /// assert_eq!(CpuFamily::Unknown.pointer_size_or(8), 8);
+    /// ```
+    pub fn pointer_size_or(self, default: usize) -> usize {
+        self.pointer_size().unwrap_or(default)
+    }
+
     /// Returns instruction alignment if fixed.
     ///
     /// Some instruction sets, such as Intel's x86, use variable length instruction encoding.
@@ -152,6 +178,33 @@ impl CpuFamily {
         }
     }
 
+    /// Returns the byte order of this CPU family.
+    ///
+    /// Returns `None` if the CPU family is unknown, or if it is commonly used in both byte orders,
+    /// such as MIPS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::{CpuFamily, Endian};
+    ///
+    /// assert_eq!(CpuFamily::Amd64.endianness(), Some(Endian::Little));
+    /// assert_eq!(CpuFamily::Ppc64.endianness(), Some(Endian::Big));
+    /// ```
+    pub fn endianness(self) -> Option<Endian> {
+        match self {
+            CpuFamily::Intel32
+            | CpuFamily::Amd64
+            | CpuFamily::Arm32
+            | CpuFamily::Arm64
+            | CpuFamily::Arm64_32
+            | CpuFamily::Wasm32 => Some(Endian::Little),
+            CpuFamily::Ppc32 | CpuFamily::Ppc64 => Some(Endian::Big),
+            CpuFamily::Mips32 | CpuFamily::Mips64 => None,
+            CpuFamily::Unknown => None,
+        }
+    }
+
     /// Returns the name of the instruction pointer register.
     ///
     /// The instruction pointer register holds a pointer to currrent code execution at all times.
@@ -183,6 +236,34 @@ impl CpuFamily {
         }
     }
 
+    /// Returns the name of the stack pointer register.
+    ///
+    /// Like [`ip_register_name`], this follows the register names exposed by breakpad. On ARM and
+    /// MIPS, this is the `sp` alias rather than the numbered general purpose register.
+    ///
+    /// Returns `None` if the CPU family is unknown or has no stack pointer register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::CpuFamily;
+    ///
+    /// assert_eq!(CpuFamily::Amd64.sp_register_name(), Some("rsp"));
+    /// ```
+    ///
+    /// [`ip_register_name`]: enum.CpuFamily.html#method.ip_register_name
+    pub fn sp_register_name(self) -> Option<&'static str> {
+        match self {
+            CpuFamily::Intel32 => Some("esp"),
+            CpuFamily::Amd64 => Some("rsp"),
+            CpuFamily::Arm32 | CpuFamily::Arm64 | CpuFamily::Arm64_32 => Some("sp"),
+            CpuFamily::Ppc32 | CpuFamily::Ppc64 => Some("r1"),
+            CpuFamily::Mips32 | CpuFamily::Mips64 => Some("sp"),
+            CpuFamily::Wasm32 => None,
+            CpuFamily::Unknown => None,
+        }
+    }
+
     /// Returns the name of a register in a given architecture used in CFI programs.
     ///
     /// Each CPU family specifies its own register sets, wherer the registers are numbered. This
@@ -336,6 +417,92 @@ impl Arch {
         }
     }
 
+    /// Parses the architecture of an LLVM target triple.
+    ///
+    /// The architecture is the first component of the triple, such as `x86_64` in
+    /// `x86_64-unknown-linux-gnu`. In addition to all names accepted when parsing an `Arch` from a
+    /// string, this supports the LLVM spelling of architectures, such as `aarch64` and `i686`.
+    ///
+    /// Returns `UnknownArchError` if the architecture is not known. This includes architectures
+    /// with a byte order that differs from the corresponding `Arch`, such as `aarch64_be` or
+    /// `powerpc64le`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::from_triple("aarch64-apple-ios").unwrap(), Arch::Arm64);
+    /// assert_eq!(Arch::from_triple("x86_64-unknown-linux-gnu").unwrap(), Arch::Amd64);
+    /// ```
+    pub fn from_triple(triple: &str) -> Result<Arch, UnknownArchError> {
+        let arch = triple.split('-').next().unwrap_or_default();
+        match arch.to_ascii_lowercase().as_str() {
+            "i486" | "i586" | "i686" => Ok(Arch::X86),
+            "aarch64" => Ok(Arch::Arm64),
+            "aarch64_32" => Ok(Arch::Arm64_32),
+            "powerpc" => Ok(Arch::Ppc),
+            "powerpc64" => Ok(Arch::Ppc64),
+            "mipsel" => Ok(Arch::Mips),
+            "mips64el" => Ok(Arch::Mips64),
+            _ => arch.parse(),
+        }
+    }
+
+    /// Creates an `Arch` from the `e_machine` field of an ELF header.
+    ///
+    /// 32-bit ARM is always mapped to the generic `Arch::Arm`, since the ELF header does not
+    /// specify the version. Likewise, `EM_MIPS` is mapped to `Arch::Mips`, as 64-bit MIPS can only
+    /// be distinguished by the `e_flags` field.
+    ///
+    /// Returns `UnknownArchError` for all unknown values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::from_elf_machine(62).unwrap(), Arch::Amd64);
+    /// assert!(Arch::from_elf_machine(0xffff).is_err());
+    /// ```
+    pub fn from_elf_machine(machine: u16) -> Result<Arch, UnknownArchError> {
+        Ok(match machine {
+            3 => Arch::X86,       // EM_386
+            8 | 10 => Arch::Mips, // EM_MIPS, EM_MIPS_RS3_LE
+            20 => Arch::Ppc,      // EM_PPC
+            21 => Arch::Ppc64,    // EM_PPC64
+            40 => Arch::Arm,      // EM_ARM
+            62 => Arch::Amd64,    // EM_X86_64
+            183 => Arch::Arm64,   // EM_AARCH64
+            _ => return Err(UnknownArchError),
+        })
+    }
+
+    /// Creates an `Arch` from the `Machine` field of a PE COFF header.
+    ///
+    /// All 32-bit ARM variants, including Thumb-2, are mapped to the generic `Arch::Arm`.
+    ///
+    /// Returns `UnknownArchError` for all unknown values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::from_pe_machine(0x8664).unwrap(), Arch::Amd64);
+    /// assert!(Arch::from_pe_machine(0xffff).is_err());
+    /// ```
+    pub fn from_pe_machine(machine: u16) -> Result<Arch, UnknownArchError> {
+        Ok(match machine {
+            0x014c => Arch::X86,                   // IMAGE_FILE_MACHINE_I386
+            0x01c0 | 0x01c2 | 0x01c4 => Arch::Arm, // IMAGE_FILE_MACHINE_ARM, THUMB, ARMNT
+            0x01f0 | 0x01f1 => Arch::Ppc,          // IMAGE_FILE_MACHINE_POWERPC, POWERPCFP
+            0x8664 => Arch::Amd64,                 // IMAGE_FILE_MACHINE_AMD64
+            0xaa64 => Arch::Arm64,                 // IMAGE_FILE_MACHINE_ARM64
+            _ => return Err(UnknownArchError),
+        })
+    }
+
     /// Returns the CPU family of the CPU architecture.
     ///
     /// # Examples
@@ -372,6 +539,135 @@ impl Arch {
         }
     }
 
+    /// Truncates an address to the pointer width of the CPU architecture.
+    ///
+    /// On 32-bit architectures, this clears the upper 32 bits, such as the sign extension of
+    /// addresses that were captured as 64-bit values. Addresses are returned unchanged on 64-bit
+    /// and unknown architectures. The pointer width is derived from
+    /// [`CpuFamily::pointer_size`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::X86.truncate_address(0xffff_ffff_8000_1000), 0x8000_1000);
+    /// assert_eq!(Arch::Amd64.truncate_address(0xffff_ffff_8000_1000), 0xffff_ffff_8000_1000);
+    /// ```
+    pub fn truncate_address(self, address: u64) -> u64 {
+        match self.cpu_family().pointer_size() {
+            Some(4) => address & 0xffff_ffff,
+            _ => address,
+        }
+    }
+
+    /// Returns the native pointer size of the CPU architecture, or `default` if it is unknown.
+    ///
+    /// The pointer size is derived from the [`CpuFamily`], see [`CpuFamily::pointer_size_or`].
+    /// To choose a default for architectures that are unknown to this library, use
+    /// [`pointer_size_hint`](Self::pointer_size_hint) with the original name of the architecture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::Arm64.pointer_size_or(4), 8);
+    /// assert_eq!(Arch::Unknown.pointer_size_or(4), 4);
+    /// ```
+    pub fn pointer_size_or(self, default: usize) -> usize {
+        self.cpu_family().pointer_size_or(default)
+    }
+
+    /// Infers the pointer size from the name of an architecture or an LLVM target triple.
+    ///
+    /// For known architectures, this returns the pointer size of the architecture parsed with
+    /// [`from_triple`](Self::from_triple). For all other names, the pointer size is derived from a
+    /// `32` or `64` suffix of the architecture name, such as in `riscv64` or `sparc64`. Returns
+    /// `None` if the name gives no indication of the pointer size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::pointer_size_hint("x86_64-unknown-linux-gnu"), Some(8));
+    /// assert_eq!(Arch::pointer_size_hint("riscv64-unknown-linux-gnu"), Some(8));
+    ///
+    /// let arch = Arch::from_triple("riscv64").unwrap_or_default();
+    /// assert_eq!(arch.pointer_size_or(Arch::pointer_size_hint("riscv64").unwrap_or(4)), 8);
+    /// ```
+    pub fn pointer_size_hint(name: &str) -> Option<usize> {
+        if let Some(size) = Arch::from_triple(name)
+            .ok()
+            .and_then(|arch| arch.cpu_family().pointer_size())
+        {
+            return Some(size);
+        }
+
+        let arch = name.split('-').next().unwrap_or_default();
+        if arch.ends_with("64") {
+            Some(8)
+        } else if arch.ends_with("32") {
+            Some(4)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the byte order of the CPU architecture.
+    ///
+    /// This is derived from the [`CpuFamily`] and returns `None` for unknown architectures. See
+    /// [`CpuFamily::endianness`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::{Arch, Endian};
+    ///
+    /// assert_eq!(Arch::Arm64.endianness(), Some(Endian::Little));
+    /// assert_eq!(Arch::Unknown.endianness(), None);
+    /// ```
+    pub fn endianness(self) -> Option<Endian> {
+        self.cpu_family().endianness()
+    }
+
+    /// Returns the name of the instruction pointer register.
+    ///
+    /// This is derived from the [`CpuFamily`]. On 32-bit ARM, the instruction pointer is `r15`,
+    /// which is returned by its `pc` alias. See [`CpuFamily::ip_register_name`] for more
+    /// information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::Amd64.ip_register_name(), Some("rip"));
+    /// assert_eq!(Arch::ArmV7.ip_register_name(), Some("pc"));
+    /// assert_eq!(Arch::Unknown.ip_register_name(), None);
+    /// ```
+    pub fn ip_register_name(self) -> Option<&'static str> {
+        self.cpu_family().ip_register_name()
+    }
+
+    /// Returns the name of the stack pointer register.
+    ///
+    /// This is derived from the [`CpuFamily`]. See [`CpuFamily::sp_register_name`] for more
+    /// information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert_eq!(Arch::X86.sp_register_name(), Some("esp"));
+    /// assert_eq!(Arch::Unknown.sp_register_name(), None);
+    /// ```
+    pub fn sp_register_name(self) -> Option<&'static str> {
+        self.cpu_family().sp_register_name()
+    }
+
     /// Returns the canonical name of the CPU architecture.
     ///
     /// This follows the Apple conventions for naming architectures. For instance, Intel 32-bit
@@ -446,6 +742,77 @@ impl Arch {
                 | Arch::Arm64_32Unknown
         )
     }
+
+    /// Returns whether code built for `other` can run on this architecture.
+    ///
+    /// This is used to decide whether debug information for `other` can symbolicate a crash on
+    /// this architecture. For instance, an `armv7s` device runs `armv7` code, so `armv7` symbols
+    /// can be used for an `armv7s` crash. The reverse does not hold, since `armv7s` code may use
+    /// instructions that an `armv7` device does not support.
+    ///
+    /// An architecture can always load itself. Apart from that, architectures only load others
+    /// within the same [`CpuFamily`]:
+    ///
+    /// | Architecture              | Can also load                    |
+    /// |---------------------------|----------------------------------|
+    /// | `x86_64h`                 | `x86_64`                         |
+    /// | `arm64`, `arm64v8`        | `arm64`, `arm64v8`               |
+    /// | `arm64e`                  | `arm64`, `arm64v8`               |
+    /// | `arm64_32`, `arm64_32_v8` | `arm64_32`, `arm64_32_v8`        |
+    /// | `armv5`                   | `arm`                            |
+    /// | `armv6`                   | `arm`, `armv5`                   |
+    /// | `armv7`                   | `arm`, `armv5`, `armv6`          |
+    /// | `armv7f`, `armv7s`        | `arm`, `armv5`, `armv6`, `armv7` |
+    /// | `armv7m`                  | `armv6m`                         |
+    /// | `armv7em`                 | `armv6m`, `armv7m`               |
+    ///
+    /// The generic `arm` is used when the version is not known, such as for ELF files, so it is
+    /// loaded by all application profile architectures, but only loads itself. `armv7k` uses a
+    /// different ABI than `armv7` and only loads itself. The `*Unknown` variants only load
+    /// themselves, as do all other architectures.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Arch;
+    ///
+    /// assert!(Arch::ArmV7s.can_load(&Arch::ArmV7));
+    /// assert!(!Arch::ArmV7.can_load(&Arch::ArmV7s));
+    /// assert!(!Arch::Amd64.can_load(&Arch::X86));
+    /// ```
+    pub fn can_load(&self, other: &Arch) -> bool {
+        if self == other {
+            return true;
+        }
+
+        matches!(
+            (*self, *other),
+            (Arch::Amd64h, Arch::Amd64)
+                | (Arch::Arm64, Arch::Arm64V8)
+                | (Arch::Arm64V8, Arch::Arm64)
+                | (Arch::Arm64e, Arch::Arm64)
+                | (Arch::Arm64e, Arch::Arm64V8)
+                | (Arch::Arm64_32, Arch::Arm64_32V8)
+                | (Arch::Arm64_32V8, Arch::Arm64_32)
+                | (Arch::ArmV5, Arch::Arm)
+                | (Arch::ArmV6, Arch::Arm)
+                | (Arch::ArmV6, Arch::ArmV5)
+                | (Arch::ArmV7, Arch::Arm)
+                | (Arch::ArmV7, Arch::ArmV5)
+                | (Arch::ArmV7, Arch::ArmV6)
+                | (Arch::ArmV7f, Arch::Arm)
+                | (Arch::ArmV7f, Arch::ArmV5)
+                | (Arch::ArmV7f, Arch::ArmV6)
+                | (Arch::ArmV7f, Arch::ArmV7)
+                | (Arch::ArmV7s, Arch::Arm)
+                | (Arch::ArmV7s, Arch::ArmV5)
+                | (Arch::ArmV7s, Arch::ArmV6)
+                | (Arch::ArmV7s, Arch::ArmV7)
+                | (Arch::ArmV7m, Arch::ArmV6m)
+                | (Arch::ArmV7em, Arch::ArmV6m)
+                | (Arch::ArmV7em, Arch::ArmV7m)
+        )
+    }
 }
 
 impl Default for Arch {
@@ -573,6 +940,29 @@ impl Language {
         }
     }
 
+    /// Creates a `Language` from its `u8` representation, as stored in SymCache function records.
+    ///
+    /// Returns `Language::Unknown` for all unknown values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Language;
+    ///
+    /// assert_eq!(Language::from_u8(7), Language::Rust);
+    /// assert_eq!(Language::from_u8(200), Language::Unknown);
+    /// ```
+    pub fn from_u8(val: u8) -> Language {
+        Self::from_u32(val.into())
+    }
+
+    /// Returns the `u8` representation of this language.
+    ///
+    /// This is the inverse of [`from_u8`](Self::from_u8).
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
     /// Returns the name of the language.
     ///
     /// The name is always given in lower case without special characters or spaces, suitable for
@@ -603,17 +993,21 @@ impl Language {
             Language::Swift => "swift",
         }
     }
-}
 
-impl Default for Language {
-    fn default() -> Language {
-        Language::Unknown
-    }
-}
-
-impl fmt::Display for Language {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let formatted = match *self {
+    /// Returns the human readable name of the language.
+    ///
+    /// This is the same as the `Display` implementation, but does not require an allocation to
+    /// obtain a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symbolic_common::Language;
+    ///
+    /// assert_eq!(Language::ObjCpp.display_name(), "Objective-C++");
+    /// ```
+    pub fn display_name(self) -> &'static str {
+        match self {
             Language::Unknown => "unknown",
             Language::C => "C",
             Language::Cpp => "C++",
@@ -623,9 +1017,19 @@ impl fmt::Display for Language {
             Language::ObjCpp => "Objective-C++",
             Language::Rust => "Rust",
             Language::Swift => "Swift",
-        };
+        }
+    }
+}
+
+impl Default for Language {
+    fn default() -> Language {
+        Language::Unknown
+    }
+}
 
-        write!(f, "{}", formatted)
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.display_name())
     }
 }
 
@@ -943,4 +1347,211 @@ mod tests {
     fn test_cfi_register_name_none() {
         assert_eq!(CpuFamily::Arm64.cfi_register_name(33), None);
     }
+
+    #[test]
+    fn test_endianness() {
+        assert_eq!(Arch::X86.endianness(), Some(Endian::Little));
+        assert_eq!(Arch::Amd64h.endianness(), Some(Endian::Little));
+        assert_eq!(Arch::ArmV7.endianness(), Some(Endian::Little));
+        assert_eq!(Arch::Arm64e.endianness(), Some(Endian::Little));
+        assert_eq!(Arch::Arm64_32.endianness(), Some(Endian::Little));
+        assert_eq!(Arch::Wasm32.endianness(), Some(Endian::Little));
+        assert_eq!(Arch::Ppc.endianness(), Some(Endian::Big));
+        assert_eq!(Arch::Ppc64.endianness(), Some(Endian::Big));
+        assert_eq!(Arch::Mips.endianness(), None);
+        assert_eq!(Arch::Unknown.endianness(), None);
+    }
+
+    #[test]
+    fn test_pointer_size_or() {
+        assert_eq!(CpuFamily::Amd64.pointer_size_or(4), 8);
+        assert_eq!(CpuFamily::Unknown.pointer_size_or(4), 4);
+        assert_eq!(Arch::X86.pointer_size_or(8), 4);
+        assert_eq!(Arch::Wasm32.pointer_size_or(8), 4);
+        assert_eq!(Arch::Arm64_32.pointer_size_or(4), 8);
+        assert_eq!(Arch::Unknown.pointer_size_or(8), 8);
+        assert_eq!(Arch::Unknown.cpu_family().pointer_size(), None);
+    }
+
+    #[test]
+    fn test_can_load() {
+        assert!(Arch::ArmV7s.can_load(&Arch::ArmV7));
+        assert!(!Arch::ArmV7.can_load(&Arch::ArmV7s));
+        assert!(Arch::Arm64e.can_load(&Arch::Arm64));
+        assert!(!Arch::Arm64.can_load(&Arch::Arm64e));
+
+        assert!(Arch::ArmV7.can_load(&Arch::Arm));
+        assert!(!Arch::Arm.can_load(&Arch::ArmV7));
+        assert!(!Arch::ArmV7k.can_load(&Arch::ArmV7));
+        assert!(!Arch::ArmV7m.can_load(&Arch::ArmV7));
+        assert!(!Arch::Arm64.can_load(&Arch::ArmV7));
+        assert!(!Arch::Amd64.can_load(&Arch::X86));
+        assert!(!Arch::Arm64Unknown.can_load(&Arch::Arm64));
+
+        for arch in &[Arch::Unknown, Arch::ArmV7k, Arch::Arm64e, Arch::Wasm32] {
+            assert!(arch.can_load(arch));
+        }
+    }
+
+    #[test]
+    fn test_pointer_size_hint() {
+        assert_eq!(Arch::pointer_size_hint("x86"), Some(4));
+        assert_eq!(Arch::pointer_size_hint("i686-pc-windows-msvc"), Some(4));
+        assert_eq!(Arch::pointer_size_hint("aarch64-apple-ios"), Some(8));
+        assert_eq!(Arch::pointer_size_hint("wasm32"), Some(4));
+        assert_eq!(
+            Arch::pointer_size_hint("riscv64-unknown-linux-gnu"),
+            Some(8)
+        );
+        assert_eq!(Arch::pointer_size_hint("sparc64"), Some(8));
+        assert_eq!(Arch::pointer_size_hint("riscv32"), Some(4));
+        assert_eq!(Arch::pointer_size_hint("s390x"), None);
+        assert_eq!(Arch::pointer_size_hint(""), None);
+
+        let arch = Arch::from_triple("riscv64").unwrap_or_default();
+        assert_eq!(arch, Arch::Unknown);
+        let hint = Arch::pointer_size_hint("riscv64").unwrap_or(4);
+        assert_eq!(arch.pointer_size_or(hint), 8);
+    }
+
+    #[test]
+    fn test_register_names() {
+        let cases = [
+            (Arch::Unknown, None, None),
+            (Arch::X86, Some("eip"), Some("esp")),
+            (Arch::X86Unknown, Some("eip"), Some("esp")),
+            (Arch::Amd64, Some("rip"), Some("rsp")),
+            (Arch::Amd64h, Some("rip"), Some("rsp")),
+            (Arch::Amd64Unknown, Some("rip"), Some("rsp")),
+            (Arch::Arm, Some("pc"), Some("sp")),
+            (Arch::ArmV5, Some("pc"), Some("sp")),
+            (Arch::ArmV6, Some("pc"), Some("sp")),
+            (Arch::ArmV6m, Some("pc"), Some("sp")),
+            (Arch::ArmV7, Some("pc"), Some("sp")),
+            (Arch::ArmV7f, Some("pc"), Some("sp")),
+            (Arch::ArmV7s, Some("pc"), Some("sp")),
+            (Arch::ArmV7k, Some("pc"), Some("sp")),
+            (Arch::ArmV7m, Some("pc"), Some("sp")),
+            (Arch::ArmV7em, Some("pc"), Some("sp")),
+            (Arch::ArmUnknown, Some("pc"), Some("sp")),
+            (Arch::Arm64, Some("pc"), Some("sp")),
+            (Arch::Arm64V8, Some("pc"), Some("sp")),
+            (Arch::Arm64e, Some("pc"), Some("sp")),
+            (Arch::Arm64Unknown, Some("pc"), Some("sp")),
+            (Arch::Ppc, Some("srr0"), Some("r1")),
+            (Arch::Ppc64, Some("srr0"), Some("r1")),
+            (Arch::Mips, Some("pc"), Some("sp")),
+            (Arch::Mips64, Some("pc"), Some("sp")),
+            (Arch::Arm64_32, Some("pc"), Some("sp")),
+            (Arch::Arm64_32V8, Some("pc"), Some("sp")),
+            (Arch::Arm64_32Unknown, Some("pc"), Some("sp")),
+            (Arch::Wasm32, None, None),
+        ];
+
+        for &(arch, ip, sp) in &cases {
+            assert_eq!(arch.ip_register_name(), ip, "{:?}", arch);
+            assert_eq!(arch.sp_register_name(), sp, "{:?}", arch);
+        }
+    }
+
+    #[test]
+    fn test_truncate_address() {
+        let address = 0xffff_ffff_8000_1000;
+
+        for &arch in &[Arch::X86, Arch::ArmV7, Arch::Ppc, Arch::Mips, Arch::Wasm32] {
+            assert_eq!(arch.truncate_address(address), 0x8000_1000, "{:?}", arch);
+        }
+
+        for &arch in &[
+            Arch::Amd64,
+            Arch::Arm64,
+            Arch::Ppc64,
+            Arch::Mips64,
+            Arch::Unknown,
+        ] {
+            assert_eq!(arch.truncate_address(address), address, "{:?}", arch);
+        }
+    }
+
+    #[test]
+    fn test_from_triple() {
+        let arch = |triple| Arch::from_triple(triple).unwrap();
+
+        // Apple
+        assert_eq!(arch("aarch64-apple-ios"), Arch::Arm64);
+        assert_eq!(arch("arm64-apple-macosx11.0"), Arch::Arm64);
+        assert_eq!(arch("arm64e-apple-ios14.0"), Arch::Arm64e);
+        assert_eq!(arch("armv7k-apple-watchos"), Arch::ArmV7k);
+        assert_eq!(arch("x86_64-apple-darwin"), Arch::Amd64);
+
+        // Linux
+        assert_eq!(arch("x86_64-unknown-linux-gnu"), Arch::Amd64);
+        assert_eq!(arch("i686-unknown-linux-gnu"), Arch::X86);
+        assert_eq!(arch("armv7-unknown-linux-gnueabihf"), Arch::ArmV7);
+        assert_eq!(arch("aarch64-linux-android"), Arch::Arm64);
+        assert_eq!(arch("powerpc64-unknown-linux-gnu"), Arch::Ppc64);
+        assert_eq!(arch("mips64el-unknown-linux-gnuabi64"), Arch::Mips64);
+
+        // Windows
+        assert_eq!(arch("x86_64-pc-windows-msvc"), Arch::Amd64);
+        assert_eq!(arch("i686-pc-windows-msvc"), Arch::X86);
+        assert_eq!(arch("aarch64-pc-windows-msvc"), Arch::Arm64);
+
+        // Case-insensitive
+        assert_eq!(arch("X86_64-PC-Windows-MSVC"), Arch::Amd64);
+
+        assert!(Arch::from_triple("bogus").is_err());
+        assert!(Arch::from_triple("sparc64-sun-solaris").is_err());
+        assert!(Arch::from_triple("aarch64_be-unknown-linux-gnu").is_err());
+        assert!(Arch::from_triple("powerpc64le-unknown-linux-gnu").is_err());
+        assert!(Arch::from_triple("").is_err());
+    }
+
+    #[test]
+    fn test_from_elf_machine() {
+        assert_eq!(Arch::from_elf_machine(3).unwrap(), Arch::X86);
+        assert_eq!(Arch::from_elf_machine(8).unwrap(), Arch::Mips);
+        assert_eq!(Arch::from_elf_machine(20).unwrap(), Arch::Ppc);
+        assert_eq!(Arch::from_elf_machine(21).unwrap(), Arch::Ppc64);
+        assert_eq!(Arch::from_elf_machine(40).unwrap(), Arch::Arm);
+        assert_eq!(Arch::from_elf_machine(62).unwrap(), Arch::Amd64);
+        assert_eq!(Arch::from_elf_machine(183).unwrap(), Arch::Arm64);
+        assert!(Arch::from_elf_machine(0).is_err());
+        assert!(Arch::from_elf_machine(243).is_err());
+    }
+
+    #[test]
+    fn test_from_pe_machine() {
+        assert_eq!(Arch::from_pe_machine(0x014c).unwrap(), Arch::X86);
+        assert_eq!(Arch::from_pe_machine(0x01c0).unwrap(), Arch::Arm);
+        assert_eq!(Arch::from_pe_machine(0x01c4).unwrap(), Arch::Arm);
+        assert_eq!(Arch::from_pe_machine(0x01f0).unwrap(), Arch::Ppc);
+        assert_eq!(Arch::from_pe_machine(0x8664).unwrap(), Arch::Amd64);
+        assert_eq!(Arch::from_pe_machine(0xaa64).unwrap(), Arch::Arm64);
+        assert!(Arch::from_pe_machine(0).is_err());
+        assert!(Arch::from_pe_machine(0x5064).is_err());
+    }
+
+    #[test]
+    fn test_language_u8_roundtrip() {
+        let languages = [
+            Language::Unknown,
+            Language::C,
+            Language::Cpp,
+            Language::D,
+            Language::Go,
+            Language::ObjC,
+            Language::ObjCpp,
+            Language::Rust,
+            Language::Swift,
+        ];
+
+        for &language in &languages {
+            assert_eq!(Language::from_u8(language.as_u8()), language);
+            assert_eq!(language.as_u8() as u32, language as u32);
+        }
+
+        assert_eq!(Language::from_u8(languages.len() as u8), Language::Unknown);
+        assert_eq!(Language::from_u8(u8::MAX), Language::Unknown);
+    }
 }
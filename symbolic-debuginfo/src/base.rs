@@ -1,10 +1,10 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::iter::FromIterator;
-use std::ops::{Bound, Deref, RangeBounds};
+use std::ops::{Bound, Deref, Range, RangeBounds};
 use std::str::FromStr;
 
-use symbolic_common::{clean_path, join_path, Arch, CodeId, DebugId, Name};
+use symbolic_common::{clean_path, join_path, Arch, CodeId, DebugId, Language, Name};
 
 /// An error returned for unknown or invalid `ObjectKinds`.
 #[derive(Debug)]
@@ -416,6 +416,63 @@ impl<'d> FromIterator<Symbol<'d>> for SymbolMap<'d> {
     }
 }
 
+/// A section in an object file.
+///
+/// Sections can be enumerated via [`Object::sections`](enum.Object.html#method.sections).
+#[derive(Clone, Eq, PartialEq)]
+pub struct Section<'data> {
+    pub(crate) name: Cow<'data, str>,
+    pub(crate) segment: Option<Cow<'data, str>>,
+    pub(crate) address: u64,
+    pub(crate) size: u64,
+    pub(crate) file_range: Option<Range<u64>>,
+}
+
+impl<'data> Section<'data> {
+    /// The name of this section, such as `.text` or `__text`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the segment containing this section, such as `__TEXT`.
+    ///
+    /// This is only available for formats that group sections into named segments, like MachO.
+    pub fn segment(&self) -> Option<&str> {
+        self.segment.as_deref()
+    }
+
+    /// The virtual address of this section as declared in the object file.
+    ///
+    /// Contrary to symbol and function addresses, this is not relative to the load address.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The size of this section in memory.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The range of bytes in the object file that contain this section's data.
+    ///
+    /// This is `None` for sections that do not occupy space in the file, such as `.bss`.
+    pub fn file_range(&self) -> Option<Range<u64>> {
+        self.file_range.clone()
+    }
+}
+
+impl fmt::Debug for Section<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Section")
+            .field("name", &self.name())
+            .field("segment", &self.segment())
+            .field("address", &format_args!("{:#x}", self.address))
+            .field("size", &format_args!("{:#x}", self.size))
+            .field("file_range", &self.file_range)
+            .finish()
+    }
+}
+
 /// File information referred by [`LineInfo`](struct.LineInfo.html) comprising a directory and name.
 ///
 /// The file path is usually relative to a compilation directory. It might contain parent directory
@@ -516,6 +573,8 @@ pub struct LineInfo<'data> {
     pub file: FileInfo<'data>,
     /// Absolute line number starting at 1. Zero means no line number.
     pub line: u64,
+    /// Column number starting at 1. Zero means no column.
+    pub column: u64,
 }
 
 impl fmt::Debug for LineInfo<'_> {
@@ -530,6 +589,7 @@ impl fmt::Debug for LineInfo<'_> {
 
         s.field("file", &self.file)
             .field("line", &self.line)
+            .field("column", &self.column)
             .finish()
     }
 }
@@ -541,10 +601,30 @@ pub struct Function<'data> {
     pub address: u64,
     /// Total code size covered by the function body, including inlined functions.
     pub size: u64,
+    /// Address of the entry point of the function, if it lies outside of this function's range.
+    ///
+    /// Functions with discontiguous address ranges, such as functions split into a hot and a cold
+    /// part by the compiler, are reported as one function per range. The functions for all ranges
+    /// except the one containing the entry point have this set.
+    pub entry_address: Option<u64>,
     /// The name and language of the function symbol.
     pub name: Name<'data>,
+    /// The unqualified name of the function as declared in source, if `name` is a linkage name.
+    ///
+    /// DWARF records both a linkage name (`DW_AT_linkage_name`) and a plain name (`DW_AT_name`)
+    /// for functions in languages with name mangling. The linkage name or the matching symbol is
+    /// reported in `name`, and the plain name is stored here.
+    pub plain_name: Option<Name<'data>>,
     /// Path to the compilation directory. File paths are relative to this.
     pub compilation_dir: &'data [u8],
+    /// The compiler that produced this function, such as `"clang version 15.0.0"`.
+    ///
+    /// This is empty if the debug information does not record a producer.
+    pub producer: &'data [u8],
+    /// The name of the compilation unit that defines this function, usually its main source file.
+    ///
+    /// This is empty if the debug information does not record compilation units.
+    pub compilation_unit: &'data [u8],
     /// Lines covered by this function, including inlined children.
     pub lines: Vec<LineInfo<'data>>,
     /// Functions that have been inlined into this function's body.
@@ -558,7 +638,7 @@ impl Function<'_> {
     ///
     /// This address points at the first instruction after the function body.
     pub fn end_address(&self) -> u64 {
-        self.address + self.size
+        self.address.saturating_add(self.size)
     }
 }
 
@@ -567,11 +647,21 @@ impl fmt::Debug for Function<'_> {
         f.debug_struct("Function")
             .field("address", &format_args!("{:#x}", self.address))
             .field("size", &format_args!("{:#x}", self.size))
+            .field(
+                "entry_address",
+                &self.entry_address.map(|addr| format!("{:#x}", addr)),
+            )
             .field("name", &self.name)
+            .field("plain_name", &self.plain_name)
             .field(
                 "compilation_dir",
                 &String::from_utf8_lossy(self.compilation_dir),
             )
+            .field("producer", &String::from_utf8_lossy(self.producer))
+            .field(
+                "compilation_unit",
+                &String::from_utf8_lossy(self.compilation_unit),
+            )
             .field("lines", &self.lines)
             .field("inlinees", &self.inlinees)
             .field("inline", &self.inline)
@@ -579,6 +669,95 @@ impl fmt::Debug for Function<'_> {
     }
 }
 
+/// A resolved source location for an instruction address.
+///
+/// This is returned by [`Object::lookup`](enum.Object.html#method.lookup) and mirrors the
+/// information stored in a SymCache, but owns all of its data so that it can outlive the debug
+/// session it was resolved from. Each instance represents one frame of an inlining hierarchy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceLocation {
+    pub(crate) function_name: String,
+    pub(crate) language: Language,
+    pub(crate) function_address: Option<u64>,
+    pub(crate) compilation_dir: String,
+    pub(crate) directory: String,
+    pub(crate) path_name: String,
+    pub(crate) line: u64,
+}
+
+impl SourceLocation {
+    /// Creates a source location for the given function and its line record covering the address.
+    pub(crate) fn from_function(function: &Function<'_>, line: Option<&LineInfo<'_>>) -> Self {
+        SourceLocation {
+            function_name: function.name.as_str().to_owned(),
+            language: function.name.language(),
+            function_address: if function.inline {
+                None
+            } else {
+                Some(function.address)
+            },
+            compilation_dir: String::from_utf8_lossy(function.compilation_dir).into_owned(),
+            directory: line.map_or_else(String::new, |l| l.file.dir_str().into_owned()),
+            path_name: line.map_or_else(String::new, |l| l.file.name_str().into_owned()),
+            line: line.map_or(0, |l| l.line),
+        }
+    }
+
+    /// Creates a source location for a symbol without debug information.
+    pub(crate) fn from_symbol(symbol: &Symbol<'_>) -> Option<Self> {
+        Some(SourceLocation {
+            function_name: symbol.name()?.to_owned(),
+            language: Language::Unknown,
+            function_address: Some(symbol.address),
+            compilation_dir: String::new(),
+            directory: String::new(),
+            path_name: String::new(),
+            line: 0,
+        })
+    }
+
+    /// The possibly mangled name of the function.
+    pub fn function_name(&self) -> &str {
+        &self.function_name
+    }
+
+    /// The language the function is written in.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// The address of the function's first instruction, or `None` if the function was inlined.
+    pub fn function_address(&self) -> Option<u64> {
+        self.function_address
+    }
+
+    /// The compilation directory of the function.
+    pub fn compilation_dir(&self) -> &str {
+        &self.compilation_dir
+    }
+
+    /// The directory of the source file, which may be empty.
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    /// The final path name fragment of the source file, which is empty if unknown.
+    pub fn path_name(&self) -> &str {
+        &self.path_name
+    }
+
+    /// Resolves and concatenates the full path of the source file.
+    pub fn full_path(&self) -> String {
+        let prefix = join_path(&self.compilation_dir, &self.directory);
+        clean_path(&join_path(&prefix, &self.path_name)).into_owned()
+    }
+
+    /// The source line, or `0` if no line information is available.
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+}
+
 /// A dynamically dispatched iterator over items with the given lifetime.
 pub type DynIterator<'a, T> = Box<dyn Iterator<Item = T> + 'a>;
 
@@ -1,7 +1,7 @@
 //! Support for Breakpad ASCII symbols, used by the Breakpad and Crashpad libraries.
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fmt;
 use std::ops::Range;
@@ -263,6 +263,65 @@ impl<'d> Iterator for BreakpadFileRecords<'d> {
 /// A map of file paths by their file ID.
 pub type BreakpadFileMap<'d> = BTreeMap<u64, &'d str>;
 
+/// An [inline origin record], specifying the function name of an inlined function.
+///
+/// The ID of this record is referenced by [`BreakpadInlineRecord`].
+///
+/// Example: `INLINE_ORIGIN 1305 SharedLibraryInfo::Initialize()`
+///
+/// [inline origin record]: https://github.com/google/breakpad/blob/master/docs/symbol_files.md#inline_origin-records
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BreakpadInlineOriginRecord<'d> {
+    /// Breakpad-internal identifier of the function.
+    pub id: u64,
+    /// The function name.
+    pub name: &'d str,
+}
+
+impl<'d> BreakpadInlineOriginRecord<'d> {
+    /// Parses an inline origin record from a single line.
+    pub fn parse(data: &'d [u8]) -> Result<Self, BreakpadError> {
+        let string = str::from_utf8(data)?;
+        Ok(parsing::inline_origin_record_final(string.trim())?)
+    }
+}
+
+/// An iterator over inline origin records in a Breakpad object.
+#[derive(Clone, Debug)]
+pub struct BreakpadInlineOriginRecords<'d> {
+    lines: Lines<'d>,
+    finished: bool,
+}
+
+impl<'d> Iterator for BreakpadInlineOriginRecords<'d> {
+    type Item = Result<BreakpadInlineOriginRecord<'d>, BreakpadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        for line in &mut self.lines {
+            // Fast path: INLINE_ORIGIN records are always before stack records.
+            if line.starts_with(b"STACK ") {
+                break;
+            }
+
+            if !line.starts_with(b"INLINE_ORIGIN ") {
+                continue;
+            }
+
+            return Some(BreakpadInlineOriginRecord::parse(line));
+        }
+
+        self.finished = true;
+        None
+    }
+}
+
+/// A map of function names of inlined functions by their inline origin ID.
+pub type BreakpadInlineOriginMap<'d> = BTreeMap<u64, &'d str>;
+
 /// A [public function symbol record].
 ///
 /// Example: `PUBLIC m 2160 0 Public2_1`
@@ -364,6 +423,14 @@ impl<'d> BreakpadFuncRecord<'d> {
         }
     }
 
+    /// Returns an iterator over inline records associated to this function.
+    pub fn inlinees(&self) -> BreakpadInlineRecords<'d> {
+        BreakpadInlineRecords {
+            lines: self.lines.clone(),
+            finished: false,
+        }
+    }
+
     /// Returns the range of addresses covered by this record.
     pub fn range(&self) -> Range<u64> {
         self.address..self.address + self.size
@@ -494,8 +561,8 @@ impl<'d> Iterator for BreakpadLineRecords<'d> {
 
             // There might be empty lines throughout the file (or at the end). This is the only
             // iterator that cannot rely on a record identifier, so we have to explicitly skip empty
-            // lines.
-            if line.is_empty() {
+            // lines. Inline records are interleaved with line records.
+            if line.is_empty() || line.starts_with(b"INLINE") {
                 continue;
             }
 
@@ -515,6 +582,96 @@ impl<'d> Iterator for BreakpadLineRecords<'d> {
     }
 }
 
+/// An address range covered by a [`BreakpadInlineRecord`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BreakpadInlineAddressRange {
+    /// The start address of this range relative to the image base (load address).
+    pub address: u64,
+    /// The size of the code covered by this range.
+    pub size: u64,
+}
+
+impl BreakpadInlineAddressRange {
+    /// Returns the range of addresses covered by this record.
+    pub fn range(&self) -> Range<u64> {
+        self.address..self.address.saturating_add(self.size)
+    }
+}
+
+/// An [inline record] associated to a `BreakpadFunctionRecord`.
+///
+/// Inline records follow the [`BreakpadFuncRecord`] that they belong to, interleaved with its
+/// line records. Records with a `depth` of `0` are inlined directly into the function, records
+/// with a higher depth are inlined into the closest preceding record with a depth one lower.
+///
+/// Example: `INLINE 0 3082 52 1305 2a9 18 3e7 a`
+///
+/// [inline record]: https://github.com/google/breakpad/blob/master/docs/symbol_files.md#inline-records
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BreakpadInlineRecord {
+    /// The nesting depth of the inlined function, starting at `0`.
+    pub inline_depth: u64,
+    /// The line number of the call site in the caller.
+    pub call_site_line: u64,
+    /// Identifier of the [`BreakpadFileRecord`] of the call site.
+    pub call_site_file_id: u64,
+    /// Identifier of the [`BreakpadInlineOriginRecord`] specifying the function name.
+    pub origin_id: u64,
+    /// The address ranges covered by the inlined function.
+    pub address_ranges: Vec<BreakpadInlineAddressRange>,
+}
+
+impl BreakpadInlineRecord {
+    /// Parses an inline record from a single line.
+    pub fn parse(data: &[u8]) -> Result<Self, BreakpadError> {
+        let string = str::from_utf8(data)?;
+        Ok(parsing::inline_record_final(string.trim())?)
+    }
+
+    /// Checks whether any of the address ranges contains the given address.
+    pub fn contains(&self, address: u64) -> bool {
+        self.address_ranges
+            .iter()
+            .any(|range| range.range().contains(&address))
+    }
+}
+
+/// An iterator over inline records in a `BreakpadFunctionRecord`.
+#[derive(Clone, Debug)]
+pub struct BreakpadInlineRecords<'d> {
+    lines: Lines<'d>,
+    finished: bool,
+}
+
+impl<'d> Iterator for BreakpadInlineRecords<'d> {
+    type Item = Result<BreakpadInlineRecord, BreakpadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        for line in &mut self.lines {
+            // Stop parsing INLINE records once other expected records are encountered.
+            if line.starts_with(b"FUNC ")
+                || line.starts_with(b"PUBLIC ")
+                || line.starts_with(b"STACK ")
+            {
+                break;
+            }
+
+            if !line.starts_with(b"INLINE ") {
+                continue;
+            }
+
+            return Some(BreakpadInlineRecord::parse(line));
+        }
+
+        self.finished = true;
+        None
+    }
+}
+
 /// A `STACK CFI` record. Usually associated with a [BreakpadStackCfiRecord].
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 pub struct BreakpadStackCfiDeltaRecord<'d> {
@@ -888,6 +1045,7 @@ impl<'data> BreakpadObject<'data> {
     pub fn debug_session(&self) -> Result<BreakpadDebugSession<'data>, BreakpadError> {
         Ok(BreakpadDebugSession {
             file_map: self.file_map(),
+            inline_origin_map: self.inline_origin_map(),
             func_records: self.func_records(),
         })
     }
@@ -931,6 +1089,22 @@ impl<'data> BreakpadObject<'data> {
             .collect()
     }
 
+    /// Returns an iterator over inline origin records.
+    pub fn inline_origin_records(&self) -> BreakpadInlineOriginRecords<'data> {
+        BreakpadInlineOriginRecords {
+            lines: Lines::new(self.data),
+            finished: false,
+        }
+    }
+
+    /// Returns a map for inlined function name lookups by id.
+    pub fn inline_origin_map(&self) -> BreakpadInlineOriginMap<'data> {
+        self.inline_origin_records()
+            .filter_map(Result::ok)
+            .map(|origin| (origin.id, origin.name))
+            .collect()
+    }
+
     /// Returns an iterator over public symbol records.
     pub fn public_records(&self) -> BreakpadPublicRecords<'data> {
         BreakpadPublicRecords {
@@ -1080,6 +1254,7 @@ impl<'data> Iterator for BreakpadSymbolIterator<'data> {
 /// Debug session for Breakpad objects.
 pub struct BreakpadDebugSession<'data> {
     file_map: BreakpadFileMap<'data>,
+    inline_origin_map: BreakpadInlineOriginMap<'data>,
     func_records: BreakpadFuncRecords<'data>,
 }
 
@@ -1088,10 +1263,22 @@ impl<'data> BreakpadDebugSession<'data> {
     pub fn functions(&self) -> BreakpadFunctionIterator<'_> {
         BreakpadFunctionIterator {
             file_map: &self.file_map,
+            inline_origin_map: &self.inline_origin_map,
             func_records: self.func_records.clone(),
         }
     }
 
+    /// Converts a single `FUNC` record of this debug file into a function.
+    ///
+    /// This resolves files and inlinees the same way as [`functions`](Self::functions). The record
+    /// must have been read from the same object as this session.
+    pub fn function(
+        &self,
+        record: BreakpadFuncRecord<'data>,
+    ) -> Result<Function<'_>, BreakpadError> {
+        self.functions().convert(record)
+    }
+
     /// Returns an iterator over all source files in this debug file.
     pub fn files(&self) -> BreakpadFileIterator<'_> {
         BreakpadFileIterator {
@@ -1145,34 +1332,166 @@ impl<'s> Iterator for BreakpadFileIterator<'s> {
 /// An iterator over functions in a Breakpad object.
 pub struct BreakpadFunctionIterator<'s> {
     file_map: &'s BreakpadFileMap<'s>,
+    inline_origin_map: &'s BreakpadInlineOriginMap<'s>,
     func_records: BreakpadFuncRecords<'s>,
 }
 
+/// A part of a line record between two boundaries of inlined functions.
+type LinePiece<'a> = (u64, u64, &'a BreakpadLineRecord);
+
 impl<'s> BreakpadFunctionIterator<'s> {
     fn convert(&self, record: BreakpadFuncRecord<'s>) -> Result<Function<'s>, BreakpadError> {
-        let mut lines = Vec::new();
-        for line in record.lines() {
-            let line = line?;
-            let filename = line.filename(self.file_map).unwrap_or_default();
+        let line_records = record.lines().collect::<Result<Vec<_>, _>>()?;
+        let inline_records = record.inlinees().collect::<Result<Vec<_>, _>>()?;
+
+        // Line records only describe the innermost frame. Split them at every start and end of
+        // an inlined function, so that every caller has a line record wherever the innermost
+        // frame changes.
+        let mut boundaries = BTreeSet::new();
+        for range in inline_records.iter().flat_map(|i| &i.address_ranges) {
+            boundaries.insert(range.address);
+            boundaries.insert(range.address.saturating_add(range.size));
+        }
 
-            lines.push(LineInfo {
-                address: line.address,
-                size: Some(line.size),
-                file: FileInfo::from_path(filename.as_bytes()),
-                line: line.line,
-            });
+        let mut pieces = Vec::with_capacity(line_records.len());
+        for line in &line_records {
+            let end = line.address.saturating_add(line.size);
+            let mut start = line.address;
+            if end <= start {
+                continue;
+            }
+
+            for &boundary in boundaries.range(start + 1..end) {
+                pieces.push((start, boundary - start, line));
+                start = boundary;
+            }
+            pieces.push((start, end - start, line));
+        }
+
+        // Inline records are listed in pre-order, so the parent of each record is the closest
+        // preceding record with a depth one lower.
+        let mut roots = Vec::new();
+        let mut children = vec![Vec::new(); inline_records.len()];
+        let mut stack: Vec<usize> = Vec::new();
+        for (index, inlinee) in inline_records.iter().enumerate() {
+            stack.truncate(inlinee.inline_depth as usize);
+            match stack.last() {
+                Some(&parent) => children[parent].push(index),
+                None => roots.push(index),
+            }
+            stack.push(index);
         }
 
         Ok(Function {
             address: record.address,
             size: record.size,
+            entry_address: None,
             name: Name::new(record.name, NameMangling::Unmangled, Language::Unknown),
+            plain_name: None,
             compilation_dir: &[],
-            lines,
-            inlinees: Vec::new(),
+            producer: &[],
+            compilation_unit: &[],
+            lines: self.lines(&pieces, |_| true, &inline_records, &roots),
+            inlinees: roots
+                .iter()
+                .map(|&index| self.convert_inlinee(index, &pieces, &inline_records, &children))
+                .collect(),
             inline: false,
         })
     }
+
+    /// Converts the inline record at `index` and all of its inlinees into a function.
+    fn convert_inlinee(
+        &self,
+        index: usize,
+        pieces: &[LinePiece<'_>],
+        inline_records: &[BreakpadInlineRecord],
+        children: &[Vec<usize>],
+    ) -> Function<'s> {
+        let record = &inline_records[index];
+        let start = record
+            .address_ranges
+            .iter()
+            .map(|range| range.address)
+            .min()
+            .unwrap_or_default();
+        let end = record
+            .address_ranges
+            .iter()
+            .map(|range| range.address.saturating_add(range.size))
+            .max()
+            .unwrap_or_default();
+
+        let name = self
+            .inline_origin_map
+            .get(&record.origin_id)
+            .copied()
+            .unwrap_or(UNKNOWN_NAME);
+
+        let direct_children = &children[index];
+        Function {
+            address: start,
+            size: end - start,
+            entry_address: None,
+            name: Name::new(name, NameMangling::Unmangled, Language::Unknown),
+            plain_name: None,
+            compilation_dir: &[],
+            producer: &[],
+            compilation_unit: &[],
+            lines: self.lines(
+                pieces,
+                |address| record.contains(address),
+                inline_records,
+                direct_children,
+            ),
+            inlinees: direct_children
+                .iter()
+                .map(|&index| self.convert_inlinee(index, pieces, inline_records, children))
+                .collect(),
+            inline: true,
+        }
+    }
+
+    /// Creates line infos for all pieces covered by a function.
+    ///
+    /// Where one of the given inlinees covers a piece, the line info points to the call site of
+    /// that inlinee instead of the line record.
+    fn lines(
+        &self,
+        pieces: &[LinePiece<'_>],
+        covers: impl Fn(u64) -> bool,
+        inline_records: &[BreakpadInlineRecord],
+        children: &[usize],
+    ) -> Vec<LineInfo<'s>> {
+        let mut lines = Vec::new();
+
+        for &(address, size, line) in pieces {
+            if !covers(address) {
+                continue;
+            }
+
+            let call_site = children
+                .iter()
+                .map(|&index| &inline_records[index])
+                .find(|inlinee| inlinee.contains(address));
+
+            let (file_id, line) = match call_site {
+                Some(inlinee) => (inlinee.call_site_file_id, inlinee.call_site_line),
+                None => (line.file_id, line.line),
+            };
+            let filename = self.file_map.get(&file_id).copied().unwrap_or_default();
+
+            lines.push(LineInfo {
+                address,
+                size: Some(size),
+                file: FileInfo::from_path(filename.as_bytes()),
+                line,
+                column: 0,
+            });
+        }
+
+        lines
+    }
 }
 
 impl<'s> Iterator for BreakpadFunctionIterator<'s> {
@@ -1194,7 +1513,8 @@ mod parsing {
     use nom::bytes::complete::take_while;
     use nom::character::complete::{char, hex_digit1, multispace1};
     use nom::combinator::{cond, eof, rest};
-    use nom::sequence::{pair, tuple};
+    use nom::multi::separated_list1;
+    use nom::sequence::{pair, separated_pair, tuple};
     use nom::{IResult, Parser};
     use nom_supreme::error::ErrorTree;
     use nom_supreme::final_parser::{Location, RecreateContext};
@@ -1562,6 +1882,100 @@ mod parsing {
         nom_supreme::final_parser::final_parser(line_record)(input)
     }
 
+    /// Parse a [`BreakpadInlineOriginRecord`].
+    ///
+    /// An INLINE_ORIGIN record has the form `INLINE_ORIGIN <id>( <name>)?`.
+    fn inline_origin_record(input: &str) -> ParseResult<BreakpadInlineOriginRecord> {
+        let (input, _) = tag("INLINE_ORIGIN")
+            .terminated(multispace1)
+            .context("inline origin record prefix")
+            .parse(input)?;
+
+        let (input, (id, name)) = pair(
+            num_dec!(u64)
+                .terminated(multispace1.or(eof))
+                .context("origin id"),
+            name.context("function name"),
+        )
+        .cut()
+        .context("inline origin record body")
+        .parse(input)?;
+
+        Ok((input, BreakpadInlineOriginRecord { id, name }))
+    }
+
+    /// Parse a [`BreakpadInlineOriginRecord`].
+    ///
+    /// An INLINE_ORIGIN record has the form `INLINE_ORIGIN <id>( <name>)?`.
+    /// This will fail if there is any input left over after the record.
+    pub fn inline_origin_record_final(
+        input: &str,
+    ) -> Result<BreakpadInlineOriginRecord, ErrorTree<ErrorLine>> {
+        nom_supreme::final_parser::final_parser(inline_origin_record)(input)
+    }
+
+    /// Parse a [`BreakpadInlineAddressRange`].
+    ///
+    /// An address range has the form `<address> <size>`.
+    fn inline_address_range(input: &str) -> ParseResult<BreakpadInlineAddressRange> {
+        let (input, (address, size)) = separated_pair(
+            num_hex!(u64).context("address"),
+            multispace1,
+            num_hex!(u64).context("size"),
+        )
+        .context("address range")
+        .parse(input)?;
+
+        Ok((input, BreakpadInlineAddressRange { address, size }))
+    }
+
+    /// Parse a [`BreakpadInlineRecord`].
+    ///
+    /// An INLINE record has the form
+    /// `INLINE <depth> <call_site_line> <call_site_file_id> <origin_id> (<address> <size>)+`.
+    fn inline_record(input: &str) -> ParseResult<BreakpadInlineRecord> {
+        let (input, _) = tag("INLINE")
+            .terminated(multispace1)
+            .context("inline record prefix")
+            .parse(input)?;
+
+        let (input, (inline_depth, call_site_line, call_site_file_id, origin_id, address_ranges)) =
+            tuple((
+                num_dec!(u64)
+                    .terminated(multispace1)
+                    .context("inline depth"),
+                line_num.terminated(multispace1).context("call site line"),
+                num_dec!(u64)
+                    .terminated(multispace1)
+                    .context("call site file id"),
+                num_dec!(u64).terminated(multispace1).context("origin id"),
+                separated_list1(multispace1, inline_address_range).context("address ranges"),
+            ))
+            .cut()
+            .context("inline record body")
+            .parse(input)?;
+
+        Ok((
+            input,
+            BreakpadInlineRecord {
+                inline_depth,
+                call_site_line,
+                call_site_file_id,
+                origin_id,
+                address_ranges,
+            },
+        ))
+    }
+
+    /// Parse a [`BreakpadInlineRecord`].
+    ///
+    /// An INLINE record has the form
+    /// `INLINE <depth> <call_site_line> <call_site_file_id> <origin_id> (<address> <size>)+`.
+    /// This will fail if there is any input left over after the record.
+    pub fn inline_record_final(input: &str) -> Result<BreakpadInlineRecord, ErrorTree<ErrorLine>> {
+        nom_supreme::final_parser::final_parser(inline_record)(input)
+    }
+
     /// Parse a [`BreakpadStackCfiDeltaRecord`].
     ///
     /// A STACK CFI Delta record has the form `STACK CFI <address> <rules>`.
@@ -1904,6 +2318,144 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_inline_origin_record() -> Result<(), BreakpadError> {
+        let string = b"INLINE_ORIGIN 1305 SharedLibraryInfo::Initialize()";
+        let record = BreakpadInlineOriginRecord::parse(string)?;
+
+        insta::assert_debug_snapshot!(record, @r###"
+        BreakpadInlineOriginRecord {
+            id: 1305,
+            name: "SharedLibraryInfo::Initialize()",
+        }
+        "###);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_inline_record() -> Result<(), BreakpadError> {
+        let string = b"INLINE 0 3082 52 1305 2a9 18 3e7 a";
+        let record = BreakpadInlineRecord::parse(string)?;
+
+        insta::assert_debug_snapshot!(record, @r###"
+        BreakpadInlineRecord {
+            inline_depth: 0,
+            call_site_line: 3082,
+            call_site_file_id: 52,
+            origin_id: 1305,
+            address_ranges: [
+                BreakpadInlineAddressRange {
+                    address: 681,
+                    size: 24,
+                },
+                BreakpadInlineAddressRange {
+                    address: 999,
+                    size: 10,
+                },
+            ],
+        }
+        "###);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_inline_record_no_ranges() {
+        let string = b"INLINE 0 3082 52 1305";
+        assert!(BreakpadInlineRecord::parse(string).is_err());
+    }
+
+    #[test]
+    fn test_inline_functions() -> Result<(), BreakpadError> {
+        let data = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 crash
+FILE 0 main.cpp
+FILE 1 util.h
+INLINE_ORIGIN 0 outer()
+FUNC 1000 30 0 main
+INLINE 0 10 0 0 1010 10
+INLINE 1 20 1 1 1018 8
+1000 10 5 0
+1010 20 30 1
+INLINE_ORIGIN 1 inner()
+";
+        let object = BreakpadObject::parse(data)?;
+        let session = object.debug_session()?;
+        let function = session.functions().next().unwrap()?;
+
+        let lines = |function: &Function<'_>| {
+            function
+                .lines
+                .iter()
+                .map(|line| (line.address, line.size, line.line))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(function.name, "main");
+        assert_eq!(
+            lines(&function),
+            [
+                (0x1000, Some(0x10), 5),
+                (0x1010, Some(0x8), 10),
+                (0x1018, Some(0x8), 10),
+                (0x1020, Some(0x10), 30)
+            ]
+        );
+
+        let outer = &function.inlinees[0];
+        assert_eq!(outer.name, "outer()");
+        assert_eq!((outer.address, outer.size), (0x1010, 0x10));
+        assert_eq!(
+            lines(outer),
+            [(0x1010, Some(0x8), 30), (0x1018, Some(0x8), 20)]
+        );
+
+        let inner = &outer.inlinees[0];
+        assert_eq!(inner.name, "inner()");
+        assert_eq!((inner.address, inner.size), (0x1018, 0x8));
+        assert_eq!(lines(inner), [(0x1018, Some(0x8), 30)]);
+        assert_eq!(inner.lines[0].file.name_str(), "util.h");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inline_functions_empty_line() -> Result<(), BreakpadError> {
+        let data = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 crash
+FILE 0 main.cpp
+INLINE_ORIGIN 0 outer()
+FUNC 1000 20 0 main
+INLINE 0 10 0 0 1008 10
+1000 8 5 0
+1008 0 6 0
+1008 10 20 0
+1018 8 7 0
+";
+        let object = BreakpadObject::parse(data)?;
+        let session = object.debug_session()?;
+        let function = session.functions().next().unwrap()?;
+
+        let lines = function
+            .lines
+            .iter()
+            .map(|line| (line.address, line.size, line.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                (0x1000, Some(0x8), 5),
+                (0x1008, Some(0x10), 10),
+                (0x1018, Some(0x8), 7)
+            ]
+        );
+
+        let outer = &function.inlinees[0];
+        assert_eq!(outer.lines.len(), 1);
+        assert_eq!(outer.lines[0].line, 20);
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_public_record() -> Result<(), BreakpadError> {
         let string = b"PUBLIC 5180 0 __clang_call_terminate";
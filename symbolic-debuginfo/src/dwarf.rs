@@ -8,7 +8,7 @@
 //! [`MachObject`]: ../macho/struct.MachObject.html
 
 use std::borrow::Cow;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fmt;
 use std::marker::PhantomData;
@@ -18,7 +18,7 @@ use std::sync::Arc;
 use fallible_iterator::FallibleIterator;
 use gimli::read::{AttributeValue, Error as GimliError, Range};
 use gimli::{constants, DwarfFileType, UnitSectionOffset};
-use lazycell::LazyCell;
+use once_cell::sync::OnceCell;
 use thiserror::Error;
 
 use symbolic_common::{AsSelf, Language, Name, NameMangling, SelfCell};
@@ -46,6 +46,9 @@ type IncompleteLineNumberProgram<'a> = gimli::read::IncompleteLineProgram<Slice<
 type LineNumberProgramHeader<'a> = gimli::read::LineProgramHeader<Slice<'a>>;
 type LineProgramFileEntry<'a> = gimli::read::FileEntry<Slice<'a>>;
 
+/// The call line, file and column of an inlined function.
+type CallSite = (Option<u64>, Option<u64>, Option<u64>);
+
 /// This applies the offset to the address.
 ///
 /// This function does not panic but would wrap around if too large or small
@@ -95,6 +98,7 @@ pub struct DwarfError {
     kind: DwarfErrorKind,
     #[source]
     source: Option<Box<dyn Error + Send + Sync + 'static>>,
+    unit_offset: Option<u64>,
 }
 
 impl DwarfError {
@@ -105,18 +109,43 @@ impl DwarfError {
         E: Into<Box<dyn Error + Send + Sync>>,
     {
         let source = Some(source.into());
-        Self { kind, source }
+        Self {
+            kind,
+            source,
+            unit_offset: None,
+        }
+    }
+
+    /// Attaches the offset of the compilation unit in which this error occurred.
+    fn with_unit_offset(mut self, offset: UnitSectionOffset) -> Self {
+        self.unit_offset = Some(match offset {
+            UnitSectionOffset::DebugInfoOffset(offset) => offset.0 as u64,
+            UnitSectionOffset::DebugTypesOffset(offset) => offset.0 as u64,
+        });
+        self
     }
 
     /// Returns the corresponding [`DwarfErrorKind`] for this error.
     pub fn kind(&self) -> DwarfErrorKind {
         self.kind
     }
+
+    /// Returns the offset of the compilation unit in which this error occurred, if known.
+    ///
+    /// This is set for errors that only affect a single unit, in which case the functions of all
+    /// other units can still be read.
+    pub fn unit_offset(&self) -> Option<u64> {
+        self.unit_offset
+    }
 }
 
 impl From<DwarfErrorKind> for DwarfError {
     fn from(kind: DwarfErrorKind) -> Self {
-        Self { kind, source: None }
+        Self {
+            kind,
+            source: None,
+            unit_offset: None,
+        }
     }
 }
 
@@ -157,6 +186,53 @@ impl fmt::Debug for DwarfSection<'_> {
     }
 }
 
+/// The data of a DWARF section, which may be shared with other users of the section.
+///
+/// This is returned from [`Dwarf::section_data`] and dereferences to the bytes of the section.
+#[derive(Clone, Debug)]
+pub enum DwarfSectionBytes<'data> {
+    /// Data borrowed from the object file.
+    Borrowed(&'data [u8]),
+    /// Data owned by this value, such as decompressed data.
+    Owned(Vec<u8>),
+    /// Data shared with other users, such as decompressed data cached by the object file.
+    Shared(Arc<[u8]>),
+}
+
+impl DwarfSectionBytes<'_> {
+    /// Returns `true` if the data is not borrowed from the object file.
+    pub fn is_owned(&self) -> bool {
+        !matches!(self, DwarfSectionBytes::Borrowed(_))
+    }
+}
+
+impl Default for DwarfSectionBytes<'_> {
+    fn default() -> Self {
+        DwarfSectionBytes::Borrowed(&[])
+    }
+}
+
+impl Deref for DwarfSectionBytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match *self {
+            DwarfSectionBytes::Borrowed(data) => data,
+            DwarfSectionBytes::Owned(ref data) => data,
+            DwarfSectionBytes::Shared(ref data) => data,
+        }
+    }
+}
+
+impl<'data> From<Cow<'data, [u8]>> for DwarfSectionBytes<'data> {
+    fn from(data: Cow<'data, [u8]>) -> Self {
+        match data {
+            Cow::Borrowed(data) => DwarfSectionBytes::Borrowed(data),
+            Cow::Owned(data) => DwarfSectionBytes::Owned(data),
+        }
+    }
+}
+
 /// Provides access to DWARF debugging information independent of the container file type.
 ///
 /// When implementing this trait, verify whether the container file type supports compressed section
@@ -191,6 +267,18 @@ pub trait Dwarf<'data> {
         self.raw_section(name)
     }
 
+    /// Returns the data of a section, decompressing it if necessary.
+    ///
+    /// This is like [`section`](Self::section), but allows to share decompressed data between
+    /// multiple calls instead of copying it. By default, this returns the data of `section`.
+    ///
+    /// The section name is given without leading punctuation, such dots or underscores. For
+    /// instance, the name of the Debug Info section would be `"debug_info"`, which translates to
+    /// `".debug_info"` in ELF and `"__debug_info"` in MachO.
+    fn section_data(&self, name: &str) -> Option<DwarfSectionBytes<'data>> {
+        self.section(name).map(|section| section.data.into())
+    }
+
     /// Determines whether the specified section exists.
     ///
     /// The section name is given without leading punctuation, such dots or underscores. For
@@ -207,6 +295,7 @@ struct DwarfRow {
     address: u64,
     file_index: u64,
     line: Option<u64>,
+    column: u64,
     size: Option<u64>,
 }
 
@@ -275,11 +364,16 @@ impl<'d, 'a> DwarfLineProgram<'d> {
             } else {
                 let file_index = program_row.file_index();
                 let line = program_row.line().map(|v| v.get());
+                let column = match program_row.column() {
+                    gimli::ColumnType::LeftEdge => 0,
+                    gimli::ColumnType::Column(column) => column.get(),
+                };
                 let mut duplicate = false;
                 if let Some(last_row) = sequence_rows.last_mut() {
                     if last_row.address == address {
                         last_row.file_index = file_index;
                         last_row.line = line;
+                        last_row.column = column;
                         duplicate = true;
                     }
                 }
@@ -288,6 +382,7 @@ impl<'d, 'a> DwarfLineProgram<'d> {
                         address,
                         file_index,
                         line,
+                        column,
                         size: None,
                     });
                 }
@@ -341,6 +436,11 @@ impl<'d, 'a> DwarfLineProgram<'d> {
 #[derive(Clone, Copy, Debug)]
 struct UnitRef<'d, 'a> {
     info: &'a DwarfInfo<'d>,
+    /// The sections that attributes of this unit resolve against.
+    ///
+    /// This is the main DWARF data, unless this is a split unit that was loaded from a `.dwo` file
+    /// or `.dwp` package.
+    dwarf: &'a DwarfInner<'d>,
     unit: &'a Unit<'d>,
 }
 
@@ -348,6 +448,18 @@ impl<'d, 'a> UnitRef<'d, 'a> {
     /// Resolve the binary value of an attribute.
     #[inline(always)]
     fn slice_value(&self, value: AttributeValue<Slice<'d>>) -> Option<&'d [u8]> {
+        self.dwarf
+            .attr_string(self.unit, value)
+            .map(|reader| reader.slice())
+            .ok()
+    }
+
+    /// Resolve the binary value of an attribute in the line program header.
+    ///
+    /// Split units share the line program of their skeleton unit, so these values always resolve
+    /// against the main DWARF data.
+    #[inline(always)]
+    fn line_slice_value(&self, value: AttributeValue<Slice<'d>>) -> Option<&'d [u8]> {
         self.info
             .attr_string(self.unit, value)
             .map(|reader| reader.slice())
@@ -371,6 +483,10 @@ impl<'d, 'a> UnitRef<'d, 'a> {
     {
         let (unit, offset) = match attr.value() {
             AttributeValue::UnitRef(offset) => (*self, offset),
+            // Offsets in split units point into the `.dwo` data, which is not indexed.
+            AttributeValue::DebugInfoRef(_) if self.dwarf.file_type == DwarfFileType::Dwo => {
+                return Ok(None)
+            }
             AttributeValue::DebugInfoRef(offset) => self.info.find_unit_offset(offset)?,
             // TODO: There is probably more that can come back here.
             _ => return Ok(None),
@@ -391,28 +507,35 @@ impl<'d, 'a> UnitRef<'d, 'a> {
         self.unit.header.offset()
     }
 
-    /// Resolves the function name of a debug entry.
-    fn resolve_function_name(
+    /// Resolves the linkage name and the plain name of a debug entry.
+    ///
+    /// Names missing from the entry are resolved from its abstract origin or specification. For
+    /// instance, out-of-line definitions of methods often carry a linkage name, while the plain
+    /// name is only declared in the class.
+    fn resolve_function_names(
         &self,
         entry: &Die<'d, '_>,
         language: Language,
         bcsymbolmap: Option<&'d BcSymbolMap<'d>>,
-    ) -> Result<Option<Name<'d>>, DwarfError> {
+    ) -> Result<(Option<Name<'d>>, Option<Name<'d>>), DwarfError> {
         let mut attrs = entry.attrs();
-        let mut fallback_name = None;
+        let mut linkage_name = None;
+        let mut plain_name = None;
         let mut reference_target = None;
 
         while let Some(attr) = attrs.next()? {
             match attr.name() {
-                // Prioritize these. If we get them, take them.
                 constants::DW_AT_linkage_name | constants::DW_AT_MIPS_linkage_name => {
-                    return Ok(self
+                    linkage_name = self
                         .string_value(attr.value())
                         .map(|n| resolve_cow_name(bcsymbolmap, n))
-                        .map(|n| Name::new(n, NameMangling::Mangled, language)));
+                        .map(|n| Name::new(n, NameMangling::Mangled, language));
                 }
                 constants::DW_AT_name => {
-                    fallback_name = Some(attr);
+                    plain_name = self
+                        .string_value(attr.value())
+                        .map(|n| resolve_cow_name(bcsymbolmap, n))
+                        .map(|n| Name::new(n, NameMangling::Unmangled, language));
                 }
                 constants::DW_AT_abstract_origin | constants::DW_AT_specification => {
                     reference_target = Some(attr);
@@ -421,24 +544,27 @@ impl<'d, 'a> UnitRef<'d, 'a> {
             }
         }
 
-        if let Some(attr) = fallback_name {
-            return Ok(self
-                .string_value(attr.value())
-                .map(|n| resolve_cow_name(bcsymbolmap, n))
-                .map(|n| Name::new(n, NameMangling::Unmangled, language)));
+        if linkage_name.is_some() && plain_name.is_some() {
+            return Ok((linkage_name, plain_name));
         }
 
         if let Some(attr) = reference_target {
-            return self.resolve_reference(attr, |ref_unit, ref_entry| {
+            let names = self.resolve_reference(attr, |ref_unit, ref_entry| {
                 if self.offset() != ref_unit.offset() || entry.offset() != ref_entry.offset() {
-                    ref_unit.resolve_function_name(ref_entry, language, bcsymbolmap)
+                    ref_unit
+                        .resolve_function_names(ref_entry, language, bcsymbolmap)
+                        .map(Some)
                 } else {
                     Ok(None)
                 }
-            });
+            })?;
+            if let Some((ref_linkage_name, ref_plain_name)) = names {
+                linkage_name = linkage_name.or(ref_linkage_name);
+                plain_name = plain_name.or(ref_plain_name);
+            }
         }
 
-        Ok(None)
+        Ok((linkage_name, plain_name))
     }
 }
 
@@ -449,16 +575,18 @@ struct DwarfUnit<'d, 'a> {
     bcsymbolmap: Option<&'d BcSymbolMap<'d>>,
     language: Language,
     line_program: Option<DwarfLineProgram<'d>>,
+    producer: &'d [u8],
+    name: &'d [u8],
     prefer_dwarf_names: bool,
 }
 
 impl<'d, 'a> DwarfUnit<'d, 'a> {
     /// Creates a DWARF unit from the gimli `Unit` type.
     fn from_unit(
-        unit: &'a Unit<'d>,
-        info: &'a DwarfInfo<'d>,
+        inner: UnitRef<'d, 'a>,
         bcsymbolmap: Option<&'d BcSymbolMap<'d>>,
     ) -> Result<Option<Self>, DwarfError> {
+        let UnitRef { info, unit, .. } = inner;
         let mut entries = unit.entries();
         let entry = match entries.next_dfs()? {
             Some((_, entry)) => entry,
@@ -468,8 +596,10 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
         // Clang's LLD might eliminate an entire compilation unit and simply set the low_pc to zero
         // and remove all range entries to indicate that it is missing. Skip such a unit, as it does
         // not contain any code that can be executed. Special case relocatable objects, as here the
-        // range information has not been written yet and all units look like this.
+        // range information has not been written yet and all units look like this. Split units
+        // carry their ranges on the skeleton unit, which has been checked before loading them.
         if info.kind != ObjectKind::Relocatable
+            && inner.dwarf.file_type == DwarfFileType::Main
             && unit.low_pc == 0
             && entry.attr(constants::DW_AT_ranges)?.is_none()
         {
@@ -487,19 +617,26 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             .map(|program| DwarfLineProgram::prepare(program.clone()));
 
         let producer = match entry.attr_value(constants::DW_AT_producer)? {
-            Some(AttributeValue::String(string)) => Some(string),
-            _ => None,
+            Some(value) => inner.slice_value(value).unwrap_or_default(),
+            None => &[],
+        };
+
+        let name = match entry.attr_value(constants::DW_AT_name)? {
+            Some(value) => inner.slice_value(value).unwrap_or_default(),
+            None => &[],
         };
 
         // Trust the symbol table more to contain accurate mangled names. However, since Dart's name
         // mangling is lossy, we need to load the demangled name instead.
-        let prefer_dwarf_names = producer.as_deref() == Some(b"Dart VM");
+        let prefer_dwarf_names = producer == b"Dart VM";
 
         Ok(Some(DwarfUnit {
-            inner: UnitRef { info, unit },
+            inner,
             bcsymbolmap,
             language,
             line_program,
+            producer,
+            name,
             prefer_dwarf_names,
         }))
     }
@@ -512,13 +649,18 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
         }
     }
 
-    /// Parses the call site and range lists of this Debugging Information Entry.
+    /// Parses the call site, entry point and range lists of this Debugging Information Entry.
+    ///
+    /// The entry point is read from `DW_AT_entry_pc`, or otherwise from `DW_AT_low_pc`. It is
+    /// `None` if the DIE has neither, which is common for functions with `DW_AT_ranges`.
     fn parse_ranges(
         &self,
         entry: &Die<'d, '_>,
         range_buf: &mut Vec<Range>,
-    ) -> Result<(Option<u64>, Option<u64>), DwarfError> {
-        let mut tuple = (None, None);
+    ) -> Result<(CallSite, Option<u64>), DwarfError> {
+        let mut tuple = (None, None, None);
+        let mut entry_pc = None;
+        let mut entry_pc_rel = None;
         let mut low_pc = None;
         let mut high_pc = None;
         let mut high_pc_rel = None;
@@ -531,18 +673,29 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                 constants::DW_AT_low_pc => match attr.value() {
                     AttributeValue::Addr(addr) => low_pc = Some(addr),
                     AttributeValue::DebugAddrIndex(index) => {
-                        low_pc = Some(self.inner.info.address(self.inner.unit, index)?)
+                        low_pc = Some(self.inner.dwarf.address(self.inner.unit, index)?)
                     }
                     _ => return Err(GimliError::UnsupportedAttributeForm.into()),
                 },
                 constants::DW_AT_high_pc => match attr.value() {
                     AttributeValue::Addr(addr) => high_pc = Some(addr),
                     AttributeValue::DebugAddrIndex(index) => {
-                        low_pc = Some(self.inner.info.address(self.inner.unit, index)?)
+                        low_pc = Some(self.inner.dwarf.address(self.inner.unit, index)?)
                     }
                     AttributeValue::Udata(size) => high_pc_rel = Some(size),
                     _ => return Err(GimliError::UnsupportedAttributeForm.into()),
                 },
+                constants::DW_AT_entry_pc => match attr.value() {
+                    AttributeValue::Addr(addr) => entry_pc = Some(addr),
+                    AttributeValue::DebugAddrIndex(index) => {
+                        entry_pc = Some(self.inner.dwarf.address(self.inner.unit, index)?)
+                    }
+                    // Since DWARF 5, a constant is an offset from the base address of the DIE.
+                    value => match value.udata_value() {
+                        Some(offset) => entry_pc_rel = Some(offset),
+                        None => return Err(GimliError::UnsupportedAttributeForm.into()),
+                    },
+                },
                 constants::DW_AT_call_line => match attr.value() {
                     AttributeValue::Udata(line) => tuple.0 = Some(line),
                     _ => return Err(GimliError::UnsupportedAttributeForm.into()),
@@ -551,10 +704,18 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                     AttributeValue::FileIndex(file) => tuple.1 = Some(file),
                     _ => return Err(GimliError::UnsupportedAttributeForm.into()),
                 },
+                constants::DW_AT_call_column => match attr.value() {
+                    AttributeValue::Udata(column) => tuple.2 = Some(column),
+                    _ => return Err(GimliError::UnsupportedAttributeForm.into()),
+                },
                 constants::DW_AT_ranges
                 | constants::DW_AT_rnglists_base
                 | constants::DW_AT_start_scope => {
-                    match self.inner.info.attr_ranges(self.inner.unit, attr.value())? {
+                    match self
+                        .inner
+                        .dwarf
+                        .attr_ranges(self.inner.unit, attr.value())?
+                    {
                         Some(mut ranges) => {
                             while let Some(range) = match ranges.next() {
                                 Ok(range) => range,
@@ -582,9 +743,17 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             }
         }
 
+        // A `low_pc` of 0 is not a valid entry point for the same reason as below.
+        let base = low_pc.filter(|&low_pc| low_pc != 0 || kind == ObjectKind::Relocatable);
+        let entry = match (entry_pc, entry_pc_rel) {
+            (Some(entry_pc), _) => Some(entry_pc),
+            (_, Some(entry_pc_rel)) => base.map(|base| base.wrapping_add(entry_pc_rel)),
+            _ => base,
+        };
+
         // Found DW_AT_ranges, so early-exit here
         if !range_buf.is_empty() {
-            return Ok(tuple);
+            return Ok((tuple, entry));
         }
 
         // To go by the logic in dwarf2read, a `low_pc` of 0 can indicate an
@@ -593,19 +762,19 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
         // yet, so we want to retain them.
         let low_pc = match low_pc {
             Some(low_pc) if low_pc != 0 || kind == ObjectKind::Relocatable => low_pc,
-            _ => return Ok(tuple),
+            _ => return Ok((tuple, entry)),
         };
 
         let high_pc = match (high_pc, high_pc_rel) {
             (Some(high_pc), _) => high_pc,
             (_, Some(high_pc_rel)) => low_pc.wrapping_add(high_pc_rel),
-            _ => return Ok(tuple),
+            _ => return Ok((tuple, entry)),
         };
 
         if low_pc == high_pc {
             // most likely low_pc == high_pc means the DIE should be ignored.
             // https://sourceware.org/ml/gdb-patches/2011-03/msg00739.html
-            return Ok(tuple);
+            return Ok((tuple, entry));
         }
 
         if low_pc > high_pc {
@@ -618,7 +787,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             end: high_pc,
         });
 
-        Ok(tuple)
+        Ok((tuple, entry))
     }
 
     /// Resolves line records of a DIE's range list and puts them into the given buffer.
@@ -655,12 +824,14 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                     size: first.size.map(|s| s + first.address - range.begin),
                     file: self.resolve_file(first.file_index).unwrap_or_default(),
                     line: first.line.unwrap_or(0),
+                    column: first.column,
                 };
 
                 for row in rows {
                     let line = row.line.unwrap_or(0);
 
-                    // We're in a range so we can collapse the lines without any side effects
+                    // We're in a range so we can collapse the lines without any side effects.
+                    // Collapsed rows keep the column of the first row on the line.
                     if (last_file, last_info.line) == (row.file_index, line) {
                         // We collapse the lines but need to fix the last line size
                         if let Some(size) = last_info.size.as_mut() {
@@ -679,6 +850,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                         size: row.size,
                         file: self.resolve_file(row.file_index).unwrap_or_default(),
                         line,
+                        column: row.column,
                     };
                 }
 
@@ -704,17 +876,23 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             dir: resolve_byte_name(
                 self.bcsymbolmap,
                 file.directory(line_program)
-                    .and_then(|attr| self.inner.slice_value(attr))
+                    .and_then(|attr| self.inner.line_slice_value(attr))
                     .unwrap_or_default(),
             ),
             name: resolve_byte_name(
                 self.bcsymbolmap,
-                self.inner.slice_value(file.path_name()).unwrap_or_default(),
+                self.inner
+                    .line_slice_value(file.path_name())
+                    .unwrap_or_default(),
             ),
         }
     }
 
     /// Resolves a file entry by its index.
+    ///
+    /// File indexes are 1-based up to DWARF 4, where `0` denotes no file. Starting with DWARF 5,
+    /// they are 0-based and `0` refers to the primary source file. Directory and file names may
+    /// be stored in `.debug_line_str`.
     fn resolve_file(&self, file_id: u64) -> Option<FileInfo<'d>> {
         let line_program = match self.line_program {
             Some(ref program) => &program.header,
@@ -736,12 +914,11 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
         Some(Name::new(name, NameMangling::Mangled, self.language))
     }
 
-    /// Resolves the name of a function from DWARF debug information.
-    fn resolve_dwarf_name(&self, entry: &Die<'d, '_>) -> Option<Name<'d>> {
+    /// Resolves the linkage name and plain name of a function from DWARF debug information.
+    fn resolve_dwarf_names(&self, entry: &Die<'d, '_>) -> (Option<Name<'d>>, Option<Name<'d>>) {
         self.inner
-            .resolve_function_name(entry, self.language, self.bcsymbolmap)
-            .ok()
-            .flatten()
+            .resolve_function_names(entry, self.language, self.bcsymbolmap)
+            .unwrap_or_default()
     }
 
     /// Collects all functions within this compilation unit.
@@ -753,6 +930,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
         let mut depth = 0;
         let mut skipped_depth = None;
         let mut functions = Vec::new();
+        let mut fragments = BTreeMap::new();
 
         let mut stack = FunctionStack::new();
         let mut entries = self.inner.unit.entries();
@@ -780,7 +958,8 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             };
 
             range_buf.clear();
-            let (call_line, call_file) = self.parse_ranges(entry, range_buf)?;
+            let ((call_line, call_file, call_column), entry_pc) =
+                self.parse_ranges(entry, range_buf)?;
 
             // Ranges can be empty for two reasons: (1) the function is a no-op and does not
             // contain any code, or (2) the function did contain eliminated dead code. In the
@@ -792,14 +971,12 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                 continue;
             }
 
-            // We have a non-inlined function which has two ranges or more, probably split because
-            // of cold paths.
-            if !inline && range_buf.len() != 1 {
-                // TODO: Emit one function record per range, instead of skipping this function. This
-                // also applies to PDB, where this is more common with LTO enabled.
-                skipped_depth = Some(depth);
-                continue;
-            }
+            // The entry point is relevant for non-inlined functions with two ranges or more,
+            // probably split because of cold paths. Compilers usually omit `DW_AT_entry_pc` for
+            // these, and GCC omits `DW_AT_low_pc` too, but lists the range with the entry point
+            // first. This is only a fallback, since the list order is not specified.
+            let entry_pc = entry_pc.unwrap_or(range_buf[0].begin);
+            let entry_address = offset(entry_pc, self.inner.info.address_offset);
 
             // In WASM files emitted by emscripted, we have observed a variety of broken ranges.
             // One of these cases also involves ranges which are not being sorted, resulting in
@@ -824,6 +1001,27 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                 continue;
             }
 
+            // Functions with multiple ranges are collected as a whole including their inlinees, and
+            // split into one function per range afterwards. See `split_fragments`.
+            let symbol_range = if !inline && range_buf.len() > 1 {
+                let ranges: Vec<_> = range_buf
+                    .iter()
+                    .map(|range| {
+                        offset(range.begin, self.inner.info.address_offset)
+                            ..offset(range.end, self.inner.info.address_offset)
+                    })
+                    .collect();
+                let entry_range = ranges
+                    .iter()
+                    .find(|range| range.start == entry_address)
+                    .cloned()
+                    .unwrap_or(function_address..function_end);
+                fragments.insert((function_address, function_size), (entry_address, ranges));
+                entry_range
+            } else {
+                function_address..function_end
+            };
+
             // Resolve functions in the symbol table first. Only if there is no entry, fall back
             // to debug information only if there is no match. Sometimes, debug info contains a
             // lesser quality of symbol names.
@@ -833,11 +1031,18 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             let symbol_name = if self.prefer_dwarf_names || inline {
                 None
             } else {
-                self.resolve_symbol_name(function_address..function_end)
+                self.resolve_symbol_name(symbol_range)
+            };
+
+            // The plain name is only kept separately if there is a linkage name. Otherwise, it
+            // is the best name DWARF has to offer.
+            let (dwarf_name, plain_name) = match self.resolve_dwarf_names(entry) {
+                (Some(linkage_name), plain_name) => (Some(linkage_name), plain_name),
+                (None, plain_name) => (plain_name, None),
             };
 
             let name = symbol_name
-                .or_else(|| self.resolve_dwarf_name(entry))
+                .or(dwarf_name)
                 .unwrap_or_else(|| Name::new("", NameMangling::Unmangled, self.language));
 
             // Avoid constant allocations by collecting repeatedly into the same buffer and
@@ -862,6 +1067,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                 // parent which belong to each range in the inlinee.
                 if let (Some(line), Some(file_id)) = (call_line, call_file) {
                     let file = self.resolve_file(file_id).unwrap_or_default();
+                    let column = call_column.unwrap_or(0);
                     let lines = &mut parent.lines;
 
                     let mut index = 0;
@@ -878,6 +1084,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                                     size: Some(range_end.min(next.address) - range_begin),
                                     file: file.clone(),
                                     line,
+                                    column,
                                 };
 
                                 lines.insert(index, line_info);
@@ -913,6 +1120,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                                     size: Some(record_end - range_end),
                                     file: record.file.clone(),
                                     line: record.line,
+                                    column: record.column,
                                 })
                             } else {
                                 None
@@ -938,6 +1146,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                                     size: Some(size),
                                     file: file.clone(),
                                     line,
+                                    column,
                                 };
 
                                 lines.insert(index, line_info);
@@ -945,6 +1154,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                             } else {
                                 record.file = file.clone();
                                 record.line = line;
+                                record.column = column;
                             };
 
                             // Insert the split record after mutating the previous one to avoid
@@ -965,6 +1175,7 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
                                     size: Some(range_end - record_end),
                                     file: file.clone(),
                                     line,
+                                    column,
                                 };
 
                                 lines.insert(index, line_info);
@@ -978,8 +1189,12 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
             let function = Function {
                 address: function_address,
                 size: function_size,
+                entry_address: None,
                 name,
+                plain_name,
                 compilation_dir: self.compilation_dir(),
+                producer: self.producer,
+                compilation_unit: self.name,
                 lines,
                 inlinees: Vec::new(),
                 inline,
@@ -991,10 +1206,70 @@ impl<'d, 'a> DwarfUnit<'d, 'a> {
         // We're done, flush the remaining stack.
         stack.flush(0, &mut functions);
 
+        if !fragments.is_empty() {
+            functions = split_fragments(functions, &mut fragments);
+        }
+
         Ok(functions)
     }
 }
 
+/// The entry point and all address ranges of a function with discontiguous ranges, keyed by the
+/// start address and size of the function.
+type Fragments = BTreeMap<(u64, u64), (u64, Vec<std::ops::Range<u64>>)>;
+
+/// Splits functions with discontiguous address ranges into one function per range.
+///
+/// Lines and inlinees are assigned to the range containing their start address. Each function
+/// shares the name of the original function, and all but the one containing the entry point
+/// record it in [`Function::entry_address`].
+fn split_fragments<'d>(
+    functions: Vec<Function<'d>>,
+    fragments: &mut Fragments,
+) -> Vec<Function<'d>> {
+    let mut split = Vec::with_capacity(functions.len() + fragments.len());
+
+    for function in functions {
+        let (entry_address, ranges) = match fragments.remove(&(function.address, function.size)) {
+            Some(fragment) => fragment,
+            None => {
+                split.push(function);
+                continue;
+            }
+        };
+
+        let mut lines = function.lines;
+        let mut inlinees = function.inlinees;
+        for range in ranges {
+            let (range_lines, other_lines) = lines
+                .into_iter()
+                .partition(|line| range.contains(&line.address));
+            lines = other_lines;
+
+            let (range_inlinees, other_inlinees) = inlinees
+                .into_iter()
+                .partition(|inlinee| range.contains(&inlinee.address));
+            inlinees = other_inlinees;
+
+            split.push(Function {
+                address: range.start,
+                size: range.end - range.start,
+                entry_address: Some(entry_address).filter(|addr| !range.contains(addr)),
+                name: function.name.clone(),
+                plain_name: function.plain_name.clone(),
+                compilation_dir: function.compilation_dir,
+                producer: function.producer,
+                compilation_unit: function.compilation_unit,
+                lines: range_lines,
+                inlinees: range_inlinees,
+                inline: false,
+            });
+        }
+    }
+
+    split
+}
+
 /// Converts a DWARF language number into our `Language` type.
 fn language_from_dwarf(language: gimli::DwLang) -> Language {
     match language {
@@ -1018,7 +1293,7 @@ fn language_from_dwarf(language: gimli::DwLang) -> Language {
 
 /// Data of a specific DWARF section.
 struct DwarfSectionData<'data, S> {
-    data: Cow<'data, [u8]>,
+    data: DwarfSectionBytes<'data>,
     endianity: Endian,
     _ph: PhantomData<S>,
 }
@@ -1034,17 +1309,34 @@ where
     {
         DwarfSectionData {
             data: dwarf
-                .section(&S::section_name()[1..])
-                .map(|section| section.data)
+                .section_data(&S::section_name()[1..])
                 .unwrap_or_default(),
             endianity: dwarf.endianity(),
             _ph: PhantomData,
         }
     }
 
+    /// Loads data for the split DWARF variant of this section from a `.dwo` or `.dwp` file.
+    fn load_dwo<D>(dwarf: &D) -> Self
+    where
+        D: Dwarf<'data>,
+    {
+        let name = format!("{}.dwo", &S::section_name()[1..]);
+        DwarfSectionData {
+            data: dwarf.section_data(&name).unwrap_or_default(),
+            endianity: dwarf.endianity(),
+            _ph: PhantomData,
+        }
+    }
+
+    /// Returns the raw loaded data.
+    fn to_slice(&'data self) -> Slice<'data> {
+        Slice::new(&self.data, self.endianity)
+    }
+
     /// Creates a gimli dwarf section object from the loaded data.
     fn to_gimli(&'data self) -> S {
-        S::from(Slice::new(&self.data, self.endianity))
+        S::from(self.to_slice())
     }
 }
 
@@ -1053,16 +1345,11 @@ where
     S: gimli::read::Section<Slice<'d>>,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let owned = match self.data {
-            Cow::Owned(_) => true,
-            Cow::Borrowed(_) => false,
-        };
-
         f.debug_struct("DwarfSectionData")
             .field("type", &S::section_name())
             .field("endianity", &self.endianity)
             .field("len()", &self.data.len())
-            .field("owned()", &owned)
+            .field("owned()", &self.data.is_owned())
             .finish()
     }
 }
@@ -1070,6 +1357,7 @@ where
 /// All DWARF sections that are needed by `DwarfDebugSession`.
 struct DwarfSections<'data> {
     debug_abbrev: DwarfSectionData<'data, gimli::read::DebugAbbrev<Slice<'data>>>,
+    debug_addr: DwarfSectionData<'data, gimli::read::DebugAddr<Slice<'data>>>,
     debug_info: DwarfSectionData<'data, gimli::read::DebugInfo<Slice<'data>>>,
     debug_line: DwarfSectionData<'data, gimli::read::DebugLine<Slice<'data>>>,
     debug_line_str: DwarfSectionData<'data, gimli::read::DebugLineStr<Slice<'data>>>,
@@ -1077,6 +1365,7 @@ struct DwarfSections<'data> {
     debug_str_offsets: DwarfSectionData<'data, gimli::read::DebugStrOffsets<Slice<'data>>>,
     debug_ranges: DwarfSectionData<'data, gimli::read::DebugRanges<Slice<'data>>>,
     debug_rnglists: DwarfSectionData<'data, gimli::read::DebugRngLists<Slice<'data>>>,
+    split: Option<DwarfSplitSections<'data>>,
 }
 
 impl<'data> DwarfSections<'data> {
@@ -1087,6 +1376,7 @@ impl<'data> DwarfSections<'data> {
     {
         DwarfSections {
             debug_abbrev: DwarfSectionData::load(dwarf),
+            debug_addr: DwarfSectionData::load(dwarf),
             debug_info: DwarfSectionData::load(dwarf),
             debug_line: DwarfSectionData::load(dwarf),
             debug_line_str: DwarfSectionData::load(dwarf),
@@ -1094,14 +1384,89 @@ impl<'data> DwarfSections<'data> {
             debug_str_offsets: DwarfSectionData::load(dwarf),
             debug_ranges: DwarfSectionData::load(dwarf),
             debug_rnglists: DwarfSectionData::load(dwarf),
+            split: None,
         }
     }
 }
 
+/// DWARF sections of a split DWARF object (`.dwo`) or package (`.dwp`).
+struct DwarfSplitSections<'data> {
+    debug_abbrev: DwarfSectionData<'data, gimli::read::DebugAbbrev<Slice<'data>>>,
+    debug_info: DwarfSectionData<'data, gimli::read::DebugInfo<Slice<'data>>>,
+    debug_line: DwarfSectionData<'data, gimli::read::DebugLine<Slice<'data>>>,
+    debug_str: DwarfSectionData<'data, gimli::read::DebugStr<Slice<'data>>>,
+    debug_str_offsets: DwarfSectionData<'data, gimli::read::DebugStrOffsets<Slice<'data>>>,
+    debug_rnglists: DwarfSectionData<'data, gimli::read::DebugRngLists<Slice<'data>>>,
+    debug_loc: DwarfSectionData<'data, gimli::read::DebugLoc<Slice<'data>>>,
+    debug_loclists: DwarfSectionData<'data, gimli::read::DebugLocLists<Slice<'data>>>,
+    debug_types: DwarfSectionData<'data, gimli::read::DebugTypes<Slice<'data>>>,
+    debug_cu_index: DwarfSectionData<'data, gimli::read::DebugCuIndex<Slice<'data>>>,
+    debug_tu_index: DwarfSectionData<'data, gimli::read::DebugTuIndex<Slice<'data>>>,
+}
+
+impl<'data> DwarfSplitSections<'data> {
+    /// Loads all `.dwo` sections from a split DWARF object or package.
+    ///
+    /// Packages index the contributions of every unit to all of these sections, so sections that
+    /// are not read by the debug session must still be loaded to resolve the others.
+    fn from_dwarf<D>(dwarf: &D) -> Self
+    where
+        D: Dwarf<'data>,
+    {
+        DwarfSplitSections {
+            debug_abbrev: DwarfSectionData::load_dwo(dwarf),
+            debug_info: DwarfSectionData::load_dwo(dwarf),
+            debug_line: DwarfSectionData::load_dwo(dwarf),
+            debug_str: DwarfSectionData::load_dwo(dwarf),
+            debug_str_offsets: DwarfSectionData::load_dwo(dwarf),
+            debug_rnglists: DwarfSectionData::load_dwo(dwarf),
+            debug_loc: DwarfSectionData::load_dwo(dwarf),
+            debug_loclists: DwarfSectionData::load_dwo(dwarf),
+            debug_types: DwarfSectionData::load_dwo(dwarf),
+            debug_cu_index: DwarfSectionData::load(dwarf),
+            debug_tu_index: DwarfSectionData::load(dwarf),
+        }
+    }
+
+    /// Returns the raw data of a section requested by gimli.
+    fn section(&'data self, id: gimli::SectionId, endianity: Endian) -> Slice<'data> {
+        match id {
+            gimli::SectionId::DebugAbbrev => self.debug_abbrev.to_slice(),
+            gimli::SectionId::DebugInfo => self.debug_info.to_slice(),
+            gimli::SectionId::DebugLine => self.debug_line.to_slice(),
+            gimli::SectionId::DebugStr => self.debug_str.to_slice(),
+            gimli::SectionId::DebugStrOffsets => self.debug_str_offsets.to_slice(),
+            gimli::SectionId::DebugRngLists => self.debug_rnglists.to_slice(),
+            gimli::SectionId::DebugLoc => self.debug_loc.to_slice(),
+            gimli::SectionId::DebugLocLists => self.debug_loclists.to_slice(),
+            gimli::SectionId::DebugTypes => self.debug_types.to_slice(),
+            gimli::SectionId::DebugCuIndex => self.debug_cu_index.to_slice(),
+            gimli::SectionId::DebugTuIndex => self.debug_tu_index.to_slice(),
+            _ => Slice::new(&[], endianity),
+        }
+    }
+}
+
+/// Supplementary DWARF data that skeleton units of split DWARF are resolved against.
+enum DwarfSplit<'data> {
+    /// A single `.dwo` object, containing the split units of one or more skeleton units.
+    Object(Arc<DwarfInner<'data>>),
+    /// A `.dwp` package, which indexes split units by their DWO id.
+    Package(Box<gimli::read::DwarfPackage<Slice<'data>>>),
+}
+
+/// A loaded compilation unit.
+struct DwarfUnitData<'data> {
+    unit: Unit<'data>,
+    /// The sections of the split unit, if this was resolved from a skeleton unit.
+    split: Option<Arc<DwarfInner<'data>>>,
+}
+
 struct DwarfInfo<'data> {
     inner: DwarfInner<'data>,
     headers: Vec<UnitHeader<'data>>,
-    units: Vec<LazyCell<Option<Unit<'data>>>>,
+    units: Vec<OnceCell<Option<DwarfUnitData<'data>>>>,
+    split: Option<DwarfSplit<'data>>,
     symbol_map: SymbolMap<'data>,
     address_offset: i64,
     kind: ObjectKind,
@@ -1125,7 +1490,7 @@ impl<'d> DwarfInfo<'d> {
     ) -> Result<Self, DwarfError> {
         let inner = gimli::read::Dwarf {
             debug_abbrev: sections.debug_abbrev.to_gimli(),
-            debug_addr: Default::default(),
+            debug_addr: sections.debug_addr.to_gimli(),
             debug_aranges: Default::default(),
             debug_info: sections.debug_info.to_gimli(),
             debug_line: sections.debug_line.to_gimli(),
@@ -1144,12 +1509,49 @@ impl<'d> DwarfInfo<'d> {
 
         // Prepare random access to unit headers.
         let headers = inner.units().collect::<Vec<_>>()?;
-        let units = headers.iter().map(|_| LazyCell::new()).collect();
+        let units = headers.iter().map(|_| OnceCell::new()).collect();
+
+        let split = match sections.split {
+            Some(ref split) if split.debug_cu_index.data.is_empty() => {
+                // Like units of packages, the addresses and `.debug_ranges` of split units are
+                // always read from the skeleton.
+                let dwo = gimli::read::Dwarf {
+                    debug_abbrev: split.debug_abbrev.to_gimli(),
+                    debug_addr: inner.debug_addr,
+                    debug_info: split.debug_info.to_gimli(),
+                    debug_line: split.debug_line.to_gimli(),
+                    debug_str: split.debug_str.to_gimli(),
+                    debug_str_offsets: split.debug_str_offsets.to_gimli(),
+                    debug_types: split.debug_types.to_gimli(),
+                    locations: gimli::read::LocationLists::new(
+                        split.debug_loc.to_gimli(),
+                        split.debug_loclists.to_gimli(),
+                    ),
+                    ranges: RangeLists::new(
+                        *inner.ranges.debug_ranges(),
+                        split.debug_rnglists.to_gimli(),
+                    ),
+                    file_type: DwarfFileType::Dwo,
+                    ..Default::default()
+                };
+                Some(DwarfSplit::Object(Arc::new(dwo)))
+            }
+            Some(ref split) => {
+                let endianity = split.debug_info.endianity;
+                let package = gimli::read::DwarfPackage::load(
+                    |id| Ok::<_, GimliError>(split.section(id, endianity)),
+                    Slice::new(&[], endianity),
+                )?;
+                Some(DwarfSplit::Package(Box::new(package)))
+            }
+            None => None,
+        };
 
         Ok(DwarfInfo {
             inner,
             headers,
             units,
+            split,
             symbol_map,
             address_offset,
             kind,
@@ -1157,27 +1559,96 @@ impl<'d> DwarfInfo<'d> {
     }
 
     /// Loads a compilation unit.
-    fn get_unit(&self, index: usize) -> Result<Option<&Unit<'d>>, DwarfError> {
+    ///
+    /// Skeleton units of split DWARF are replaced with their split unit if it can be found in the
+    /// supplementary DWARF data.
+    fn get_unit(&self, index: usize) -> Result<Option<UnitRef<'d, '_>>, DwarfError> {
         // Silently ignore unit references out-of-bound
         let cell = match self.units.get(index) {
             Some(cell) => cell,
             None => return Ok(None),
         };
 
-        let unit_opt = cell.try_borrow_with(|| {
+        let unit_opt = cell.get_or_try_init(|| {
             // Parse the compilation unit from the header. This requires a top-level DIE that
             // describes the unit itself. For some older DWARF files, this DIE might be missing
             // which causes gimli to error out. We prefer to skip them silently as this simply marks
             // an empty unit for us.
             let header = self.headers[index];
-            match self.inner.unit(header) {
-                Ok(unit) => Ok(Some(unit)),
-                Err(gimli::read::Error::MissingUnitDie) => Ok(None),
-                Err(error) => Err(DwarfError::from(error)),
-            }
+            let unit = match self.inner.unit(header) {
+                Ok(unit) => unit,
+                Err(gimli::read::Error::MissingUnitDie) => return Ok(None),
+                Err(error) => return Err(DwarfError::from(error)),
+            };
+
+            Ok(Some(match self.load_split_unit(&unit)? {
+                Some((split, unit)) => DwarfUnitData {
+                    unit,
+                    split: Some(split),
+                },
+                None => DwarfUnitData { unit, split: None },
+            }))
         })?;
 
-        Ok(unit_opt.as_ref())
+        Ok(unit_opt.as_ref().map(|data| UnitRef {
+            info: self,
+            dwarf: data.split.as_deref().unwrap_or(&self.inner),
+            unit: &data.unit,
+        }))
+    }
+
+    /// Loads the split unit for a skeleton unit from the supplementary DWARF data.
+    ///
+    /// Returns `None` if this is not a skeleton unit, or if its split unit cannot be found.
+    #[allow(clippy::type_complexity)]
+    fn load_split_unit(
+        &self,
+        skeleton: &Unit<'d>,
+    ) -> Result<Option<(Arc<DwarfInner<'d>>, Unit<'d>)>, DwarfError> {
+        let (split, dwo_id) = match (&self.split, skeleton.dwo_id) {
+            (Some(split), Some(dwo_id)) => (split, dwo_id),
+            _ => return Ok(None),
+        };
+
+        // Apply the same check as `DwarfUnit::from_unit` to the skeleton unit, since split units
+        // do not carry the address ranges of the unit.
+        let mut entries = skeleton.entries();
+        if let Some((_, entry)) = entries.next_dfs()? {
+            if self.kind != ObjectKind::Relocatable
+                && skeleton.low_pc == 0
+                && entry.attr(constants::DW_AT_ranges)?.is_none()
+            {
+                return Ok(None);
+            }
+        }
+
+        let dwarf = match split {
+            DwarfSplit::Object(dwarf) => dwarf.clone(),
+            DwarfSplit::Package(package) => match package.find_cu(dwo_id, &self.inner)? {
+                Some(dwarf) => Arc::new(dwarf),
+                None => return Ok(None),
+            },
+        };
+
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let mut unit = dwarf.unit(header)?;
+            if unit.dwo_id != Some(dwo_id) {
+                continue;
+            }
+
+            // The skeleton unit holds the relocated base addresses, the line program and the
+            // compilation directory of the split unit.
+            unit.copy_relocated_attributes(skeleton);
+            unit.line_program = skeleton.line_program.clone();
+            if unit.comp_dir.is_none() {
+                unit.comp_dir = skeleton.comp_dir;
+            }
+
+            return Ok(Some((dwarf, unit)));
+        }
+
+        Ok(None)
     }
 
     /// Resolves an offset into a different compilation unit.
@@ -1197,14 +1668,39 @@ impl<'d> DwarfInfo<'d> {
         };
 
         if let Some(unit) = self.get_unit(index)? {
-            if let Some(unit_offset) = section_offset.to_unit_offset(unit) {
-                return Ok((UnitRef { unit, info: self }, unit_offset));
+            // Split units live in a different section, so offsets cannot be mapped into them.
+            if unit.dwarf.file_type == DwarfFileType::Main {
+                if let Some(unit_offset) = section_offset.to_unit_offset(unit.unit) {
+                    return Ok((unit, unit_offset));
+                }
             }
         }
 
         Err(DwarfErrorKind::InvalidUnitRef(offset.0).into())
     }
 
+    /// Loads the compilation unit at `index` along with its line program.
+    ///
+    /// Returns `None` for empty units and units without a top-level DIE.
+    fn load_unit(
+        &'d self,
+        index: usize,
+        bcsymbolmap: Option<&'d BcSymbolMap<'d>>,
+    ) -> Result<Option<DwarfUnit<'d, 'd>>, DwarfError> {
+        let offset = match self.headers.get(index) {
+            Some(header) => header.offset(),
+            None => return Ok(None),
+        };
+
+        let unit = match self.get_unit(index) {
+            Ok(Some(unit)) => unit,
+            Ok(None) => return Ok(None),
+            Err(error) => return Err(error.with_unit_offset(offset)),
+        };
+
+        DwarfUnit::from_unit(unit, bcsymbolmap).map_err(|error| error.with_unit_offset(offset))
+    }
+
     /// Returns an iterator over all compilation units.
     fn units(&'d self, bcsymbolmap: Option<&'d BcSymbolMap<'d>>) -> DwarfUnitIterator<'_> {
         DwarfUnitIterator {
@@ -1245,16 +1741,10 @@ impl<'s> Iterator for DwarfUnitIterator<'s> {
 
     fn next(&mut self) -> Option<Self::Item> {
         while self.index < self.info.headers.len() {
-            let result = self.info.get_unit(self.index);
+            let result = self.info.load_unit(self.index, self.bcsymbolmap);
             self.index += 1;
 
-            let unit = match result {
-                Ok(Some(unit)) => unit,
-                Ok(None) => continue,
-                Err(error) => return Some(Err(error)),
-            };
-
-            match DwarfUnit::from_unit(unit, self.info, self.bcsymbolmap) {
+            match result {
                 Ok(Some(unit)) => return Some(Ok(unit)),
                 Ok(None) => continue,
                 Err(error) => return Some(Err(error)),
@@ -1267,6 +1757,35 @@ impl<'s> Iterator for DwarfUnitIterator<'s> {
 
 impl std::iter::FusedIterator for DwarfUnitIterator<'_> {}
 
+/// Top-level functions already read from a DWARF file.
+///
+/// Functions that are defined in multiple compilation units, such as inline functions in headers,
+/// are only returned for the first unit that defines them. This set records the address and size
+/// of every top-level function read so far, to skip such duplicates in subsequent units.
+///
+/// Use this with [`DwarfDebugSession::unit_functions`] to read compilation units independently,
+/// for instance in parallel, and merge them in the same way as
+/// [`DwarfDebugSession::functions`].
+#[derive(Clone, Debug, Default)]
+pub struct DwarfSeenFunctions(BTreeSet<(u64, u64)>);
+
+impl DwarfSeenFunctions {
+    /// Creates an empty set of seen functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no function is contained in both sets.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.0.is_disjoint(&other.0)
+    }
+
+    /// Adds all functions of `other` to this set.
+    pub fn extend(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+}
+
 /// A debugging session for DWARF debugging information.
 pub struct DwarfDebugSession<'data> {
     cell: SelfCell<Box<DwarfSections<'data>>, DwarfInfo<'data>>,
@@ -1295,6 +1814,35 @@ impl<'data> DwarfDebugSession<'data> {
         })
     }
 
+    /// Parses DWARF debugging information built with split DWARF (`-gsplit-dwarf`).
+    ///
+    /// The main file only contains skeleton units, while the actual debug information is stored in
+    /// a `.dwo` object or a `.dwp` package passed as `split`. Skeleton units are resolved against
+    /// this supplementary data by their DWO id. Skeleton units that cannot be resolved are treated
+    /// like regular units.
+    pub fn parse_with_split_dwarf<D, S>(
+        dwarf: &D,
+        split: &S,
+        symbol_map: SymbolMap<'data>,
+        address_offset: i64,
+        kind: ObjectKind,
+    ) -> Result<Self, DwarfError>
+    where
+        D: Dwarf<'data>,
+        S: Dwarf<'data>,
+    {
+        let mut sections = DwarfSections::from_dwarf(dwarf);
+        sections.split = Some(DwarfSplitSections::from_dwarf(split));
+        let cell = SelfCell::try_new(Box::new(sections), |sections| {
+            DwarfInfo::parse(unsafe { &*sections }, symbol_map, address_offset, kind)
+        })?;
+
+        Ok(DwarfDebugSession {
+            cell,
+            bcsymbolmap: None,
+        })
+    }
+
     /// Loads the [`BcSymbolMap`] into this debug session.
     ///
     /// All the file and function names yielded by this debug session will be resolved using
@@ -1318,11 +1866,40 @@ impl<'data> DwarfDebugSession<'data> {
             units: self.cell.get().units(self.bcsymbolmap.as_deref()),
             functions: Vec::new().into_iter(),
             range_buf: Vec::new(),
-            seen_ranges: BTreeSet::new(),
+            seen: DwarfSeenFunctions::new(),
             finished: false,
         }
     }
 
+    /// Returns the number of compilation units in this debug file.
+    pub fn unit_count(&self) -> usize {
+        self.cell.get().headers.len()
+    }
+
+    /// Returns all functions of the compilation unit at `index`.
+    ///
+    /// Top-level functions contained in `seen` are skipped along with their inlinees, and all
+    /// other top-level functions are added to it. Reading all units in order with the same set
+    /// yields the same functions as [`functions`](Self::functions). Empty units and indexes past
+    /// [`unit_count`](Self::unit_count) yield no functions.
+    pub fn unit_functions(
+        &self,
+        index: usize,
+        seen: &mut DwarfSeenFunctions,
+    ) -> Result<Vec<Function<'_>>, DwarfError> {
+        let unit = match self
+            .cell
+            .get()
+            .load_unit(index, self.bcsymbolmap.as_deref())?
+        {
+            Some(unit) => unit,
+            None => return Ok(Vec::new()),
+        };
+
+        unit.functions(&mut Vec::new(), &mut seen.0)
+            .map_err(|error| error.with_unit_offset(unit.inner.offset()))
+    }
+
     /// Looks up a file's source contents by its full canonicalized path.
     ///
     /// The given path must be canonicalized.
@@ -1361,13 +1938,28 @@ impl<'s> Iterator for DwarfUnitFileIterator<'s> {
     fn next(&mut self) -> Option<Self::Item> {
         let unit = self.unit.as_ref()?;
         let line_program = unit.line_program.as_ref().map(|p| &p.header)?;
-        let file = line_program.file_names().get(self.index)?;
+        let file_names = line_program.file_names();
+        let file = file_names.get(self.index)?;
+        let info = unit.file_info(line_program, file);
+
+        // Starting with DWARF 5, entry 0 is the primary source file of the unit. Compilers
+        // usually repeat it as a regular entry, so skip it if another entry resolves to the same
+        // file to list files in the same way as in previous DWARF versions.
+        if self.index == 0
+            && line_program.version() >= 5
+            && file_names[1..]
+                .iter()
+                .any(|other| unit.file_info(line_program, other) == info)
+        {
+            self.index += 1;
+            return self.next();
+        }
 
         self.index += 1;
 
         Some(FileEntry {
             compilation_dir: unit.compilation_dir(),
-            info: unit.file_info(line_program, file),
+            info,
         })
     }
 }
@@ -1428,7 +2020,7 @@ pub struct DwarfFunctionIterator<'s> {
     units: DwarfUnitIterator<'s>,
     functions: std::vec::IntoIter<Function<'s>>,
     range_buf: Vec<Range>,
-    seen_ranges: BTreeSet<(u64, u64)>,
+    seen: DwarfSeenFunctions,
     finished: bool,
 }
 
@@ -1451,9 +2043,9 @@ impl<'s> Iterator for DwarfFunctionIterator<'s> {
                 None => break,
             };
 
-            self.functions = match unit.functions(&mut self.range_buf, &mut self.seen_ranges) {
+            self.functions = match unit.functions(&mut self.range_buf, &mut self.seen.0) {
                 Ok(functions) => functions.into_iter(),
-                Err(error) => return Some(Err(error)),
+                Err(error) => return Some(Err(error.with_unit_offset(unit.inner.offset()))),
             };
         }
 
@@ -2,11 +2,13 @@
 
 use std::borrow::Cow;
 use std::cell::Cell;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::error::Error;
 use std::ffi::CStr;
 use std::fmt;
 use std::io::Cursor;
+use std::sync::{Arc, Weak};
 
 use core::cmp;
 use flate2::{Decompress, FlushDecompress};
@@ -19,13 +21,16 @@ use goblin::{
     elf, strtab,
 };
 use nom::InputTakeAtPosition;
+#[cfg(feature = "minidebuginfo")]
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 use scroll::Pread;
 use thiserror::Error;
 
 use symbolic_common::{Arch, AsSelf, CodeId, DebugId, Uuid};
 
 use crate::base::*;
-use crate::dwarf::{Dwarf, DwarfDebugSession, DwarfError, DwarfSection, Endian};
+use crate::dwarf::{Dwarf, DwarfDebugSession, DwarfError, DwarfSection, DwarfSectionBytes, Endian};
 use crate::private::Parse;
 
 const UUID_SIZE: usize = 16;
@@ -67,11 +72,26 @@ impl ElfError {
     }
 }
 
+/// Compression of section data using zstd, which is not defined by goblin.
+#[cfg(feature = "zstd")]
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
 /// Executable and Linkable Format, used for executables and libraries on Linux.
 pub struct ElfObject<'data> {
     elf: elf::Elf<'data>,
     data: &'data [u8],
     is_malformed: bool,
+    /// Decompressed data of compressed sections that are in use, keyed by section name.
+    ///
+    /// Only weak references are kept, so that sections are freed once all debug sessions using
+    /// them have been dropped.
+    decompressed_sections: Mutex<HashMap<String, Weak<[u8]>>>,
+    /// Symbols of the embedded MiniDebugInfo, loaded on first access.
+    ///
+    /// Their names are owned, since they are copied from the decompressed data. Storing them as
+    /// `'static` keeps this object covariant over `'data`, which a cell of borrowed data would not.
+    #[cfg(feature = "minidebuginfo")]
+    mini_debug_info_symbols: OnceCell<Vec<Symbol<'static>>>,
 }
 
 impl<'data> ElfObject<'data> {
@@ -171,6 +191,9 @@ impl<'data> ElfObject<'data> {
                         elf: obj,
                         data,
                         is_malformed: true,
+                        decompressed_sections: Mutex::default(),
+                        #[cfg(feature = "minidebuginfo")]
+                        mini_debug_info_symbols: OnceCell::new(),
                     });
                 }
             };
@@ -339,6 +362,9 @@ impl<'data> ElfObject<'data> {
             elf: obj,
             data,
             is_malformed: false,
+            decompressed_sections: Mutex::default(),
+            #[cfg(feature = "minidebuginfo")]
+            mini_debug_info_symbols: OnceCell::new(),
         })
     }
 
@@ -412,29 +438,18 @@ impl<'data> ElfObject<'data> {
 
     /// The CPU architecture of this object, as specified in the ELF header.
     pub fn arch(&self) -> Arch {
-        match self.elf.header.e_machine {
-            goblin::elf::header::EM_386 => Arch::X86,
-            goblin::elf::header::EM_X86_64 => Arch::Amd64,
-            goblin::elf::header::EM_AARCH64 => Arch::Arm64,
-            // NOTE: This could actually be any of the other 32bit ARMs. Since we don't need this
-            // information, we use the generic Arch::Arm. By reading CPU_arch and FP_arch attributes
-            // from the SHT_ARM_ATTRIBUTES section it would be possible to distinguish the ARM arch
-            // version and infer hard/soft FP.
-            //
-            // For more information, see:
-            // http://code.metager.de/source/xref/gnu/src/binutils/readelf.c#11282
-            // https://stackoverflow.com/a/20556156/4228225
-            goblin::elf::header::EM_ARM => Arch::Arm,
-            goblin::elf::header::EM_PPC => Arch::Ppc,
-            goblin::elf::header::EM_PPC64 => Arch::Ppc64,
-            goblin::elf::header::EM_MIPS | goblin::elf::header::EM_MIPS_RS3_LE => {
-                if self.elf.header.e_flags & MIPS_64_FLAGS != 0 {
-                    Arch::Mips64
-                } else {
-                    Arch::Mips
-                }
-            }
-            _ => Arch::Unknown,
+        // NOTE: 32-bit ARM could actually be any of the other 32bit ARMs. Since we don't need this
+        // information, we use the generic Arch::Arm. By reading CPU_arch and FP_arch attributes
+        // from the SHT_ARM_ATTRIBUTES section it would be possible to distinguish the ARM arch
+        // version and infer hard/soft FP.
+        //
+        // For more information, see:
+        // http://code.metager.de/source/xref/gnu/src/binutils/readelf.c#11282
+        // https://stackoverflow.com/a/20556156/4228225
+        match Arch::from_elf_machine(self.elf.header.e_machine) {
+            Ok(Arch::Mips) if self.elf.header.e_flags & MIPS_64_FLAGS != 0 => Arch::Mips64,
+            Ok(arch) => arch,
+            Err(_) => Arch::Unknown,
         }
     }
 
@@ -495,7 +510,9 @@ impl<'data> ElfObject<'data> {
 
     /// Determines whether this object exposes a public symbol table.
     pub fn has_symbols(&self) -> bool {
-        !self.elf.syms.is_empty() || !self.elf.dynsyms.is_empty()
+        !self.elf.syms.is_empty()
+            || !self.elf.dynsyms.is_empty()
+            || (cfg!(feature = "minidebuginfo") && self.has_section("gnu_debugdata"))
     }
 
     /// Returns an iterator over symbols in the public symbol table.
@@ -511,8 +528,72 @@ impl<'data> ElfObject<'data> {
     }
 
     /// Returns an ordered map of symbols in the symbol table.
+    ///
+    /// If the symbol table has been stripped, this also contains the symbols of the embedded
+    /// MiniDebugInfo, if present. This requires the `minidebuginfo` feature.
     pub fn symbol_map(&self) -> SymbolMap<'data> {
-        self.symbols().collect()
+        let mut symbols: Vec<_> = self.symbols().collect();
+        if self.elf.syms.is_empty() {
+            symbols.extend(self.mini_debug_info_symbols().iter().cloned());
+        }
+        symbols.into()
+    }
+
+    /// Returns the symbols of the embedded MiniDebugInfo.
+    ///
+    /// MiniDebugInfo is an xz-compressed ELF file in the `.gnu_debugdata` section, which contains
+    /// the symbol table that has been stripped from the main file. See
+    /// <https://sourceware.org/gdb/onlinedocs/gdb/MiniDebugInfo.html>. It is only decompressed on
+    /// the first call.
+    #[cfg(feature = "minidebuginfo")]
+    fn mini_debug_info_symbols(&self) -> &[Symbol<'data>] {
+        self.mini_debug_info_symbols
+            .get_or_init(|| self.load_mini_debug_info_symbols())
+    }
+
+    /// Decompresses the embedded MiniDebugInfo and loads its symbols.
+    #[cfg(feature = "minidebuginfo")]
+    fn load_mini_debug_info_symbols(&self) -> Vec<Symbol<'static>> {
+        let section = match self.find_section("gnu_debugdata") {
+            Some((_, section)) => section,
+            None => return Vec::new(),
+        };
+
+        let mut input: &[u8] = &section.data;
+        let mut decompressed = Vec::new();
+        if lzma_rs::xz_decompress(&mut input, &mut decompressed).is_err() {
+            return Vec::new();
+        }
+
+        let object = match ElfObject::parse(&decompressed) {
+            Ok(object) => object,
+            Err(_) => return Vec::new(),
+        };
+
+        // The embedded file is discarded after this, so symbol names have to be copied.
+        object
+            .symbols()
+            .map(|symbol| Symbol {
+                name: symbol.name.map(|name| Cow::Owned(name.into_owned())),
+                address: symbol.address,
+                size: symbol.size,
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "minidebuginfo"))]
+    fn mini_debug_info_symbols(&self) -> &[Symbol<'data>] {
+        &[]
+    }
+
+    /// Returns an iterator over the sections in this object.
+    ///
+    /// Sections whose file range exceeds the 64-bit address space yield an error.
+    pub fn sections(&self) -> ElfSectionIterator<'data, '_> {
+        ElfSectionIterator {
+            headers: self.elf.section_headers.iter(),
+            strtab: &self.elf.shdr_strtab,
+        }
     }
 
     /// Determines whether this object contains debug information.
@@ -537,6 +618,25 @@ impl<'data> ElfObject<'data> {
         DwarfDebugSession::parse(self, symbols, self.load_address() as i64, self.kind())
     }
 
+    /// Constructs a debugging session for a binary built with split DWARF.
+    ///
+    /// `split` is the `.dwo` object or `.dwp` package containing the debug information of the
+    /// skeleton units in this file. See [`DwarfDebugSession::parse_with_split_dwarf`] for more
+    /// information.
+    pub fn debug_session_with_split_dwarf(
+        &self,
+        split: &ElfObject<'data>,
+    ) -> Result<DwarfDebugSession<'data>, DwarfError> {
+        let symbols = self.symbol_map();
+        DwarfDebugSession::parse_with_split_dwarf(
+            self,
+            split,
+            symbols,
+            self.load_address() as i64,
+            self.kind(),
+        )
+    }
+
     /// Determines whether this object contains stack unwinding information.
     pub fn has_unwind_info(&self) -> bool {
         self.has_section("eh_frame") || self.has_section("debug_frame")
@@ -558,6 +658,9 @@ impl<'data> ElfObject<'data> {
     }
 
     /// Decompresses the given compressed section data, if supported.
+    ///
+    /// This supports the legacy GNU format of `.zdebug_*` sections, as well as `SHF_COMPRESSED`
+    /// sections using zlib. Sections compressed with zstd require the `zstd` feature.
     fn decompress_section(&self, section_data: &[u8]) -> Option<Vec<u8>> {
         let (size, compressed) = if section_data.starts_with(b"ZLIB") {
             // The GNU compression header is a 4 byte magic "ZLIB", followed by an 8-byte big-endian
@@ -576,12 +679,21 @@ impl<'data> ElfObject<'data> {
             let context = Ctx::new(container, endianness);
 
             let compression = CompressionHeader::parse(section_data, 0, context).ok()?;
-            if compression.ch_type != ELFCOMPRESS_ZLIB {
-                return None;
+            let compressed = section_data.get(CompressionHeader::size(context)..)?;
+            match compression.ch_type {
+                ELFCOMPRESS_ZLIB => (compression.ch_size, compressed),
+                #[cfg(feature = "zstd")]
+                ELFCOMPRESS_ZSTD => {
+                    use std::io::Read;
+
+                    let mut input = compressed;
+                    let mut decoder = ruzstd::StreamingDecoder::new(&mut input).ok()?;
+                    let mut decompressed = Vec::with_capacity(compression.ch_size as usize);
+                    decoder.read_to_end(&mut decompressed).ok()?;
+                    return Some(decompressed);
+                }
+                _ => return None,
             }
-
-            let compressed = &section_data[CompressionHeader::size(context)..];
-            (compression.ch_size, compressed)
         };
 
         let mut decompressed = Vec::with_capacity(size as usize);
@@ -827,6 +939,63 @@ impl<'data> Dwarf<'data> for ElfObject<'data> {
 
         Some(section)
     }
+
+    fn section_data(&self, name: &str) -> Option<DwarfSectionBytes<'data>> {
+        let (compressed, section) = self.find_section(name)?;
+        if !compressed {
+            return Some(section.data.into());
+        }
+
+        // Debug sessions that are alive at the same time share decompressed sections.
+        let mut cache = self.decompressed_sections.lock();
+        if let Some(decompressed) = cache.get(name).and_then(Weak::upgrade) {
+            return Some(DwarfSectionBytes::Shared(decompressed));
+        }
+
+        let decompressed: Arc<[u8]> = self.decompress_section(&section.data)?.into();
+        cache.retain(|_, section| section.strong_count() > 0);
+        cache.insert(name.to_owned(), Arc::downgrade(&decompressed));
+        Some(DwarfSectionBytes::Shared(decompressed))
+    }
+}
+
+/// An iterator over sections in the ELF file.
+///
+/// Returned by [`ElfObject::sections`](struct.ElfObject.html#method.sections).
+pub struct ElfSectionIterator<'data, 'object> {
+    headers: std::slice::Iter<'object, SectionHeader>,
+    strtab: &'object strtab::Strtab<'data>,
+}
+
+impl<'data, 'object> Iterator for ElfSectionIterator<'data, 'object> {
+    type Item = Result<Section<'data>, ElfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for header in &mut self.headers {
+            if header.sh_type == elf::section_header::SHT_NULL {
+                continue;
+            }
+
+            let file_range = if header.sh_type == elf::section_header::SHT_NOBITS {
+                None
+            } else {
+                match header.sh_offset.checked_add(header.sh_size) {
+                    Some(end) => Some(header.sh_offset..end),
+                    None => return Some(Err(ElfError::new("section file range out of bounds"))),
+                }
+            };
+
+            return Some(Ok(Section {
+                name: Cow::Borrowed(self.strtab.get_at(header.sh_name).unwrap_or_default()),
+                segment: None,
+                address: header.sh_addr,
+                size: header.sh_size,
+                file_range,
+            }));
+        }
+
+        None
+    }
 }
 
 /// An iterator over symbols in the ELF file.
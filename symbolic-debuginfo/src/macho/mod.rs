@@ -299,6 +299,17 @@ impl<'d> MachObject<'d> {
         self.symbols().collect()
     }
 
+    /// Returns an iterator over the sections in this object.
+    ///
+    /// Sections that cannot be parsed are skipped. Sections whose file range exceeds the 64-bit
+    /// address space yield an error.
+    pub fn sections(&self) -> MachOSectionIterator<'d, '_> {
+        MachOSectionIterator {
+            segments: self.macho.segments.iter(),
+            sections: None,
+        }
+    }
+
     /// Determines whether this object contains debug information.
     pub fn has_debug_info(&self) -> bool {
         self.has_section("debug_info")
@@ -492,6 +503,60 @@ impl<'data> Dwarf<'data> for MachObject<'data> {
     }
 }
 
+/// An iterator over sections in the MachO file.
+///
+/// Returned by [`MachObject::sections`](struct.MachObject.html#method.sections).
+pub struct MachOSectionIterator<'data, 'object> {
+    segments: std::slice::Iter<'object, mach::segment::Segment<'data>>,
+    sections: Option<mach::segment::SectionIterator<'data>>,
+}
+
+impl<'data, 'object> Iterator for MachOSectionIterator<'data, 'object> {
+    type Item = Result<Section<'data>, MachError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = self.sections.as_mut().and_then(Iterator::next);
+            let header = match next {
+                Some(Ok((header, _))) => header,
+                // Gracefully recover from corrupt section headers
+                Some(Err(_)) => continue,
+                None => {
+                    self.sections = Some(self.segments.next()?.into_iter());
+                    continue;
+                }
+            };
+
+            let section_type = header.flags & mach::constants::SECTION_TYPE;
+            let is_zerofill = matches!(
+                section_type,
+                mach::constants::S_ZEROFILL
+                    | mach::constants::S_GB_ZEROFILL
+                    | mach::constants::S_THREAD_LOCAL_ZEROFILL
+            );
+
+            // dsymutil leaves section headers of stripped sections with an offset of 0.
+            let file_range = if is_zerofill || header.offset == 0 {
+                None
+            } else {
+                let offset = u64::from(header.offset);
+                match offset.checked_add(header.size) {
+                    Some(end) => Some(offset..end),
+                    None => return Some(Err(MachError::new("section file range out of bounds"))),
+                }
+            };
+
+            return Some(Ok(Section {
+                name: Cow::Owned(header.name().unwrap_or_default().to_owned()),
+                segment: Some(Cow::Owned(header.segname().unwrap_or_default().to_owned())),
+                address: header.addr,
+                size: header.size,
+                file_range,
+            }));
+        }
+    }
+}
+
 /// An iterator over symbols in the MachO file.
 ///
 /// Returned by [`MachObject::symbols`](struct.MachObject.html#method.symbols).
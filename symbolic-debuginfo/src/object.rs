@@ -3,10 +3,11 @@
 use std::borrow::Cow;
 use std::error::Error;
 use std::fmt;
+use std::path::Path;
 
 use goblin::Hint;
 
-use symbolic_common::{Arch, AsSelf, CodeId, DebugId};
+use symbolic_common::{Arch, AsSelf, ByteView, CodeId, DebugId, SelfCell};
 
 use crate::base::*;
 use crate::breakpad::*;
@@ -92,6 +93,18 @@ impl ObjectError {
         let repr = ObjectErrorRepr::Transparent(source.into());
         Self { repr }
     }
+
+    /// Returns the offset of the DWARF compilation unit in which this error occurred, if known.
+    ///
+    /// See [`DwarfError::unit_offset`] for more information.
+    pub fn unit_offset(&self) -> Option<u64> {
+        match self.repr {
+            ObjectErrorRepr::Transparent(ref inner) => {
+                inner.downcast_ref::<DwarfError>()?.unit_offset()
+            }
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Debug for ObjectError {
@@ -211,6 +224,18 @@ impl<'data> Object<'data> {
         Ok(object)
     }
 
+    /// Opens and parses the object file at the given path.
+    ///
+    /// The file is memory mapped instead of being read into memory, so only the parts that are
+    /// accessed are loaded from disk. The returned [`OwnedObject`] keeps the mapping alive for as
+    /// long as the object is used, and gives access to the parsed object via
+    /// [`OwnedObject::get`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<OwnedObject, ObjectError> {
+        let byteview = ByteView::open(path).map_err(ObjectError::transparent)?;
+        let cell = SelfCell::try_new(byteview, |data| Object::parse(unsafe { &*data }))?;
+        Ok(OwnedObject(cell))
+    }
+
     /// The container format of this file, corresponding to the variant of this instance.
     pub fn file_format(&self) -> FileFormat {
         match *self {
@@ -246,6 +271,16 @@ impl<'data> Object<'data> {
         match_inner!(self, Object(ref o) => o.arch())
     }
 
+    /// Loads a [`BcSymbolMap`] to resolve obfuscated names of bitcode builds.
+    ///
+    /// This only applies to MachO objects and is a no-op for all other file formats. See
+    /// [`MachObject::load_symbolmap`] for more information.
+    pub fn load_symbolmap(&mut self, symbolmap: BcSymbolMap<'data>) {
+        if let Object::MachO(ref mut o) = *self {
+            o.load_symbolmap(symbolmap);
+        }
+    }
+
     /// The kind of this object.
     pub fn kind(&self) -> ObjectKind {
         match_inner!(self, Object(ref o) => o.kind())
@@ -271,6 +306,18 @@ impl<'data> Object<'data> {
         match_inner!(self, Object(ref o) => o.symbol_map())
     }
 
+    /// Returns an iterator over the sections in this object.
+    ///
+    /// This is supported for ELF and MachO files. For all other formats, the iterator is empty.
+    /// Sections whose file range exceeds the 64-bit address space yield an error.
+    pub fn sections(&self) -> SectionIterator<'data, '_> {
+        match *self {
+            Object::Elf(ref o) => SectionIterator::Elf(o.sections()),
+            Object::MachO(ref o) => SectionIterator::MachO(o.sections()),
+            _ => SectionIterator::Unsupported,
+        }
+    }
+
     /// Determines whether this object contains debug information.
     pub fn has_debug_info(&self) -> bool {
         match_inner!(self, Object(ref o) => o.has_debug_info())
@@ -322,6 +369,63 @@ impl<'data> Object<'data> {
         }
     }
 
+    /// Constructs a debugging session for a binary built with split DWARF.
+    ///
+    /// `split` is the `.dwo` object or `.dwp` package containing the debug information of the
+    /// skeleton units in this object. Split DWARF is only supported for ELF files. For all other
+    /// combinations of objects, this is equivalent to [`debug_session`](Self::debug_session).
+    pub fn debug_session_with_split_dwarf(
+        &self,
+        split: &Object<'data>,
+    ) -> Result<ObjectDebugSession<'data>, ObjectError> {
+        match (self, split) {
+            (Object::Elf(o), Object::Elf(split)) => o
+                .debug_session_with_split_dwarf(split)
+                .map(ObjectDebugSession::Dwarf)
+                .map_err(ObjectError::transparent),
+            _ => self.debug_session(),
+        }
+    }
+
+    /// Resolves the source locations of an instruction address directly from the debug info.
+    ///
+    /// The returned locations represent the inlining hierarchy at `address`, starting with the
+    /// innermost inlined function and ending with the outermost function. If there is no debug
+    /// information covering the address, this falls back to the symbol table, unless the symbol
+    /// starts before the end of a preceding function. An empty vector is returned if the address
+    /// cannot be resolved at all.
+    ///
+    /// This creates a new debug session and scans all functions on every call. To resolve many
+    /// addresses, convert the object into a SymCache instead, which yields the same information.
+    pub fn lookup(&self, address: u64) -> Result<Vec<SourceLocation>, ObjectError> {
+        let session = self.debug_session()?;
+        // The end of the closest function before `address`, which hides symbols starting earlier.
+        let mut preceding_end = 0;
+        for function in session.functions() {
+            let function = function?;
+            if address >= function.end_address() {
+                preceding_end = preceding_end.max(function.end_address());
+                continue;
+            }
+            if address < function.address {
+                continue;
+            }
+
+            let mut locations = Vec::new();
+            collect_source_locations(&function, address, &mut locations);
+            locations.reverse();
+            return Ok(locations);
+        }
+
+        let symbol_map = self.symbol_map();
+        Ok(symbol_map
+            .lookup(address)
+            .filter(|symbol| symbol.address >= preceding_end)
+            .and_then(SourceLocation::from_symbol)
+            .into_iter()
+            .collect())
+    }
+
     /// Determines whether this object contains stack unwinding information.
     pub fn has_unwind_info(&self) -> bool {
         match_inner!(self, Object(ref o) => o.has_unwind_info())
@@ -343,6 +447,30 @@ impl<'data> Object<'data> {
     }
 }
 
+/// An [`Object`] that owns the file it was parsed from.
+///
+/// This is returned by [`Object::open`], and avoids having to keep the buffer of an object alive
+/// separately. All accessors of the object are available via [`get`](Self::get).
+pub struct OwnedObject(SelfCell<ByteView<'static>, Object<'static>>);
+
+impl OwnedObject {
+    /// Returns the parsed object, borrowing from the owned file.
+    pub fn get(&self) -> &Object<'_> {
+        self.0.get()
+    }
+
+    /// Returns the memory mapped file of this object.
+    pub fn byteview(&self) -> &ByteView<'static> {
+        self.0.owner()
+    }
+}
+
+impl fmt::Debug for OwnedObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedObject").field(self.get()).finish()
+    }
+}
+
 impl<'slf, 'data: 'slf> AsSelf<'slf> for Object<'data> {
     type Ref = Object<'slf>;
 
@@ -497,6 +625,30 @@ impl<'session> DebugSession<'session> for ObjectDebugSession<'_> {
     }
 }
 
+/// Pushes the source locations of `function` and its inlinees covering `address`, outermost first.
+fn collect_source_locations(function: &Function<'_>, address: u64, out: &mut Vec<SourceLocation>) {
+    let line = function
+        .lines
+        .iter()
+        .filter(|line| line.address <= address)
+        .max_by_key(|line| line.address);
+    out.push(SourceLocation::from_function(function, line));
+
+    // An inlinee's bounding range may span code of its caller or of sibling inlinees, so only
+    // descend into an inlinee with a line record at the address.
+    let inlinee = function.inlinees.iter().find(|inlinee| {
+        inlinee.lines.iter().any(|line| {
+            let end = line.size.map_or(inlinee.end_address(), |size| {
+                line.address.saturating_add(size)
+            });
+            line.address <= address && address < end
+        })
+    });
+    if let Some(inlinee) = inlinee {
+        collect_source_locations(inlinee, address, out);
+    }
+}
+
 /// An iterator over functions in an [`Object`](enum.Object.html).
 #[allow(missing_docs)]
 pub enum ObjectFunctionIterator<'s> {
@@ -582,6 +734,30 @@ impl<'data, 'object> Iterator for SymbolIterator<'data, 'object> {
     }
 }
 
+/// A generic section iterator
+#[allow(missing_docs)]
+pub enum SectionIterator<'data, 'object> {
+    Elf(ElfSectionIterator<'data, 'object>),
+    MachO(MachOSectionIterator<'data, 'object>),
+    Unsupported,
+}
+
+impl<'data, 'object> Iterator for SectionIterator<'data, 'object> {
+    type Item = Result<Section<'data>, ObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            SectionIterator::Elf(ref mut iter) => {
+                Some(iter.next()?.map_err(ObjectError::transparent))
+            }
+            SectionIterator::MachO(ref mut iter) => {
+                Some(iter.next()?.map_err(ObjectError::transparent))
+            }
+            SectionIterator::Unsupported => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ArchiveInner<'d> {
     Breakpad(MonoArchive<'d, BreakpadObject<'d>>),
@@ -361,7 +361,7 @@ impl<'data: 'object, 'object> ObjectLike<'data, 'object> for PdbObject<'data> {
     }
 }
 
-pub(crate) fn arch_from_machine(machine: MachineType) -> Arch {
+fn arch_from_machine(machine: MachineType) -> Arch {
     match machine {
         MachineType::X86 => Arch::X86,
         MachineType::Amd64 => Arch::Amd64,
@@ -979,6 +979,7 @@ impl<'s> Unit<'s> {
                 size: line_info.length.map(u64::from),
                 file: self.debug_info.file_info(file_info)?,
                 line: line_info.line_start.into(),
+                column: line_info.column_start.map_or(0, u64::from),
             });
         }
 
@@ -1014,8 +1015,12 @@ impl<'s> Unit<'s> {
         Ok(Some(Function {
             address,
             size: proc.len.into(),
+            entry_address: None,
             name,
+            plain_name: None,
             compilation_dir: &[],
+            producer: &[],
+            compilation_unit: &[],
             lines,
             inlinees: Vec::new(),
             inline: false,
@@ -1059,8 +1064,12 @@ impl<'s> Unit<'s> {
         Ok(Some(Function {
             address: start,
             size: end - start,
+            entry_address: None,
             name,
+            plain_name: None,
             compilation_dir: &[],
+            producer: &[],
+            compilation_unit: &[],
             lines,
             inlinees: Vec::new(),
             inline: true,
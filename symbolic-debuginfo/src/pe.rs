@@ -108,6 +108,9 @@ impl<'data> PeObject<'data> {
     /// [`PdbObject`](crate::pdb::PdbObject), this identifier actually refers to the
     /// PDB. While strictly the filename of the PDB would also be necessary fully resolve
     /// it, in most instances the GUID and age contained in this identifier are sufficient.
+    ///
+    /// If the PE does not reference a PDB, the identifier is derived from the `TimeDateStamp` and
+    /// `SizeOfImage` header fields instead, which also make up the [`code_id`](Self::code_id).
     pub fn debug_id(&self) -> DebugId {
         self.pe
             .debug_data
@@ -125,9 +128,26 @@ impl<'data> PeObject<'data> {
                 let uuid = Uuid::from_slice(&data).ok()?;
                 Some(DebugId::from_parts(uuid, debug_info.age))
             })
+            .or_else(|| self.module_debug_id())
             .unwrap_or_default()
     }
 
+    /// Derives a debug identifier from the image timestamp and size.
+    ///
+    /// The UUID contains the big endian `TimeDateStamp` in its first four bytes, followed by the
+    /// big endian `SizeOfImage`. The remaining bytes and the age are zero.
+    fn module_debug_id(&self) -> Option<DebugId> {
+        let header = &self.pe.header;
+        let optional_header = header.optional_header.as_ref()?;
+
+        let mut data = [0; 16];
+        data[0..4].copy_from_slice(&header.coff_header.time_date_stamp.to_be_bytes());
+        data[4..8].copy_from_slice(&optional_header.windows_fields.size_of_image.to_be_bytes());
+
+        let uuid = Uuid::from_slice(&data).ok()?;
+        Some(DebugId::from_uuid(uuid))
+    }
+
     /// The name of the referenced PDB file.
     pub fn debug_file_name(&self) -> Option<Cow<'_, str>> {
         self.pe
@@ -142,7 +162,7 @@ impl<'data> PeObject<'data> {
     /// The CPU architecture of this object, as specified in the COFF header.
     pub fn arch(&self) -> Arch {
         let machine = self.pe.header.coff_header.machine;
-        crate::pdb::arch_from_machine(machine.into())
+        Arch::from_pe_machine(machine).unwrap_or(Arch::Unknown)
     }
 
     /// The kind of this object, as specified in the PE header.
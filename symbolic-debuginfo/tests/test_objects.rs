@@ -1,7 +1,11 @@
-use std::{ffi::CString, fmt};
+use std::{convert::TryInto, ffi::CString, fmt};
 
 use symbolic_common::ByteView;
-use symbolic_debuginfo::{elf::ElfObject, FileEntry, Function, Object, SymbolMap};
+use symbolic_debuginfo::{
+    dwarf::{Dwarf, DwarfSeenFunctions},
+    elf::ElfObject,
+    FileEntry, FileFormat, Function, Object, ObjectDebugSession, Section, SymbolMap,
+};
 use symbolic_testutils::fixture;
 
 use similar_asserts::assert_eq;
@@ -26,6 +30,31 @@ impl fmt::Debug for SymbolsDebug<'_> {
     }
 }
 
+/// Helper to create neat snapshots for section lists.
+struct SectionsDebug<'a>(&'a [Section<'a>]);
+
+impl fmt::Debug for SectionsDebug<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in self.0 {
+            let offset = match section.file_range() {
+                Some(range) => format!("{:x}", range.start),
+                None => "-".into(),
+            };
+
+            writeln!(
+                f,
+                "{:>16x} {:>8x} {:>8} {}",
+                section.address(),
+                section.size(),
+                offset,
+                section.name()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Helper to create neat snapshots for file lists.
 struct FilesDebug<'a>(&'a [FileEntry<'a>]);
 
@@ -75,6 +104,34 @@ impl fmt::Debug for FunctionsDebug<'_> {
     }
 }
 
+#[test]
+fn test_open() -> Result<(), Error> {
+    let path = fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash");
+    let owned = Object::open(&path)?;
+    let object = owned.get();
+    assert_eq!(object.file_format(), FileFormat::MachO);
+    assert!(object.has_debug_info());
+
+    let session = object.debug_session()?;
+    let function = session
+        .functions()
+        .filter_map(|f| f.ok())
+        .find(|f| !f.lines.is_empty())
+        .expect("function with line records");
+
+    let locations = object.lookup(function.address)?;
+    let outermost = locations.last().expect("source location");
+    assert_eq!(outermost.function_name(), function.name.as_str());
+    assert_eq!(outermost.function_address(), Some(function.address));
+
+    let view = ByteView::open(&path)?;
+    let parsed = Object::parse(&view)?;
+    assert_eq!(parsed.lookup(function.address)?, locations);
+    assert_eq!(owned.byteview().as_slice(), &view[..]);
+
+    Ok(())
+}
+
 #[test]
 fn test_breakpad() -> Result<(), Error> {
     // Using the windows version here since it contains all record kinds
@@ -209,6 +266,25 @@ fn test_elf_symbols() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+#[cfg(feature = "minidebuginfo")]
+fn test_elf_mini_debug_info() -> Result<(), Error> {
+    let view = ByteView::open(fixture("linux/minidebuginfo"))?;
+    let object = Object::parse(&view)?;
+
+    assert!(object.has_symbols());
+    let symbols = object.symbol_map();
+    let names: Vec<_> = symbols.iter().filter_map(|symbol| symbol.name()).collect();
+    assert!(names.contains(&"minidebuginfo_compute"));
+    assert!(names.contains(&"minidebuginfo_helper"));
+
+    // The embedded symbols are only decompressed once, but returned on every call.
+    let again = object.symbol_map();
+    assert_eq!(again.iter().count(), symbols.iter().count());
+
+    Ok(())
+}
+
 #[test]
 fn test_elf_files() -> Result<(), Error> {
     let view = ByteView::open(fixture("linux/crash.debug"))?;
@@ -234,6 +310,84 @@ fn test_elf_functions() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_elf_unit_functions() -> Result<(), Error> {
+    let view = ByteView::open(fixture("linux/crash.debug"))?;
+    let object = Object::parse(&view)?;
+
+    let session = match object.debug_session()? {
+        ObjectDebugSession::Dwarf(session) => session,
+        _ => panic!("expected a DWARF debug session"),
+    };
+
+    let functions = session.functions().collect::<Result<Vec<_>, _>>()?;
+
+    let mut seen = DwarfSeenFunctions::new();
+    let mut unit_functions = Vec::new();
+    for index in 0..session.unit_count() {
+        unit_functions.extend(session.unit_functions(index, &mut seen)?);
+    }
+
+    assert!(session.unit_count() > 1);
+    assert_eq!(
+        format!("{:?}", FunctionsDebug(&unit_functions, 0)),
+        format!("{:?}", FunctionsDebug(&functions, 0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_elf_dwarf5() -> Result<(), Error> {
+    let view4 = ByteView::open(fixture("linux/dwarf5/gen/lines-dwarf4"))?;
+    let object4 = Object::parse(&view4)?;
+    let session4 = object4.debug_session()?;
+
+    let view5 = ByteView::open(fixture("linux/dwarf5/gen/lines-dwarf5"))?;
+    let object5 = Object::parse(&view5)?;
+    let session5 = object5.debug_session()?;
+
+    let files4 = session4.files().collect::<Result<Vec<_>, _>>()?;
+    let files5 = session5.files().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        format!("{:?}", FilesDebug(&files5)),
+        format!("{:?}", FilesDebug(&files4))
+    );
+
+    let functions4 = session4.functions().collect::<Result<Vec<_>, _>>()?;
+    let functions5 = session5.functions().collect::<Result<Vec<_>, _>>()?;
+    assert!(!functions5.is_empty());
+    assert_eq!(
+        format!("{:?}", FunctionsDebug(&functions5, 0)),
+        format!("{:?}", FunctionsDebug(&functions4, 0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_elf_cold_fragments() -> Result<(), Error> {
+    let view = ByteView::open(fixture("linux/cold/gen/cold"))?;
+    let object = Object::parse(&view)?;
+
+    let session = object.debug_session()?;
+    let functions = session.functions().collect::<Result<Vec<_>, _>>()?;
+    let mut fragments: Vec<_> = functions
+        .iter()
+        .filter(|function| function.name == "process")
+        .map(|function| (function.address, function.size, function.entry_address))
+        .collect();
+    fragments.sort();
+
+    // The cold part precedes the hot part, which contains the entry point.
+    assert_eq!(
+        fragments,
+        [(0x107a, 0x21, Some(0x11a0)), (0x11a0, 0x11, None)]
+    );
+
+    Ok(())
+}
+
 fn elf_debug_crc() -> Result<u32, Error> {
     Ok(u32::from_str_radix(
         std::fs::read_to_string(fixture("linux/elf_debuglink/gen/debug_info.txt.crc"))?.trim(),
@@ -289,6 +443,90 @@ fn test_elf_debug_link_compressed() -> Result<(), Error> {
     check_debug_info("elf_with_compressed_debuglink", "debug_info.txt")
 }
 
+#[test]
+fn test_elf_sections() -> Result<(), Error> {
+    let view = ByteView::open(fixture("linux/crash"))?;
+    let object = Object::parse(&view)?;
+
+    let sections = object.sections().collect::<Result<Vec<_>, _>>()?;
+    insta::assert_debug_snapshot!("elf_sections", SectionsDebug(&sections));
+
+    Ok(())
+}
+
+#[test]
+fn test_elf_sections_overflow() -> Result<(), Error> {
+    let mut data = std::fs::read(fixture("linux/crash"))?;
+
+    // Move the first section past the end of the address space.
+    let shoff = u64::from_le_bytes(data[0x28..0x30].try_into()?) as usize;
+    let shentsize = u16::from_le_bytes(data[0x3a..0x3c].try_into()?) as usize;
+    let sh_offset = shoff + shentsize + 0x18;
+    data[sh_offset..sh_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    let object = Object::parse(&data)?;
+    let result = object.sections().collect::<Result<Vec<_>, _>>();
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_elf_shared_sections() -> Result<(), Error> {
+    let view = ByteView::open(fixture("linux/crash-zlib.debug"))?;
+    let object = ElfObject::parse(&view)?;
+
+    // Decompressed sections are shared while they are in use.
+    let first = object
+        .section_data("debug_info")
+        .expect("missing debug_info");
+    let second = object
+        .section_data("debug_info")
+        .expect("missing debug_info");
+    assert!(first.is_owned());
+    assert_eq!(first.as_ptr(), second.as_ptr());
+
+    let section = object.section("debug_info").expect("missing debug_info");
+    assert_eq!(&*first, &*section.data);
+
+    Ok(())
+}
+
+#[test]
+fn test_mach_sections() -> Result<(), Error> {
+    let view = ByteView::open(fixture("macos/crash"))?;
+    let object = Object::parse(&view)?;
+
+    let sections = object.sections().collect::<Result<Vec<_>, _>>()?;
+    let text = sections
+        .iter()
+        .find(|section| section.segment() == Some("__TEXT") && section.name() == "__text")
+        .expect("missing __text section");
+
+    assert!(text.size() > 0);
+    assert!(text.address() >= object.load_address());
+    let file_range = text
+        .file_range()
+        .expect("__text must be backed by file data");
+    assert_eq!(file_range.end - file_range.start, text.size());
+
+    Ok(())
+}
+
+#[test]
+fn test_elf_code_id() -> Result<(), Error> {
+    let executable_view = ByteView::open(fixture("linux/crash"))?;
+    let executable = Object::parse(&executable_view)?;
+    let debug_view = ByteView::open(fixture("linux/crash.debug"))?;
+    let debug = Object::parse(&debug_view)?;
+
+    let expected = Some("f1c3bcc0279865fe3058404b2831d9e64135386c".into());
+    assert_eq!(executable.code_id(), expected);
+    assert_eq!(debug.code_id(), expected);
+
+    Ok(())
+}
+
 #[test]
 fn test_mach_executable() -> Result<(), Error> {
     let view = ByteView::open(fixture("macos/crash"))?;
@@ -347,6 +585,20 @@ fn test_mach_dsym() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_mach_code_id() -> Result<(), Error> {
+    let executable_view = ByteView::open(fixture("macos/crash"))?;
+    let executable = Object::parse(&executable_view)?;
+    let dsym_view = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+    let dsym = Object::parse(&dsym_view)?;
+
+    let expected = Some("67e9247c814e392ba027dbde6748fcbf".into());
+    assert_eq!(executable.code_id(), expected);
+    assert_eq!(dsym.code_id(), expected);
+
+    Ok(())
+}
+
 #[test]
 fn test_mach_symbols() -> Result<(), Error> {
     let view = ByteView::open(fixture("macos/crash"))?;
@@ -447,6 +699,52 @@ fn test_pe_64() -> Result<(), Error> {
     Ok(())
 }
 
+/// Clears the debug data directory of a PE, removing the reference to its PDB.
+fn strip_pe_debug_directory(data: &mut [u8]) {
+    let read_u32 = |data: &[u8], offset: usize| {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+
+    // PE signature and COFF file header precede the optional header.
+    let optional_header = read_u32(data, 0x3c) as usize + 4 + 20;
+    let data_directories =
+        match u16::from_le_bytes([data[optional_header], data[optional_header + 1]]) {
+            0x10b => optional_header + 96,
+            0x20b => optional_header + 112,
+            magic => panic!("invalid optional header magic {:#x}", magic),
+        };
+
+    // The debug directory is the 7th data directory entry.
+    let debug_directory = data_directories + 6 * 8;
+    for byte in &mut data[debug_directory..debug_directory + 8] {
+        *byte = 0;
+    }
+}
+
+#[test]
+fn test_pe_debug_id_without_pdb() -> Result<(), Error> {
+    let mut data = std::fs::read(fixture("windows/crash.exe"))?;
+    strip_pe_debug_directory(&mut data);
+
+    let pe = match Object::parse(&data)? {
+        Object::Pe(pe) => pe,
+        object => panic!("expected a PE, got {:?}", object.file_format()),
+    };
+
+    assert_eq!(pe.debug_file_name(), None);
+    assert_eq!(
+        pe.debug_id().to_string(),
+        "5ab38077-0000-9000-0000-000000000000"
+    );
+
+    Ok(())
+}
+
 // NB: No test for PE symbols because our executable does not export any symbols
 // NB: No test for PE functions because we can only read debug info from PDBs
 
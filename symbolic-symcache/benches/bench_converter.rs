@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use symbolic_common::ByteView;
+use symbolic_debuginfo::Object;
+use symbolic_symcache::NewSymCacheConverter;
+use symbolic_testutils::fixture;
+
+fn convert(object: &Object<'_>, parallel: bool) -> Vec<u8> {
+    let mut converter = NewSymCacheConverter::new();
+    converter.set_arch(object.arch());
+    converter.set_debug_id(object.debug_id());
+    if parallel {
+        converter.process_object_parallel(object).expect("process");
+    } else {
+        converter.process_object(object).expect("process");
+    }
+
+    let mut buffer = Vec::new();
+    converter.serialize(&mut buffer).expect("serialize");
+    buffer
+}
+
+fn bench_convert(c: &mut Criterion, name: &str, path: &str) {
+    let buffer = ByteView::open(fixture(path)).expect("open");
+    let object = Object::parse(&buffer).expect("parse");
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("serial", |b| b.iter(|| convert(&object, false)));
+    group.bench_function("parallel", |b| b.iter(|| convert(&object, true)));
+    group.finish();
+}
+
+fn bench_convert_linux(c: &mut Criterion) {
+    bench_convert(c, "convert_linux", "linux/crash.debug");
+}
+
+fn bench_convert_macos(c: &mut Criterion) {
+    bench_convert(
+        c,
+        "convert_macos",
+        "macos/crash.dSYM/Contents/Resources/DWARF/crash",
+    );
+}
+
+criterion_group!(bench_converter, bench_convert_linux, bench_convert_macos);
+
+criterion_main!(bench_converter);
@@ -0,0 +1,66 @@
+//! Sweeps lookups over SymCaches in the new format.
+//!
+//! The `seeds` directory contains SymCaches converted from small test fixtures, with and without
+//! a name index. Pass it as a read-only corpus after the writable one:
+//!
+//! ```sh
+//! cargo fuzz run lookup corpus/lookup seeds
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use symbolic_symcache::NewSymCache;
+
+/// The maximum number of functions whose ranges and names are swept, to keep iterations fast.
+const MAX_FUNCTIONS: usize = 256;
+
+/// The maximum number of ranges swept per function.
+const MAX_RANGES: usize = 64;
+
+fn lookup_all(symcache: &NewSymCache<'_>, addr: u64) {
+    for source_location in symcache.lookup(addr) {
+        let _ = (
+            source_location.file().map(|file| file.full_path()),
+            source_location.function().map(|function| function.name()),
+            source_location.line(),
+            source_location.column(),
+        );
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let symcache = match NewSymCache::parse(data) {
+        Ok(symcache) => symcache,
+        Err(_) => return,
+    };
+
+    // Sweep the edges of the covered range, and of every range attributed to a function.
+    if let Some(range) = symcache.address_range() {
+        let edges = [
+            range.start.wrapping_sub(1),
+            range.start,
+            range.end.wrapping_sub(1),
+            range.end,
+        ];
+        for &addr in &edges {
+            lookup_all(&symcache, addr);
+        }
+    }
+    for function in symcache.functions().take(MAX_FUNCTIONS) {
+        for (start, end) in symcache.function_ranges(&function).take(MAX_RANGES) {
+            lookup_all(&symcache, start);
+            lookup_all(&symcache, end.wrapping_sub(1));
+            lookup_all(&symcache, end);
+        }
+        if let Some(name) = function.name() {
+            symcache
+                .lookup_by_name(name)
+                .take(MAX_FUNCTIONS)
+                .for_each(drop);
+        }
+    }
+
+    symcache.source_locations().for_each(drop);
+    let _ = symcache.validate();
+});
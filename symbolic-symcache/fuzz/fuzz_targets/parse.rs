@@ -0,0 +1,32 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use symbolic_symcache::{NewSymCache, SymCache};
+
+/// Addresses that exercise the edges of the covered range.
+const ADDRESSES: &[u64] = &[0, 1, 0x1000, u32::MAX as u64, u64::MAX];
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(symcache) = SymCache::parse(data) {
+        for function in symcache.functions() {
+            let _ = function.map(|function| function.address());
+        }
+        for &addr in ADDRESSES {
+            if let Ok(lookup) = symcache.lookup(addr) {
+                lookup.for_each(drop);
+            }
+        }
+    }
+
+    // The new format copies its records if the input is not aligned to eight bytes.
+    if let Ok(symcache) = NewSymCache::parse(data) {
+        symcache.functions().for_each(drop);
+        symcache.source_locations().for_each(drop);
+        let _ = symcache.address_range();
+        for &addr in ADDRESSES {
+            for source_location in symcache.lookup(addr) {
+                let _ = (source_location.file(), source_location.function());
+            }
+        }
+    }
+});
@@ -8,3 +8,13 @@ mod new;
 mod old;
 
 pub use old::*;
+
+/// The SymCache format that is still in development, exposed to the fuzz targets.
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub use new::SymCache as NewSymCache;
+
+/// The converter for the SymCache format that is still in development, exposed to the benchmarks.
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use new::SymCacheConverter as NewSymCacheConverter;
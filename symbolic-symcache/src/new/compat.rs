@@ -1,12 +1,21 @@
 //! Types & Definitions needed to keep compatibility with existing API
 
-use std::io::{Seek, Write};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::ops::{ControlFlow, Range};
+use std::path::Path;
 
-use symbolic_common::{Arch, DebugId};
-use symbolic_debuginfo::{Function as SymbolicFunction, ObjectLike, Symbol};
+use symbolic_common::{Arch, DebugId, Language};
+use symbolic_debuginfo::breakpad::BreakpadObject;
+use symbolic_debuginfo::{Function as SymbolicFunction, Object, ObjectLike, Symbol};
+use tempfile::NamedTempFile;
 
-use super::writer::SymCacheConverter;
+use super::writer::{Progress, SymCacheConverter, WriteStats, WriterReport};
 use super::*;
+use crate::old::SymCache as LegacySymCache;
 use crate::{SymCacheError, SymCacheErrorKind};
 
 impl<'data> SymCache<'data> {
@@ -28,6 +37,41 @@ impl<'data> SymCache<'data> {
         }
     }
 
+    /// An iterator over the functions in this SymCache, ordered by their [entry
+    /// pc](Function::entry_pc).
+    ///
+    /// This walks the range table, which is sorted by address, and yields every function at the
+    /// range starting at its entry pc, without collecting all functions first. Inlined functions
+    /// do not have an entry pc and are excluded. So are functions whose entry pc is not the start
+    /// of a range where they are the outermost function, such as functions completely overlapped
+    /// by another function.
+    ///
+    /// Top-level functions whose entry pc does not fit into 32 bits, which only occurs in caches
+    /// with wide ranges, are yielded once at the first range where they are the outermost
+    /// function instead.
+    pub fn functions_by_address(&self) -> FunctionsByAddressIter<'data, '_> {
+        FunctionsByAddressIter {
+            cache: self,
+            range_idx: 0,
+            yielded: HashSet::new(),
+        }
+    }
+
+    /// An iterator over the functions in this SymCache whose name matches `pattern`.
+    ///
+    /// The pattern is a simple glob, where `*` matches any sequence of characters and `?` matches
+    /// a single character. A pattern without any wildcards matches all names starting with it.
+    /// Both the [name](Function::name) and the [plain name](Function::plain_name) of each
+    /// function are matched as stored in the SymCache. Names are not demangled first, so patterns
+    /// for symbols that have no debug information need to match the mangled name.
+    pub fn functions_matching<'p>(&self, pattern: &'p str) -> MatchingFunctionIter<'data, '_, 'p> {
+        MatchingFunctionIter {
+            functions: self.functions(),
+            pattern,
+            is_glob: pattern.contains(|c| c == '*' || c == '?'),
+        }
+    }
+
     /// An iterator over the files in this SymCache.
     pub fn files(&self) -> FileIter<'data, '_> {
         FileIter {
@@ -44,7 +88,7 @@ pub struct FileIter<'data, 'cache> {
 }
 
 impl<'data, 'cache> Iterator for FileIter<'data, 'cache> {
-    type Item = File<'data>;
+    type Item = File<'cache>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.cache.get_file(self.file_idx).map(|file| {
@@ -61,7 +105,7 @@ pub struct FunctionIter<'data, 'cache> {
 }
 
 impl<'data, 'cache> Iterator for FunctionIter<'data, 'cache> {
-    type Item = Function<'data>;
+    type Item = Function<'cache>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.cache.get_function(self.function_idx).map(|file| {
@@ -71,6 +115,181 @@ impl<'data, 'cache> Iterator for FunctionIter<'data, 'cache> {
     }
 }
 
+/// An iterator over functions in address order.
+///
+/// Created via [`SymCache::functions_by_address`].
+#[derive(Debug, Clone)]
+pub struct FunctionsByAddressIter<'data, 'cache> {
+    cache: &'cache SymCache<'data>,
+    range_idx: usize,
+    /// Functions without an entry pc that have been yielded already.
+    yielded: HashSet<u32>,
+}
+
+impl<'data, 'cache> Iterator for FunctionsByAddressIter<'data, 'cache> {
+    type Item = Function<'cache>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cache = self.cache;
+        while let Some(start) = cache.ranges.get(self.range_idx) {
+            let outermost = cache.source_locations_at(Some(self.range_idx)).last();
+            self.range_idx += 1;
+
+            let outermost = match outermost {
+                Some(outermost) => outermost,
+                None => continue,
+            };
+            let function = match outermost.function() {
+                Some(function) => function,
+                None => continue,
+            };
+
+            if function.entry_pc() == u32::MAX {
+                // The entry pc was dropped because it did not fit into 32 bits.
+                if cache.features().has_wide_ranges()
+                    && self.yielded.insert(outermost.source_location.function_idx)
+                {
+                    return Some(function);
+                }
+            } else if u64::from(function.entry_pc()) == start {
+                return Some(function);
+            }
+        }
+
+        None
+    }
+}
+
+/// An iterator over the functions matching a pattern.
+///
+/// Created via [`SymCache::functions_matching`].
+#[derive(Debug, Clone)]
+pub struct MatchingFunctionIter<'data, 'cache, 'p> {
+    functions: FunctionIter<'data, 'cache>,
+    pattern: &'p str,
+    is_glob: bool,
+}
+
+impl<'data, 'cache, 'p> Iterator for MatchingFunctionIter<'data, 'cache, 'p> {
+    type Item = Function<'cache>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pattern, is_glob) = (self.pattern, self.is_glob);
+        let matches = |name: &str| {
+            if is_glob {
+                glob_match(pattern, name)
+            } else {
+                name.starts_with(pattern)
+            }
+        };
+
+        self.functions.find(|function| {
+            function.name().map_or(false, matches) || function.plain_name().map_or(false, matches)
+        })
+    }
+}
+
+/// Checks whether `name` matches the glob `pattern` in its entirety.
+///
+/// `*` matches any sequence of characters, including an empty one, and `?` matches exactly one
+/// character. All other characters match themselves.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (mut pattern_rest, mut name_rest) = (pattern, name);
+    // The pattern after the last `*`, and the part of the name it is currently matched against.
+    let mut backtrack = None;
+
+    loop {
+        let mut pattern_chars = pattern_rest.chars();
+        match pattern_chars.next() {
+            Some('*') => {
+                pattern_rest = pattern_chars.as_str();
+                backtrack = Some((pattern_rest, name_rest));
+                continue;
+            }
+            Some(expected) => {
+                let mut name_chars = name_rest.chars();
+                if let Some(actual) = name_chars.next() {
+                    if expected == '?' || expected == actual {
+                        pattern_rest = pattern_chars.as_str();
+                        name_rest = name_chars.as_str();
+                        continue;
+                    }
+                }
+            }
+            None if name_rest.is_empty() => return true,
+            None => {}
+        }
+
+        // On a mismatch, let the last `*` consume one more character and try again.
+        let (star_pattern, star_name) = match backtrack {
+            Some(backtrack) => backtrack,
+            None => return false,
+        };
+        let mut star_chars = star_name.chars();
+        if star_chars.next().is_none() {
+            return false;
+        }
+        pattern_rest = star_pattern;
+        name_rest = star_chars.as_str();
+        backtrack = Some((pattern_rest, name_rest));
+    }
+}
+
+/// Options for [`SymCacheWriter::write_object_with_options`].
+///
+/// The default options write a complete SymCache and keep all strings in memory.
+#[derive(Default)]
+pub struct WriteOptions<'a> {
+    names_only: bool,
+    temp_dir: Option<(&'a Path, usize)>,
+    progress: Option<&'a mut dyn FnMut(Progress) -> ControlFlow<()>>,
+}
+
+impl<'a> WriteOptions<'a> {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Determines whether only function names are written.
+    ///
+    /// This omits all files, line numbers and inlined functions, and is considerably smaller than
+    /// a complete SymCache. Lookups return the name of the outermost function only. See
+    /// [`SymCacheConverter::set_names_only`] for more information.
+    pub fn names_only(mut self, names_only: bool) -> Self {
+        self.names_only = names_only;
+        self
+    }
+
+    /// Keeps the memory used for strings bounded.
+    ///
+    /// String data exceeding `memory_budget` bytes is moved to a temporary file in `temp_dir`.
+    /// See [`SymCacheConverter::set_memory_budget`] for more information.
+    pub fn temp_dir(mut self, temp_dir: &'a Path, memory_budget: usize) -> Self {
+        self.temp_dir = Some((temp_dir, memory_budget));
+        self
+    }
+
+    /// Reports [`Progress`] along the way.
+    ///
+    /// The callback is invoked after each top-level function has been processed. Returning
+    /// [`ControlFlow::Break`] cancels the conversion.
+    pub fn progress(mut self, progress: &'a mut dyn FnMut(Progress) -> ControlFlow<()>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+}
+
+impl fmt::Debug for WriteOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteOptions")
+            .field("names_only", &self.names_only)
+            .field("temp_dir", &self.temp_dir)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
 /// A high level writer that can construct SymCaches.
 ///
 /// When using this writer directly, make sure to call [`finish`](SymCacheWriter::finish)
@@ -90,8 +309,34 @@ where
     ///
     /// Any object which implements [`ObjectLike`] can be written into a
     /// [`SymCache`](crate::SymCache) by this function.  This already implicitly
-    /// calls [`SymCacheWriter::finish`], thus consuming the writer.
+    /// calls [`SymCacheWriter::finish`], thus consuming the writer. See
+    /// [`write_object_with_options`](Self::write_object_with_options) for more control over the
+    /// conversion.
     pub fn write_object<'d, 'o, O>(object: &'o O, target: W) -> Result<W, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let (writer, _, _) = Self::write_object_with_options(object, target, WriteOptions::new())?;
+        Ok(writer)
+    }
+
+    /// Converts an entire object into a SymCache with the given [`WriteOptions`].
+    ///
+    /// Returns a [`WriterReport`] of all units and functions that had to be skipped, which gives
+    /// callers the chance to log them, along with [`WriteStats`] on the written tables. The
+    /// statistics are collected while writing, so there is no need to parse the SymCache again to
+    /// obtain its size.
+    ///
+    /// If the [progress callback](WriteOptions::progress) returns [`ControlFlow::Break`],
+    /// conversion is aborted and an error of kind [`SymCacheErrorKind::Cancelled`] is returned.
+    /// Since the SymCache is only serialized once the entire object has been processed, nothing is
+    /// written to `target` in that case.
+    pub fn write_object_with_options<'d, 'o, O>(
+        object: &'o O,
+        target: W,
+        options: WriteOptions<'_>,
+    ) -> Result<(W, WriterReport, WriteStats), SymCacheError>
     where
         O: ObjectLike<'d, 'o>,
         O::Error: std::error::Error + Send + Sync + 'static,
@@ -100,8 +345,62 @@ where
 
         converter.set_arch(object.arch());
         converter.set_debug_id(object.debug_id());
+        converter.set_names_only(options.names_only);
+        if let Some((temp_dir, memory_budget)) = options.temp_dir {
+            converter.set_memory_budget(memory_budget);
+            converter.set_temp_dir(temp_dir);
+        }
 
-        converter.process_object(object)?;
+        let report = match options.progress {
+            Some(progress) => converter.process_object_with_progress(object, progress)?,
+            None => converter.process_object(object)?,
+        };
+
+        let (writer, stats) = Self {
+            converter,
+            writer: target,
+        }
+        .finish_with_stats()?;
+
+        Ok((writer, report, stats))
+    }
+
+    /// Converts an object built with split DWARF into a SymCache.
+    ///
+    /// The `supplement` is the `.dwo` object or `.dwp` package containing the debug information
+    /// of the skeleton units in `object`. See
+    /// [`SymCacheConverter::process_object_with_dwarf_supplement`] for more information.
+    pub fn write_object_with_dwarf_supplement<'d>(
+        object: &Object<'d>,
+        supplement: &Object<'d>,
+        target: W,
+    ) -> Result<W, SymCacheError> {
+        let mut converter = SymCacheConverter::new();
+
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+
+        converter.process_object_with_dwarf_supplement(object, supplement)?;
+
+        Self {
+            converter,
+            writer: target,
+        }
+        .finish()
+    }
+
+    /// Converts a Breakpad object into a SymCache.
+    ///
+    /// The output is identical to [`write_object`](Self::write_object), but the conversion reads
+    /// Breakpad records directly. See [`SymCacheConverter::process_breakpad`] for more
+    /// information.
+    pub fn write_breakpad(object: &BreakpadObject<'_>, target: W) -> Result<W, SymCacheError> {
+        let mut converter = SymCacheConverter::new();
+
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+
+        converter.process_breakpad(object)?;
 
         Self {
             converter,
@@ -110,6 +409,41 @@ where
         .finish()
     }
 
+    /// Writes a new SymCache with the contents of `existing` and the functions of `object`.
+    ///
+    /// The new SymCache keeps the architecture, debug identifier and range offset of `existing`.
+    /// Where functions of `object` overlap with code of `existing`, the object takes precedence and
+    /// a warning is added to the returned [`WriterReport`]. See
+    /// [`SymCacheConverter::extend_object`] for more information.
+    ///
+    /// This only supports the new SymCache format, which is not yet exposed by this crate. The
+    /// public writer of the current format cannot extend existing SymCaches.
+    pub fn extend<'d, 'o, O>(
+        existing: &SymCache<'_>,
+        object: &'o O,
+        target: W,
+    ) -> Result<(W, WriterReport), SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let mut converter = SymCacheConverter::new();
+
+        converter.set_arch(existing.arch());
+        converter.set_debug_id(existing.debug_id());
+        converter.set_range_threshold(existing.header.range_offset);
+
+        let report = converter.extend_object(existing, object)?;
+
+        let writer = Self {
+            converter,
+            writer: target,
+        }
+        .finish()?;
+
+        Ok((writer, report))
+    }
+
     /// Constructs a new `SymCacheWriter` and writes the preamble.
     pub fn new(writer: W) -> Result<Self, SymCacheError> {
         Ok(SymCacheWriter {
@@ -128,6 +462,38 @@ where
         self.converter.set_debug_id(debug_id)
     }
 
+    /// Sets the minimum address of all ranges in this SymCache.
+    ///
+    /// See [`SymCacheConverter::set_range_threshold`] for more information.
+    pub fn set_range_threshold(&mut self, threshold: u64) {
+        self.converter.set_range_threshold(threshold)
+    }
+
+    /// Restricts this SymCache to the given address range.
+    ///
+    /// Only functions and symbols that intersect `range` are added. See
+    /// [`SymCacheConverter::set_address_range`] for more information.
+    pub fn set_address_range(&mut self, range: Range<u64>) {
+        self.converter.set_address_range(range)
+    }
+
+    /// Sets a function that transforms function and symbol names before they are added.
+    ///
+    /// See [`SymCacheConverter::set_name_transformer`] for more information.
+    pub fn set_name_transformer<F>(&mut self, transformer: F)
+    where
+        F: FnMut(&str, Language) -> Option<Cow<'_, str>> + 'static,
+    {
+        self.converter.set_name_transformer(transformer)
+    }
+
+    /// Removes a prefix from all paths before they are added.
+    ///
+    /// See [`SymCacheConverter::strip_path_prefix`] for more information.
+    pub fn strip_path_prefix(&mut self, prefix: &str) {
+        self.converter.strip_path_prefix(prefix)
+    }
+
     /// Adds a new symbol to this SymCache.
     ///
     /// Symbols **must** be added in ascending order using this method. This will emit a function
@@ -150,13 +516,666 @@ where
 
     /// Persists all open segments to the writer and fixes up the header.
     pub fn finish(self) -> Result<W, SymCacheError> {
+        let (writer, _) = self.finish_with_stats()?;
+        Ok(writer)
+    }
+
+    /// Like [`finish`](Self::finish), but also returns [`WriteStats`] on the written tables.
+    pub fn finish_with_stats(self) -> Result<(W, WriteStats), SymCacheError> {
         let SymCacheWriter {
             converter,
             mut writer,
         } = self;
-        converter
+        converter.check_limits()?;
+        let stats = converter
             .serialize(&mut writer)
             .map_err(|err| SymCacheError::new(SymCacheErrorKind::WriteFailed, err))?;
-        Ok(writer)
+        Ok((writer, stats))
+    }
+}
+
+/// The size of the buffer used by [`SymCacheWriter::write_object_to_path`].
+const FILE_BUFFER_SIZE: usize = 1024 * 1024;
+
+impl SymCacheWriter<fs::File> {
+    /// Converts an entire object into a SymCache file at `path`, returning its size in bytes.
+    ///
+    /// The SymCache is written to a temporary file in the same directory once the object has been
+    /// converted. The file is preallocated to the size of the SymCache, written through a large
+    /// buffer, and synced to disk before it replaces the file at `path`. If writing fails, the
+    /// temporary file is removed and an existing file at `path` is left untouched, so that no
+    /// truncated SymCache with a valid header is left behind.
+    pub fn write_object_to_path<'d, 'o, O>(object: &'o O, path: &Path) -> Result<u64, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let mut converter = SymCacheConverter::new();
+
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+
+        converter.process_object(object)?;
+        converter.check_limits()?;
+
+        write_to_path(converter, path)
+            .map_err(|err| SymCacheError::new(SymCacheErrorKind::WriteFailed, err))
+    }
+}
+
+/// Serializes the converter into a file at `path`, returning the number of bytes written.
+///
+/// The file is written next to `path` and only moved into place once it is complete. The
+/// temporary file is removed when it is dropped on error.
+fn write_to_path(converter: SymCacheConverter, path: &Path) -> io::Result<u64> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let temp_file = NamedTempFile::new_in(dir)?;
+    temp_file
+        .as_file()
+        .set_len(converter.serialized_size_hint())?;
+
+    let mut writer = BufWriter::with_capacity(FILE_BUFFER_SIZE, temp_file);
+    converter.serialize(&mut writer)?;
+    let mut temp_file = writer.into_inner()?;
+
+    // The size hint is an upper bound, so cut off any preallocated space that was not written.
+    let size = temp_file.seek(SeekFrom::Current(0))?;
+    temp_file.as_file().set_len(size)?;
+    temp_file.as_file().sync_all()?;
+    temp_file.persist(path).map_err(|err| err.error)?;
+
+    Ok(size)
+}
+
+/// Converts a SymCache in the legacy format into the current format.
+///
+/// This allows migrating stored SymCaches without the debug files they were written from. The
+/// converted SymCache resolves every address to the same functions, files and line numbers as the
+/// legacy SymCache, but only contains information present in the legacy format. In particular, it
+/// does not contain line addresses, and SymCaches written by old versions may lack compilation
+/// directories and inlined functions.
+pub fn convert<W>(old: &[u8], writer: W) -> Result<W, SymCacheError>
+where
+    W: Write + Seek,
+{
+    let symcache = LegacySymCache::parse(old)?;
+    let mut converter = SymCacheConverter::new();
+
+    converter.set_arch(symcache.arch());
+    converter.set_debug_id(symcache.debug_id());
+
+    converter.process_legacy_symcache(&symcache)?;
+
+    SymCacheWriter { converter, writer }.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io::Cursor;
+
+    use symbolic_common::ByteView;
+    use symbolic_debuginfo::Object;
+    use symbolic_testutils::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_finish_with_stats() -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = Vec::new();
+        let mut writer = SymCacheWriter::new(Cursor::new(&mut buffer))?;
+        writer.set_range_threshold(0x1000);
+        for &(name, address) in &[
+            ("below", 0x800),
+            ("a", 0x1000),
+            ("b", 0x2000),
+            ("a", 0x3000),
+        ] {
+            writer.add_symbol(Symbol {
+                name: Some(name.into()),
+                address,
+                size: 0x10,
+            })?;
+        }
+        let (_, stats) = writer.finish_with_stats()?;
+
+        // The symbol below the range threshold is skipped before its name is added. Both symbols
+        // named `a` share their name, but are distinct functions with their own range.
+        assert_eq!(stats.strings_total, 3);
+        assert_eq!(stats.strings_unique, 2);
+        assert_eq!(stats.functions_total, 3);
+        assert_eq!(stats.functions_unique, 3);
+        assert_eq!(stats.files, 0);
+        assert_eq!(stats.source_locations, 3);
+        assert_eq!(stats.ranges, 3);
+        assert_eq!(stats.string_bytes, 2);
+        assert_eq!(stats.source_bytes, 0);
+        assert_eq!(stats.records_skipped, 1);
+        assert_eq!(stats.total_bytes, buffer.len() as u64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_object_to_path() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("crash.symc");
+        let size = SymCacheWriter::write_object_to_path(&object, &path)?;
+
+        let expected = SymCacheWriter::write_object(&object, Cursor::new(Vec::new()))?.into_inner();
+        let written = ByteView::open(&path)?;
+        assert_eq!(size, expected.len() as u64);
+        assert_eq!(&*written, &expected[..]);
+        SymCache::parse(&written)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_object_to_path_failed() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("missing").join("crash.symc");
+        let error = SymCacheWriter::write_object_to_path(&object, &path).unwrap_err();
+        assert_eq!(error.kind(), SymCacheErrorKind::WriteFailed);
+        assert!(!path.exists());
+
+        // A directory cannot be replaced by the SymCache. It is left as is, and the temporary
+        // file is removed.
+        let path = temp_dir.path().join("crash.symc");
+        fs::create_dir(&path)?;
+        fs::write(path.join("contents"), b"contents")?;
+        let error = SymCacheWriter::write_object_to_path(&object, &path).unwrap_err();
+        assert_eq!(error.kind(), SymCacheErrorKind::WriteFailed);
+        assert_eq!(fs::read(path.join("contents"))?, b"contents");
+        assert_eq!(fs::read_dir(temp_dir.path())?.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_object_to_path_replace() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        // An existing file is replaced only once the new SymCache is complete.
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("crash.symc");
+        fs::write(&path, b"outdated")?;
+        let size = SymCacheWriter::write_object_to_path(&object, &path)?;
+        assert_eq!(fs::metadata(&path)?.len(), size);
+        SymCache::parse(&fs::read(&path)?)?;
+        assert_eq!(fs::read_dir(temp_dir.path())?.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_breakpad() -> Result<(), Box<dyn std::error::Error>> {
+        let inlinees = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 crash
+FILE 0 /src/main.cpp
+FILE 1 /src/util.h
+INLINE_ORIGIN 0 outer()
+FUNC 1000 30 0 main
+INLINE 0 10 0 0 1010 10
+1000 10 5 0
+1010 20 30 1
+FUNC 1030 10 0 helper
+1030 8 7 1
+1038 8 8 2
+PUBLIC 1040 0 public_symbol
+";
+        // The size of this function exceeds the address space.
+        let overflow = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 crash
+FILE 0 /src/main.cpp
+FUNC ffffffffffffff00 200 0 overflow
+ffffffffffffff00 10 3 0
+";
+
+        let fixtures = [
+            ByteView::open(fixture("linux/crash.sym"))?,
+            ByteView::open(fixture("macos/crash.sym"))?,
+            ByteView::open(fixture("windows/crash.sym"))?,
+            ByteView::from_slice(inlinees),
+            ByteView::from_slice(overflow),
+        ];
+
+        for buffer in &fixtures {
+            let object = BreakpadObject::parse(buffer)?;
+            let generic = SymCacheWriter::write_object(&object, Cursor::new(Vec::new()))?;
+            let direct = SymCacheWriter::write_breakpad(&object, Cursor::new(Vec::new()))?;
+            let (generic, direct) = (generic.into_inner(), direct.into_inner());
+
+            assert_eq!(direct, generic);
+
+            let symcache = SymCache::parse(&direct)?;
+            symcache.validate()?;
+            assert!(symcache.functions().next().is_some());
+        }
+
+        Ok(())
+    }
+
+    fn names<'a>(symcache: &'a SymCache<'_>, addr: u64) -> Vec<Option<&'a str>> {
+        symcache
+            .lookup(addr)
+            .map(|sl| sl.function().and_then(|f| f.name()))
+            .collect()
+    }
+
+    #[test]
+    fn test_extend() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+        let host = SymCacheWriter::write_object(&object, Cursor::new(Vec::new()))?.into_inner();
+        let host = SymCache::parse(&host)?;
+
+        let plugin = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 plugin
+FILE 0 /src/plugin.cpp
+FUNC 100000 20 0 plugin_entry
+100000 20 12 0
+FUNC 1c70 8 0 plugin_hook
+1c70 8 3 0
+";
+        let plugin = BreakpadObject::parse(plugin)?;
+
+        let (buffer, report) = SymCacheWriter::extend(&host, &plugin, Cursor::new(Vec::new()))?;
+        let buffer = buffer.into_inner();
+        let extended = SymCache::parse(&buffer)?;
+        extended.validate()?;
+
+        assert_eq!(extended.arch(), host.arch());
+        assert_eq!(extended.debug_id(), host.debug_id());
+
+        // Host code is resolved as before, including right after the overwritten function.
+        for &addr in &[0x1c78, 0x1dc0, 0x1df0] {
+            assert!(!names(&host, addr).is_empty());
+            assert_eq!(names(&extended, addr), names(&host, addr));
+        }
+
+        let sl = extended.lookup(0x100010).next().unwrap();
+        assert_eq!(sl.function().and_then(|f| f.name()), Some("plugin_entry"));
+        assert_eq!(sl.line(), Some(12));
+        assert_eq!(names(&extended, 0x1c70), vec![Some("plugin_hook")]);
+        assert_eq!(names(&extended, 0x100020), names(&host, 0x100020));
+
+        assert!(report.warnings.iter().any(|warning| matches!(
+            warning
+                .error
+                .source()
+                .and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::AddressCollision(0x1c70))
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_with_progress() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut calls = 0;
+        let mut last = None;
+        let mut progress = |p: Progress| {
+            calls += 1;
+            last = Some(p);
+            ControlFlow::Continue(())
+        };
+        let options = WriteOptions::new().progress(&mut progress);
+        let mut buffer = Vec::new();
+        SymCacheWriter::write_object_with_options(&object, Cursor::new(&mut buffer), options)?;
+
+        assert!(calls > 0);
+        assert_eq!(last.unwrap().functions_processed, calls);
+        SymCache::parse(&buffer)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_cancelled() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut calls = 0;
+        let mut progress = |p: Progress| {
+            calls += 1;
+            if p.functions_processed == 10 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        };
+        let options = WriteOptions::new().progress(&mut progress);
+        let mut buffer = Vec::new();
+        let result =
+            SymCacheWriter::write_object_with_options(&object, Cursor::new(&mut buffer), options);
+
+        let error = result.err().expect("writing should be cancelled");
+        assert_eq!(error.kind(), SymCacheErrorKind::Cancelled);
+        assert_eq!(calls, 10);
+        assert!(buffer.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_names_only() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut full_buffer = Vec::new();
+        SymCacheWriter::write_object(&object, Cursor::new(&mut full_buffer))?;
+        let full = SymCache::parse(&full_buffer)?;
+
+        let mut names_buffer = Vec::new();
+        let options = WriteOptions::new().names_only(true);
+        SymCacheWriter::write_object_with_options(
+            &object,
+            Cursor::new(&mut names_buffer),
+            options,
+        )?;
+        let names_only = SymCache::parse(&names_buffer)?;
+
+        assert!(names_buffer.len() < full_buffer.len());
+        assert!(names_only.header.num_ranges < full.header.num_ranges);
+        assert_eq!(names_only.header.num_files, 0);
+        assert!(names_only.features().is_names_only());
+        assert!(!names_only.features().has_source());
+        assert!(!names_only.features().has_inline());
+        assert!(!names_only.has_file_info());
+
+        for range in full.ranges.iter() {
+            let addr = full.header.range_offset + range;
+            let expected = full.lookup(addr).last().map(|sl| sl.function());
+            let mut source_locations = names_only.lookup(addr);
+            let actual = source_locations.next().map(|sl| sl.function());
+            assert!(source_locations.next().is_none());
+            assert_eq!(
+                actual.flatten().and_then(|f| f.name()),
+                expected.flatten().and_then(|f| f.name()),
+                "{:#x}",
+                addr
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_legacy() -> Result<(), Box<dyn std::error::Error>> {
+        type Frame = (Option<String>, Option<u64>, u32, String);
+
+        for path in &["symcache/compat/v1.symc", "symcache/current/linux.symc"] {
+            let buffer = ByteView::open(fixture(path))?;
+            let legacy = LegacySymCache::parse(&buffer)?;
+
+            let converted = convert(&buffer, Cursor::new(Vec::new()))?.into_inner();
+            let symcache = SymCache::parse(&converted)?;
+            symcache.validate()?;
+            assert_eq!(symcache.arch(), legacy.arch());
+            assert_eq!(symcache.debug_id(), legacy.debug_id());
+
+            let mut addresses = Vec::new();
+            for function in legacy.functions() {
+                let function = function?;
+                let start = function.address();
+                addresses.extend(&[start, start + 1, function.end_address().wrapping_sub(1)]);
+                for line in function.lines() {
+                    let address = start + line?.address();
+                    addresses.extend(&[address.saturating_sub(1), address, address + 1]);
+                }
+            }
+
+            for addr in addresses {
+                let expected: Vec<Frame> = legacy
+                    .lookup(addr)?
+                    .map(|frame| {
+                        frame.map(|frame| {
+                            let address =
+                                Some(frame.function_address()).filter(|_| !frame.is_inlined());
+                            let symbol = frame.symbol().to_owned();
+                            (Some(symbol), address, frame.line(), frame.filename().into())
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                let actual: Vec<Frame> = symcache
+                    .lookup(addr)
+                    .map(|sl| {
+                        let function = sl.function().unwrap();
+                        let address =
+                            Some(u64::from(function.entry_pc())).filter(|_| !sl.is_inlined());
+                        let name = function.name().map(String::from);
+                        let file = sl.file().map_or("", |file| file.path_name());
+                        (name, address, sl.line().unwrap_or(0), file.into())
+                    })
+                    .collect();
+                assert_eq!(actual, expected, "{}: {:#x}", path, addr);
+            }
+        }
+
+        let error = convert(b"not a symcache", Cursor::new(Vec::new())).unwrap_err();
+        assert_eq!(error.kind(), SymCacheErrorKind::BadFileMagic);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_functions_by_address() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+        let mut buffer = Vec::new();
+        SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let addresses: Vec<_> = symcache
+            .functions_by_address()
+            .map(|function| function.entry_pc())
+            .collect();
+        assert!(!addresses.is_empty());
+        assert!(addresses.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let num_entries = symcache
+            .functions()
+            .filter(|function| function.entry_pc() != u32::MAX)
+            .count();
+        assert!(addresses.len() <= num_entries);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("foo", "foo"));
+        assert!(!glob_match("foo", "foobar"));
+        assert!(glob_match("foo*", "foobar"));
+        assert!(glob_match("*bar", "foobar"));
+        assert!(glob_match("f?o*r", "foobar"));
+        assert!(glob_match("*o*a*", "foobar"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+        assert!(!glob_match("*baz", "foobar"));
+        assert!(glob_match("std::?*::new", "std::vec::Vec<T>::new"));
+    }
+
+    #[test]
+    fn test_functions_matching() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+        let mut buffer = Vec::new();
+        SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let names = |functions: &mut dyn Iterator<Item = Function<'_>>| -> Vec<String> {
+            functions
+                .filter_map(|function| function.name().map(str::to_owned))
+                .collect()
+        };
+
+        // Linkage names are matched as stored, which is mangled for this fixture.
+        let prefix = "_ZN15google_breakpad16ExceptionHandler";
+        let expected: Vec<_> = names(&mut symcache.functions())
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        assert!(!expected.is_empty());
+        assert!(expected.len() < symcache.functions().count());
+        assert_eq!(names(&mut symcache.functions_matching(prefix)), expected);
+
+        // Destructors of all classes in the `google_breakpad` namespace, such as `D2Ev`.
+        let is_destructor = |name: &str| {
+            let bytes = name.as_bytes();
+            name.ends_with("Ev") && bytes.len() > 4 && bytes[bytes.len() - 4] == b'D'
+        };
+        let expected: Vec<_> = names(&mut symcache.functions())
+            .into_iter()
+            .filter(|name| name.starts_with("_ZN15google_breakpad") && is_destructor(name))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(
+            names(&mut symcache.functions_matching("_ZN15google_breakpad*D?Ev")),
+            expected
+        );
+
+        Ok(())
+    }
+
+    fn debug_function_names(buffer: &[u8]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let symcache = SymCache::parse(buffer)?;
+        Ok(symcache
+            .functions()
+            .filter(|function| function.language() != Language::Unknown)
+            .filter_map(|function| function.name().map(str::to_owned))
+            .collect())
+    }
+
+    #[test]
+    fn test_write_with_report() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+        let mut good_buffer = Vec::new();
+        let (_, report, _) = SymCacheWriter::write_object_with_options(
+            &object,
+            Cursor::new(&mut good_buffer),
+            WriteOptions::new(),
+        )?;
+        assert!(report.warnings.is_empty());
+
+        // The abbreviation offset of the unit at 0x2519d points outside of `.debug_abbrev`.
+        let buffer = ByteView::open(fixture("linux/crash-corrupt-cu.debug"))?;
+        let object = Object::parse(&buffer)?;
+        let mut buffer = Vec::new();
+        let (_, report, _) = SymCacheWriter::write_object_with_options(
+            &object,
+            Cursor::new(&mut buffer),
+            WriteOptions::new(),
+        )?;
+
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].unit_offset, Some(0x2519d));
+        assert_eq!(
+            report.warnings[0].error.kind(),
+            SymCacheErrorKind::BadDebugFile
+        );
+
+        let good_names = debug_function_names(&good_buffer)?;
+        let names = debug_function_names(&buffer)?;
+        assert!(!names.is_empty());
+        assert!(names.len() < good_names.len());
+        assert!(names.iter().all(|name| good_names.contains(name)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_without_split_units() -> Result<(), Box<dyn std::error::Error>> {
+        // Without skeleton units, the supplement must not change the output.
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut expected = Vec::new();
+        SymCacheWriter::write_object(&object, Cursor::new(&mut expected))?;
+
+        let mut actual = Vec::new();
+        SymCacheWriter::write_object_with_dwarf_supplement(
+            &object,
+            &object,
+            Cursor::new(&mut actual),
+        )?;
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_split_dwarf() -> Result<(), Box<dyn std::error::Error>> {
+        let write =
+            |path: &str, supplement: Option<&str>| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+                // The supplement is opened first, since it must outlive the object.
+                let supplement = supplement
+                    .map(|supplement| ByteView::open(fixture(supplement)))
+                    .transpose()?;
+                let buffer = ByteView::open(fixture(path))?;
+                let object = Object::parse(&buffer)?;
+
+                let mut symcache = Vec::new();
+                match supplement {
+                    Some(ref buffer) => {
+                        let supplement = Object::parse(buffer)?;
+                        SymCacheWriter::write_object_with_dwarf_supplement(
+                            &object,
+                            &supplement,
+                            Cursor::new(&mut symcache),
+                        )?;
+                    }
+                    None => {
+                        SymCacheWriter::write_object(&object, Cursor::new(&mut symcache))?;
+                    }
+                }
+                Ok(symcache)
+            };
+
+        // Symbolicates every instruction of `sum_of_squares` and `main`.
+        let symbolicate = |buffer: &[u8]| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+            let symcache = SymCache::parse(buffer)?;
+            (0x1129..0x1150)
+                .map(|addr| -> Result<String, Box<dyn std::error::Error>> {
+                    let frames = symcache.symbolicate(addr)?;
+                    let lines: Vec<_> = frames.iter().map(ToString::to_string).collect();
+                    Ok(lines.join("\n"))
+                })
+                .collect()
+        };
+
+        let expected = symbolicate(&write("linux/split/gen/lines", None)?)?;
+        insta::assert_snapshot!(expected[0x1147 - 0x1129].as_str(), @r###"
+        square (/build/lines/lines.c:4)
+        sum_of_squares (/build/lines/lines.c:8)
+        main (/build/lines/lines.c:15)
+        "###);
+
+        // Skeleton units alone contain no functions, so only the symbol table is used.
+        let skeleton = symbolicate(&write("linux/split/gen/lines-split", None)?)?;
+        assert_eq!(skeleton[0x1147 - 0x1129], "main");
+
+        for supplement in &[
+            "linux/split/gen/lines-split.dwo",
+            "linux/split/gen/lines-split.dwp",
+        ] {
+            let actual = symbolicate(&write("linux/split/gen/lines-split", Some(*supplement))?)?;
+            assert_eq!(actual, expected, "{}", supplement);
+        }
+
+        Ok(())
     }
 }
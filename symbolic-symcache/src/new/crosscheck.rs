@@ -0,0 +1,101 @@
+//! Cross-checking of debug information against the symbol table.
+
+use symbolic_debuginfo::ObjectLike;
+
+use super::SymCache;
+
+/// The outcome of [`SymCache::crosscheck`].
+///
+/// This counts how often the function names derived from debug information agree with the names
+/// in the symbol table of the original object file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CrosscheckReport {
+    /// The number of function entry addresses that were compared.
+    pub checked: usize,
+    /// The number of entry addresses without a symbol starting at exactly that address.
+    pub missing: usize,
+    /// The number of entry addresses where the symbol name differs from the function name.
+    pub mismatches: usize,
+}
+
+impl CrosscheckReport {
+    /// The ratio of mismatched names to the number of compared functions.
+    ///
+    /// Returns `0.0` if no functions were compared.
+    pub fn mismatch_rate(&self) -> f64 {
+        if self.checked == 0 {
+            return 0.0;
+        }
+        self.mismatches as f64 / self.checked as f64
+    }
+}
+
+impl<'data> SymCache<'data> {
+    /// Compares the names of all functions in this SymCache against the symbol table of `object`.
+    ///
+    /// Every non-inlined function that originates from debug information is looked up by its entry
+    /// address in the object's symbol map. Functions that were added from the symbol table in the
+    /// first place are skipped, as they trivially agree. The `object` should be the same file this
+    /// SymCache was created from.
+    pub fn crosscheck<'d, 'o, O>(&self, object: &'o O) -> CrosscheckReport
+    where
+        O: ObjectLike<'d, 'o>,
+    {
+        let symbol_map = object.symbol_map();
+        let mut report = CrosscheckReport::default();
+
+        for function in self.functions.iter() {
+            let entry_pc = match function.entry_pc() {
+                Some(entry_pc) if function.lang != u32::MAX => entry_pc,
+                _ => continue,
+            };
+
+            let name = match self.get_string(function.name_idx) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            report.checked += 1;
+
+            let address = self.header.range_offset.saturating_add(u64::from(entry_pc));
+            match symbol_map.lookup(address) {
+                Some(symbol) if symbol.address == address => {
+                    if symbol.name() != Some(name) {
+                        report.mismatches += 1;
+                    }
+                }
+                _ => report.missing += 1,
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbolic_common::ByteView;
+    use symbolic_debuginfo::Object;
+    use symbolic_testutils::fixture;
+
+    use super::super::writer::SymCacheConverter;
+    use super::*;
+
+    #[test]
+    fn test_crosscheck_linux() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let report = symcache.crosscheck(&object);
+        assert!(report.checked > 0);
+        assert!(report.mismatch_rate() < 0.05, "{:?}", report);
+
+        Ok(())
+    }
+}
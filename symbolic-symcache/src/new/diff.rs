@@ -0,0 +1,183 @@
+//! Comparison of the functions in two SymCaches.
+
+use std::collections::BTreeMap;
+
+use super::SymCache;
+
+/// A function whose entry address differs between two SymCaches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MovedFunction {
+    /// The name of the function.
+    pub name: String,
+    /// The entry address of the function in the old SymCache.
+    pub old_address: u64,
+    /// The entry address of the function in the new SymCache.
+    pub new_address: u64,
+}
+
+/// The outcome of [`diff`].
+///
+/// All lists are sorted by function name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymCacheDiff {
+    /// Names of functions that only occur in the new SymCache.
+    pub added: Vec<String>,
+    /// Names of functions that only occur in the old SymCache.
+    pub removed: Vec<String>,
+    /// Functions that occur in both SymCaches, but at different entry addresses.
+    pub moved: Vec<MovedFunction>,
+}
+
+impl SymCacheDiff {
+    /// Whether both SymCaches contain the same functions at the same addresses.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// Collects the lowest entry address of every function name in the SymCache.
+///
+/// Functions that only occur inlined have no entry address.
+fn function_addresses<'a>(symcache: &'a SymCache<'_>) -> BTreeMap<&'a str, Option<u64>> {
+    let mut addresses = BTreeMap::new();
+    for function in symcache.functions.iter() {
+        let name = match symcache.get_string(function.name_idx) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let address = match function.entry_pc {
+            u32::MAX => None,
+            entry_pc => Some(symcache.header.range_offset.saturating_add(entry_pc as u64)),
+        };
+
+        let entry = addresses.entry(name).or_insert(address);
+        *entry = match (*entry, address) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+    addresses
+}
+
+/// Compares the functions of two SymCaches, for instance of two builds of the same library.
+///
+/// Functions are matched by name. A function that occurs in both SymCaches is reported as moved if
+/// its entry address changed. If several functions share a name, the lowest entry address is
+/// compared. Functions that only occur inlined have no entry address and are never reported as
+/// moved.
+pub fn diff(old: &SymCache<'_>, new: &SymCache<'_>) -> SymCacheDiff {
+    let old_functions = function_addresses(old);
+    let new_functions = function_addresses(new);
+    let mut diff = SymCacheDiff::default();
+
+    for (&name, &old_address) in &old_functions {
+        match new_functions.get(name) {
+            None => diff.removed.push(name.to_owned()),
+            Some(&new_address) => {
+                if let (Some(old_address), Some(new_address)) = (old_address, new_address) {
+                    if old_address != new_address {
+                        diff.moved.push(MovedFunction {
+                            name: name.to_owned(),
+                            old_address,
+                            new_address,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    diff.added = new_functions
+        .keys()
+        .filter(|name| !old_functions.contains_key(*name))
+        .map(|&name| name.to_owned())
+        .collect();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use symbolic_common::ByteView;
+    use symbolic_debuginfo::Object;
+    use symbolic_testutils::fixture;
+
+    use super::super::writer::SymCacheConverter;
+    use super::*;
+
+    fn write_fixture(path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture(path))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn test_diff_xul() -> Result<(), Box<dyn std::error::Error>> {
+        let old_buffer = write_fixture("xul.sym")?;
+        let old = SymCache::parse(&old_buffer)?;
+        let new_buffer = write_fixture("xul2.sym")?;
+        let new = SymCache::parse(&new_buffer)?;
+
+        let result = diff(&old, &new);
+        let counts = (result.added.len(), result.removed.len(), result.moved.len());
+        insta::assert_debug_snapshot!(counts, @r###"
+        (
+            1,
+            1,
+            0,
+        )
+        "###);
+        assert_eq!(result.added, ["Interpret(JSContext*, js::RunState&)"]);
+
+        assert!(diff(&old, &old).is_empty());
+
+        let reverse = diff(&new, &old);
+        assert_eq!(reverse.added, result.removed);
+        assert_eq!(reverse.removed, result.added);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_moved() -> Result<(), Box<dyn std::error::Error>> {
+        use symbolic_debuginfo::Symbol;
+
+        let write = |address: u64| -> Result<Vec<u8>, std::io::Error> {
+            let mut converter = SymCacheConverter::new();
+            converter.process_symbolic_symbol(&Symbol {
+                name: Some("function".into()),
+                address,
+                size: 0x10,
+            });
+            let mut buffer = Vec::new();
+            converter.serialize(&mut buffer)?;
+            Ok(buffer)
+        };
+
+        let old_buffer = write(0x1000)?;
+        let new_buffer = write(0x2000)?;
+        let result = diff(
+            &SymCache::parse(&old_buffer)?,
+            &SymCache::parse(&new_buffer)?,
+        );
+
+        assert!(result.added.is_empty());
+        assert!(result.removed.is_empty());
+        assert_eq!(
+            result.moved,
+            [MovedFunction {
+                name: "function".into(),
+                old_address: 0x1000,
+                new_address: 0x2000,
+            }]
+        );
+
+        Ok(())
+    }
+}
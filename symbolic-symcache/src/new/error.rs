@@ -1,14 +1,23 @@
 use thiserror::Error;
 
-/// Errors returned while loading/parsing a serialized SymCache.
+use super::RecordKind;
+use crate::{SymCacheError, SymCacheErrorKind, ValueKind};
+
+/// Errors returned while loading/parsing or writing a serialized SymCache.
 ///
 /// After a SymCache was successfully parsed via [`Format::parse`], an Error that occurs during
 /// access of any data indicates either corruption of the serialized file, or a bug in the
 /// converter/serializer.
+///
+/// The `TooMany*` variants are returned by the converter if the input exceeds what can be
+/// referenced by the 32-bit indexes of the format. Index `u32::MAX` is reserved to denote a
+/// missing reference, so each table holds at most `u32::MAX` entries.
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum Error {
     /// The buffer is not correctly aligned.
+    ///
+    /// This is no longer returned, since records are copied out of unaligned buffers.
     #[error("source buffer is not correctly aligned")]
     BufferNotAligned,
     /// The header's size doesn't match our expected size.
@@ -23,7 +32,102 @@ pub enum Error {
     /// The format version in the header is wrong/unknown.
     #[error("unknown SymCache version")]
     WrongVersion,
+    /// The header contains a required feature flag that this reader does not understand.
+    ///
+    /// The value is the index of the lowest unknown required bit in the flags.
+    #[error("unsupported SymCache feature bit {0}")]
+    UnsupportedFeature(u32),
     /// The self-advertised size of the buffer is not correct.
     #[error("incorrect buffer length")]
     BadFormatLength,
+    /// The buffer is shorter than its self-advertised size.
+    #[error("buffer is truncated")]
+    Truncated,
+    /// A section in the section table is missing, out of bounds, or has the wrong size.
+    #[error("invalid section {0}")]
+    BadSection(u32),
+    /// The checksum in the trailer does not match the contents of the buffer.
+    #[error("checksum mismatch")]
+    BadChecksum,
+    /// The string data is compressed, which requires the `compression` feature.
+    #[error("string data is compressed")]
+    Compressed,
+    /// The compressed string data is malformed.
+    #[error("invalid compressed string data")]
+    BadCompression,
+    /// A string reference is out of bounds or not valid UTF-8.
+    ///
+    /// [`SymCache::string`](super::SymCache::string) returns [`BadEncoding`](Self::BadEncoding)
+    /// for strings that are not valid UTF-8 instead.
+    #[error("invalid string reference {0}")]
+    InvalidStringRef(u32),
+    /// A string is not valid UTF-8.
+    #[error("string {0} is not valid UTF-8")]
+    BadEncoding(u32),
+    /// A file reference is out of bounds.
+    #[error("invalid file reference {0}")]
+    InvalidFileRef(u32),
+    /// A function reference is out of bounds.
+    #[error("invalid function reference {0}")]
+    InvalidFunctionRef(u32),
+    /// A source location reference is out of bounds.
+    #[error("invalid source location reference {0}")]
+    InvalidSourceLocationRef(u32),
+    /// A record contains a reference outside of its target table.
+    ///
+    /// This is returned by [`SymCache::validate`](super::SymCache::validate).
+    #[error("invalid {kind} record {index}")]
+    InvalidRecord {
+        /// The kind of the offending record.
+        kind: RecordKind,
+        /// The index of the offending record in its table.
+        index: u32,
+    },
+    /// There are more strings than can be indexed.
+    #[error("too many strings (at most {} are supported)", u32::MAX)]
+    TooManyStrings,
+    /// The concatenated string bytes exceed the addressable size.
+    #[error("string bytes exceed the maximum size of {} bytes", u32::MAX)]
+    TooManyStringBytes,
+    /// There are more files than can be indexed.
+    #[error("too many files (at most {} are supported)", u32::MAX)]
+    TooManyFiles,
+    /// There are more functions than can be indexed.
+    #[error("too many functions (at most {} are supported)", u32::MAX)]
+    TooManyFunctions,
+    /// There are more source locations than can be indexed.
+    #[error("too many source locations (at most {} are supported)", u32::MAX)]
+    TooManySourceLocations,
+    /// Code of an object overlaps with code of the SymCache it is added to.
+    ///
+    /// This is reported as a warning by
+    /// [`SymCacheConverter::extend_object`](super::writer::SymCacheConverter::extend_object), which
+    /// keeps the code of the object. The value is the first overlapping address.
+    #[error("code at {0:#x} overlaps with existing code")]
+    AddressCollision(u64),
+}
+
+impl From<Error> for SymCacheError {
+    fn from(error: Error) -> Self {
+        let kind = match error {
+            Error::WrongFormat | Error::WrongEndianness => SymCacheErrorKind::BadFileMagic,
+            Error::WrongVersion | Error::UnsupportedFeature(_) => {
+                SymCacheErrorKind::UnsupportedVersion
+            }
+            Error::BufferNotAligned
+            | Error::HeaderTooSmall
+            | Error::BadFormatLength
+            | Error::Truncated
+            | Error::BadSection(_) => SymCacheErrorKind::BadFileHeader,
+            Error::TooManyStrings => SymCacheErrorKind::TooManyValues(ValueKind::Symbol),
+            Error::TooManyStringBytes => SymCacheErrorKind::ValueTooLarge(ValueKind::Symbol),
+            Error::TooManyFiles => SymCacheErrorKind::TooManyValues(ValueKind::File),
+            Error::TooManyFunctions => SymCacheErrorKind::TooManyValues(ValueKind::Function),
+            Error::TooManySourceLocations => SymCacheErrorKind::TooManyValues(ValueKind::Line),
+            Error::AddressCollision(_) => SymCacheErrorKind::BadDebugFile,
+            _ => SymCacheErrorKind::BadCacheFile,
+        };
+
+        SymCacheError::new(kind, error)
+    }
 }
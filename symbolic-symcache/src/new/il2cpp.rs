@@ -0,0 +1,165 @@
+//! Mapping of IL2CPP generated C++ source locations back to C# source.
+//!
+//! Unity IL2CPP builds translate C# code to C++ before compiling it, so that debug information
+//! refers to generated C++ files. The build ships a `LineNumberMappings.json` file, which maps
+//! lines of the generated C++ files back to the C# source:
+//!
+//! ```json
+//! {
+//!   "/Il2CppOutputProject/Source/il2cppOutput/Assembly-CSharp.cpp": {
+//!     "/Assets/Scripts/Player.cs": {
+//!       "1200": 17,
+//!       "1208": 18
+//!     }
+//!   }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use super::writer::SymCacheConverter;
+
+/// A mapping from lines of IL2CPP generated C++ files to C# source.
+///
+/// Apply this to a [`SymCacheConverter`] via [`SymCacheConverter::set_il2cpp_line_mapping`].
+#[derive(Clone, Debug, Default)]
+pub struct LineMapping {
+    /// The paths of all C# files.
+    cs_files: Vec<String>,
+    /// Maps each C++ file to its lines, and each line to the C# file index and line.
+    cpp_files: HashMap<String, HashMap<u32, (usize, u32)>>,
+}
+
+impl LineMapping {
+    /// Parses a line mapping in the `LineNumberMappings.json` format.
+    ///
+    /// Line numbers that are not valid integers are ignored.
+    pub fn parse(data: &[u8]) -> Result<Self, serde_json::Error> {
+        type RawMapping = HashMap<String, HashMap<String, HashMap<String, u32>>>;
+        let raw: RawMapping = serde_json::from_slice(data)?;
+
+        let mut mapping = LineMapping::default();
+        for (cpp_file, cs_files) in raw {
+            let mut lines = HashMap::new();
+            for (cs_file, cs_lines) in cs_files {
+                let cs_file_idx = mapping.cs_files.len();
+                mapping.cs_files.push(cs_file);
+
+                for (cpp_line, cs_line) in cs_lines {
+                    if let Ok(cpp_line) = cpp_line.parse() {
+                        lines.insert(cpp_line, (cs_file_idx, cs_line));
+                    }
+                }
+            }
+            mapping.cpp_files.insert(cpp_file, lines);
+        }
+
+        Ok(mapping)
+    }
+
+    /// Looks up the C# file and line of a line in a generated C++ file.
+    ///
+    /// Returns `None` if the line is not mapped, or mapped to line `0`.
+    pub fn lookup(&self, cpp_file: &str, cpp_line: u32) -> Option<(&str, u32)> {
+        let &(cs_file_idx, cs_line) = self.cpp_files.get(cpp_file)?.get(&cpp_line)?;
+        if cs_line == 0 {
+            return None;
+        }
+        Some((&self.cs_files[cs_file_idx], cs_line))
+    }
+}
+
+impl SymCacheConverter {
+    /// Rewrites all source locations in generated C++ files to their C# source.
+    ///
+    /// Lines are looked up by the full path of their file. Lines that are not contained in the
+    /// mapping keep their C++ location. This replaces any previously set
+    /// [line transformer](Self::set_line_transformer).
+    pub fn set_il2cpp_line_mapping(&mut self, mapping: LineMapping) {
+        self.set_line_transformer(move |file, line| {
+            let (cs_file, cs_line) = mapping.lookup(&file.full_path(), line)?;
+            Some((cs_file.to_owned(), cs_line))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbolic_common::ByteView;
+    use symbolic_debuginfo::Object;
+    use symbolic_testutils::fixture;
+
+    use super::super::SymCache;
+    use super::*;
+
+    const GENERATED: &str = "/Il2CppOutputProject/Source/il2cppOutput/Assembly-CSharp.cpp";
+
+    #[test]
+    fn test_lookup() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("il2cpp/LineNumberMappings.json"))?;
+        let mapping = LineMapping::parse(&buffer)?;
+
+        assert_eq!(
+            mapping.lookup(GENERATED, 12),
+            Some(("/Assets/Scripts/Player.cs", 17))
+        );
+        assert_eq!(
+            mapping.lookup(GENERATED, 20),
+            Some(("/Assets/Scripts/Enemy.cs", 5))
+        );
+        assert_eq!(mapping.lookup(GENERATED, 13), None);
+        assert_eq!(mapping.lookup(GENERATED, 24), None);
+        assert_eq!(mapping.lookup("/other.cpp", 12), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_mapping() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("il2cpp/LineNumberMappings.json"))?;
+        let mapping = LineMapping::parse(&buffer)?;
+
+        let data = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 GameAssembly.so
+FILE 0 /Il2CppOutputProject/Source/il2cppOutput/Assembly-CSharp.cpp
+FUNC 1000 30 0 Player_Update_m1
+1000 10 12 0
+1010 10 13 0
+1020 10 20 0
+";
+        let object = Object::parse(data)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_il2cpp_line_mapping(mapping);
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let mut files: Vec<_> = symcache.files().map(|file| file.full_path()).collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "/Assets/Scripts/Enemy.cs",
+                "/Assets/Scripts/Player.cs",
+                GENERATED
+            ]
+        );
+
+        let location = |addr| {
+            let sl = symcache.lookup(addr).next().unwrap();
+            (sl.file().unwrap().full_path(), sl.line())
+        };
+        assert_eq!(
+            location(0x1000),
+            ("/Assets/Scripts/Player.cs".into(), Some(17))
+        );
+        assert_eq!(location(0x1010), (GENERATED.into(), Some(13)));
+        assert_eq!(
+            location(0x1020),
+            ("/Assets/Scripts/Enemy.cs".into(), Some(5))
+        );
+
+        Ok(())
+    }
+}
@@ -1,32 +1,287 @@
-use symbolic_common::Language;
+use std::fmt;
+use std::ops::Range;
 
-use super::{raw, SymCache};
+use symbolic_common::{CpuFamily, InstructionInfo, Language};
+
+use super::{raw, Error, Result, SymCache};
 
 impl<'data> SymCache<'data> {
     /// Looks up an instruction address in the SymCache, yielding an iterator of [`SourceLocation`]s.
     ///
     /// This always returns an iterator, however that iterator might be empty in case no [`SourceLocation`]
-    /// was found for the given `addr`.
+    /// was found for the given `addr`. On 32-bit ARM, the Thumb bit of `addr` is ignored.
+    ///
+    /// Addresses within a [gap](raw::SourceLocation::GAP), such as padding after the last
+    /// instruction of a function, yield no results.
     pub fn lookup(&self, addr: u64) -> SourceLocationIter<'data, '_> {
-        use std::convert::TryFrom;
-        let addr = match addr
-            .checked_sub(self.header.range_offset)
-            .and_then(|r| u32::try_from(r).ok())
-        {
-            Some(addr) => addr,
-            None => {
-                return SourceLocationIter {
-                    cache: self,
-                    source_location_idx: u32::MAX,
-                }
+        let range_idx = self
+            .relative_addr(addr)
+            .and_then(|addr| self.range_idx(addr));
+        self.source_locations_at(range_idx)
+    }
+
+    /// Looks up an absolute instruction address of an image loaded at `image_base`.
+    ///
+    /// This is equivalent to [`lookup`](Self::lookup) with the address relative to the image
+    /// base. If `addr` is below `image_base`, the returned iterator is empty.
+    pub fn lookup_abs(&self, addr: u64, image_base: u64) -> SourceLocationIter<'data, '_> {
+        match addr.checked_sub(image_base) {
+            Some(addr) => self.lookup(addr),
+            None => self.source_locations_at(None),
+        }
+    }
+
+    /// Symbolicates an entire stack trace, given the instruction address of each frame.
+    ///
+    /// The first address is the instruction pointer of the crashing or suspended frame and is
+    /// looked up as is. All other addresses are return addresses, which point to the instruction
+    /// after the call. These are adjusted to the preceding instruction based on the
+    /// [architecture](Self::arch) of this SymCache, so that they resolve to the call site. See
+    /// [`InstructionInfo::caller_address`] for more information.
+    ///
+    /// Returns the [`SourceLocation`]s of each frame, including inlined frames, in the same order
+    /// as the input. Frames that cannot be resolved yield an empty list.
+    pub fn symbolicate_stack(&self, addresses: &[u64]) -> Vec<Vec<SourceLocation<'data, '_>>> {
+        addresses
+            .iter()
+            .enumerate()
+            .map(|(index, &addr)| {
+                let caller_address = InstructionInfo::new(self.arch(), addr)
+                    .is_crashing_frame(index == 0)
+                    .caller_address();
+                self.lookup(caller_address).collect()
+            })
+            .collect()
+    }
+
+    /// Looks up an instruction address and resolves the name, file and line of every frame.
+    ///
+    /// This is a convenience wrapper around [`lookup`](Self::lookup) that returns owned
+    /// [`Frame`]s, starting with the innermost inlined function and ending with its outermost
+    /// caller. Each frame can be formatted as `name (file:line)`. The list is empty if the
+    /// address cannot be resolved.
+    ///
+    /// Returns [`Error::InvalidFunctionRef`] or [`Error::InvalidFileRef`] if a source location
+    /// references a function or file outside of its table.
+    pub fn symbolicate(&self, addr: u64) -> Result<Vec<Frame<'_>>> {
+        self.lookup(addr)
+            .map(|sl| {
+                let raw = sl.source_location;
+                let function = self
+                    .get_function(raw.function_idx)
+                    .ok_or(Error::InvalidFunctionRef(raw.function_idx))?;
+                let file = match raw.file_idx() {
+                    Some(file_idx) => Some(self.full_path(file_idx)?),
+                    None => None,
+                };
+                Ok(Frame {
+                    name: function.name(),
+                    file,
+                    line: sl.line(),
+                })
+            })
+            .collect()
+    }
+
+    /// Checks whether the SymCache has any [`SourceLocation`] for the given `addr`.
+    ///
+    /// This is equivalent to checking whether [`lookup`](Self::lookup) yields any items, but
+    /// does not resolve any functions or files.
+    pub fn covers(&self, addr: u64) -> bool {
+        self.relative_addr(addr)
+            .and_then(|addr| self.range_idx(addr))
+            .map_or(false, |range_idx| !self.is_gap(range_idx))
+    }
+
+    /// Returns the range of addresses that [`lookup`](Self::lookup) can resolve.
+    ///
+    /// The range starts at the first entry of the range table. If the last entry is a
+    /// [gap](raw::SourceLocation::GAP), the range ends where the gap starts. Otherwise, the last
+    /// entry extends up to the end of the covered address range, and the range ends where the
+    /// SymCache was restricted to when writing, or where addresses no longer fit into the 32-bit
+    /// offsets from the range threshold unless the SymCache has
+    /// [wide ranges](super::Features::has_wide_ranges). Addresses outside of this range never
+    /// resolve, but not all addresses within it do. Returns `None` if the SymCache contains no
+    /// ranges.
+    pub fn address_range(&self) -> Option<Range<u64>> {
+        let header = &self.header;
+        let first = self.ranges.first()?;
+        let start = header
+            .range_offset
+            .saturating_add(first)
+            .max(header.address_range_start);
+        let mut end = header.address_range_end;
+        if let Some(limit) = self.ranges.limit() {
+            end = end.min(header.range_offset.saturating_add(limit));
+        }
+        let last_idx = self.ranges.len() - 1;
+        if self.is_gap(last_idx) {
+            let last = self.ranges.last()?;
+            end = end.min(header.range_offset.saturating_add(last));
+        }
+        Some(start..end)
+    }
+
+    /// Looks up all functions with the given [name](Function::name).
+    ///
+    /// If the SymCache has a [name index](super::Features::has_name_index), this performs a binary
+    /// search. Otherwise, all functions are scanned. Functions are yielded in the order of their
+    /// index in both cases. Mangled names are not matched.
+    pub fn lookup_by_name<'n>(&self, name: &'n str) -> FunctionsByNameIter<'data, '_, 'n> {
+        let features = self.features();
+        let indexed = features.has_name_index() && features.has_sorted_strings();
+        let position = if !indexed {
+            0..self.functions.len()
+        } else if let Some(name_idx) = self.find_string(name) {
+            let function_name = |function_idx: u32| {
+                self.functions
+                    .get(function_idx as usize)
+                    .map_or(u32::MAX, |function| function.name_idx)
+            };
+            let index = &self.name_index;
+            let start = index.partition_point(|&idx| function_name(idx) < name_idx);
+            let end = index.partition_point(|&idx| function_name(idx) <= name_idx);
+            start..end
+        } else {
+            0..0
+        };
+
+        FunctionsByNameIter {
+            cache: self,
+            name,
+            position,
+            indexed,
+        }
+    }
+
+    /// Returns an iterator over the contiguous address ranges attributed to the given function.
+    ///
+    /// An address is attributed to a function if the function occurs anywhere in the inlining
+    /// hierarchy at that address. Ranges are yielded in ascending order as half-open
+    /// `(start, end)` tuples of addresses relative to the range threshold the SymCache was written
+    /// with, which is `0` by default. The last range of the SymCache extends up to the end of the
+    /// covered address range.
+    ///
+    /// The ranges of all functions are indexed on the first call, which takes a pass over the
+    /// range table. Subsequent calls perform a binary search in that index.
+    pub fn function_ranges(&self, function: &Function<'_>) -> FunctionRangeIter<'data, '_> {
+        let index = self.function_range_index();
+        let start = index.partition_point(|&(function_idx, _)| function_idx < function.idx);
+        let end = index.partition_point(|&(function_idx, _)| function_idx <= function.idx);
+        FunctionRangeIter {
+            cache: self,
+            ranges: &index[start..end],
+        }
+    }
+
+    /// Returns the ranges of all functions as `(function_idx, range_idx)`, sorted by function.
+    fn function_range_index(&self) -> &[(u32, u32)] {
+        self.function_range_index.0.get_or_init(|| {
+            let mut index = Vec::new();
+            for range_idx in 0..self.ranges.len() {
+                index.extend(
+                    self.source_locations_at(Some(range_idx))
+                        .map(|sl| (sl.source_location.function_idx, range_idx as u32)),
+                );
             }
+            // A function can occur more than once in the inlining hierarchy of a range.
+            index.sort_unstable();
+            index.dedup();
+            index
+        })
+    }
+
+    /// Returns the number of distinct source locations that are inlined into the given function.
+    ///
+    /// This counts the source locations of inlinees whose call site lies directly within
+    /// `function`, which helps to flag functions with excessive inlining. Source locations that
+    /// only differ in the address range they cover are counted once.
+    pub fn inline_count(&self, function: &Function<'_>) -> usize {
+        let mut seen = std::collections::HashSet::new();
+        self.source_locations
+            .iter()
+            .filter(|sl| {
+                let caller = sl
+                    .inlined_into_idx()
+                    .and_then(|idx| self.source_locations.get(idx as usize));
+                caller.map_or(false, |caller| caller.function_idx == function.idx)
+            })
+            .filter(|sl| seen.insert(*sl))
+            .count()
+    }
+
+    /// Returns an iterator over all [`SourceLocation`]s in this SymCache.
+    ///
+    /// Every entry is validated before it is yielded. Entries referencing files, functions,
+    /// strings or other source locations that do not exist yield an [`Error`]. The source
+    /// locations of [gaps](raw::SourceLocation::GAP) are skipped.
+    pub fn source_locations(&self) -> AllSourceLocationsIter<'data, '_> {
+        AllSourceLocationsIter {
+            cache: self,
+            source_location_idx: 0,
+        }
+    }
+
+    /// Creates a [`SequentialLookup`] cursor for efficiently looking up ascending addresses.
+    pub fn sequential_lookup(&self) -> SequentialLookup<'data, '_> {
+        SequentialLookup {
+            cache: self,
+            last_addr: 0,
+            next_range_idx: 0,
+        }
+    }
+
+    /// Converts an absolute address into an address relative to the `range_offset`.
+    ///
+    /// On 32-bit ARM, this clears the Thumb bit, which is set in return addresses and function
+    /// pointers to Thumb code. The converter clears it when writing ranges as well.
+    ///
+    /// Returns `None` if the address is outside of the range covered by this SymCache.
+    fn relative_addr(&self, addr: u64) -> Option<u64> {
+        let addr = match self.arch().cpu_family() {
+            CpuFamily::Arm32 => addr & !1,
+            _ => addr,
         };
 
-        let source_location_start = (self.source_locations.len() - self.ranges.len()) as u32;
-        let source_location_idx = match self.ranges.binary_search_by_key(&addr, |r| r.0) {
-            Ok(idx) => source_location_start + idx as u32,
-            Err(idx) if idx == 0 => u32::MAX,
-            Err(idx) => source_location_start + idx as u32 - 1,
+        if addr < self.header.address_range_start || addr >= self.header.address_range_end {
+            return None;
+        }
+
+        let addr = addr.checked_sub(self.header.range_offset)?;
+        match self.ranges.limit() {
+            Some(limit) if addr >= limit => None,
+            _ => Some(addr),
+        }
+    }
+
+    /// Finds the index of the range containing the relative `addr` via binary search.
+    fn range_idx(&self, addr: u64) -> Option<usize> {
+        match self.ranges.binary_search(addr) {
+            Ok(idx) => Some(idx),
+            Err(0) => None,
+            Err(idx) => Some(idx - 1),
+        }
+    }
+
+    /// Checks whether the range at `range_idx` is a [gap](raw::SourceLocation::GAP).
+    pub(crate) fn is_gap(&self, range_idx: usize) -> bool {
+        let source_location_start = self.source_locations.len() - self.ranges.len();
+        self.source_locations
+            .get(source_location_start + range_idx)
+            .map_or(false, raw::SourceLocation::is_gap)
+    }
+
+    /// Creates an iterator over the inlining hierarchy of the given range.
+    ///
+    /// The iterator is empty if the range is a [gap](raw::SourceLocation::GAP).
+    pub(crate) fn source_locations_at(
+        &self,
+        range_idx: Option<usize>,
+    ) -> SourceLocationIter<'data, '_> {
+        let source_location_start = self.source_locations.len() - self.ranges.len();
+        let source_location_idx = match range_idx {
+            Some(idx) if !self.is_gap(idx) => (source_location_start + idx) as u32,
+            _ => u32::MAX,
         };
         SourceLocationIter {
             cache: self,
@@ -34,20 +289,82 @@ impl<'data> SymCache<'data> {
         }
     }
 
-    pub(crate) fn get_file(&self, file_idx: u32) -> Option<File<'data>> {
+    /// Resolves the full path of the file at `file_idx` in the files table.
+    ///
+    /// The compilation directory, directory and path name of the file are joined and normalized
+    /// as described in [`File::full_path`]. An absolute directory or path name overrides the
+    /// preceding components, and Windows paths retain their backslashes.
+    ///
+    /// Returns [`Error::InvalidFileRef`] if the index is out of bounds, and
+    /// [`Error::InvalidStringRef`] if any of the path components cannot be resolved.
+    pub fn full_path(&self, file_idx: u32) -> Result<String> {
+        let raw_file = self
+            .files
+            .get(file_idx as usize)
+            .ok_or(Error::InvalidFileRef(file_idx))?;
+
+        let optional_string = |string_idx: Option<u32>| match string_idx {
+            Some(string_idx) => self
+                .get_string(string_idx)
+                .map(Some)
+                .ok_or(Error::InvalidStringRef(string_idx)),
+            None => Ok(None),
+        };
+
+        let file = File {
+            comp_dir: optional_string(raw_file.comp_dir_idx())?,
+            directory: optional_string(raw_file.directory_idx())?,
+            path_name: self
+                .get_string(raw_file.path_name_idx)
+                .ok_or(Error::InvalidStringRef(raw_file.path_name_idx))?,
+            source: None,
+        };
+
+        Ok(file.full_path())
+    }
+
+    pub(crate) fn get_file(&self, file_idx: u32) -> Option<File<'_>> {
         let raw_file = self.files.get(file_idx as usize)?;
         Some(File {
             comp_dir: self.get_string(raw_file.comp_dir_idx),
             directory: self.get_string(raw_file.directory_idx),
-            path_name: self.get_string(raw_file.path_name_idx).unwrap(),
+            path_name: self.get_string(raw_file.path_name_idx)?,
+            source: self.get_source(file_idx),
         })
     }
 
-    pub(crate) fn get_function(&self, function_idx: u32) -> Option<Function<'data>> {
+    /// Returns the column of the source location at `source_location_idx`, or `0` if it has none.
+    pub(crate) fn get_column(&self, source_location_idx: u32) -> u16 {
+        self.columns
+            .get(source_location_idx as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Resolves the embedded contents of a file, if it has any.
+    fn get_source(&self, file_idx: u32) -> Option<&[u8]> {
+        let file_source = self.file_sources.get(file_idx as usize)?;
+        let start_offset = file_source.offset()? as usize;
+        let end_offset = start_offset.checked_add(file_source.len as usize)?;
+        self.source_bytes.get(start_offset..end_offset)
+    }
+
+    pub(crate) fn get_function(&self, function_idx: u32) -> Option<Function<'_>> {
         let raw_function = self.functions.get(function_idx as usize)?;
+        let name = self
+            .get_string(raw_function.name_idx)
+            .filter(|name| !name.is_empty());
+        let plain_name = self
+            .get_string(raw_function.plain_name_idx)
+            .filter(|name| !name.is_empty());
         Some(Function {
-            name: self.get_string(raw_function.name_idx),
+            idx: function_idx,
+            name,
+            // The name is the linkage name if there is a separate plain name.
+            mangled_name: name.filter(|_| plain_name.is_some()),
+            plain_name,
             comp_dir: self.get_string(raw_function.comp_dir_idx),
+            compilation_unit: self.get_string(raw_function.unit_idx),
             entry_pc: raw_function.entry_pc,
             language: Language::from_u32(raw_function.lang),
         })
@@ -81,6 +398,8 @@ pub struct File<'data> {
     pub directory: Option<&'data str>,
     /// The file path.
     pub path_name: &'data str,
+    /// The embedded contents of the file.
+    pub(crate) source: Option<&'data [u8]>,
 }
 
 impl<'data> File<'data> {
@@ -99,6 +418,15 @@ impl<'data> File<'data> {
         self.path_name
     }
 
+    /// The contents of this source file, if they were embedded into the SymCache.
+    ///
+    /// See [`SymCacheConverter::set_source_provider`](super::writer::SymCacheConverter::set_source_provider)
+    /// for embedding sources. The contents are returned as written, without validating their
+    /// encoding.
+    pub fn source(&self) -> Option<&'data [u8]> {
+        self.source
+    }
+
     /// Resolves and concatenates the full path based on its individual fragments.
     pub fn full_path(&self) -> String {
         let comp_dir = self.comp_dir().unwrap_or_default();
@@ -116,23 +444,59 @@ impl<'data> File<'data> {
 /// A Function definition as included in the SymCache.
 #[derive(Clone, Debug)]
 pub struct Function<'data> {
+    idx: u32,
     name: Option<&'data str>,
+    mangled_name: Option<&'data str>,
+    plain_name: Option<&'data str>,
     comp_dir: Option<&'data str>,
+    compilation_unit: Option<&'data str>,
     entry_pc: u32,
     language: Language,
 }
 
 impl<'data> Function<'data> {
-    /// The possibly mangled name/symbol of this function.
+    /// The full, possibly mangled name or symbol of this function.
+    ///
+    /// If debug information contains both a linkage name and a plain name, this is the linkage
+    /// name, and the unqualified plain name is available as [`plain_name`](Self::plain_name).
+    ///
+    /// This is `None` if the function has no name, which happens for functions and symbols in
+    /// some stripped objects. An empty name is never returned. Such functions can still be looked
+    /// up, and their source locations retain files, lines and the [entry pc](Self::entry_pc).
     pub fn name(&self) -> Option<&'data str> {
         self.name
     }
 
+    /// The linkage name of this function, if debug information contains it in addition to a
+    /// [`plain_name`](Self::plain_name).
+    ///
+    /// This is the same as the [`name`](Self::name) in that case. It is always `None` for
+    /// SymCaches written before linkage names were stored.
+    pub fn mangled_name(&self) -> Option<&'data str> {
+        self.mangled_name
+    }
+
+    /// The unqualified name of this function as declared in source, if its [`name`](Self::name)
+    /// is a linkage name.
+    ///
+    /// This is always `None` for SymCaches written before linkage names were stored.
+    pub fn plain_name(&self) -> Option<&'data str> {
+        self.plain_name
+    }
+
     /// The compilation directory of this function.
     pub fn comp_dir(&self) -> Option<&'data str> {
         self.comp_dir
     }
 
+    /// The name of the compilation unit that defines this function, usually its main source file.
+    ///
+    /// This is `None` for functions from symbol tables and Breakpad files, and for SymCaches
+    /// written before compilation unit names were stored.
+    pub fn compilation_unit(&self) -> Option<&'data str> {
+        self.compilation_unit
+    }
+
     /// The entry pc of the function.
     pub fn entry_pc(&self) -> u32 {
         self.entry_pc
@@ -142,6 +506,13 @@ impl<'data> Function<'data> {
     pub fn language(&self) -> Language {
         self.language
     }
+
+    /// The human readable name of the language the function is written in.
+    ///
+    /// This is `"unknown"` for functions that originate from a symbol table.
+    pub fn language_name(&self) -> &'static str {
+        self.language.display_name()
+    }
 }
 
 /// A Source Location as included in the SymCache.
@@ -151,31 +522,294 @@ impl<'data> Function<'data> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SourceLocation<'data, 'cache> {
     pub(crate) cache: &'cache SymCache<'data>,
-    pub(crate) source_location: &'data raw::SourceLocation,
+    pub(crate) source_location: &'cache raw::SourceLocation,
+    pub(crate) source_location_idx: u32,
 }
 
 impl<'data, 'cache> SourceLocation<'data, 'cache> {
-    /// The source line corresponding to the instruction.
+    /// The source line corresponding to the instruction, starting at 1.
     ///
-    /// This might return `0` when no line information can be found.
-    pub fn line(&self) -> u32 {
-        self.source_location.line
+    /// This returns `None` if no line information can be found. Debug information that encodes a
+    /// missing line as line `0` is treated the same way.
+    pub fn line(&self) -> Option<u32> {
+        match self.source_location.line {
+            0 => None,
+            line => Some(line),
+        }
+    }
+
+    /// The source column corresponding to the instruction, starting at 1.
+    ///
+    /// This returns `None` if the debug information has no column for this location, which is
+    /// always the case for Breakpad symbols and SymCaches without
+    /// [column information](super::Features::has_columns).
+    pub fn column(&self) -> Option<u32> {
+        match self.cache.get_column(self.source_location_idx) {
+            0 => None,
+            column => Some(column.into()),
+        }
     }
 
     /// The source file corresponding to the instruction.
-    pub fn file(&self) -> Option<File<'data>> {
+    pub fn file(&self) -> Option<File<'cache>> {
         self.cache.get_file(self.source_location.file_idx)
     }
 
     /// The function corresponding to the instruction.
-    pub fn function(&self) -> Option<Function<'data>> {
+    pub fn function(&self) -> Option<Function<'cache>> {
         self.cache.get_function(self.source_location.function_idx)
     }
 
+    /// Whether this source location was inlined into the next one of the lookup.
+    ///
+    /// The outermost source location of a lookup, which corresponds to a real function on the
+    /// stack, is never inlined.
+    pub fn is_inlined(&self) -> bool {
+        self.source_location.inlined_into_idx().is_some()
+    }
+
     // TODO: maybe forward some of the `File` and `Function` accessors, such as:
     // `function_name` or `full_path` for convenience.
 }
 
+/// A resolved stack frame, as returned by [`SymCache::symbolicate`].
+///
+/// The [`Display`](fmt::Display) implementation formats the frame as `name (file:line)`. The
+/// line is omitted if it is unknown, and the parenthetical is omitted if the frame has no file.
+/// Functions without a name are formatted as `??`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame<'data> {
+    /// The name of the function, see [`Function::name`].
+    pub name: Option<&'data str>,
+    /// The full path of the source file, see [`File::full_path`].
+    pub file: Option<String>,
+    /// The source line, see [`SourceLocation::line`].
+    pub line: Option<u32>,
+}
+
+impl fmt::Display for Frame<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name.unwrap_or("??"))?;
+        match (&self.file, self.line) {
+            (Some(file), None) => write!(f, " ({})", file),
+            (Some(file), Some(line)) => write!(f, " ({}:{})", file, line),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+/// A cursor for looking up a sequence of ascending addresses.
+///
+/// Each lookup resumes scanning the range table where the previous one ended, which makes lookups
+/// of sorted addresses amortized `O(1)`. If an address is lower than the previous one, this falls
+/// back to a binary search, so the results are always the same as with [`SymCache::lookup`].
+///
+/// Created via [`SymCache::sequential_lookup`].
+#[derive(Debug, Clone)]
+pub struct SequentialLookup<'data, 'cache> {
+    cache: &'cache SymCache<'data>,
+    /// The relative address of the previous lookup.
+    last_addr: u64,
+    /// The index of the first range starting after `last_addr`.
+    next_range_idx: usize,
+}
+
+impl<'data, 'cache> SequentialLookup<'data, 'cache> {
+    /// Looks up an instruction address, yielding an iterator of [`SourceLocation`]s.
+    ///
+    /// See [`SymCache::lookup`] for more information.
+    pub fn next(&mut self, addr: u64) -> SourceLocationIter<'data, 'cache> {
+        let cache = self.cache;
+        let addr = match cache.relative_addr(addr) {
+            Some(addr) => addr,
+            None => return cache.source_locations_at(None),
+        };
+
+        if addr < self.last_addr {
+            self.next_range_idx = cache.range_idx(addr).map_or(0, |idx| idx + 1);
+        } else {
+            let ranges = &cache.ranges;
+            while ranges
+                .get(self.next_range_idx)
+                .map_or(false, |start| start <= addr)
+            {
+                self.next_range_idx += 1;
+            }
+        }
+
+        self.last_addr = addr;
+        cache.source_locations_at(self.next_range_idx.checked_sub(1))
+    }
+}
+
+/// An iterator over the functions with a given name.
+///
+/// Created via [`SymCache::lookup_by_name`].
+#[derive(Debug, Clone)]
+pub struct FunctionsByNameIter<'data, 'cache, 'n> {
+    cache: &'cache SymCache<'data>,
+    name: &'n str,
+    /// The remaining positions in the name index, or function indexes if there is no index.
+    position: Range<usize>,
+    indexed: bool,
+}
+
+impl<'data, 'cache, 'n> Iterator for FunctionsByNameIter<'data, 'cache, 'n> {
+    type Item = Function<'cache>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let name = self.name;
+        for position in &mut self.position {
+            if self.indexed {
+                let function_idx = self.cache.name_index[position];
+                if let Some(function) = self.cache.get_function(function_idx) {
+                    return Some(function);
+                }
+            } else {
+                let function = self.cache.get_function(position as u32);
+                if let Some(function) = function.filter(|f| f.name() == Some(name)) {
+                    return Some(function);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over the address ranges of a function.
+///
+/// Created via [`SymCache::function_ranges`].
+#[derive(Debug, Clone)]
+pub struct FunctionRangeIter<'data, 'cache> {
+    cache: &'cache SymCache<'data>,
+    /// The remaining ranges of the function as `(function_idx, range_idx)`, in ascending order.
+    ranges: &'cache [(u32, u32)],
+}
+
+impl<'data, 'cache> FunctionRangeIter<'data, 'cache> {
+    /// The relative end address of the given range.
+    fn range_end(&self, range_idx: usize) -> u64 {
+        let ranges = &self.cache.ranges;
+        match ranges.get(range_idx + 1) {
+            Some(next) => next,
+            None => {
+                let header = &self.cache.header;
+                let end = header.address_range_end.saturating_sub(header.range_offset);
+                match ranges.limit() {
+                    Some(limit) => end.min(limit - 1),
+                    None => end,
+                }
+            }
+        }
+    }
+}
+
+impl<'data, 'cache> Iterator for FunctionRangeIter<'data, 'cache> {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&(_, first_idx), _) = self.ranges.split_first()?;
+        let start = self.cache.ranges.get(first_idx as usize)?;
+
+        // Adjacent ranges of the same function are merged.
+        let mut last_idx = first_idx;
+        let mut len = 1;
+        while let Some(&(_, range_idx)) = self.ranges.get(len) {
+            if range_idx != last_idx + 1 {
+                break;
+            }
+            last_idx = range_idx;
+            len += 1;
+        }
+        self.ranges = &self.ranges[len..];
+
+        Some((start, self.range_end(last_idx as usize)))
+    }
+}
+
+/// An iterator over all source locations in a SymCache.
+///
+/// Created via [`SymCache::source_locations`].
+#[derive(Debug, Clone)]
+pub struct AllSourceLocationsIter<'data, 'cache> {
+    cache: &'cache SymCache<'data>,
+    source_location_idx: u32,
+}
+
+impl<'data, 'cache> AllSourceLocationsIter<'data, 'cache> {
+    /// Checks that a string reference is valid, allowing `u32::MAX` if `optional`.
+    fn check_string(&self, string_idx: u32, optional: bool) -> Result<()> {
+        if optional && string_idx == u32::MAX {
+            return Ok(());
+        }
+        match self.cache.get_string(string_idx) {
+            Some(_) => Ok(()),
+            None => Err(Error::InvalidStringRef(string_idx)),
+        }
+    }
+
+    /// Checks that all references of a source location are valid.
+    fn check(&self, source_location: &raw::SourceLocation) -> Result<()> {
+        let cache = self.cache;
+
+        if let Some(file_idx) = source_location.file_idx() {
+            let file = cache
+                .files
+                .get(file_idx as usize)
+                .ok_or(Error::InvalidFileRef(file_idx))?;
+            self.check_string(file.comp_dir_idx, true)?;
+            self.check_string(file.directory_idx, true)?;
+            self.check_string(file.path_name_idx, false)?;
+        }
+
+        let function = cache
+            .functions
+            .get(source_location.function_idx as usize)
+            .ok_or(Error::InvalidFunctionRef(source_location.function_idx))?;
+        self.check_string(function.name_idx, true)?;
+        self.check_string(function.plain_name_idx, true)?;
+        self.check_string(function.comp_dir_idx, true)?;
+
+        if let Some(inlined_into_idx) = source_location.inlined_into_idx() {
+            if inlined_into_idx as usize >= cache.source_locations.len() {
+                return Err(Error::InvalidSourceLocationRef(inlined_into_idx));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'data, 'cache> Iterator for AllSourceLocationsIter<'data, 'cache> {
+    type Item = Result<SourceLocation<'data, 'cache>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (source_location_idx, source_location) = loop {
+            let source_location_idx = self.source_location_idx;
+            let source_location = self
+                .cache
+                .source_locations
+                .get(source_location_idx as usize)?;
+            self.source_location_idx += 1;
+            if !source_location.is_gap() {
+                break (source_location_idx, source_location);
+            }
+        };
+
+        Some(self.check(source_location).map(|()| SourceLocation {
+            cache: self.cache,
+            source_location,
+            source_location_idx,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Gaps are skipped, so the remaining source locations are only an upper bound.
+        let remaining = self.cache.source_locations.len() - self.source_location_idx as usize;
+        (0, Some(remaining))
+    }
+}
+
 /// An Iterator that yields [`SourceLocation`]s, representing an inlining hierarchy.
 #[derive(Debug, Clone)]
 pub struct SourceLocationIter<'data, 'cache> {
@@ -183,6 +817,16 @@ pub struct SourceLocationIter<'data, 'cache> {
     pub(crate) source_location_idx: u32,
 }
 
+impl<'data, 'cache> SourceLocationIter<'data, 'cache> {
+    /// The number of frames remaining in this inlining hierarchy, without advancing the iterator.
+    ///
+    /// For an address that is covered by a function without inlinees, this is `1`. If nothing
+    /// was found, this is `0`.
+    pub fn depth(&self) -> usize {
+        self.clone().count()
+    }
+}
+
 impl<'data, 'cache> Iterator for SourceLocationIter<'data, 'cache> {
     type Item = SourceLocation<'data, 'cache>;
 
@@ -194,11 +838,715 @@ impl<'data, 'cache> Iterator for SourceLocationIter<'data, 'cache> {
             .source_locations
             .get(self.source_location_idx as usize)
             .map(|source_location| {
-                self.source_location_idx = source_location.inlined_into_idx;
+                let source_location_idx = self.source_location_idx;
+                // Callers are always stored before their inlinees. Stop at anything else, so that
+                // a corrupted SymCache cannot make this iterator loop forever.
+                self.source_location_idx = match source_location.inlined_into_idx {
+                    idx if idx < source_location_idx => idx,
+                    _ => u32::MAX,
+                };
                 SourceLocation {
                     cache: self.cache,
                     source_location,
+                    source_location_idx,
                 }
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use symbolic_common::{ByteView, Language, Name, NameMangling};
+    use symbolic_debuginfo::{FileInfo, Function, LineInfo, Object, Symbol};
+    use symbolic_testutils::fixture;
+
+    use super::super::writer::SymCacheConverter;
+    use super::*;
+
+    fn locations(iter: SourceLocationIter<'_, '_>) -> Vec<*const raw::SourceLocation> {
+        iter.map(|sl| sl.source_location as *const _).collect()
+    }
+
+    /// Serializes a new converter after filling it with `process`.
+    fn convert<F>(process: F) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&mut SymCacheConverter) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        let mut converter = SymCacheConverter::new();
+        process(&mut converter)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Converts the debug file at the given fixture path with default settings.
+    fn convert_fixture(path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture(path))?;
+        let object = Object::parse(&buffer)?;
+        convert(|converter| {
+            converter.process_object(&object)?;
+            Ok(())
+        })
+    }
+
+    /// Creates a top-level function with a line record of `0x10` bytes in `/src/main.cpp` at each
+    /// `(address, line)`. The function spans all of its line records.
+    fn function(
+        name: &'static str,
+        lines: &[(u64, u64)],
+        inlinees: Vec<Function<'static>>,
+    ) -> Function<'static> {
+        let lines: Vec<_> = lines
+            .iter()
+            .map(|&(address, line)| LineInfo {
+                address,
+                size: Some(0x10),
+                file: FileInfo {
+                    name: b"main.cpp",
+                    dir: b"/src",
+                },
+                line,
+                column: 0,
+            })
+            .collect();
+        Function {
+            address: lines[0].address,
+            size: 0x10 * lines.len() as u64,
+            entry_address: None,
+            name: Name::new(name, NameMangling::Unmangled, Language::Cpp),
+            plain_name: None,
+            compilation_dir: b"",
+            producer: b"",
+            compilation_unit: b"",
+            lines,
+            inlinees,
+            inline: false,
+        }
+    }
+
+    /// Creates an inlined function, see [`function`].
+    fn inlinee(
+        name: &'static str,
+        lines: &[(u64, u64)],
+        inlinees: Vec<Function<'static>>,
+    ) -> Function<'static> {
+        Function {
+            inline: true,
+            ..function(name, lines, inlinees)
+        }
+    }
+
+    #[test]
+    fn test_sequential_lookup() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = convert_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let offset = symcache.header.range_offset;
+        let first = offset + symcache.ranges.first().unwrap();
+        let last = offset + symcache.ranges.last().unwrap();
+        let addrs: Vec<u64> = (first.saturating_sub(0x10)..last + 0x10)
+            .step_by(3)
+            .collect();
+
+        let mut cursor = symcache.sequential_lookup();
+        for &addr in &addrs {
+            let expected = locations(symcache.lookup(addr));
+            assert_eq!(locations(cursor.next(addr)), expected, "{:#x}", addr);
+        }
+
+        // Going backwards falls back to a binary search.
+        for &addr in addrs.iter().rev().step_by(7) {
+            let expected = locations(symcache.lookup(addr));
+            assert_eq!(locations(cursor.next(addr)), expected, "{:#x}", addr);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_symbolicate_stack() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let buffer = convert(|converter| {
+            converter.set_arch(object.arch());
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        // Pick addresses at the start of a range, where the adjusted address resolves differently.
+        let frames = |addr: u64| symcache.lookup(addr).collect::<Vec<_>>();
+        let addresses: Vec<u64> = symcache
+            .ranges
+            .iter()
+            .filter(|&addr| addr > 0 && frames(addr) != frames(addr - 1))
+            .take(3)
+            .collect();
+        assert_eq!(addresses.len(), 3);
+
+        let stack = symcache.symbolicate_stack(&addresses);
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack[0], frames(addresses[0]));
+        assert_eq!(stack[1], frames(addresses[1] - 1));
+        assert_eq!(stack[2], frames(addresses[2] - 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_covers() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = convert_fixture("linux/crash.debug")?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let offset = symcache.header.range_offset;
+        let first = offset + symcache.ranges.first().unwrap();
+        let last = offset + symcache.ranges.last().unwrap();
+        for addr in (first.saturating_sub(0x10)..last + 0x10).step_by(5) {
+            let covered = symcache.lookup(addr).next().is_some();
+            assert_eq!(symcache.covers(addr), covered, "{:#x}", addr);
+        }
+        assert!(!symcache.covers(u64::MAX));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_range() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let buffer = convert(|converter| {
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let range = symcache.address_range().unwrap();
+        assert_eq!(range.start, symcache.ranges.first().unwrap());
+        assert_eq!(range.end, 0x1_0000_0000);
+        assert!(!symcache.covers(range.start - 1));
+        assert!(symcache.covers(range.start));
+        assert!(symcache.covers(range.end - 1));
+        assert!(!symcache.covers(range.end));
+
+        let buffer = convert(|converter| {
+            converter.set_address_range(range.start..range.start + 0x100);
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let restricted = SymCache::parse(&buffer)?;
+        assert_eq!(
+            restricted.address_range(),
+            Some(range.start..range.start + 0x100)
+        );
+
+        let buffer = convert(|_| Ok(()))?;
+        assert_eq!(SymCache::parse(&buffer)?.address_range(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_by_name() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let linear_buffer = convert(|converter| {
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let linear = SymCache::parse(&linear_buffer)?;
+        assert!(!linear.features().has_name_index());
+
+        let buffer = convert(|converter| {
+            converter.set_name_index(true);
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let indexed = SymCache::parse(&buffer)?;
+        assert!(indexed.features().has_name_index());
+
+        let entry_pcs = |symcache: &SymCache<'_>, name: &str| {
+            symcache
+                .lookup_by_name(name)
+                .map(|function| function.entry_pc())
+                .collect::<Vec<_>>()
+        };
+
+        for name in linear.functions().filter_map(|function| function.name()) {
+            let expected = entry_pcs(&linear, name);
+            assert!(!expected.is_empty());
+            assert_eq!(entry_pcs(&indexed, name), expected, "{}", name);
+        }
+        assert!(entry_pcs(&indexed, "does not exist").is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_abs() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = convert_fixture("linux/crash.debug")?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let image_base = 0x7f00_0000_0000;
+        for range in symcache.ranges.iter().step_by(7) {
+            let addr = symcache.header.range_offset + range;
+            assert_eq!(
+                locations(symcache.lookup_abs(image_base + addr, image_base)),
+                locations(symcache.lookup(addr)),
+                "{:#x}",
+                addr
+            );
+        }
+
+        assert!(symcache.lookup_abs(0x1000, image_base).next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_path() -> Result<(), Box<dyn std::error::Error>> {
+        let files = [
+            // relative path
+            (
+                Some("/home/user/project"),
+                Some("src"),
+                "main.c",
+                "/home/user/project/src/main.c",
+            ),
+            // absolute directory overrides the compilation directory
+            (
+                Some("/home/user/project"),
+                Some("/usr/include"),
+                "stdio.h",
+                "/usr/include/stdio.h",
+            ),
+            // parent and current directory components
+            (
+                Some("/home/user/project/build"),
+                Some("../src/./lib"),
+                "lib.c",
+                "/home/user/project/src/lib/lib.c",
+            ),
+            // missing compilation directory
+            (None, Some("src"), "main.c", "src/main.c"),
+            // missing directory
+            (
+                Some("/home/user/project"),
+                None,
+                "main.c",
+                "/home/user/project/main.c",
+            ),
+            // only a path name
+            (None, None, "main.c", "main.c"),
+            // windows paths
+            (
+                Some("C:\\project"),
+                Some("src"),
+                "main.c",
+                "C:\\project\\src\\main.c",
+            ),
+        ];
+
+        let buffer = convert(|converter| {
+            for &(comp_dir, directory, path_name, _) in &files {
+                converter.insert_file(path_name, directory, comp_dir);
+            }
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        for (file_idx, &(_, _, _, expected)) in files.iter().enumerate() {
+            assert_eq!(symcache.full_path(file_idx as u32)?, expected);
+        }
+
+        let file_idx = files.len() as u32;
+        assert!(matches!(
+            symcache.full_path(file_idx),
+            Err(Error::InvalidFileRef(idx)) if idx == file_idx
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_ranges() -> Result<(), Box<dyn std::error::Error>> {
+        // `split` is discontiguous, with its cold part placed behind `other`.
+        let mut split = function("split", &[(0x1000, 1), (0x1008, 2), (0x1020, 3)], vec![]);
+        split.size = 0x30;
+
+        let buffer = convert(|converter| {
+            converter.process_symbolic_function(&split);
+            converter.process_symbolic_function(&function("other", &[(0x1010, 4)], vec![]));
+            converter.process_symbolic_function(&function("last", &[(0x1030, 5)], vec![]));
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let split = symcache
+            .functions()
+            .find(|f| f.name() == Some("split"))
+            .unwrap();
+        let ranges: Vec<_> = symcache.function_ranges(&split).collect();
+        assert_eq!(ranges, vec![(0x1000, 0x1010), (0x1020, 0x1030)]);
+
+        let size: u64 = ranges.iter().map(|(start, end)| end - start).sum();
+        assert_eq!(size, 0x20);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nameless_function() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = convert(|converter| {
+            converter.process_symbolic_function(&function("", &[(0x1000, 42)], vec![]));
+            converter.process_symbolic_symbol(&Symbol {
+                name: Some("".into()),
+                address: 0x1020,
+                size: 0x10,
+            });
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+        symcache.validate()?;
+
+        // Empty names are not stored at all.
+        assert!(symcache.functions.iter().all(|f| f.name_idx == u32::MAX));
+
+        let source_location = symcache.lookup(0x1008).next().unwrap();
+        assert_eq!(source_location.line(), Some(42));
+        assert_eq!(
+            source_location
+                .file()
+                .map(|file| file.full_path())
+                .as_deref(),
+            Some("/src/main.cpp")
+        );
+        let function = source_location.function().unwrap();
+        assert_eq!(function.name(), None);
+        assert_eq!(function.entry_pc(), 0x1000);
+
+        let function = symcache.lookup(0x1020).next().and_then(|sl| sl.function());
+        assert_eq!(
+            function.map(|f| (f.name(), f.entry_pc())),
+            Some((None, 0x1020))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inline_depth() -> Result<(), Box<dyn std::error::Error>> {
+        // `outer` inlines `middle` at 0x1010, which in turn inlines `inner` at 0x1020.
+        let inner = inlinee("inner", &[(0x1020, 30)], vec![]);
+        let middle = inlinee("middle", &[(0x1010, 20), (0x1020, 21)], vec![inner]);
+        let outer = function(
+            "outer",
+            &[(0x1000, 10), (0x1010, 11), (0x1020, 11)],
+            vec![middle],
+        );
+
+        let buffer = convert(|converter| {
+            converter.process_symbolic_function(&outer);
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert_eq!(symcache.lookup(0x1000).depth(), 1);
+        assert_eq!(symcache.lookup(0x1010).depth(), 2);
+        assert_eq!(symcache.lookup(0x1020).depth(), 3);
+        assert_eq!(symcache.lookup(0x800).depth(), 0);
+
+        let mut chain = symcache.lookup(0x1020);
+        chain.next();
+        assert_eq!(chain.depth(), 2);
+
+        let inlined: Vec<_> = symcache.lookup(0x1020).map(|sl| sl.is_inlined()).collect();
+        assert_eq!(inlined, [true, true, false]);
+
+        let inline_count = |name| {
+            let function = symcache
+                .functions()
+                .find(|f| f.name() == Some(name))
+                .unwrap();
+            symcache.inline_count(&function)
+        };
+        assert_eq!(inline_count("outer"), 2);
+        assert_eq!(inline_count("middle"), 1);
+        assert_eq!(inline_count("inner"), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_symbolicate() -> Result<(), Box<dyn std::error::Error>> {
+        let inner = inlinee("inner", &[(0x1010, 30)], vec![]);
+        let outer = function("outer", &[(0x1000, 10), (0x1010, 11)], vec![inner]);
+
+        let buffer = convert(|converter| {
+            converter.process_symbolic_function(&outer);
+            converter.process_symbolic_symbol(&Symbol {
+                name: Some("symbol".into()),
+                address: 0x2000,
+                size: 0x10,
+            });
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let format = |addr| -> Result<String, Error> {
+            let frames = symcache.symbolicate(addr)?;
+            let lines: Vec<_> = frames.iter().map(ToString::to_string).collect();
+            Ok(lines.join("\n"))
+        };
+
+        insta::assert_snapshot!(format(0x1014)?, @r###"
+        inner (/src/main.cpp:30)
+        outer (/src/main.cpp:11)
+        "###);
+        insta::assert_snapshot!(format(0x2000)?, @"symbol");
+        assert!(symcache.symbolicate(0x1020)?.is_empty());
+
+        let frame = Frame {
+            name: None,
+            file: Some("main.cpp".into()),
+            line: None,
+        };
+        assert_eq!(frame.to_string(), "?? (main.cpp)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_zero() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 crash
+FILE 0 /src/main.c
+FUNC 1000 20 0 main
+1000 10 0 0
+1010 10 5 0
+";
+        let object = Object::parse(buffer)?;
+
+        let buffer = convert(|converter| {
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let sl = symcache.lookup(0x1000).next().unwrap();
+        assert_eq!(sl.line(), None);
+        assert_eq!(
+            sl.file().map(|file| file.full_path()).as_deref(),
+            Some("/src/main.c")
+        );
+        let sl = symcache.lookup(0x1010).next().unwrap();
+        assert_eq!(sl.line(), Some(5));
+
+        let frames = symcache.symbolicate(0x1000)?;
+        assert_eq!(frames[0].line, None);
+        assert_eq!(frames[0].to_string(), "main (/src/main.c)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inline_depth_macos() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = convert_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let offset = symcache.header.range_offset;
+        let mut max_depth = 0;
+        for range in symcache.ranges.iter() {
+            let lookup = symcache.lookup(offset + range);
+            let depth = lookup.depth();
+            assert_eq!(depth, lookup.count());
+            max_depth = max_depth.max(depth);
+        }
+        assert!(max_depth > 1);
+
+        let inlined = symcache
+            .functions()
+            .map(|function| symcache.inline_count(&function))
+            .sum::<usize>();
+        assert!(inlined > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumb_lookup() -> Result<(), Box<dyn std::error::Error>> {
+        use symbolic_common::Arch;
+
+        let write = |arch: Arch| {
+            convert(|converter| {
+                converter.set_arch(arch);
+                // The range ends at an odd address, which a Thumb return address can point to.
+                converter.set_address_range(0x1000..0x1021);
+                converter.process_symbolic_symbol(&Symbol {
+                    name: Some("thumb_function".into()),
+                    address: 0x1001,
+                    size: 0x20,
+                });
+                Ok(())
+            })
+        };
+
+        let buffer = write(Arch::ArmV7)?;
+        let symcache = SymCache::parse(&buffer)?;
+        for &addr in &[0x1000, 0x1001, 0x1020, 0x1021] {
+            assert!(symcache.covers(addr), "{:#x}", addr);
+            let function = symcache.lookup(addr).next().and_then(|sl| sl.function());
+            assert_eq!(
+                function.as_ref().and_then(|f| f.name()),
+                Some("thumb_function")
+            );
+        }
+
+        let mut sequential = symcache.sequential_lookup();
+        assert!(sequential.next(0x1021).next().is_some());
+
+        // On other architectures, the low bit is part of the address.
+        let buffer = write(Arch::Amd64)?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(!symcache.covers(0x1000));
+        assert!(symcache.covers(0x1001));
+        assert!(!symcache.covers(0x1021));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_language_name() -> Result<(), Box<dyn std::error::Error>> {
+        let mut main = function("main", &[(0x1000, 1)], vec![]);
+        main.name = Name::new("main", NameMangling::Unmangled, Language::Rust);
+
+        let buffer = convert(|converter| {
+            converter.process_symbolic_function(&main);
+            converter.process_symbolic_symbol(&Symbol {
+                name: Some("symbol".into()),
+                address: 0x2000,
+                size: 0x10,
+            });
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let language_name = |addr| {
+            let source_location = symcache.lookup(addr).next().unwrap();
+            source_location.function().unwrap().language_name()
+        };
+        assert_eq!(language_name(0x1000), "Rust");
+        assert_eq!(language_name(0x2000), "unknown");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_locations() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = convert_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        // Gaps are not yielded.
+        let locations = symcache.source_locations().collect::<Result<Vec<_>, _>>()?;
+        let num_gaps = symcache
+            .source_locations
+            .iter()
+            .filter(|sl| sl.is_gap())
+            .count();
+        assert_eq!(
+            locations.len() + num_gaps,
+            symcache.header.num_source_locations as usize
+        );
+
+        let mut source_locations = symcache.source_locations.to_vec();
+        source_locations[0].function_idx = u32::MAX - 1;
+        let broken = SymCache {
+            source_locations: source_locations.into(),
+            ..symcache
+        };
+
+        let mut iter = broken.source_locations();
+        assert!(matches!(
+            iter.next(),
+            Some(Err(Error::InvalidFunctionRef(idx))) if idx == u32::MAX - 1
+        ));
+        assert!(iter.all(|sl| sl.is_ok()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_lookup() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let buffer = convert(|converter| {
+            converter.process_object(&object)?;
+            Ok(())
+        })?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let offset = symcache.header.range_offset;
+        for range in symcache.ranges.iter().step_by(50) {
+            let addr = offset + range;
+
+            let expected: Vec<_> = symcache
+                .lookup(addr)
+                .map(|sl| {
+                    let function = sl.function().unwrap();
+                    let path = sl.file().map(|file| file.full_path()).unwrap_or_default();
+                    let name = function.mangled_name().or_else(|| function.name());
+                    (
+                        name.unwrap().to_owned(),
+                        path,
+                        u64::from(sl.line().unwrap_or(0)),
+                    )
+                })
+                .collect();
+
+            let actual: Vec<_> = object
+                .lookup(addr)?
+                .into_iter()
+                .map(|sl| {
+                    let path = match sl.path_name() {
+                        "" => String::new(),
+                        _ => sl.full_path(),
+                    };
+                    (sl.function_name().to_owned(), path, sl.line())
+                })
+                .collect();
+
+            assert_eq!(actual, expected, "{:#x}", addr);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_columns() -> Result<(), Box<dyn std::error::Error>> {
+        // Columns are read from the DWARF line program, where column 0 means no column.
+        let buffer = convert_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        let symcache = SymCache::parse(&buffer)?;
+        let columns = symcache
+            .source_locations()
+            .map(|sl| sl.map(|sl| sl.column()))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(symcache.features().has_columns());
+        assert!(columns.iter().any(Option::is_some));
+        assert!(!columns.contains(&Some(0)));
+
+        // Breakpad symbols have no columns.
+        let buffer = convert_fixture("xul.sym")?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(!symcache.features().has_columns());
+        let sl = symcache.lookup(0xc6dd98).next().unwrap();
+        assert!(sl.function().unwrap().name().is_some());
+        assert_eq!(sl.column(), None);
+        for sl in symcache.source_locations() {
+            assert_eq!(sl?.column(), None);
+        }
+
+        Ok(())
+    }
+}
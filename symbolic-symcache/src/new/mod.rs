@@ -1,57 +1,328 @@
 //! The SymCache binary format.
 //!
 //!
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::{mem, ptr};
 
+use once_cell::sync::OnceCell;
 use symbolic_common::{Arch, DebugId};
 
 mod compat;
+mod crosscheck;
+mod diff;
 mod error;
+#[cfg(feature = "il2cpp")]
+mod il2cpp;
 mod lookup;
 pub(crate) mod raw;
+mod string_table;
+mod validate;
 mod writer;
 
 pub use compat::*;
 pub use error::Error;
 pub use lookup::*;
+pub use validate::RecordKind;
+#[cfg(feature = "bench")]
+pub use writer::SymCacheConverter;
 
 use raw::align_to_eight;
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Optional features contained in a [`SymCache`].
+///
+/// Readers can use this to check which optional data was written, without having to scan the
+/// contents of the SymCache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Features(u32);
+
+impl Features {
+    /// Returns the raw bitfield of features.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether the SymCache contains inlined functions.
+    pub fn has_inline(self) -> bool {
+        self.0 & raw::FLAG_HAS_INLINE != 0
+    }
+
+    /// Whether the SymCache contains source file and line information.
+    pub fn has_source(self) -> bool {
+        self.0 & raw::FLAG_HAS_SOURCE != 0
+    }
+
+    /// Whether the SymCache contains column information.
+    pub fn has_columns(self) -> bool {
+        self.0 & raw::FLAG_HAS_COLUMNS != 0
+    }
+
+    /// Whether the SymCache contains an index for looking up functions by name.
+    pub fn has_name_index(self) -> bool {
+        self.0 & raw::FLAG_HAS_NAME_INDEX != 0
+    }
+
+    /// Whether the string data of the SymCache is compressed.
+    pub fn is_compressed(self) -> bool {
+        self.0 & raw::FLAG_COMPRESSED != 0
+    }
+
+    /// Whether the string table is sorted by content, allowing binary searches.
+    pub fn has_sorted_strings(self) -> bool {
+        self.0 & raw::FLAG_SORTED_STRINGS != 0
+    }
+
+    /// Whether the SymCache only contains the names of top-level functions.
+    ///
+    /// Such a SymCache has no files, line numbers or inlined functions. Lookups yield at most one
+    /// source location with the name of the function covering the address.
+    pub fn is_names_only(self) -> bool {
+        self.0 & raw::FLAG_NAMES_ONLY != 0
+    }
+
+    /// Whether the range table stores 64-bit addresses.
+    ///
+    /// This is only the case if the SymCache covers addresses more than 4 GiB above its range
+    /// threshold.
+    pub fn has_wide_ranges(self) -> bool {
+        self.0 & raw::FLAG_WIDE_RANGES != 0
+    }
+
+    /// Whether the SymCache contains the contents of source files.
+    ///
+    /// See [`File::source`] for reading the contents of a file.
+    pub fn has_embedded_sources(self) -> bool {
+        self.0 & raw::FLAG_HAS_EMBEDDED_SOURCES != 0
+    }
+}
+
+/// The range table of a [`SymCache`], containing the start addresses of all ranges relative to the
+/// range threshold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Ranges<'data> {
+    /// Ranges with 32-bit addresses.
+    Narrow(Cow<'data, [raw::Range]>),
+    /// Ranges with 64-bit addresses, see [`Features::has_wide_ranges`].
+    Wide(Cow<'data, [raw::WideRange]>),
+}
+
+impl<'data> Ranges<'data> {
+    /// Returns the number of ranges.
+    fn len(&self) -> usize {
+        match self {
+            Ranges::Narrow(ranges) => ranges.len(),
+            Ranges::Wide(ranges) => ranges.len(),
+        }
+    }
+
+    /// Returns the start address of the range at `range_idx`.
+    fn get(&self, range_idx: usize) -> Option<u64> {
+        match self {
+            Ranges::Narrow(ranges) => ranges.get(range_idx).map(|range| u64::from(range.0)),
+            Ranges::Wide(ranges) => ranges.get(range_idx).map(|range| range.0),
+        }
+    }
+
+    /// Returns the start address of the first range.
+    fn first(&self) -> Option<u64> {
+        self.get(0)
+    }
+
+    /// Returns the start address of the last range.
+    fn last(&self) -> Option<u64> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    /// Returns the first address that cannot be stored in this range table, if any.
+    fn limit(&self) -> Option<u64> {
+        match self {
+            Ranges::Narrow(_) => Some(u64::from(u32::MAX) + 1),
+            Ranges::Wide(_) => None,
+        }
+    }
+
+    /// Binary searches the ranges for one starting at `addr`.
+    ///
+    /// See [`slice::binary_search`] for the returned value.
+    fn binary_search(&self, addr: u64) -> Result<usize, usize> {
+        use std::convert::TryFrom;
+        match self {
+            Ranges::Narrow(ranges) => match u32::try_from(addr) {
+                Ok(addr) => ranges.binary_search_by_key(&addr, |range| range.0),
+                Err(_) => Err(ranges.len()),
+            },
+            Ranges::Wide(ranges) => ranges.binary_search_by_key(&addr, |range| range.0),
+        }
+    }
+
+    /// Returns an iterator over the start addresses of all ranges.
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len()).filter_map(move |range_idx| self.get(range_idx))
+    }
+}
+
+/// Whether the string data of a SymCache is valid UTF-8, which is determined on first access.
+///
+/// This is a cache of the validation result, so it is ignored when comparing SymCaches.
+struct ValidStrings(AtomicU8);
+
+impl ValidStrings {
+    const UNKNOWN: u8 = 0;
+    const VALID: u8 = 1;
+    const INVALID: u8 = 2;
+
+    fn new() -> Self {
+        ValidStrings(AtomicU8::new(Self::UNKNOWN))
+    }
+
+    /// Returns the string data as `str` if all of it is valid UTF-8.
+    ///
+    /// The data is validated on the first call. It must be the same on every call, which holds
+    /// for the immutable string data of a SymCache.
+    fn get<'data>(&self, string_bytes: &'data [u8]) -> Option<&'data str> {
+        match self.0.load(Ordering::Relaxed) {
+            // SAFETY: the same bytes have been validated on a previous call.
+            Self::VALID => Some(unsafe { std::str::from_utf8_unchecked(string_bytes) }),
+            Self::INVALID => None,
+            _ => {
+                let string_data = std::str::from_utf8(string_bytes).ok();
+                let state = match string_data {
+                    Some(_) => Self::VALID,
+                    None => Self::INVALID,
+                };
+                self.0.store(state, Ordering::Relaxed);
+                string_data
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ValidStrings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ValidStrings").finish()
+    }
+}
+
+impl PartialEq for ValidStrings {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ValidStrings {}
+
+/// The ranges of all functions as `(function_idx, range_idx)`, sorted by function, which is built
+/// on first access.
+///
+/// A function is listed for every range it occurs at anywhere in the inlining hierarchy. This is a
+/// cache of the range table, so it is ignored when comparing SymCaches.
+struct FunctionRangeIndex(OnceCell<Vec<(u32, u32)>>);
+
+impl FunctionRangeIndex {
+    fn new() -> Self {
+        FunctionRangeIndex(OnceCell::new())
+    }
+}
+
+impl fmt::Debug for FunctionRangeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FunctionRangeIndex").finish()
+    }
+}
+
+impl PartialEq for FunctionRangeIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for FunctionRangeIndex {}
+
 /// The serialized SymCache binary format.
 ///
 /// This can be parsed from a binary buffer via [`Format::parse`], and lookups on it can be performed
 /// via the [`Format::lookup`] method.
+///
+/// Record tables borrow from the parsed buffer. They are only copied if the buffer is not aligned
+/// to eight bytes. Compressed string data is decompressed into an owned buffer, so strings borrow
+/// from the SymCache rather than from the parsed buffer.
 #[derive(Debug, PartialEq, Eq)]
 pub struct SymCache<'data> {
-    header: &'data raw::Header,
-    strings: &'data [raw::String],
-    files: &'data [raw::File],
-    functions: &'data [raw::Function],
-    source_locations: &'data [raw::SourceLocation],
-    ranges: &'data [raw::Range],
-    string_bytes: &'data [u8],
+    header: raw::Header,
+    /// The section table.
+    sections: Cow<'data, [raw::Section]>,
+    strings: Cow<'data, [raw::String]>,
+    files: Cow<'data, [raw::File]>,
+    functions: Cow<'data, [raw::Function]>,
+    source_locations: Cow<'data, [raw::SourceLocation]>,
+    /// The columns of source locations by index, empty for SymCaches without columns.
+    columns: Cow<'data, [u16]>,
+    ranges: Ranges<'data>,
+    /// Indexes of all functions sorted by name, if the SymCache has a name index.
+    name_index: Cow<'data, [u32]>,
+    /// The string data, which is owned if it was decompressed on load.
+    string_bytes: Cow<'data, [u8]>,
+    /// The locations of embedded file contents by file index, empty for SymCaches without them.
+    file_sources: Cow<'data, [raw::FileSource]>,
+    /// The embedded contents of source files, which is empty for SymCaches without them.
+    source_bytes: &'data [u8],
+    /// Whether the string data is valid UTF-8, see [`string`](Self::string).
+    valid_strings: ValidStrings,
+    /// The ranges of all functions, see [`function_ranges`](Self::function_ranges).
+    function_range_index: FunctionRangeIndex,
 }
 
 impl<'data> SymCache<'data> {
     /// Parse the SymCache binary format into a convenient type that allows safe access and allows
     /// fast lookups.
     ///
-    /// See the [raw module](raw) for an explanation of the binary format.
+    /// See the [raw module](raw) for an explanation of the binary format. Only SymCaches of
+    /// version [`SYMCACHE_VERSION`](raw::SYMCACHE_VERSION) are supported.
+    ///
+    /// SymCaches locate their sections via a [section table](raw::SectionTable), and sections
+    /// unknown to this reader are skipped. An invalid entry for a known section returns
+    /// [`Error::BadSection`].
+    ///
+    /// Unknown [optional flags](raw::FLAG_REQUIRED_MASK) in the header are ignored, while unknown
+    /// required flags return [`Error::UnsupportedFeature`].
+    ///
+    /// The buffer should be aligned to eight bytes, so that all record tables can be borrowed from
+    /// it. Otherwise, for instance if the buffer is a slice at an odd offset into a memory map, the
+    /// records are copied into aligned tables, which yields the same results at the cost of memory.
+    ///
+    /// If the SymCache ends with a [checksum trailer](raw::Trailer), the checksum is verified and
+    /// [`Error::BadChecksum`] is returned on mismatch. A buffer shorter than advertised in the
+    /// header returns [`Error::Truncated`].
+    ///
+    /// [Compressed](Features::is_compressed) string data is decompressed once while parsing, so
+    /// that lookups are as fast as on a SymCache that was never compressed. Its uncompressed size
+    /// may exceed the compressed size by at most
+    /// [`MAX_COMPRESSION_RATIO`](raw::MAX_COMPRESSION_RATIO), otherwise [`Error::BadCompression`]
+    /// is returned. Without the `compression` feature, compressed SymCaches return
+    /// [`Error::Compressed`].
     pub fn parse(buf: &'data [u8]) -> Result<Self> {
-        if align_to_eight(buf.as_ptr() as usize) != 0 {
-            return Err(Error::BufferNotAligned);
+        let mut symcache = Self::parse_raw(buf)?;
+        if symcache.features().is_compressed() {
+            let string_bytes = decompress_string_bytes(&symcache.string_bytes)?;
+            symcache.string_bytes = Cow::Owned(string_bytes);
         }
+        Ok(symcache)
+    }
 
+    /// Parses the SymCache binary format without decompressing compressed string data.
+    fn parse_raw(buf: &'data [u8]) -> Result<Self> {
         let mut header_size = mem::size_of::<raw::Header>();
         header_size += align_to_eight(header_size);
 
         if buf.len() < header_size {
             return Err(Error::HeaderTooSmall);
         }
-        // SAFETY: we checked that the buffer is well aligned and large enough to fit a `raw::Header`.
-        let header = unsafe { &*(buf.as_ptr() as *const raw::Header) };
+        // SAFETY: we checked that the buffer is large enough to fit a `raw::Header`, which is read
+        // without assuming alignment.
+        let header = unsafe { ptr::read_unaligned(buf.as_ptr() as *const raw::Header) };
         if header.magic == raw::SYMCACHE_MAGIC_FLIPPED {
             return Err(Error::WrongEndianness);
         }
@@ -61,96 +332,177 @@ impl<'data> SymCache<'data> {
         if header.version != raw::SYMCACHE_VERSION {
             return Err(Error::WrongVersion);
         }
+        let unknown_required = header.flags & raw::FLAG_REQUIRED_MASK & !raw::KNOWN_FLAGS;
+        if unknown_required != 0 {
+            return Err(Error::UnsupportedFeature(unknown_required.trailing_zeros()));
+        }
+        let wide_ranges = header.flags & raw::FLAG_WIDE_RANGES != 0;
+        let range_size = if wide_ranges {
+            mem::size_of::<raw::WideRange>()
+        } else {
+            mem::size_of::<raw::Range>()
+        };
 
-        let mut strings_size = mem::size_of::<raw::String>() * header.num_strings as usize;
-        strings_size += align_to_eight(strings_size);
-
-        let mut files_size = mem::size_of::<raw::File>() * header.num_files as usize;
-        files_size += align_to_eight(files_size);
-
-        let mut functions_size = mem::size_of::<raw::Function>() * header.num_functions as usize;
-        functions_size += align_to_eight(functions_size);
-
-        let mut source_locations_size =
-            mem::size_of::<raw::SourceLocation>() * header.num_source_locations as usize;
-        source_locations_size += align_to_eight(source_locations_size);
+        let num_name_index = if header.flags & raw::FLAG_HAS_NAME_INDEX != 0 {
+            header.num_functions
+        } else {
+            0
+        };
 
-        let mut ranges_size = mem::size_of::<raw::Range>() * header.num_ranges as usize;
-        ranges_size += align_to_eight(ranges_size);
+        let section_lens = [
+            (
+                raw::SECTION_STRINGS,
+                section_len(mem::size_of::<raw::String>(), header.num_strings)?,
+            ),
+            (
+                raw::SECTION_FILES,
+                section_len(mem::size_of::<raw::File>(), header.num_files)?,
+            ),
+            (
+                raw::SECTION_FUNCTIONS,
+                section_len(mem::size_of::<raw::Function>(), header.num_functions)?,
+            ),
+            (
+                raw::SECTION_SOURCE_LOCATIONS,
+                section_len(
+                    mem::size_of::<raw::SourceLocation>(),
+                    header.num_source_locations,
+                )?,
+            ),
+            (
+                raw::SECTION_RANGES,
+                section_len(range_size, header.num_ranges)?,
+            ),
+            (
+                raw::SECTION_NAME_INDEX,
+                section_len(mem::size_of::<u32>(), num_name_index)?,
+            ),
+            (raw::SECTION_STRING_BYTES, header.string_bytes as usize),
+        ];
 
-        let expected_buf_size = header_size
-            + strings_size
-            + files_size
-            + functions_size
-            + source_locations_size
-            + ranges_size
-            + header.string_bytes as usize;
+        let has_checksum = header.flags & raw::FLAG_HAS_CHECKSUM != 0;
+        let (section_table, sections) =
+            table_sections(buf, header_size, has_checksum, &section_lens)?;
+        let source_bytes =
+            optional_section(buf, header_size, &section_table, raw::SECTION_SOURCE_BYTES)?;
+        let file_sources =
+            optional_section(buf, header_size, &section_table, raw::SECTION_FILE_SOURCES)?;
+        let file_sources_len = mem::size_of::<raw::FileSource>() * header.num_files as usize;
+        if !file_sources.is_empty() && file_sources.len() != file_sources_len {
+            return Err(Error::BadSection(raw::SECTION_FILE_SOURCES));
+        }
+        let columns = optional_section(buf, header_size, &section_table, raw::SECTION_COLUMNS)?;
+        let columns_len = mem::size_of::<u16>() * header.num_source_locations as usize;
+        if !columns.is_empty() && columns.len() != columns_len {
+            return Err(Error::BadSection(raw::SECTION_COLUMNS));
+        }
 
-        if buf.len() != expected_buf_size || source_locations_size < ranges_size {
+        // Every range has a source location at the same index, which lookups rely on.
+        if header.num_source_locations < header.num_ranges {
             return Err(Error::BadFormatLength);
         }
 
-        // SAFETY: we just made sure that all the pointers we are constructing via pointer
-        // arithmetic are within `buf`
-        let strings_start = unsafe { buf.as_ptr().add(header_size) };
-        let files_start = unsafe { strings_start.add(strings_size) };
-        let functions_start = unsafe { files_start.add(files_size) };
-        let source_locations_start = unsafe { functions_start.add(functions_size) };
-        let ranges_start = unsafe { source_locations_start.add(source_locations_size) };
-        let string_bytes_start = unsafe { ranges_start.add(ranges_size) };
-
-        // SAFETY: the above buffer size check also made sure we are not going out of bounds
-        // here
-        let strings = unsafe {
-            &*(ptr::slice_from_raw_parts(strings_start, header.num_strings as usize)
-                as *const [raw::String])
-        };
-        let files = unsafe {
-            &*(ptr::slice_from_raw_parts(files_start, header.num_files as usize)
-                as *const [raw::File])
-        };
-        let functions = unsafe {
-            &*(ptr::slice_from_raw_parts(functions_start, header.num_functions as usize)
-                as *const [raw::Function])
-        };
-        let source_locations = unsafe {
-            &*(ptr::slice_from_raw_parts(
-                source_locations_start,
-                header.num_source_locations as usize,
-            ) as *const [raw::SourceLocation])
-        };
-        let ranges = unsafe {
-            &*(ptr::slice_from_raw_parts(ranges_start, header.num_ranges as usize)
-                as *const [raw::Range])
-        };
-        let string_bytes = unsafe {
-            &*(ptr::slice_from_raw_parts(string_bytes_start, header.string_bytes as usize)
-                as *const [u8])
-        };
+        if has_checksum {
+            let (body, trailer) = buf.split_at(buf.len() - mem::size_of::<raw::Trailer>());
+            // SAFETY: the trailer is the last record of the buffer, which we checked to be large
+            // enough above. It is read without assuming alignment.
+            let trailer = unsafe { ptr::read_unaligned(trailer.as_ptr() as *const raw::Trailer) };
+            if trailer.magic != raw::TRAILER_MAGIC || trailer.checksum != crc32fast::hash(body) {
+                return Err(Error::BadChecksum);
+            }
+        }
 
+        let [strings, files, functions, source_locations, ranges, name_index, string_bytes] =
+            sections;
+
+        let valid_strings = ValidStrings::new();
+        // SAFETY: we checked that the section sizes match the number of records in the header.
         Ok(SymCache {
             header,
-            strings,
-            files,
-            functions,
-            source_locations,
-            ranges,
-            string_bytes,
+            sections: section_table,
+            strings: unsafe { cast_section(strings) },
+            files: unsafe { cast_section(files) },
+            functions: unsafe { cast_section(functions) },
+            source_locations: unsafe { cast_section(source_locations) },
+            columns: unsafe { cast_section(columns) },
+            ranges: if wide_ranges {
+                Ranges::Wide(unsafe { cast_section(ranges) })
+            } else {
+                Ranges::Narrow(unsafe { cast_section(ranges) })
+            },
+            name_index: unsafe { cast_section(name_index) },
+            string_bytes: Cow::Borrowed(string_bytes),
+            file_sources: unsafe { cast_section(file_sources) },
+            source_bytes,
+            valid_strings,
+            function_range_index: FunctionRangeIndex::new(),
         })
     }
 
+    /// Resolves a string reference to the string data in the parsed buffer.
+    ///
+    /// The returned string is borrowed from the string data without copying or allocating. The UTF-8
+    /// encoding of all string data is validated at once on the first access, so later accesses
+    /// are as cheap as slicing the buffer. If the string data is not valid as a whole, every
+    /// access validates the requested string only.
+    ///
+    /// Returns [`Error::InvalidStringRef`] if the reference is out of bounds, and
+    /// [`Error::BadEncoding`] if the string is not valid UTF-8.
+    pub fn string(&self, string_idx: u32) -> Result<&str> {
+        let invalid = || Error::InvalidStringRef(string_idx);
+        let string = self.strings.get(string_idx as usize).ok_or_else(invalid)?;
+
+        let start_offset = string.string_offset as usize;
+        let end_offset = start_offset
+            .checked_add(string.string_len as usize)
+            .ok_or_else(invalid)?;
+        let bytes = self
+            .string_bytes
+            .get(start_offset..end_offset)
+            .ok_or_else(invalid)?;
+
+        match self.valid_strings.get(&self.string_bytes) {
+            // A string within valid string data may still start or end inside of a character.
+            Some(string_data) => string_data
+                .get(start_offset..end_offset)
+                .ok_or(Error::BadEncoding(string_idx)),
+            // Strings with valid encoding can still be read if other strings are corrupted.
+            None => std::str::from_utf8(bytes).map_err(|_| Error::BadEncoding(string_idx)),
+        }
+    }
+
     /// Resolves a string reference to the pointed-to `&str` data.
-    fn get_string(&self, string_idx: u32) -> Option<&'data str> {
+    ///
+    /// Like [`string`](Self::string), but returns `None` for missing and invalid strings.
+    fn get_string(&self, string_idx: u32) -> Option<&str> {
         if string_idx == u32::MAX {
             return None;
         }
-        let string = self.strings.get(string_idx as usize)?;
+        self.string(string_idx).ok()
+    }
 
-        let start_offset = string.string_offset as usize;
-        let end_offset = start_offset + string.string_len as usize;
-        let bytes = self.string_bytes.get(start_offset..end_offset)?;
+    /// Finds the index of the string with the given contents in the string table.
+    ///
+    /// If the string table is [sorted](Features::has_sorted_strings), this performs a binary
+    /// search. Otherwise, all strings are scanned.
+    pub fn find_string(&self, s: &str) -> Option<u32> {
+        let bytes = |string: &raw::String| {
+            let start_offset = string.string_offset as usize;
+            let end_offset = start_offset.checked_add(string.string_len as usize)?;
+            self.string_bytes.get(start_offset..end_offset)
+        };
 
-        std::str::from_utf8(bytes).ok()
+        let string_idx = if self.features().has_sorted_strings() {
+            self.strings
+                .binary_search_by(|string| bytes(string).unwrap_or_default().cmp(s.as_bytes()))
+                .ok()?
+        } else {
+            self.strings
+                .iter()
+                .position(|string| bytes(string) == Some(s.as_bytes()))?
+        };
+
+        Some(string_idx as u32)
     }
 
     /// The version of the SymCache file format.
@@ -160,11 +512,873 @@ impl<'data> SymCache<'data> {
 
     /// The architecture of the symbol file.
     pub fn arch(&self) -> Arch {
-        self.header.arch
+        Arch::from_u32(self.header.arch)
     }
 
     /// The debug identifier of the cache file.
     pub fn debug_id(&self) -> DebugId {
         self.header.debug_id
     }
+
+    /// The optional features contained in this SymCache.
+    pub fn features(&self) -> Features {
+        Features(self.header.flags)
+    }
+
+    /// The distinct compilers that produced the functions in this SymCache.
+    ///
+    /// This is based on `DW_AT_producer` in DWARF, and is empty if the debug information did not
+    /// record a producer, or if the SymCache was built from a symbol table only.
+    pub fn producers(&self) -> Vec<&str> {
+        let mut seen = std::collections::HashSet::new();
+        self.functions
+            .iter()
+            .filter(|function| seen.insert(function.producer_idx))
+            .filter_map(|function| self.get_string(function.producer_idx))
+            .collect()
+    }
+}
+
+/// Decompresses the string data section of a SymCache with [`raw::FLAG_COMPRESSED`].
+///
+/// The uncompressed size is read from the section prefix, and is checked against the size of the
+/// zstd frame before allocating, so that corrupted SymCaches cannot request arbitrary amounts of
+/// memory.
+#[cfg(feature = "compression")]
+fn decompress_string_bytes(section: &[u8]) -> Result<Vec<u8>> {
+    let prefix_size = mem::size_of::<raw::CompressedStringBytes>();
+    if section.len() < prefix_size {
+        return Err(Error::BadCompression);
+    }
+    // SAFETY: we just checked that the section is large enough to fit the prefix, which is read
+    // without assuming alignment.
+    let prefix =
+        unsafe { ptr::read_unaligned(section.as_ptr() as *const raw::CompressedStringBytes) };
+    let frame = &section[prefix_size..];
+    if frame.len() != prefix.compressed_len as usize {
+        return Err(Error::BadCompression);
+    }
+
+    let uncompressed_len = prefix.uncompressed_len as usize;
+    let max_len = frame
+        .len()
+        .saturating_mul(raw::MAX_COMPRESSION_RATIO as usize);
+    if uncompressed_len > max_len {
+        return Err(Error::BadCompression);
+    }
+
+    let string_bytes =
+        zstd::block::decompress(frame, uncompressed_len).map_err(|_| Error::BadCompression)?;
+    if string_bytes.len() != uncompressed_len {
+        return Err(Error::BadCompression);
+    }
+    Ok(string_bytes)
+}
+
+/// Rejects compressed string data, which requires the `compression` feature.
+#[cfg(not(feature = "compression"))]
+fn decompress_string_bytes(_section: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::Compressed)
+}
+
+/// The known sections of a SymCache, ordered by their id from [`raw::SECTION_STRINGS`] to
+/// [`raw::SECTION_STRING_BYTES`].
+type Sections<'data> = [&'data [u8]; 7];
+
+/// Returns the size of a section of `len` records of `record_size` bytes, excluding padding.
+///
+/// Fails if the section does not fit into the address space, which can only happen for corrupted
+/// headers on 32-bit platforms.
+fn section_len(record_size: usize, len: u32) -> Result<usize> {
+    record_size
+        .checked_mul(len as usize)
+        .ok_or(Error::Truncated)
+}
+
+/// Returns the given offset after padding it to eight bytes.
+fn align_up(offset: usize) -> Result<usize> {
+    offset
+        .checked_add(align_to_eight(offset))
+        .ok_or(Error::Truncated)
+}
+
+/// Locates the sections of a SymCache via its [section table](raw::SectionTable).
+///
+/// Known sections must match their size in `lens`, and may only be missing from the table if
+/// they are empty. Sections with unknown ids are skipped, but the buffer must still end right
+/// after the last section or the trailer. Returns the section table along with the known
+/// sections.
+fn table_sections<'data>(
+    buf: &'data [u8],
+    header_size: usize,
+    has_checksum: bool,
+    lens: &[(u32, usize); 7],
+) -> Result<(Cow<'data, [raw::Section]>, Sections<'data>)> {
+    use std::convert::TryFrom;
+
+    let body_end = if has_checksum {
+        buf.len()
+            .checked_sub(mem::size_of::<raw::Trailer>())
+            .ok_or(Error::Truncated)?
+    } else {
+        buf.len()
+    };
+
+    let entries_start = header_size + mem::size_of::<raw::SectionTable>();
+    if body_end < entries_start {
+        return Err(Error::Truncated);
+    }
+    // SAFETY: we checked that the section table fits into the buffer after the header. It is read
+    // without assuming alignment.
+    let table =
+        unsafe { ptr::read_unaligned(buf.as_ptr().add(header_size) as *const raw::SectionTable) };
+    let entries_end = section_len(mem::size_of::<raw::Section>(), table.num_sections)?
+        .checked_add(entries_start)
+        .ok_or(Error::Truncated)?;
+    if body_end < entries_end {
+        return Err(Error::Truncated);
+    }
+    // SAFETY: we checked that the entries fit into the buffer.
+    let entries = unsafe { cast_section::<raw::Section>(&buf[entries_start..entries_end]) };
+
+    let mut sections_end = entries_end;
+    for entry in entries.iter() {
+        let end = usize::try_from(entry.offset)
+            .ok()
+            .zip(usize::try_from(entry.len).ok())
+            .and_then(|(start, len)| start.checked_add(len))
+            .ok_or(Error::BadSection(entry.id))?;
+        sections_end = sections_end.max(end);
+    }
+    // The trailer starts at an offset aligned to eight bytes.
+    if has_checksum {
+        sections_end = align_up(sections_end)?;
+    }
+    if body_end < sections_end {
+        return Err(Error::Truncated);
+    }
+    if body_end != sections_end {
+        return Err(Error::BadFormatLength);
+    }
+
+    let mut sections = [&buf[..0]; 7];
+    for (section, &(id, len)) in sections.iter_mut().zip(lens) {
+        let mut matches = entries.iter().filter(|entry| entry.id == id);
+        let entry = match (matches.next(), matches.next()) {
+            (Some(entry), None) => entry,
+            (None, _) if len == 0 => continue,
+            _ => return Err(Error::BadSection(id)),
+        };
+
+        // All sections end within the buffer, which we checked above.
+        let start = entry.offset as usize;
+        if entry.len != len as u64 || align_to_eight(start) != 0 || start < entries_end {
+            return Err(Error::BadSection(id));
+        }
+        *section = &buf[start..start + len];
+    }
+
+    Ok((entries, sections))
+}
+
+/// Locates an optional section, such as the [source bytes](raw::SECTION_SOURCE_BYTES), in the
+/// sections of a SymCache.
+///
+/// The size of these sections is not recorded in the header, so it is taken from the section
+/// table. All sections have been checked to end within the buffer by [`table_sections`]. Returns
+/// an empty slice if the section is missing.
+fn optional_section<'data>(
+    buf: &'data [u8],
+    header_size: usize,
+    entries: &[raw::Section],
+    id: u32,
+) -> Result<&'data [u8]> {
+    let mut matches = entries.iter().filter(|entry| entry.id == id);
+    let entry = match (matches.next(), matches.next()) {
+        (Some(entry), None) => entry,
+        (None, _) => return Ok(&buf[..0]),
+        _ => return Err(Error::BadSection(id)),
+    };
+
+    let entries_end = header_size
+        + mem::size_of::<raw::SectionTable>()
+        + mem::size_of::<raw::Section>() * entries.len();
+    let start = entry.offset as usize;
+    if align_to_eight(start) != 0 || start < entries_end {
+        return Err(Error::BadSection(id));
+    }
+    Ok(&buf[start..start + entry.len as usize])
+}
+
+/// Reinterprets a section as a slice of raw records.
+///
+/// The records are borrowed if the section starts at an address aligned for `T`, and copied into
+/// an aligned table otherwise.
+///
+/// # Safety
+///
+/// `T` must be a record of the [raw format](raw) that is valid for any bit pattern.
+unsafe fn cast_section<T: Clone>(section: &[u8]) -> Cow<'_, [T]> {
+    let len = section.len() / mem::size_of::<T>();
+    let records = section.as_ptr() as *const T;
+    if records as usize % mem::align_of::<T>() == 0 {
+        Cow::Borrowed(&*(ptr::slice_from_raw_parts(records, len)))
+    } else {
+        Cow::Owned(
+            (0..len)
+                .map(|idx| ptr::read_unaligned(records.add(idx)))
+                .collect(),
+        )
+    }
+}
+
+/// Recomputes the checksum in the trailer of a SymCache after modifying it in tests.
+#[cfg(test)]
+pub(crate) fn update_checksum(buffer: &mut [u8]) {
+    let trailer_start = buffer.len() - mem::size_of::<raw::Trailer>();
+    let checksum = crc32fast::hash(&buffer[..trailer_start]);
+    buffer[trailer_start + 4..].copy_from_slice(&checksum.to_ne_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use symbolic_common::ByteView;
+    use symbolic_debuginfo::Object;
+    use symbolic_testutils::fixture;
+
+    use super::writer::SymCacheConverter;
+    use super::*;
+    use crate::{SymCacheError, SymCacheErrorKind};
+
+    #[test]
+    fn test_producers_linux() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let producers = symcache.producers();
+        assert!(!producers.is_empty());
+        assert!(producers.iter().all(|producer| !producer.is_empty()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_features() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let features = symcache.features();
+        let has_inline = symcache
+            .source_locations
+            .iter()
+            .any(|sl| sl.inlined_into_idx != u32::MAX);
+        assert_eq!(features.has_inline(), has_inline);
+        assert!(features.has_source());
+        let has_columns = symcache.columns.iter().any(|&column| column != 0);
+        assert_eq!(features.has_columns(), has_columns);
+        assert!(!features.has_name_index());
+        assert!(!features.is_compressed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_feature_flags() -> Result<(), Box<dyn std::error::Error>> {
+        let write = |flags: u32| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            let mut converter = SymCacheConverter::new();
+            converter.set_feature_flags(flags);
+            let mut buffer = Vec::new();
+            converter.serialize(&mut buffer)?;
+            Ok(buffer)
+        };
+
+        // Unknown optional flags are ignored.
+        let buffer = write(1 << 15)?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert_eq!(symcache.features().bits() & !raw::KNOWN_FLAGS, 1 << 15);
+
+        // Unknown required flags are rejected.
+        let buffer = write(1 << 15 | 1 << 21 | 1 << 31)?;
+        let error = SymCache::parse(&buffer).unwrap_err();
+        assert!(matches!(error, Error::UnsupportedFeature(21)));
+        assert_eq!(
+            SymCacheError::from(error).kind(),
+            SymCacheErrorKind::UnsupportedVersion
+        );
+
+        // Flags derived from the contents cannot be set.
+        let buffer = write(raw::FLAG_COMPRESSED)?;
+        assert!(!SymCache::parse(&buffer)?.features().is_compressed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+
+        let mut data = buffer.clone();
+        data[..4].copy_from_slice(b"XXXX");
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::WrongFormat));
+        assert_eq!(
+            SymCacheError::from(error).kind(),
+            SymCacheErrorKind::BadFileMagic
+        );
+
+        let mut data = buffer.clone();
+        data[4..8].copy_from_slice(&u32::MAX.to_ne_bytes());
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::WrongVersion));
+        assert_eq!(
+            SymCacheError::from(error).kind(),
+            SymCacheErrorKind::UnsupportedVersion
+        );
+
+        let error = SymCache::parse(&buffer[..buffer.len() - 8]).unwrap_err();
+        assert!(matches!(error, Error::Truncated));
+        assert_eq!(
+            SymCacheError::from(error).kind(),
+            SymCacheErrorKind::BadFileHeader
+        );
+
+        let mut data = buffer.clone();
+        data.extend_from_slice(&[0; 8]);
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::BadFormatLength));
+
+        Ok(())
+    }
+
+    /// Returns the raw bytes of the given records, as written by the converter.
+    fn bytes_of<T>(records: &[T]) -> &[u8] {
+        // SAFETY: the records are plain `repr(C)` structs, just like in the converter.
+        unsafe {
+            std::slice::from_raw_parts(records.as_ptr() as *const u8, mem::size_of_val(records))
+        }
+    }
+
+    /// Writes a SymCache from the given header and sections, each padded to eight bytes.
+    ///
+    /// The section table lists the sections in the given order, which is also their order in the
+    /// buffer.
+    fn forge(header: &raw::Header, sections: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut data = bytes_of(std::slice::from_ref(header)).to_vec();
+        data.extend_from_slice(bytes_of(&[raw::SectionTable {
+            num_sections: sections.len() as u32,
+            reserved: 0,
+        }]));
+        let mut offset = data.len() + mem::size_of::<raw::Section>() * sections.len();
+        for &(id, contents) in sections {
+            offset += align_to_eight(offset);
+            data.extend_from_slice(bytes_of(&[raw::Section {
+                id,
+                reserved: 0,
+                offset: offset as u64,
+                len: contents.len() as u64,
+            }]));
+            offset += contents.len();
+        }
+        for &(_, contents) in sections {
+            data.resize(data.len() + align_to_eight(data.len()), 0);
+            data.extend_from_slice(contents);
+        }
+        data
+    }
+
+    /// Returns the header of an empty SymCache without checksum.
+    fn empty_header() -> Result<raw::Header, Box<dyn std::error::Error>> {
+        let mut buffer = Vec::new();
+        SymCacheConverter::new().serialize(&mut buffer)?;
+        let mut header = SymCache::parse(&buffer)?.header.clone();
+        header.flags &= !raw::FLAG_HAS_CHECKSUM;
+        Ok(header)
+    }
+
+    #[test]
+    fn test_parse_malformed() -> Result<(), Box<dyn std::error::Error>> {
+        // More ranges than source locations, which lookups rely on.
+        let mut header = empty_header()?;
+        header.num_source_locations = 1;
+        header.num_ranges = 4;
+        let data = forge(
+            &header,
+            &[
+                (raw::SECTION_SOURCE_LOCATIONS, &[0xff; 16]),
+                (raw::SECTION_RANGES, &[0; 16]),
+            ],
+        );
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::BadFormatLength));
+
+        // Tables larger than any buffer.
+        let mut header = empty_header()?;
+        header.flags |= raw::FLAG_HAS_NAME_INDEX;
+        header.num_strings = u32::MAX;
+        header.num_functions = u32::MAX;
+        header.num_source_locations = u32::MAX;
+        header.num_ranges = u32::MAX;
+        header.string_bytes = u32::MAX;
+        let mut data = forge(&header, &[(raw::SECTION_STRINGS, &[])]);
+        // The section table entry claims the size from the header, beyond the end of the buffer.
+        let len_offset = mem::size_of::<raw::Header>() + mem::size_of::<raw::SectionTable>() + 16;
+        let strings_len = mem::size_of::<raw::String>() as u64 * u64::from(u32::MAX);
+        data[len_offset..len_offset + 8].copy_from_slice(&strings_len.to_ne_bytes());
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::Truncated));
+
+        // An unknown architecture.
+        let mut header = empty_header()?;
+        header.arch = u32::MAX;
+        let data = forge(&header, &[]);
+        assert_eq!(SymCache::parse(&data)?.arch(), Arch::Unknown);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_malformed_lookup() -> Result<(), Box<dyn std::error::Error>> {
+        let function = [
+            0xff, 0xff, 0xff, 0xff, // name_idx
+            0xff, 0xff, 0xff, 0xff, // plain_name_idx
+            0xff, 0xff, 0xff, 0xff, // comp_dir_idx
+            0x00, 0x00, 0x00, 0x00, // entry_pc
+            0x00, 0x00, 0x00, 0x00, // lang
+            0xff, 0xff, 0xff, 0xff, // producer_idx
+            0xff, 0xff, 0xff, 0xff, // unit_idx
+        ];
+        // A source location that claims to be inlined into itself.
+        let source_location = [
+            0xff, 0xff, 0xff, 0xff, // file_idx
+            0x00, 0x00, 0x00, 0x00, // line
+            0x00, 0x00, 0x00, 0x00, // function_idx
+            0x00, 0x00, 0x00, 0x00, // inlined_into_idx
+        ];
+
+        let mut header = empty_header()?;
+        header.num_functions = 1;
+        header.num_source_locations = 1;
+        header.num_ranges = 1;
+        header.range_offset = 0;
+        header.address_range_start = 0;
+        header.address_range_end = u64::MAX;
+
+        let data = forge(
+            &header,
+            &[
+                (raw::SECTION_FUNCTIONS, &function),
+                (raw::SECTION_SOURCE_LOCATIONS, &source_location),
+                (raw::SECTION_RANGES, &[0x00; 4]),
+            ],
+        );
+        let symcache = SymCache::parse(&data)?;
+        assert!(symcache.validate().is_err());
+        assert_eq!(symcache.lookup(0).count(), 1);
+        assert_eq!(symcache.functions().count(), 1);
+        assert!(symcache.source_locations().all(|sl| sl.is_ok()));
+
+        // Relative addresses beyond the end of the address space.
+        header.range_offset = u64::MAX;
+        let data = forge(
+            &header,
+            &[
+                (raw::SECTION_FUNCTIONS, &function),
+                (raw::SECTION_SOURCE_LOCATIONS, &source_location),
+                (raw::SECTION_RANGES, &[0xff; 4]),
+            ],
+        );
+        let symcache = SymCache::parse(&data)?;
+        assert_eq!(symcache.address_range(), Some(u64::MAX..u64::MAX));
+        assert_eq!(symcache.lookup(u64::MAX).count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_unaligned() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+
+        // Copies the SymCache to `offset` bytes past an address aligned to eight bytes.
+        let copy_at = |offset: usize| {
+            let mut storage = vec![0; buffer.len() + 8 + offset];
+            let start = align_to_eight(storage.as_ptr() as usize) + offset;
+            storage[start..start + buffer.len()].copy_from_slice(&buffer);
+            (storage, start..start + buffer.len())
+        };
+        let (aligned_storage, aligned_range) = copy_at(0);
+        let aligned = SymCache::parse(&aligned_storage[aligned_range])?;
+        let (unaligned_storage, unaligned_range) = copy_at(1);
+        let unaligned = SymCache::parse(&unaligned_storage[unaligned_range])?;
+
+        // The writer aligns all sections, so an aligned buffer is read in place.
+        assert!(aligned
+            .sections
+            .iter()
+            .all(|section| section.offset % 8 == 0));
+        assert!(matches!(aligned.source_locations, Cow::Borrowed(_)));
+        assert!(matches!(unaligned.source_locations, Cow::Owned(_)));
+        assert_eq!(unaligned, aligned);
+
+        let frames = |symcache: &SymCache<'_>, addr| -> Vec<(Option<String>, Option<u32>)> {
+            symcache
+                .lookup(addr)
+                .map(|sl| {
+                    let name = sl.function().and_then(|f| f.name().map(str::to_owned));
+                    (name, sl.line())
+                })
+                .collect()
+        };
+        let offset = aligned.header.range_offset;
+        for range in aligned.ranges.iter() {
+            let addr = offset + range;
+            assert_eq!(
+                frames(&unaligned, addr),
+                frames(&aligned, addr),
+                "{:#x}",
+                addr
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_checksum() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(symcache.header.flags & raw::FLAG_HAS_CHECKSUM != 0);
+        // The header is stored by value at the start of the buffer.
+        let flags_offset = &symcache.header.flags as *const u32 as usize
+            - &symcache.header as *const raw::Header as usize;
+        let string_bytes_start = symcache.string_bytes.as_ptr() as usize - buffer.as_ptr() as usize;
+        let string_bytes_end = string_bytes_start + symcache.string_bytes.len();
+
+        let mut data = buffer.clone();
+        data[string_bytes_start] ^= 0xff;
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::BadChecksum));
+        assert_eq!(
+            SymCacheError::from(error).kind(),
+            SymCacheErrorKind::BadCacheFile
+        );
+
+        let error = SymCache::parse(&buffer[..string_bytes_end]).unwrap_err();
+        assert!(matches!(error, Error::Truncated));
+
+        // SymCaches without the trailer are not verified.
+        let mut data = buffer[..string_bytes_end].to_vec();
+        data[flags_offset..flags_offset + 4]
+            .copy_from_slice(&(symcache.header.flags & !raw::FLAG_HAS_CHECKSUM).to_ne_bytes());
+        let unverified = SymCache::parse(&data)?;
+        assert_eq!(unverified.functions().count(), symcache.functions().count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_string() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(symcache.features().has_sorted_strings());
+        let strings: Vec<_> = (0..symcache.strings.len() as u32)
+            .map(|string_idx| symcache.get_string(string_idx).unwrap())
+            .collect();
+        assert!(strings.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let check = |symcache: &SymCache<'_>| {
+            for name in symcache.functions().filter_map(|function| function.name()) {
+                let string_idx = symcache.find_string(name).unwrap();
+                assert_eq!(symcache.get_string(string_idx), Some(name));
+            }
+            assert_eq!(symcache.find_string("does not exist"), None);
+        };
+        check(&symcache);
+
+        // SymCaches without sorted strings are scanned instead.
+        // The header is stored by value at the start of the buffer.
+        let flags_offset = &symcache.header.flags as *const u32 as usize
+            - &symcache.header as *const raw::Header as usize;
+        let mut data = buffer.clone();
+        data[flags_offset..flags_offset + 4]
+            .copy_from_slice(&(symcache.header.flags & !raw::FLAG_SORTED_STRINGS).to_ne_bytes());
+        update_checksum(&mut data);
+        let unsorted = SymCache::parse(&data)?;
+        assert!(!unsorted.features().has_sorted_strings());
+        check(&unsorted);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compression() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut uncompressed = Vec::new();
+        converter.serialize(&mut uncompressed)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_compression_level(3);
+        converter.process_object(&object)?;
+        let mut compressed = Vec::new();
+        converter.serialize(&mut compressed)?;
+
+        let raw = SymCache::parse_raw(&compressed)?;
+        assert!(raw.features().is_compressed());
+        let symcache = SymCache::parse(&uncompressed)?;
+        assert!(raw.string_bytes.len() < symcache.string_bytes.len() / 2);
+        assert!(compressed.len() < uncompressed.len());
+
+        // Parsing decompresses the string data once, and yields the same lookups.
+        let decompressed = SymCache::parse(&compressed)?;
+        assert!(decompressed.features().is_compressed());
+        assert_eq!(decompressed.string_bytes, symcache.string_bytes);
+        let offset = symcache.header.range_offset;
+        for range in symcache.ranges.iter() {
+            let addr = offset + range;
+            let expected: Vec<_> = symcache
+                .lookup(addr)
+                .map(|sl| (sl.function().and_then(|f| f.name()), sl.line()))
+                .collect();
+            let actual: Vec<_> = decompressed
+                .lookup(addr)
+                .map(|sl| (sl.function().and_then(|f| f.name()), sl.line()))
+                .collect();
+            assert_eq!(actual, expected, "{:#x}", addr);
+        }
+
+        // Sections following the string data are located via the section table.
+        let extended = add_unknown_section(&compressed, b"written by a newer converter");
+        assert_eq!(
+            SymCache::parse(&extended)?.string_bytes,
+            symcache.string_bytes
+        );
+
+        let prefix_start = raw.string_bytes.as_ptr() as usize - compressed.as_ptr() as usize;
+        let mut data = compressed.clone();
+        data[prefix_start + 8] ^= 0xff;
+        update_checksum(&mut data);
+        assert!(matches!(SymCache::parse(&data), Err(Error::BadCompression)));
+
+        // The uncompressed size is checked before allocating, since it is read from the file.
+        let compressed_len = (raw.string_bytes.len() - 8) as u32;
+        for &uncompressed_len in &[compressed_len * raw::MAX_COMPRESSION_RATIO + 1, u32::MAX] {
+            let mut data = compressed.clone();
+            data[prefix_start + 4..prefix_start + 8]
+                .copy_from_slice(&uncompressed_len.to_ne_bytes());
+            update_checksum(&mut data);
+            assert!(matches!(SymCache::parse(&data), Err(Error::BadCompression)));
+        }
+
+        Ok(())
+    }
+
+    /// Adds a section with an id unknown to this reader to a SymCache.
+    ///
+    /// The section is stored after all other sections, but listed first in the section table.
+    fn add_unknown_section(buffer: &[u8], contents: &[u8]) -> Vec<u8> {
+        let symcache = SymCache::parse_raw(buffer).unwrap();
+        let header_size = mem::size_of::<raw::Header>();
+        let sections_start = symcache.strings.as_ptr() as usize - buffer.as_ptr() as usize;
+        let body_end = buffer.len() - mem::size_of::<raw::Trailer>();
+
+        // The new entry moves all sections back by its size.
+        let shift = mem::size_of::<raw::Section>() as u64;
+        let mut sections = vec![raw::Section {
+            id: 0xffff,
+            reserved: 0,
+            offset: body_end as u64 + shift,
+            len: contents.len() as u64,
+        }];
+        sections.extend(symcache.sections.iter().map(|section| raw::Section {
+            offset: section.offset + shift,
+            ..section.clone()
+        }));
+
+        let mut data = buffer[..header_size].to_vec();
+        data.extend_from_slice(bytes_of(&[raw::SectionTable {
+            num_sections: sections.len() as u32,
+            reserved: 0,
+        }]));
+        data.extend_from_slice(bytes_of(&sections));
+        data.extend_from_slice(&buffer[sections_start..body_end]);
+        data.extend_from_slice(contents);
+        data.resize(data.len() + align_to_eight(data.len()), 0);
+        data.extend_from_slice(&buffer[body_end..]);
+        update_checksum(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_unknown_section() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_name_index(true);
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert_eq!(symcache.version(), raw::SYMCACHE_VERSION);
+        // The DWARF of the fixture has columns, so the column table is written as well.
+        assert_eq!(symcache.sections.len(), 8);
+
+        let extended_buffer = add_unknown_section(&buffer, b"written by a newer converter");
+        let extended = SymCache::parse(&extended_buffer)?;
+        assert_eq!(extended.sections.len(), 9);
+        extended.validate()?;
+
+        let resolve = |symcache: &SymCache<'_>, addr: u64| -> Vec<_> {
+            symcache
+                .lookup(addr)
+                .map(|sl| {
+                    (
+                        sl.function().and_then(|f| f.name()).map(str::to_owned),
+                        sl.file().map(|f| f.full_path()),
+                        sl.line(),
+                    )
+                })
+                .collect()
+        };
+        for range in symcache.ranges.iter() {
+            let addr = symcache.header.range_offset + range;
+            let expected = resolve(&symcache, addr);
+            assert_eq!(resolve(&extended, addr), expected, "{:#x}", addr);
+        }
+        for function in symcache.functions().filter_map(|function| function.name()) {
+            assert_eq!(
+                extended.lookup_by_name(function).count(),
+                symcache.lookup_by_name(function).count()
+            );
+        }
+
+        // Known sections must match the sizes in the header.
+        let len_offset =
+            &extended.sections[1].len as *const u64 as usize - extended_buffer.as_ptr() as usize;
+        let mut data = extended_buffer.clone();
+        data[len_offset..len_offset + 8].copy_from_slice(&0u64.to_ne_bytes());
+        update_checksum(&mut data);
+        let error = SymCache::parse(&data).unwrap_err();
+        assert!(matches!(error, Error::BadSection(raw::SECTION_STRINGS)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compilation_units() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let main = symcache
+            .functions()
+            .find(|function| function.name() == Some("main"))
+            .unwrap();
+        assert_eq!(main.compilation_unit(), Some("../linux/main.cpp"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compilation_units_breakpad() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("xul.sym"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert!(symcache.functions().next().is_some());
+        assert!(symcache
+            .functions()
+            .all(|function| function.compilation_unit().is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_bad_encoding() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let (string_idx, string) = symcache
+            .strings
+            .iter()
+            .enumerate()
+            .find(|(_, string)| string.string_len > 0)
+            .unwrap();
+        let string_idx = string_idx as u32;
+        let offset = symcache.string_bytes.as_ptr() as usize - buffer.as_ptr() as usize
+            + string.string_offset as usize;
+
+        let mut data = buffer.clone();
+        data[offset] = 0xff;
+        update_checksum(&mut data);
+        let corrupted = SymCache::parse(&data)?;
+        assert!(matches!(
+            corrupted.string(string_idx),
+            Err(Error::BadEncoding(idx)) if idx == string_idx
+        ));
+        assert_eq!(corrupted.get_string(string_idx), None);
+        assert!(matches!(
+            corrupted.string(corrupted.strings.len() as u32),
+            Err(Error::InvalidStringRef(_))
+        ));
+
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@
 //!
 //! TODO: actually write some docs ;-)
 
-use symbolic_common::{Arch, DebugId};
+use symbolic_common::DebugId;
 
 /// The magic file preamble as individual bytes.
 const SYMCACHE_MAGIC_BYTES: [u8; 4] = *b"SYMC";
@@ -14,8 +14,95 @@ pub const SYMCACHE_MAGIC: u32 = u32::from_be_bytes(SYMCACHE_MAGIC_BYTES);
 /// The byte-flipped magic, which indicates an endianness mismatch.
 pub const SYMCACHE_MAGIC_FLIPPED: u32 = SYMCACHE_MAGIC.swap_bytes();
 
+/// The magic identifying a [`Trailer`].
+///
+/// Serialized as ASCII "SYMT" on little-endian (x64) systems.
+pub const TRAILER_MAGIC: u32 = u32::from_be_bytes(*b"SYMT");
+
 /// The latest version of the file format.
-pub const SYMCACHE_VERSION: u32 = 1_000;
+pub const SYMCACHE_VERSION: u32 = 1_001;
+
+/// The SymCache contains inlined functions.
+pub const FLAG_HAS_INLINE: u32 = 1 << 0;
+/// The SymCache contains source file and line information.
+pub const FLAG_HAS_SOURCE: u32 = 1 << 1;
+/// The SymCache contains column information, see [`SECTION_COLUMNS`].
+pub const FLAG_HAS_COLUMNS: u32 = 1 << 2;
+/// The SymCache only contains the names of top-level functions, without files, lines or inlinees.
+pub const FLAG_NAMES_ONLY: u32 = 1 << 3;
+/// The SymCache contains the contents of source files, see [`SECTION_FILE_SOURCES`].
+pub const FLAG_HAS_EMBEDDED_SOURCES: u32 = 1 << 4;
+
+/// The string data of the SymCache is compressed with zstd, see [`CompressedStringBytes`].
+pub const FLAG_COMPRESSED: u32 = 1 << 16;
+/// The [`String`] table is sorted by the contents of the strings.
+pub const FLAG_SORTED_STRINGS: u32 = 1 << 17;
+/// The [`Range`] table contains [`WideRange`] records with 64-bit relative addresses.
+///
+/// The converter only writes these if a relative address does not fit into 32 bits.
+pub const FLAG_WIDE_RANGES: u32 = 1 << 18;
+/// The SymCache ends with a [`Trailer`] containing a checksum of all preceding bytes.
+///
+/// This is required, since a reader that does not know the trailer would read it as part of the
+/// last section.
+pub const FLAG_HAS_CHECKSUM: u32 = 1 << 19;
+/// The SymCache contains an index for looking up functions by name.
+///
+/// The index is stored in [`SECTION_NAME_INDEX`]. It contains the indexes of all [`Function`]s as
+/// `u32`, sorted by the index of their name in the sorted [`String`] table and then by function
+/// index.
+pub const FLAG_HAS_NAME_INDEX: u32 = 1 << 20;
+
+/// The bits of [`Header::flags`] that are reserved for required features.
+///
+/// Flags in the lower half mark optional features, which a reader can ignore if it does not know
+/// them. Flags in the upper half mark features that change how the SymCache must be read, and
+/// readers reject SymCaches with required flags they do not know. This allows extending the format
+/// without bumping [`SYMCACHE_VERSION`].
+pub const FLAG_REQUIRED_MASK: u32 = 0xffff_0000;
+
+/// All flags known to this reader.
+pub const KNOWN_FLAGS: u32 = FLAG_HAS_INLINE
+    | FLAG_HAS_SOURCE
+    | FLAG_HAS_COLUMNS
+    | FLAG_COMPRESSED
+    | FLAG_NAMES_ONLY
+    | FLAG_SORTED_STRINGS
+    | FLAG_WIDE_RANGES
+    | FLAG_HAS_CHECKSUM
+    | FLAG_HAS_NAME_INDEX
+    | FLAG_HAS_EMBEDDED_SOURCES;
+
+/// The [`Section`] containing the [`String`] table.
+pub const SECTION_STRINGS: u32 = 1;
+/// The [`Section`] containing the [`File`] table.
+pub const SECTION_FILES: u32 = 2;
+/// The [`Section`] containing the [`Function`] table.
+pub const SECTION_FUNCTIONS: u32 = 3;
+/// The [`Section`] containing the [`SourceLocation`] table.
+pub const SECTION_SOURCE_LOCATIONS: u32 = 4;
+/// The [`Section`] containing the [`Range`] table.
+pub const SECTION_RANGES: u32 = 5;
+/// The [`Section`] containing the name index, see [`FLAG_HAS_NAME_INDEX`].
+pub const SECTION_NAME_INDEX: u32 = 6;
+/// The [`Section`] containing the string data, which may be [compressed](FLAG_COMPRESSED).
+pub const SECTION_STRING_BYTES: u32 = 7;
+/// The [`Section`] containing the embedded contents of source files.
+///
+/// Unlike other sections, its size is not recorded in the [`Header`]. Files refer to their
+/// contents via [`SECTION_FILE_SOURCES`]. The section is missing if no source files were embedded.
+pub const SECTION_SOURCE_BYTES: u32 = 8;
+/// The [`Section`] containing the [`FileSource`] table.
+///
+/// If present, it contains one [`FileSource`] for every [`File`], at the same index. The section
+/// is missing if no source files were embedded.
+pub const SECTION_FILE_SOURCES: u32 = 9;
+/// The [`Section`] containing the column table.
+///
+/// If present, it contains one `u16` column for every [`SourceLocation`], at the same index.
+/// Columns start at 1, and `0` means that the source location has no column. The section is
+/// missing if no source location has a column.
+pub const SECTION_COLUMNS: u32 = 10;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[repr(C)]
@@ -27,8 +114,17 @@ pub struct Header {
 
     /// Debug identifier of the object file.
     pub debug_id: DebugId,
-    /// CPU architecture of the object file.
-    pub arch: Arch,
+    /// CPU architecture of the object file, as the discriminant of an [`Arch`].
+    ///
+    /// This is stored as a plain integer so that reading a header with an unknown architecture
+    /// does not produce an invalid enum value.
+    ///
+    /// [`Arch`]: symbolic_common::Arch
+    pub arch: u32,
+    /// A bitfield of `FLAG_*` constants describing the contents of the SymCache.
+    ///
+    /// See [`FLAG_REQUIRED_MASK`] for which flags a reader must understand.
+    pub flags: u32,
 
     /// The offset with which all ranges have been offset in the SymCache.
     pub range_offset: u64,
@@ -44,35 +140,202 @@ pub struct Header {
     /// Number of included [`Range`]s.
     pub num_ranges: u32,
     /// Total number of bytes used for string data.
+    ///
+    /// If the string data is [compressed](FLAG_COMPRESSED), this is the size of the compressed
+    /// section, including its [`CompressedStringBytes`] prefix.
     pub string_bytes: u32,
+
+    /// The first address covered by this SymCache.
+    pub address_range_start: u64,
+    /// The first address after the range covered by this SymCache.
+    ///
+    /// This is `u64::MAX` unless the SymCache was restricted to an address range when writing.
+    pub address_range_end: u64,
+}
+
+/// The prefix of the string data section of a SymCache with [`FLAG_COMPRESSED`].
+///
+/// It is followed by a single zstd frame containing the uncompressed string data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct CompressedStringBytes {
+    /// The size of the zstd frame following this prefix.
+    pub compressed_len: u32,
+    /// The size of the string data after decompression.
+    ///
+    /// This is at most [`MAX_COMPRESSION_RATIO`] times `compressed_len`.
+    pub uncompressed_len: u32,
+}
+
+/// The maximum ratio of the uncompressed size to the compressed size of string data.
+///
+/// Readers allocate the uncompressed size before decompressing, so this bounds the memory that a
+/// corrupted SymCache can make them allocate. Writers store string data that compresses better
+/// than this uncompressed.
+pub const MAX_COMPRESSION_RATIO: u32 = 64;
+
+/// The table locating the sections of a SymCache.
+///
+/// This follows the [`Header`] and is itself followed by `num_sections` [`Section`] records.
+/// Readers locate the sections they know by their id and skip all others, so that sections can be
+/// added without breaking older readers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct SectionTable {
+    /// Number of [`Section`]s in the table.
+    pub num_sections: u32,
+    /// Reserved for future use, always zero.
+    pub reserved: u32,
+}
+
+/// The location of a section within a SymCache.
+///
+/// Sections start at offsets aligned to eight bytes and do not overlap, but may appear in any
+/// order. A section that is missing from the table is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct Section {
+    /// The kind of section, one of the `SECTION_*` constants or an id unknown to this reader.
+    pub id: u32,
+    /// Reserved for future use, always zero.
+    pub reserved: u32,
+    /// The offset of the section from the start of the SymCache.
+    pub offset: u64,
+    /// The size of the section in bytes, excluding padding.
+    pub len: u64,
+}
+
+/// The last record of a SymCache with [`FLAG_HAS_CHECKSUM`].
+///
+/// It follows the last section after padding it to eight bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct Trailer {
+    /// The magic identifying the trailer, always [`TRAILER_MAGIC`].
+    pub magic: u32,
+    /// The CRC32 checksum of all bytes before the trailer, including padding.
+    pub checksum: u32,
+}
+
+/// The value of an optional `u32` field in a record that is not set.
+///
+/// Records are read directly from the buffer, so optional fields are stored as plain integers
+/// instead of `Option`s, whose layout is not guaranteed. This applies to references to other
+/// records and to the entry pc of inlined functions. Use the accessor methods of the records to
+/// read such fields as `Option`s.
+pub const NONE: u32 = u32::MAX;
+
+/// Converts an optional `u32` field of a record, which is [`NONE`] if it is not set.
+fn optional(value: u32) -> Option<u32> {
+    match value {
+        NONE => None,
+        value => Some(value),
+    }
 }
 
 /// Serialized Function metadata in the SymCache.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[repr(C)]
 pub struct Function {
-    /// The functions name (reference to a [`String`]).
+    /// The full name of the function, which is its linkage name if it has one (reference to a
+    /// [`String`]), or [`NONE`].
     pub name_idx: u32,
-    /// The compilation directory (reference to a [`String`]).
+    /// The optional plain name as declared in source, if the name is a linkage name (reference
+    /// to a [`String`]), or [`NONE`].
+    pub plain_name_idx: u32,
+    /// The compilation directory (reference to a [`String`]), or [`NONE`].
     pub comp_dir_idx: u32,
-    /// The first address covered by this function.
+    /// The first address covered by this function, or [`NONE`] for inlined functions.
     pub entry_pc: u32,
     /// The language of the function.
     pub lang: u32,
+    /// The compiler that produced the function (reference to a [`String`]), or [`NONE`].
+    pub producer_idx: u32,
+    /// The optional name of the compilation unit defining the function (reference to a
+    /// [`String`]), or [`NONE`].
+    pub unit_idx: u32,
+}
+
+impl Function {
+    /// The [name](Self::name_idx) of the function, if any.
+    pub fn name_idx(&self) -> Option<u32> {
+        optional(self.name_idx)
+    }
+
+    /// The [plain name](Self::plain_name_idx) of the function, if any.
+    pub fn plain_name_idx(&self) -> Option<u32> {
+        optional(self.plain_name_idx)
+    }
+
+    /// The [compilation directory](Self::comp_dir_idx) of the function, if any.
+    pub fn comp_dir_idx(&self) -> Option<u32> {
+        optional(self.comp_dir_idx)
+    }
+
+    /// The [entry pc](Self::entry_pc) of the function, if it is not inlined.
+    pub fn entry_pc(&self) -> Option<u32> {
+        optional(self.entry_pc)
+    }
+
+    /// The [producer](Self::producer_idx) of the function, if any.
+    pub fn producer_idx(&self) -> Option<u32> {
+        optional(self.producer_idx)
+    }
+
+    /// The [compilation unit](Self::unit_idx) of the function, if any.
+    pub fn unit_idx(&self) -> Option<u32> {
+        optional(self.unit_idx)
+    }
 }
 
 /// Serialized File in the SymCache.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[repr(C)]
 pub struct File {
-    /// The optional compilation directory prefix (reference to a [`String`]).
+    /// The optional compilation directory prefix (reference to a [`String`]), or [`NONE`].
     pub comp_dir_idx: u32,
-    /// The optional directory prefix (reference to a [`String`]).
+    /// The optional directory prefix (reference to a [`String`]), or [`NONE`].
     pub directory_idx: u32,
     /// The file path (reference to a [`String`]).
     pub path_name_idx: u32,
 }
 
+impl File {
+    /// The [compilation directory](Self::comp_dir_idx) of the file, if any.
+    pub fn comp_dir_idx(&self) -> Option<u32> {
+        optional(self.comp_dir_idx)
+    }
+
+    /// The [directory](Self::directory_idx) of the file, if any.
+    pub fn directory_idx(&self) -> Option<u32> {
+        optional(self.directory_idx)
+    }
+}
+
+/// The location of the embedded contents of a [`File`], see [`SECTION_FILE_SOURCES`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[repr(C)]
+pub struct FileSource {
+    /// The offset of the embedded contents of the file into the [source
+    /// bytes](SECTION_SOURCE_BYTES), or [`NONE`] if the file has no embedded contents.
+    pub offset: u32,
+    /// The size of the embedded contents of the file in bytes.
+    pub len: u32,
+}
+
+impl FileSource {
+    /// A file without embedded contents.
+    pub const NONE: FileSource = FileSource {
+        offset: NONE,
+        len: 0,
+    };
+
+    /// The [offset](Self::offset) of the embedded contents of the file, if any.
+    pub fn offset(&self) -> Option<u32> {
+        optional(self.offset)
+    }
+}
+
 /// A location in a source file, comprising a file, a line, a function, and
 /// the index of the source location this was inlined into, if any.
 ///
@@ -83,17 +346,46 @@ pub struct File {
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 #[repr(C)]
 pub struct SourceLocation {
-    /// The optional source file (reference to a [`File`]).
+    /// The optional source file (reference to a [`File`]), or [`NONE`].
     pub file_idx: u32,
     /// The line number.
     pub line: u32,
-    /// The function (reference to a [`Function`]).
+    /// The function (reference to a [`Function`]), or [`NONE`] for [gaps](Self::GAP).
     pub function_idx: u32,
     /// The caller source location in case this location was inlined
-    /// (reference to another [`SourceLocation`]).
+    /// (reference to another [`SourceLocation`]), or [`NONE`].
     pub inlined_into_idx: u32,
 }
 
+impl SourceLocation {
+    /// The source location of a [`Range`] that is known to contain no code.
+    ///
+    /// The writer inserts a gap at the end of every function that is not directly followed by
+    /// another range, such as padding between functions. Lookups within a gap yield no results.
+    /// Gaps never occur as the caller of an inlined source location.
+    pub const GAP: SourceLocation = SourceLocation {
+        file_idx: NONE,
+        line: 0,
+        function_idx: NONE,
+        inlined_into_idx: NONE,
+    };
+
+    /// Whether this is the source location of a [gap](Self::GAP).
+    pub fn is_gap(&self) -> bool {
+        self.function_idx == NONE
+    }
+
+    /// The [source file](Self::file_idx) of this location, if any.
+    pub fn file_idx(&self) -> Option<u32> {
+        optional(self.file_idx)
+    }
+
+    /// The [caller source location](Self::inlined_into_idx), if this location was inlined.
+    pub fn inlined_into_idx(&self) -> Option<u32> {
+        optional(self.inlined_into_idx)
+    }
+}
+
 /// Serialized String in the SymCache.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[repr(C)]
@@ -107,11 +399,17 @@ pub struct String {
 /// A representation of a code range in the SymCache.
 ///
 /// We only save the start address, the end is implicitly given
-/// by the next range's start.
+/// by the next range's start. Address ranges without code are
+/// represented by ranges with a [gap](SourceLocation::GAP).
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[repr(C)]
 pub struct Range(pub u32);
 
+/// A [`Range`] with a 64-bit start address, stored with [`FLAG_WIDE_RANGES`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[repr(C)]
+pub struct WideRange(pub u64);
+
 /// Returns the amount left to add to the remainder to get 8 if
 /// `to_align` isn't a multiple of 8.
 pub fn align_to_eight(to_align: usize) -> usize {
@@ -131,15 +429,18 @@ mod tests {
 
     #[test]
     fn test_sizeof() {
-        assert_eq!(mem::size_of::<Header>(), 80);
+        assert_eq!(mem::size_of::<Header>(), 96);
         assert_eq!(mem::align_of::<Header>(), 8);
 
-        assert_eq!(mem::size_of::<Function>(), 16);
+        assert_eq!(mem::size_of::<Function>(), 28);
         assert_eq!(mem::align_of::<Function>(), 4);
 
         assert_eq!(mem::size_of::<File>(), 12);
         assert_eq!(mem::align_of::<File>(), 4);
 
+        assert_eq!(mem::size_of::<FileSource>(), 8);
+        assert_eq!(mem::align_of::<FileSource>(), 4);
+
         assert_eq!(mem::size_of::<SourceLocation>(), 16);
         assert_eq!(mem::align_of::<SourceLocation>(), 4);
 
@@ -148,5 +449,52 @@ mod tests {
 
         assert_eq!(mem::size_of::<Range>(), 4);
         assert_eq!(mem::align_of::<Range>(), 4);
+
+        assert_eq!(mem::size_of::<WideRange>(), 8);
+        assert_eq!(mem::align_of::<WideRange>(), 8);
+    }
+
+    /// Reads a record from its serialized bytes, like a SymCache buffer does.
+    fn read<T>(bytes: &[u8]) -> T {
+        assert_eq!(bytes.len(), mem::size_of::<T>());
+        unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) }
+    }
+
+    #[test]
+    fn test_optional_fields() {
+        // Optional fields are stored as plain integers with all bits set.
+        let function: Function = read(&[0xff; 28]);
+        assert_eq!(function.name_idx(), None);
+        assert_eq!(function.plain_name_idx(), None);
+        assert_eq!(function.comp_dir_idx(), None);
+        assert_eq!(function.entry_pc(), None);
+        assert_eq!(function.producer_idx(), None);
+        assert_eq!(function.unit_idx(), None);
+
+        let file: File = read(&[0xff; 12]);
+        assert_eq!(file.comp_dir_idx(), None);
+        assert_eq!(file.directory_idx(), None);
+
+        let file_source: FileSource = read(&[0xff; 8]);
+        assert_eq!(file_source.offset(), None);
+        assert_eq!(FileSource::NONE.offset(), None);
+
+        let source_location: SourceLocation = read(&[0xff; 16]);
+        assert_eq!(source_location.file_idx(), None);
+        assert_eq!(source_location.inlined_into_idx(), None);
+
+        let mut bytes = [0xff; 16];
+        bytes[0..4].copy_from_slice(&3u32.to_ne_bytes());
+        bytes[12..16].copy_from_slice(&0u32.to_ne_bytes());
+        let source_location: SourceLocation = read(&bytes);
+        assert_eq!(source_location.file_idx(), Some(3));
+        assert_eq!(source_location.inlined_into_idx(), Some(0));
+
+        // The serialized bytes of a gap are unchanged.
+        let gap = SourceLocation::GAP;
+        let gap_bytes: [u8; 16] = unsafe { mem::transmute(gap) };
+        let mut expected = [0xff; 16];
+        expected[4..8].copy_from_slice(&[0; 4]);
+        assert_eq!(gap_bytes, expected);
     }
 }
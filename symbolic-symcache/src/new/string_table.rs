@@ -0,0 +1,457 @@
+//! Interning of strings for the [SymCache Converter](super::writer::SymCacheConverter).
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use fnv::{FnvHashMap, FnvHasher};
+
+use super::raw;
+
+/// A deduplicating table of strings and their concatenated bytes.
+///
+/// String bytes are kept in memory until they exceed the configured memory budget. After that,
+/// they are moved to an anonymous temporary file, and only a hash and the offset of each string
+/// remain in memory. Looking up a string that has been moved to disk requires reading it back
+/// for comparison. Strings read back this way are cached in memory up to the memory budget, so
+/// that repeated lookups of the same string only read it once.
+#[derive(Debug, Default)]
+pub(crate) struct StringTable {
+    /// Maps string hashes to the indexes of all strings with that hash.
+    index: FnvHashMap<u64, Vec<u32>>,
+    /// All strings in insertion order.
+    strings: Vec<raw::String>,
+    /// Bytes of strings that have not been moved to `spilled` yet.
+    memory: Vec<u8>,
+    /// The temporary file containing the bytes of strings before `spilled_len`.
+    spilled: Option<File>,
+    /// The number of bytes in `spilled`.
+    spilled_len: u64,
+    /// Bytes of strings in `spilled` that were read back for comparison, by string index.
+    spilled_cache: FnvHashMap<u32, Box<[u8]>>,
+    /// The total size of all strings in `spilled_cache`.
+    spilled_cache_len: usize,
+    /// The maximum size of `memory` before it is moved to disk.
+    memory_budget: Option<usize>,
+    /// The directory in which the temporary file is created.
+    temp_dir: Option<PathBuf>,
+    /// The first error that occurred while accessing the temporary file.
+    error: Option<io::Error>,
+}
+
+impl StringTable {
+    /// Limits the memory used for string bytes to roughly `budget` bytes.
+    pub fn set_memory_budget(&mut self, budget: usize) {
+        self.memory_budget = Some(budget);
+    }
+
+    /// Sets the directory in which the temporary file is created.
+    pub fn set_temp_dir(&mut self, temp_dir: &Path) {
+        self.temp_dir = Some(temp_dir.to_owned());
+    }
+
+    /// The number of strings in this table.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// The total size of all string bytes.
+    ///
+    /// This may exceed the `u32` offsets of the format, in which case the table cannot be
+    /// serialized. See [`Error::TooManyStringBytes`](super::Error::TooManyStringBytes).
+    pub fn bytes_len(&self) -> u64 {
+        self.spilled_len + self.memory.len() as u64
+    }
+
+    /// Inserts a string, returning its index in insertion order.
+    ///
+    /// If the string was already present, it is not added again. Indexes, offsets and lengths that
+    /// do not fit into `u32` saturate at `u32::MAX`. Such a table is rejected when serializing, so
+    /// these values are never written.
+    pub fn insert(&mut self, s: &str) -> u32 {
+        let mut hasher = FnvHasher::default();
+        s.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let num_candidates = self.index.get(&hash).map_or(0, Vec::len);
+        for candidate in 0..num_candidates {
+            let string_idx = self.index[&hash][candidate];
+            if self.equals(string_idx, s) {
+                return string_idx;
+            }
+        }
+
+        let string_idx = u32::try_from(self.strings.len()).unwrap_or(u32::MAX);
+        self.strings.push(raw::String {
+            string_offset: u32::try_from(self.bytes_len()).unwrap_or(u32::MAX),
+            string_len: u32::try_from(s.len()).unwrap_or(u32::MAX),
+        });
+        self.index.entry(hash).or_default().push(string_idx);
+        self.memory.extend_from_slice(s.as_bytes());
+
+        if matches!(self.memory_budget, Some(budget) if self.memory.len() > budget) {
+            if let Err(error) = self.spill() {
+                self.error.get_or_insert(error);
+            }
+        }
+
+        string_idx
+    }
+
+    /// Checks whether the string at `string_idx` is equal to `s`.
+    fn equals(&mut self, string_idx: u32, s: &str) -> bool {
+        let string = self.strings[string_idx as usize].clone();
+        if string.string_len as usize != s.len() {
+            return false;
+        }
+
+        let offset = u64::from(string.string_offset);
+        if offset >= self.spilled_len {
+            // Saturated offsets may point past the end of memory. They never compare equal.
+            let start = (offset - self.spilled_len) as usize;
+            return self.memory.get(start..start + s.len()) == Some(s.as_bytes());
+        }
+
+        if let Some(bytes) = self.spilled_cache.get(&string_idx) {
+            return **bytes == *s.as_bytes();
+        }
+
+        match self.read_spilled(string) {
+            Ok(bytes) => {
+                let equal = bytes == s.as_bytes();
+                self.cache_spilled(string_idx, bytes.into_boxed_slice());
+                equal
+            }
+            Err(error) => {
+                self.error.get_or_insert(error);
+                false
+            }
+        }
+    }
+
+    /// Caches the bytes of a string that has been read back from disk.
+    ///
+    /// The cache is cleared once it would exceed the memory budget.
+    fn cache_spilled(&mut self, string_idx: u32, bytes: Box<[u8]>) {
+        let budget = self.memory_budget.unwrap_or_default();
+        if bytes.len() > budget {
+            return;
+        }
+
+        if self.spilled_cache_len + bytes.len() > budget {
+            self.spilled_cache.clear();
+            self.spilled_cache_len = 0;
+        }
+
+        self.spilled_cache_len += bytes.len();
+        self.spilled_cache.insert(string_idx, bytes);
+    }
+
+    /// Reads the bytes of a string that has been moved to disk.
+    fn read_spilled(&self, string: raw::String) -> io::Result<Vec<u8>> {
+        let mut file = match self.spilled {
+            Some(ref file) => file,
+            None => return Err(io::ErrorKind::NotFound.into()),
+        };
+
+        let mut bytes = vec![0; string.string_len as usize];
+        file.seek(SeekFrom::Start(string.string_offset.into()))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns the bytes of the string at `string_idx`, which must not have been moved to disk.
+    fn memory_bytes(&self, string_idx: u32) -> &[u8] {
+        let string = &self.strings[string_idx as usize];
+        let start = (u64::from(string.string_offset) - self.spilled_len) as usize;
+        &self.memory[start..start + string.string_len as usize]
+    }
+
+    /// Returns the bytes of the string at `string_idx`, reading them back from disk if necessary.
+    pub fn get_bytes(&self, string_idx: u32) -> io::Result<Cow<'_, [u8]>> {
+        let string = self.strings[string_idx as usize].clone();
+        let offset = u64::from(string.string_offset);
+        if offset >= self.spilled_len {
+            let start = (offset - self.spilled_len) as usize;
+            let end = start + string.string_len as usize;
+            return Ok(Cow::Borrowed(&self.memory[start..end]));
+        }
+
+        self.read_spilled(string).map(Cow::Owned)
+    }
+
+    /// Returns the first bytes of every string, padded with zeros.
+    ///
+    /// Strings that have been moved to disk are read back in a single pass over the file.
+    fn prefixes(&self) -> io::Result<Vec<[u8; 8]>> {
+        let mut spilled = match self.spilled {
+            Some(ref file) => {
+                let mut file = file;
+                file.seek(SeekFrom::Start(0))?;
+                Some(BufReader::new(file.take(self.spilled_len)))
+            }
+            None => None,
+        };
+
+        let mut prefixes = Vec::with_capacity(self.strings.len());
+        for string in &self.strings {
+            let mut prefix = [0; 8];
+            let len = (string.string_len as usize).min(prefix.len());
+            let offset = u64::from(string.string_offset);
+
+            match spilled {
+                // Strings are stored contiguously in insertion order.
+                Some(ref mut reader) if offset < self.spilled_len => {
+                    reader.read_exact(&mut prefix[..len])?;
+                    let rest = u64::from(string.string_len) - len as u64;
+                    io::copy(&mut reader.by_ref().take(rest), &mut io::sink())?;
+                }
+                _ => {
+                    let start = (offset - self.spilled_len) as usize;
+                    prefix[..len].copy_from_slice(&self.memory[start..start + len]);
+                }
+            }
+
+            prefixes.push(prefix);
+        }
+
+        Ok(prefixes)
+    }
+
+    /// Returns the indexes of all strings in lexicographic order of their bytes.
+    ///
+    /// Strings are compared by a prefix kept in memory. Only strings with equal prefixes are read
+    /// back from disk, each of them once, so that the memory budget is still respected.
+    pub fn sorted_order(&self) -> io::Result<Vec<u32>> {
+        let prefixes = self.prefixes()?;
+        let mut order: Vec<u32> = (0..self.strings.len() as u32).collect();
+
+        if self.spilled.is_none() {
+            let compare = |&a: &u32, &b: &u32| {
+                prefixes[a as usize]
+                    .cmp(&prefixes[b as usize])
+                    .then_with(|| self.memory_bytes(a).cmp(self.memory_bytes(b)))
+            };
+
+            // Strings held in memory can be compared without errors, which allows sorting in
+            // parallel.
+            #[cfg(feature = "rayon")]
+            {
+                use rayon::slice::ParallelSliceMut;
+                order.par_sort_unstable_by(compare);
+            }
+            #[cfg(not(feature = "rayon"))]
+            order.sort_unstable_by(compare);
+
+            return Ok(order);
+        }
+
+        order.sort_unstable_by_key(|&string_idx| prefixes[string_idx as usize]);
+
+        // Sort every run of strings with equal prefixes by their full bytes.
+        let mut start = 0;
+        while start < order.len() {
+            let prefix = prefixes[order[start] as usize];
+            let len = order[start..]
+                .iter()
+                .take_while(|&&string_idx| prefixes[string_idx as usize] == prefix)
+                .count();
+            let run = &mut order[start..start + len];
+            start += len;
+
+            if run.len() < 2 {
+                continue;
+            }
+
+            let mut keys = run
+                .iter()
+                .map(|&string_idx| Ok((self.get_bytes(string_idx)?, string_idx)))
+                .collect::<io::Result<Vec<_>>>()?;
+            keys.sort_unstable();
+
+            for (slot, (_, string_idx)) in run.iter_mut().zip(keys) {
+                *slot = string_idx;
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Appends all bytes held in memory to the temporary file.
+    fn spill(&mut self) -> io::Result<()> {
+        if self.spilled.is_none() {
+            let file = match self.temp_dir {
+                Some(ref temp_dir) => tempfile::tempfile_in(temp_dir)?,
+                None => tempfile::tempfile()?,
+            };
+            self.spilled = Some(file);
+        }
+
+        if let Some(ref mut file) = self.spilled {
+            file.seek(SeekFrom::Start(self.spilled_len))?;
+            file.write_all(&self.memory)?;
+        }
+
+        self.spilled_len += self.memory.len() as u64;
+        self.memory.clear();
+        Ok(())
+    }
+
+    /// Consumes the table, returning all strings and a reader over the concatenated bytes.
+    ///
+    /// Only the bytes of strings whose index is set in `retain` are read, and the offsets of these
+    /// strings are moved up to close the gaps. All other strings are returned with a length of
+    /// zero and must not be written. Also returns the number of bytes yielded by the reader.
+    pub fn into_parts(self, retain: &[bool]) -> io::Result<(Vec<raw::String>, u64, impl Read)> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let spilled: Box<dyn Read> = match self.spilled {
+            Some(mut file) => {
+                file.seek(SeekFrom::Start(0))?;
+                Box::new(file.take(self.spilled_len))
+            }
+            None => Box::new(io::empty()),
+        };
+
+        // Strings are stored consecutively in insertion order, so the bytes to read alternate
+        // between runs of retained and dropped strings.
+        let mut strings = self.strings;
+        let mut runs = Vec::new();
+        let mut run = Run::default();
+        let mut offset = 0;
+        for (string_idx, string) in strings.iter_mut().enumerate() {
+            let len = u64::from(string.string_len);
+            if retain.get(string_idx).copied().unwrap_or(false) {
+                if run.skip > 0 {
+                    runs.push(std::mem::take(&mut run));
+                }
+                run.keep += len;
+                string.string_offset = u32::try_from(offset).unwrap_or(u32::MAX);
+                offset += len;
+            } else {
+                run.skip += len;
+                string.string_offset = 0;
+                string.string_len = 0;
+            }
+        }
+        runs.push(run);
+        runs.reverse();
+
+        let reader = RetainReader {
+            inner: spilled.chain(io::Cursor::new(self.memory)),
+            runs,
+        };
+        Ok((strings, offset, reader))
+    }
+}
+
+/// A number of bytes to read, followed by a number of bytes to skip.
+#[derive(Debug, Default)]
+struct Run {
+    keep: u64,
+    skip: u64,
+}
+
+/// A reader that only yields the bytes of retained strings, see [`StringTable::into_parts`].
+struct RetainReader<R> {
+    inner: R,
+    /// The remaining runs in reverse order.
+    runs: Vec<Run>,
+}
+
+impl<R: Read> Read for RetainReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(run) = self.runs.last_mut() {
+            if run.keep > 0 {
+                let len = buf
+                    .len()
+                    .min(usize::try_from(run.keep).unwrap_or(usize::MAX));
+                let read = self.inner.read(&mut buf[..len])?;
+                run.keep -= read as u64;
+                return Ok(read);
+            }
+
+            if run.skip > 0 {
+                let skipped = io::copy(&mut (&mut self.inner).take(run.skip), &mut io::sink())?;
+                if skipped < run.skip {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+            }
+            self.runs.pop();
+        }
+
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spilled_lookups_are_cached() {
+        let mut table = StringTable::default();
+        table.set_memory_budget(16);
+
+        let first = table.insert("first string");
+        let second = table.insert("second string");
+        assert!(table.spilled.is_some());
+        assert_eq!(table.insert("first string"), first);
+
+        // Cached strings are not read from disk again.
+        let spilled = table.spilled.take();
+        assert_eq!(table.insert("first string"), first);
+        assert!(table.error.is_none());
+        table.spilled = spilled;
+
+        // The cache never exceeds the memory budget.
+        assert!(table.spilled_cache_len <= 16);
+        assert_eq!(table.insert("second string"), second);
+        assert!(table.spilled_cache_len <= 16);
+    }
+
+    #[test]
+    fn test_sorted_order_spilled() {
+        let strings = [
+            "prefix_b",
+            "prefix_a_long",
+            "",
+            "prefix",
+            "prefix_a",
+            "other",
+            "prefix\0",
+            "a",
+        ];
+
+        let mut memory = StringTable::default();
+        let mut spilled = StringTable::default();
+        spilled.set_memory_budget(0);
+        for s in &strings {
+            memory.insert(s);
+            spilled.insert(s);
+        }
+        assert!(spilled.spilled.is_some());
+
+        let order = memory.sorted_order().unwrap();
+        let sorted: Vec<_> = order.iter().map(|&i| strings[i as usize]).collect();
+        assert_eq!(
+            sorted,
+            [
+                "",
+                "a",
+                "other",
+                "prefix",
+                "prefix\0",
+                "prefix_a",
+                "prefix_a_long",
+                "prefix_b"
+            ]
+        );
+        assert_eq!(spilled.sorted_order().unwrap(), order);
+    }
+}
@@ -0,0 +1,222 @@
+//! Validation of the references between the tables of a SymCache.
+
+use std::fmt;
+
+use super::{raw, Error, Result, SymCache};
+
+/// The kind of a record in a [`SymCache`].
+///
+/// This is reported by [`Error::InvalidRecord`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RecordKind {
+    /// A string, referencing a range of the string bytes.
+    String,
+    /// A file, referencing strings for its path and a range of the source bytes.
+    File,
+    /// A function, referencing strings for its name, compilation directory, producer and unit.
+    Function,
+    /// A source location, referencing a file, a function and its caller's source location.
+    SourceLocation,
+}
+
+impl fmt::Display for RecordKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordKind::String => write!(f, "string"),
+            RecordKind::File => write!(f, "file"),
+            RecordKind::Function => write!(f, "function"),
+            RecordKind::SourceLocation => write!(f, "source location"),
+        }
+    }
+}
+
+/// Checks that `idx` is within a table of `len` records, allowing `u32::MAX` if `optional`.
+fn is_valid_ref(idx: u32, len: usize, optional: bool) -> bool {
+    (optional && idx == u32::MAX) || (idx as usize) < len
+}
+
+impl<'data> SymCache<'data> {
+    /// Checks the referential integrity of this SymCache.
+    ///
+    /// [`parse`](Self::parse) only verifies the size of all tables, so that a SymCache produced
+    /// by other tools may still contain references that point outside of their tables, which
+    /// results in missing names or files during lookups. This checks that:
+    ///
+    ///  - all strings are within the string bytes and valid UTF-8,
+    ///  - all string references of files and functions are within the strings table,
+    ///  - all embedded sources of files are within the source bytes,
+    ///  - all file, function and caller references of source locations are within their tables,
+    ///  - all callers precede the source locations inlined into them and are not associated with
+    ///    a range, so that inline chains cannot contain cycles.
+    ///
+    /// Returns [`Error::InvalidRecord`] for the first offending record.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |kind, index: usize| Error::InvalidRecord {
+            kind,
+            index: index as u32,
+        };
+
+        for (index, string) in self.strings.iter().enumerate() {
+            let start = string.string_offset as usize;
+            let end = start + string.string_len as usize;
+            let bytes = self
+                .string_bytes
+                .get(start..end)
+                .ok_or_else(|| invalid(RecordKind::String, index))?;
+            std::str::from_utf8(bytes).map_err(|_| invalid(RecordKind::String, index))?;
+        }
+
+        let num_strings = self.strings.len();
+        for (index, file) in self.files.iter().enumerate() {
+            let source = self.file_sources.get(index).and_then(|source| {
+                source
+                    .offset()
+                    .map(|offset| u64::from(offset) + u64::from(source.len))
+            });
+            let has_valid_source = source.map_or(true, |end| end <= self.source_bytes.len() as u64);
+            let is_valid = is_valid_ref(file.comp_dir_idx, num_strings, true)
+                && is_valid_ref(file.directory_idx, num_strings, true)
+                && is_valid_ref(file.path_name_idx, num_strings, false)
+                && has_valid_source;
+            if !is_valid {
+                return Err(invalid(RecordKind::File, index));
+            }
+        }
+
+        for (index, function) in self.functions.iter().enumerate() {
+            let is_valid = is_valid_ref(function.name_idx, num_strings, true)
+                && is_valid_ref(function.plain_name_idx, num_strings, true)
+                && is_valid_ref(function.comp_dir_idx, num_strings, true)
+                && is_valid_ref(function.producer_idx, num_strings, true)
+                && is_valid_ref(function.unit_idx, num_strings, true);
+            if !is_valid {
+                return Err(invalid(RecordKind::Function, index));
+            }
+        }
+
+        // Callers are stored before the source locations of the ranges.
+        let num_callers = self.source_locations.len() - self.ranges.len();
+        for (index, source_location) in self.source_locations.iter().enumerate() {
+            // Only ranges can be gaps, which reference no function.
+            if index >= num_callers && *source_location == raw::SourceLocation::GAP {
+                continue;
+            }
+
+            let is_valid = is_valid_ref(source_location.file_idx, self.files.len(), true)
+                && is_valid_ref(source_location.function_idx, self.functions.len(), false)
+                && is_valid_ref(
+                    source_location.inlined_into_idx,
+                    index.min(num_callers),
+                    true,
+                );
+            if !is_valid {
+                return Err(invalid(RecordKind::SourceLocation, index));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use symbolic_common::ByteView;
+    use symbolic_debuginfo::Object;
+    use symbolic_testutils::fixture;
+
+    use super::super::raw;
+    use super::super::update_checksum;
+    use super::super::writer::SymCacheConverter;
+    use super::*;
+
+    fn write_fixture(path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture(path))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Returns the offset of a table within the buffer it was parsed from.
+    fn table_offset<T>(buffer: &[u8], table: &[T]) -> usize {
+        table.as_ptr() as usize - buffer.as_ptr() as usize
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = write_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        SymCache::parse(&buffer)?.validate()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_corrupted() -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = write_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        let symcache = SymCache::parse(&buffer)?;
+        let header = symcache.header.clone();
+        let strings_start = table_offset(&buffer, &symcache.strings);
+        let functions_start = table_offset(&buffer, &symcache.functions);
+
+        // Point the name of the fourth function past the end of the strings table.
+        let name_idx = functions_start + 3 * mem::size_of::<raw::Function>();
+        buffer[name_idx..name_idx + 4].copy_from_slice(&header.num_strings.to_ne_bytes());
+        update_checksum(&mut buffer);
+
+        let error = SymCache::parse(&buffer)?.validate().unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidRecord {
+                kind: RecordKind::Function,
+                index: 3
+            }
+        ));
+
+        // Strings are checked first. Make the second string extend past the string bytes.
+        let string_len = strings_start + mem::size_of::<raw::String>() + 4;
+        buffer[string_len..string_len + 4].copy_from_slice(&u32::MAX.to_ne_bytes());
+        update_checksum(&mut buffer);
+
+        let error = SymCache::parse(&buffer)?.validate().unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidRecord {
+                kind: RecordKind::String,
+                index: 1
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_cycle() -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = write_fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash")?;
+        let symcache = SymCache::parse(&buffer)?;
+        let num_callers = symcache.header.num_source_locations - symcache.header.num_ranges;
+        let range_locations_start = table_offset(&buffer, &symcache.source_locations)
+            + num_callers as usize * mem::size_of::<raw::SourceLocation>();
+
+        // Make the source location of the first range its own caller. This reference is within
+        // the source locations table, but forms a cycle in the inline chain.
+        let inlined_into_idx = range_locations_start + 12;
+        buffer[inlined_into_idx..inlined_into_idx + 4].copy_from_slice(&num_callers.to_ne_bytes());
+        update_checksum(&mut buffer);
+
+        let error = SymCache::parse(&buffer)?.validate().unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidRecord {
+                kind: RecordKind::SourceLocation,
+                index
+            } if index == num_callers
+        ));
+
+        Ok(())
+    }
+}
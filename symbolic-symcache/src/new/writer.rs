@@ -1,376 +1,3768 @@
 //! Defines the [SymCache Converter](`SymCacheConverter`).
 
-use std::collections::btree_map;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::io::Write;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::{Bound, ControlFlow, Range};
+use std::path::Path;
 
-use indexmap::{IndexMap, IndexSet};
-use symbolic_common::{Arch, DebugId, Language};
-use symbolic_debuginfo::{DebugSession, Function, ObjectLike, Symbol};
+use indexmap::IndexSet;
+use symbolic_common::{Arch, CpuFamily, DebugId, Language};
+use symbolic_debuginfo::breakpad::{
+    BreakpadDebugSession, BreakpadError, BreakpadFileMap, BreakpadFuncRecord, BreakpadLineRecord,
+    BreakpadObject,
+};
+use symbolic_debuginfo::dwarf::DwarfError;
+use symbolic_debuginfo::{
+    DebugSession, FileInfo, Function, Object, ObjectError, ObjectLike, Symbol, SymbolMap,
+};
 
-use super::raw;
+use super::string_table::StringTable;
+use super::{raw, Error, File, SymCache};
+use crate::old::SymCache as LegacySymCache;
 use crate::{SymCacheError, SymCacheErrorKind};
 
-/// The SymCache Converter.
+/// Progress information reported while processing an object.
 ///
-/// This can convert data in various source formats to an intermediate representation, which can
-/// then be serialized to disk via its [`Converter::serialize`] method.
-#[derive(Debug, Default)]
-pub struct SymCacheConverter {
-    /// Debug identifier of the object file.
-    debug_id: DebugId,
-    /// CPU architecture of the object file.
-    arch: Arch,
+/// See [`WriteOptions::progress`](super::WriteOptions::progress).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// The number of top-level functions that have been processed so far.
+    pub functions_processed: usize,
+    /// The total number of top-level functions, if known upfront.
+    pub functions_total: Option<usize>,
+}
 
-    /// The minimum addr for all the ranges in the debug file.
-    /// This is used to ignore ranges that are below this threshold, as linkers leave range data
-    /// intact, but rather set removed ranges to 0 (or below this threshold).
-    /// Also, this is used as an offset for the saved ranges, to decrease the likelihood they
-    /// overflow `u32`.
-    // TODO: figure out a better name. is this the *load bias*? where do we get this from?
-    range_threshold: u64,
+/// A non-fatal problem that caused parts of an object to be skipped.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct WriterWarning {
+    /// The offset of the DWARF compilation unit that was skipped, if known.
+    pub unit_offset: Option<u64>,
+    /// The error encountered while reading the debug information.
+    pub error: SymCacheError,
+}
 
-    /// The concatenation of all strings that have been added to this `Converter`.
-    string_bytes: Vec<u8>,
-    /// A map from [`String`]s that have been added to this `Converter` to [`StringRef`]s, i.e.,
-    /// indices into the `string_bytes` vector.
-    strings: IndexMap<String, raw::String>,
-    /// The set of all [`raw::File`]s that have been added to this `Converter`.
-    files: IndexSet<raw::File>,
-    /// The set of all [`raw::Function`]s that have been added to this `Converter`.
-    functions: IndexSet<raw::Function>,
-    /// The set of all [`raw::SourceLocation`]s that have been added to this `Converter` and that
-    /// aren't directly associated with a code range.
-    source_locations: IndexSet<raw::SourceLocation>,
-    /// A map from code ranges to the [`raw::SourceLocation`]s they correspond to.
+/// A report of all non-fatal problems encountered while processing an object.
+///
+/// See [`SymCacheConverter::process_object`].
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct WriterReport {
+    /// Warnings for all compilation units and functions that were skipped.
+    pub warnings: Vec<WriterWarning>,
+    /// The number of caller source locations that were identical to an existing inline chain.
     ///
-    /// Only the starting address of a range is saved, the end address is given implicitly
-    /// by the start address of the next range.
-    ranges: BTreeMap<u32, raw::SourceLocation>,
+    /// Each of these shares the index of the existing record instead of being written again.
+    pub shared_source_locations: usize,
 }
 
-impl SymCacheConverter {
-    /// Creates a new Converter.
-    pub fn new() -> Self {
-        Self::default()
+/// Statistics on the written SymCache and how effectively records were deduplicated.
+///
+/// See [`SymCacheConverter::serialize`]. The unique counts match the counts in the header of the
+/// written SymCache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WriteStats {
+    /// The number of strings that were added, including duplicates.
+    pub strings_total: usize,
+    /// The number of distinct strings that were written.
+    pub strings_unique: usize,
+    /// The number of functions that were added, including duplicates.
+    pub functions_total: usize,
+    /// The number of distinct functions that were written.
+    pub functions_unique: usize,
+    /// The number of distinct files that were written.
+    pub files: usize,
+    /// The number of source locations that were written, including those of all ranges.
+    pub source_locations: usize,
+    /// The number of address ranges that were written.
+    pub ranges: usize,
+    /// The number of bytes of string data that were written.
+    pub string_bytes: usize,
+    /// The number of bytes of embedded source contents that were written.
+    pub source_bytes: usize,
+    /// The number of functions, symbols and line records that were skipped, because their
+    /// address is below the [range threshold](SymCacheConverter::set_range_threshold) or outside
+    /// the [address range](SymCacheConverter::set_address_range).
+    pub records_skipped: usize,
+    /// The total number of bytes written.
+    pub total_bytes: u64,
+}
+
+/// Converts the position of a record in its table into an index.
+///
+/// Positions beyond the range of `u32` saturate at `u32::MAX`, which denotes a missing reference.
+/// Converters containing such a record are rejected by
+/// [`check_limits`](SymCacheConverter::check_limits), so the saturated index is never written.
+fn to_index(position: usize) -> u32 {
+    u32::try_from(position).unwrap_or(u32::MAX)
+}
+
+/// Converts a line number into the `u32` stored in source locations.
+///
+/// Numbers beyond the range of `u32` saturate at `u32::MAX`. Unlike indexes, this does not
+/// corrupt any references, and such numbers are only produced by broken debug information.
+fn to_line_number(line: u64) -> u32 {
+    u32::try_from(line).unwrap_or(u32::MAX)
+}
+
+/// Converts a column number into the `u16` stored in the [column table](raw::SECTION_COLUMNS).
+///
+/// Columns beyond the range of `u16` are dropped, since a saturated column would point to the
+/// wrong place in the line.
+fn to_column(column: u64) -> u16 {
+    u16::try_from(column).unwrap_or(0)
+}
+
+/// A source location collected by the converter.
+///
+/// This is written as a [`raw::SourceLocation`], and its column is written to the [column
+/// table](raw::SECTION_COLUMNS) at the same index. Keeping columns in a separate table saves the
+/// space of a column in every record of SymCaches without columns.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct SourceLocationRecord {
+    /// See [`raw::SourceLocation::file_idx`].
+    file_idx: u32,
+    /// See [`raw::SourceLocation::line`].
+    line: u32,
+    /// See [`raw::SourceLocation::function_idx`].
+    function_idx: u32,
+    /// See [`raw::SourceLocation::inlined_into_idx`].
+    inlined_into_idx: u32,
+    /// The column number starting at 1, or `0` if there is no column.
+    column: u16,
+}
+
+impl SourceLocationRecord {
+    /// See [`raw::SourceLocation::GAP`].
+    const GAP: SourceLocationRecord = SourceLocationRecord {
+        file_idx: raw::NONE,
+        line: 0,
+        function_idx: raw::NONE,
+        inlined_into_idx: raw::NONE,
+        column: 0,
+    };
+
+    /// Whether this is the source location of a [gap](raw::SourceLocation::GAP).
+    fn is_gap(&self) -> bool {
+        self.function_idx == raw::NONE
     }
 
-    /// Sets the CPU architecture of this SymCache.
-    pub fn set_arch(&mut self, arch: Arch) {
-        self.arch = arch;
+    /// The serialized record of this source location, without its column.
+    fn to_raw(&self) -> raw::SourceLocation {
+        raw::SourceLocation {
+            file_idx: self.file_idx,
+            line: self.line,
+            function_idx: self.function_idx,
+            inlined_into_idx: self.inlined_into_idx,
+        }
     }
+}
 
-    /// Sets the debug identifier of this SymCache.
-    pub fn set_debug_id(&mut self, debug_id: DebugId) {
-        self.debug_id = debug_id;
+/// The number of entries in each table of a converter.
+#[derive(Clone, Copy, Debug)]
+struct TableSizes {
+    strings: usize,
+    string_bytes: u64,
+    files: usize,
+    functions: usize,
+    source_locations: usize,
+}
+
+/// Checks that all tables can be referenced by the `u32` indexes and offsets of the format.
+fn check_limits(sizes: TableSizes) -> Result<(), Error> {
+    let max = u32::MAX as usize;
+    if sizes.strings > max {
+        return Err(Error::TooManyStrings);
+    }
+    if sizes.string_bytes > u64::from(u32::MAX) {
+        return Err(Error::TooManyStringBytes);
     }
+    if sizes.files > max {
+        return Err(Error::TooManyFiles);
+    }
+    if sizes.functions > max {
+        return Err(Error::TooManyFunctions);
+    }
+    if sizes.source_locations > max {
+        return Err(Error::TooManySourceLocations);
+    }
+    Ok(())
+}
 
-    /// Tries to convert the given `addr`, compressing it into 32-bits and applying the
-    /// `range_threshold` (TODO: find better name for that), rejecting any addr that is below the
-    /// threshold or exceeds 32-bits.
-    fn offset_addr(&self, addr: u64) -> Option<u32> {
-        use std::convert::TryFrom;
-        addr.checked_sub(self.range_threshold)
-            .and_then(|r| u32::try_from(r).ok())
+/// Returns the offset of the compilation unit an error from a debug session refers to.
+fn unit_offset(error: &(dyn std::error::Error + 'static)) -> Option<u64> {
+    if let Some(error) = error.downcast_ref::<ObjectError>() {
+        return error.unit_offset();
     }
+    error.downcast_ref::<DwarfError>()?.unit_offset()
+}
 
-    /// Insert a string into this converter.
-    ///
-    /// If the string was already present, it is not added again. The returned `u32`
-    /// is the string's index in insertion order.
-    fn insert_string(&mut self, s: &str) -> u32 {
-        if let Some(existing_idx) = self.strings.get_index_of(s) {
-            return existing_idx as u32;
-        }
-        let string_offset = self.string_bytes.len() as u32;
-        let string_len = s.len() as u32;
-        self.string_bytes.extend(s.bytes());
-        let (string_idx, _) = self.strings.insert_full(
-            s.to_owned(),
-            raw::String {
-                string_offset,
-                string_len,
-            },
-        );
-        string_idx as u32
+/// A function that maps function names before they are written to the SymCache.
+///
+/// See [`SymCacheConverter::set_name_transformer`].
+pub struct NameTransformer(Box<dyn FnMut(&str, Language) -> Option<Cow<'_, str>>>);
+
+impl fmt::Debug for NameTransformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NameTransformer").finish()
     }
+}
 
-    /// Insert a [`raw::SourceLocation`] into this converter.
-    ///
-    /// If the `SourceLocation` was already present, it is not added again. The returned `u32`
-    /// is the `SourceLocation`'s index in insertion order.
-    fn insert_source_location(&mut self, source_location: raw::SourceLocation) -> u32 {
-        self.source_locations.insert_full(source_location).0 as u32
+/// A function that maps the file and line of source locations before they are written to the
+/// SymCache.
+///
+/// See [`SymCacheConverter::set_line_transformer`].
+pub struct LineTransformer(Box<dyn FnMut(&File<'_>, u32) -> Option<(String, u32)>>);
+
+impl fmt::Debug for LineTransformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LineTransformer").finish()
     }
+}
 
-    /// Insert a file into this converter.
+/// A function that supplies the contents of source files to embed into the SymCache.
+///
+/// See [`SymCacheConverter::set_source_provider`].
+pub struct SourceProvider(Box<dyn FnMut(&FileInfo<'_>) -> Option<Vec<u8>>>);
+
+impl fmt::Debug for SourceProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SourceProvider").finish()
+    }
+}
+
+/// The default limit for the total size of embedded sources, see
+/// [`SymCacheConverter::set_source_size_limit`].
+const DEFAULT_SOURCE_SIZE_LIMIT: u32 = 16 * 1024 * 1024;
+
+/// The settings of a [`SymCacheConverter`] that determine how records are converted.
+///
+/// Unlike the converter, this is thread-safe, which allows to [prepare](Preparer) functions in
+/// parallel.
+#[derive(Clone, Copy, Debug)]
+struct ConvertOptions<'c> {
+    arch: Arch,
+    range_threshold: u64,
+    address_range: Option<&'c Range<u64>>,
+    names_only: bool,
+    skip_inlinees: bool,
+    path_prefixes: &'c [String],
+}
+
+impl ConvertOptions<'_> {
+    /// Removes the first matching [path prefix](SymCacheConverter::strip_path_prefix) from `path`.
+    fn strip_path<'s>(&self, path: &'s str) -> &'s str {
+        for prefix in self.path_prefixes {
+            if let Some(rest) = path.strip_prefix(prefix.as_str()) {
+                if rest.is_empty() {
+                    return rest;
+                }
+                if rest.starts_with(|c: char| c == '/' || c == '\\') {
+                    return &rest[1..];
+                }
+            }
+        }
+        path
+    }
+
+    /// Removes the first matching path prefix from the full path of a file.
     ///
-    /// If the file was already present, it is not added again. The returned `u32`
-    /// is the file's index in insertion order.
-    fn insert_file(
-        &mut self,
+    /// A prefix may span several components of the path, so the full path is resolved like
+    /// [`File::full_path`] before stripping it. Returns `None` if no prefix matches, in which case
+    /// the file should be added with its original components.
+    fn strip_file_path(
+        &self,
         path_name: &str,
         directory: Option<&str>,
         comp_dir: Option<&str>,
-    ) -> u32 {
-        let path_name_idx = self.insert_string(path_name);
-        let directory_idx = directory.map_or(u32::MAX, |d| self.insert_string(d));
-        let comp_dir_idx = comp_dir.map_or(u32::MAX, |cd| self.insert_string(cd));
+    ) -> Option<String> {
+        if self.path_prefixes.is_empty() {
+            return None;
+        }
 
-        let (file_idx, _) = self.files.insert_full(raw::File {
-            path_name_idx,
-            directory_idx,
-            comp_dir_idx,
-        });
+        let file = File {
+            comp_dir,
+            directory,
+            path_name,
+            source: None,
+        };
+        let full_path = file.full_path();
+        let stripped = self.strip_path(&full_path);
+        if stripped.len() == full_path.len() {
+            return None;
+        }
+        Some(stripped.to_owned())
+    }
 
-        file_idx as u32
+    /// Checks whether the half-open address range `[start, end)` intersects the configured
+    /// [`address_range`](SymCacheConverter::set_address_range).
+    ///
+    /// An empty range is treated as covering its start address.
+    fn intersects_address_range(&self, start: u64, end: u64) -> bool {
+        match self.address_range {
+            Some(range) => start < range.end && end.max(start.saturating_add(1)) > range.start,
+            None => true,
+        }
     }
 
-    /// Insert a function into this converter.
+    /// Tries to convert the given `addr` into an address relative to the `range_threshold`,
+    /// rejecting any addr that is below the threshold.
     ///
-    /// If the function was already present, it is not added again. The returned `u32`
-    /// is the function's index in insertion order.
-    fn insert_function(
-        &mut self,
-        name: &str,
-        comp_dir: Option<&str>,
-        entry_pc: u32,
-        lang: Language,
-    ) -> u32 {
-        let name_idx = self.insert_string(name);
-        let comp_dir_idx = comp_dir
-            .map(|comp_dir| self.insert_string(comp_dir))
-            .unwrap_or(u32::MAX);
-        let lang = lang as u32;
-        let (fun_idx, _) = self.functions.insert_full(raw::Function {
-            name_idx,
-            comp_dir_idx,
-            entry_pc,
-            lang,
-        });
-        fun_idx as u32
+    /// On 32-bit ARM, this also clears the Thumb bit. Symbols of Thumb functions are recorded at
+    /// odd addresses, while instructions are always aligned to two bytes.
+    fn offset_addr(&self, addr: u64) -> Option<u64> {
+        let addr = match self.arch.cpu_family() {
+            CpuFamily::Arm32 => addr & !1,
+            _ => addr,
+        };
+        addr.checked_sub(self.range_threshold)
     }
 
-    // Methods processing symbolic-debuginfo [`ObjectLike`] below:
-    // Feel free to move these to a separate file.
+    /// Converts the given `addr` into the entry pc of a function record.
+    ///
+    /// Entry pcs are stored in 32 bits, so functions starting more than 4GiB above the
+    /// `range_threshold` have no entry pc, just like functions below the threshold.
+    fn entry_pc(&self, addr: u64) -> u32 {
+        self.offset_addr(addr)
+            .and_then(|addr| u32::try_from(addr).ok())
+            .unwrap_or(u32::MAX)
+    }
+}
 
-    /// This processes the given [`ObjectLike`] object, collecting all its functions and line
-    /// information into the converter.
-    pub fn process_object<'d, 'o, O>(&mut self, object: &'o O) -> Result<(), SymCacheError>
-    where
-        O: ObjectLike<'d, 'o>,
-        O::Error: std::error::Error + Send + Sync + 'static,
-    {
-        let session = object
-            .debug_session()
-            .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+/// A function whose records have been interned locally, but not yet inserted into a converter.
+///
+/// Preparing a function does not depend on the state of the converter, which allows to prepare
+/// functions in parallel. All local tables keep the order in which records are first referenced,
+/// so inserting prepared functions in order produces the same output as inserting them directly.
+#[derive(Debug, Default)]
+struct PreparedFunction<'a> {
+    /// Strings in the order in which they are first referenced.
+    strings: IndexSet<Cow<'a, str>>,
+    /// The number of strings that were referenced, including duplicates.
+    strings_total: usize,
+    /// Files whose string indexes refer to `strings`.
+    files: IndexSet<raw::File>,
+    /// Records of the function and its inlinees, whose string indexes refer to `strings`.
+    functions: IndexSet<raw::Function>,
+    /// The number of function records, including duplicates.
+    functions_total: usize,
+    /// The number of line records below the range threshold.
+    records_skipped: usize,
+    /// Changes to the range table, in the order in which they are applied.
+    ranges: Vec<PreparedRange>,
+}
 
-        for function in session.functions() {
-            let function =
-                function.map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+impl<'a> PreparedFunction<'a> {
+    /// Interns a string locally, returning its index in `strings`.
+    fn insert_string(&mut self, s: Cow<'a, str>) -> u32 {
+        self.strings_total += 1;
+        to_index(self.strings.insert_full(s).0)
+    }
+}
 
-            self.process_symbolic_function(&function);
-        }
+/// A change to the range table by a [`PreparedFunction`].
+///
+/// Source locations refer to the local files and functions of the prepared function.
+#[derive(Debug)]
+enum PreparedRange {
+    /// A line record of a top-level function, which replaces any range starting at `addr`.
+    Line {
+        addr: u64,
+        source_location: SourceLocationRecord,
+    },
+    /// A line record of an inlined function, whose caller is resolved on insertion.
+    Inlined {
+        addr: u64,
+        source_location: SourceLocationRecord,
+        caller_idx: Option<u32>,
+    },
+    /// The end of a top-level function, which is inserted as a [gap](raw::SourceLocation::GAP).
+    ///
+    /// Lookups up to the start of the next range yield no results instead of resolving to the
+    /// preceding function. Functions and symbols starting at the same address replace the gap.
+    Gap { addr: u64 },
+}
+
+/// Prepares functions for insertion into a converter, see [`PreparedFunction`].
+///
+/// Transformers are not thread-safe, so a preparer with transformers can only be used
+/// sequentially.
+struct Preparer<'c> {
+    options: ConvertOptions<'c>,
+    name_transformer: Option<&'c mut NameTransformer>,
+    line_transformer: Option<&'c mut LineTransformer>,
+}
 
-        for symbol in object.symbols() {
-            self.process_symbolic_symbol(&symbol);
+impl<'c> Preparer<'c> {
+    /// Creates a preparer without transformers.
+    #[cfg(feature = "rayon")]
+    fn new(options: ConvertOptions<'c>) -> Self {
+        Preparer {
+            options,
+            name_transformer: None,
+            line_transformer: None,
         }
+    }
 
-        Ok(())
+    /// Prepares a top-level function and all of its inlinees.
+    fn prepare<'a>(&mut self, function: &'a Function<'a>) -> PreparedFunction<'a> {
+        let mut prepared = PreparedFunction::default();
+        self.prepare_function(&mut prepared, function, None);
+        prepared
     }
 
-    pub fn process_symbolic_function(&mut self, function: &Function<'_>) {
-        let comp_dir = std::str::from_utf8(function.compilation_dir).ok();
+    /// Recursively prepares a function and all of its inlinees.
+    ///
+    /// `caller_idx` is the local index of the function that an inlined `function` was inlined
+    /// into.
+    fn prepare_function<'a>(
+        &mut self,
+        prepared: &mut PreparedFunction<'a>,
+        function: &'a Function<'a>,
+        caller_idx: Option<u32>,
+    ) {
+        let options = self.options;
+        if (options.names_only || options.skip_inlinees) && function.inline {
+            return;
+        }
+
+        let comp_dir = std::str::from_utf8(function.compilation_dir)
+            .ok()
+            .filter(|_| !options.names_only);
+        let producer = std::str::from_utf8(function.producer)
+            .ok()
+            .filter(|producer| !producer.is_empty() && !options.names_only);
+        let unit = std::str::from_utf8(function.compilation_unit)
+            .ok()
+            .filter(|unit| !unit.is_empty() && !options.names_only);
 
+        // All parts of a function with discontiguous ranges share the entry point, and thereby
+        // the same function record.
         let entry_pc = if function.inline {
             u32::MAX
         } else {
-            function.address as u32
+            let entry_address = function.entry_address.unwrap_or(function.address);
+            options.entry_pc(entry_address)
         };
-        let function_idx = self.insert_function(
+        // The name is the full linkage name or symbol, and the plain name is kept separately.
+        let function_idx = self.prepare_record(
+            prepared,
             function.name.as_str(),
+            function.plain_name.as_ref().map(|name| name.as_str()),
             comp_dir,
+            producer,
+            unit,
             entry_pc,
             function.name.language(),
         );
 
         for line in &function.lines {
-            let path_name = line.file.name_str();
-            let file_idx = self.insert_file(&path_name, Some(&line.file.dir_str()), comp_dir);
-
-            let source_location = raw::SourceLocation {
-                file_idx,
-                line: line.line as u32,
+            let (addr, source_location) = match self.prepare_line_record(
+                prepared,
                 function_idx,
-                inlined_into_idx: u32::MAX,
+                line.address,
+                line.file.name_str(),
+                Some(line.file.dir_str()),
+                comp_dir,
+                to_line_number(line.line),
+                to_column(line.column),
+            ) {
+                Some(record) => record,
+                None => continue,
             };
 
-            match self.ranges.entry(line.address as u32) {
-                btree_map::Entry::Vacant(entry) => {
-                    if function.inline {
-                        // BUG:
-                        // the abstraction should have defined this line record inside the caller
-                        // function already!
-                    }
-                    entry.insert(source_location);
+            // BUG:
+            // the abstraction may yield multiple top-level functions for the same
+            // instruction addr, in which case the last one wins.
+            prepared.ranges.push(if function.inline {
+                PreparedRange::Inlined {
+                    addr,
+                    source_location,
+                    caller_idx,
                 }
-                btree_map::Entry::Occupied(mut entry) => {
-                    if function.inline {
-                        let caller_source_location = entry.get().clone();
-
-                        let mut callee_source_location = source_location;
-                        let (inlined_into_idx, _) =
-                            self.source_locations.insert_full(caller_source_location);
-
-                        callee_source_location.inlined_into_idx = inlined_into_idx as u32;
-                        entry.insert(callee_source_location);
-                    } else {
-                        // BUG:
-                        // the abstraction yields multiple top-level functions for the same
-                        // instruction addr
-                        entry.insert(source_location);
-                    }
+            } else {
+                PreparedRange::Line {
+                    addr,
+                    source_location,
                 }
-            }
+            });
+        }
+
+        if !function.inline && function.lines.is_empty() {
+            self.prepare_lineless(prepared, function_idx, function.address);
         }
 
         for inlinee in &function.inlinees {
-            self.process_symbolic_function(inlinee);
+            self.prepare_function(prepared, inlinee, Some(function_idx));
+        }
+
+        if !function.inline {
+            self.prepare_gap(prepared, function.address, function.size);
         }
     }
 
-    pub fn process_symbolic_symbol(&mut self, symbol: &Symbol<'_>) {
-        let name = match symbol.name {
-            Some(ref name) => name.as_ref(),
-            None => return,
-        };
+    /// Prepares a Breakpad function without inlinees.
+    ///
+    /// This is equivalent to [`prepare`](Self::prepare) with the converted record, but reads the
+    /// line records and the paths of `FILE` records directly.
+    fn prepare_breakpad<'a>(
+        &mut self,
+        record: &BreakpadFuncRecord<'a>,
+        lines: &[BreakpadLineRecord],
+        file_map: &BreakpadFileMap<'a>,
+    ) -> PreparedFunction<'a> {
+        let mut prepared = PreparedFunction::default();
 
-        let name_idx = self.insert_string(name);
+        // Breakpad functions have an empty compilation directory, no producer and no unit.
+        let comp_dir = Some("").filter(|_| !self.options.names_only);
+        let entry_pc = self.options.entry_pc(record.address);
+        let function_idx = self.prepare_record(
+            &mut prepared,
+            record.name,
+            None,
+            comp_dir,
+            None,
+            None,
+            entry_pc,
+            Language::Unknown,
+        );
 
-        match self.ranges.entry(symbol.address as u32) {
-            btree_map::Entry::Vacant(entry) => {
-                let function = raw::Function {
-                    name_idx,
-                    comp_dir_idx: u32::MAX,
-                    entry_pc: symbol.address as u32,
-                    lang: u32::MAX,
-                };
-                let function_idx = self.functions.insert_full(function).0 as u32;
+        for line in lines {
+            let path = file_map.get(&line.file_id).copied().unwrap_or_default();
+            let (directory, path_name) = symbolic_common::split_path(path);
 
-                entry.insert(raw::SourceLocation {
-                    file_idx: u32::MAX,
-                    line: 0,
-                    function_idx,
-                    inlined_into_idx: u32::MAX,
+            // Breakpad symbol files do not contain columns.
+            if let Some((addr, source_location)) = self.prepare_line_record(
+                &mut prepared,
+                function_idx,
+                line.address,
+                Cow::Borrowed(path_name),
+                Some(Cow::Borrowed(directory.unwrap_or_default())),
+                comp_dir,
+                to_line_number(line.line),
+                0,
+            ) {
+                prepared.ranges.push(PreparedRange::Line {
+                    addr,
+                    source_location,
                 });
             }
-            btree_map::Entry::Occupied(entry) => {
-                // ASSUMPTION:
-                // the `functions` iterator has already filled in this addr via debug session.
-                // we could trace the caller hierarchy up to the root, and assert that it is
-                // indeed the same function, and maybe update its `entry_pc`, but we don’t do
-                // that for now.
-                let _function_idx = entry.get().function_idx as usize;
-            }
         }
+
+        if lines.is_empty() {
+            self.prepare_lineless(&mut prepared, function_idx, record.address);
+        }
+
+        self.prepare_gap(&mut prepared, record.address, record.size);
+        prepared
     }
 
-    // Methods for serializing to a [`Write`] below:
-    // Feel free to move these to a separate file.
+    /// Interns the record of a function, returning its local index.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_record<'a>(
+        &mut self,
+        prepared: &mut PreparedFunction<'a>,
+        name: &'a str,
+        plain_name: Option<&'a str>,
+        comp_dir: Option<&'a str>,
+        producer: Option<&'a str>,
+        unit: Option<&'a str>,
+        entry_pc: u32,
+        lang: Language,
+    ) -> u32 {
+        let options = self.options;
+        let name_idx = self.prepare_name(prepared, name, lang);
+        let plain_name_idx = match plain_name {
+            Some(plain_name) => self.prepare_name(prepared, plain_name, lang),
+            None => u32::MAX,
+        };
+        let comp_dir_idx = comp_dir
+            .map(|comp_dir| prepared.insert_string(Cow::Borrowed(options.strip_path(comp_dir))))
+            .unwrap_or(u32::MAX);
+        let producer_idx = producer
+            .map(|producer| prepared.insert_string(Cow::Borrowed(producer)))
+            .unwrap_or(u32::MAX);
+        let unit_idx = unit
+            .map(|unit| prepared.insert_string(Cow::Borrowed(unit)))
+            .unwrap_or(u32::MAX);
+        prepared.functions_total += 1;
+        let (function_idx, _) = prepared.functions.insert_full(raw::Function {
+            name_idx,
+            plain_name_idx,
+            comp_dir_idx,
+            entry_pc,
+            lang: lang as u32,
+            producer_idx,
+            unit_idx,
+        });
+        to_index(function_idx)
+    }
 
-    /// Serialize the converted data.
+    /// Prepares the source location of a line record of the function at `function_idx`.
     ///
-    /// This writes the SymCache binary format into the given [`Write`].
-    pub fn serialize<W: Write>(self, writer: &mut W) -> std::io::Result<()> {
-        let mut writer = WriteWrapper::new(writer);
-
-        let num_strings = self.strings.len() as u32;
-        let num_files = self.files.len() as u32;
-        let num_functions = self.functions.len() as u32;
-        let num_source_locations = (self.source_locations.len() + self.ranges.len()) as u32;
-        let num_ranges = self.ranges.len() as u32;
-        let string_bytes = self.string_bytes.len() as u32;
+    /// Returns the offset address of the line record along with its source location, or `None`
+    /// if the line record is below the range threshold.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_line_record<'a>(
+        &mut self,
+        prepared: &mut PreparedFunction<'a>,
+        function_idx: u32,
+        address: u64,
+        path_name: Cow<'a, str>,
+        directory: Option<Cow<'a, str>>,
+        comp_dir: Option<&'a str>,
+        line: u32,
+        column: u16,
+    ) -> Option<(u64, SourceLocationRecord)> {
+        let addr = match self.options.offset_addr(address) {
+            Some(addr) => addr,
+            None => {
+                prepared.records_skipped += 1;
+                return None;
+            }
+        };
 
-        let header = raw::Header {
-            magic: raw::SYMCACHE_MAGIC,
-            version: raw::SYMCACHE_VERSION,
+        let source_location = if self.options.names_only {
+            SourceLocationRecord {
+                file_idx: u32::MAX,
+                line: 0,
+                function_idx,
+                inlined_into_idx: u32::MAX,
+                column: 0,
+            }
+        } else {
+            let (file_idx, line, column) =
+                self.prepare_line(prepared, path_name, directory, comp_dir, line, column);
 
-            debug_id: self.debug_id,
-            arch: self.arch,
+            SourceLocationRecord {
+                file_idx,
+                line,
+                function_idx,
+                inlined_into_idx: u32::MAX,
+                column,
+            }
+        };
 
-            range_offset: self.range_threshold,
+        Some((addr, source_location))
+    }
 
-            num_strings,
-            num_files,
-            num_functions,
-            num_source_locations,
-            num_ranges,
-            string_bytes,
+    /// Covers a top-level function without line records by a range without a file.
+    ///
+    /// Otherwise, the function could not be looked up at all, even though its address and size
+    /// are known.
+    fn prepare_lineless(
+        &self,
+        prepared: &mut PreparedFunction<'_>,
+        function_idx: u32,
+        address: u64,
+    ) {
+        let addr = match self.options.offset_addr(address) {
+            Some(addr) => addr,
+            None => {
+                prepared.records_skipped += 1;
+                return;
+            }
         };
 
-        writer.write(&[header])?;
-        writer.align()?;
-
-        for (_, s) in self.strings {
-            writer.write(&[s])?;
-        }
-        writer.align()?;
+        prepared.ranges.push(PreparedRange::Line {
+            addr,
+            source_location: SourceLocationRecord {
+                file_idx: u32::MAX,
+                line: 0,
+                function_idx,
+                inlined_into_idx: u32::MAX,
+                column: 0,
+            },
+        });
+    }
+
+    /// Marks the end of a top-level function, see [`PreparedRange::Gap`].
+    fn prepare_gap(&self, prepared: &mut PreparedFunction<'_>, address: u64, size: u64) {
+        if size == 0 {
+            return;
+        }
+
+        if let Some(addr) = self.options.offset_addr(address.saturating_add(size)) {
+            prepared.ranges.push(PreparedRange::Gap { addr });
+        }
+    }
+
+    /// Transforms and interns the name of a function, like [`SymCacheConverter::insert_name`].
+    fn prepare_name<'a>(
+        &mut self,
+        prepared: &mut PreparedFunction<'a>,
+        name: &'a str,
+        language: Language,
+    ) -> u32 {
+        let transformed = match self.name_transformer {
+            Some(ref mut transformer) => (transformer.0)(name, language),
+            None => None,
+        };
+        let name = transformed.unwrap_or(Cow::Borrowed(name));
+        if name.is_empty() {
+            return u32::MAX;
+        }
+        prepared.insert_string(name)
+    }
+
+    /// Transforms and interns the file of a line record, like
+    /// [`SymCacheConverter::insert_line`].
+    ///
+    /// Returns the index of the file, the line number and the column. The column refers to the
+    /// original line, so it is dropped if the line is transformed.
+    fn prepare_line<'a>(
+        &mut self,
+        prepared: &mut PreparedFunction<'a>,
+        path_name: Cow<'a, str>,
+        directory: Option<Cow<'a, str>>,
+        comp_dir: Option<&'a str>,
+        line: u32,
+        column: u16,
+    ) -> (u32, u32, u16) {
+        if let Some(ref mut transformer) = self.line_transformer {
+            let file = File {
+                comp_dir,
+                directory: directory.as_deref(),
+                path_name: &path_name,
+                source: None,
+            };
+            if let Some((path, line)) = (transformer.0)(&file, line) {
+                return (
+                    self.prepare_file(prepared, Cow::Owned(path), None, None),
+                    line,
+                    0,
+                );
+            }
+        }
+
+        let comp_dir = comp_dir.map(Cow::Borrowed);
+        (
+            self.prepare_file(prepared, path_name, directory, comp_dir),
+            line,
+            column,
+        )
+    }
+
+    /// Interns a file, like [`SymCacheConverter::insert_file`].
+    fn prepare_file<'a>(
+        &self,
+        prepared: &mut PreparedFunction<'a>,
+        path_name: Cow<'a, str>,
+        directory: Option<Cow<'a, str>>,
+        comp_dir: Option<Cow<'a, str>>,
+    ) -> u32 {
+        let stripped =
+            self.options
+                .strip_file_path(&path_name, directory.as_deref(), comp_dir.as_deref());
+        let (path_name, directory, comp_dir) = match stripped {
+            Some(path) => (Cow::Owned(path), None, None),
+            None => (path_name, directory, comp_dir),
+        };
+
+        let path_name_idx = prepared.insert_string(path_name);
+        let directory_idx = directory.map_or(u32::MAX, |d| prepared.insert_string(d));
+        let comp_dir_idx = comp_dir.map_or(u32::MAX, |cd| prepared.insert_string(cd));
+
+        let (file_idx, _) = prepared.files.insert_full(raw::File {
+            path_name_idx,
+            directory_idx,
+            comp_dir_idx,
+        });
+
+        to_index(file_idx)
+    }
+}
+
+/// The number of functions that are prepared in parallel before they are inserted.
+#[cfg(feature = "rayon")]
+const PARALLEL_BATCH_SIZE: usize = 4096;
+
+/// The SymCache Converter.
+///
+/// This can convert data in various source formats to an intermediate representation, which can
+/// then be serialized to disk via its [`Converter::serialize`] method.
+///
+/// # Determinism
+///
+/// The serialized output only depends on the data that was added and the order in which it was
+/// added. All interned records are kept in insertion order, and never in hash order, so the
+/// output is byte-for-byte identical across runs, processes and platforms of the same endianness.
+/// Any collection added to the converter must preserve this property.
+///
+/// # Memory
+///
+/// All records are kept in memory until the converter is serialized. Their size grows linearly
+/// with the number of functions and line records, and is dominated by string data for symbol
+/// files with long names. To bound the memory used for strings, set a
+/// [memory budget](Self::set_memory_budget), which moves string data to a temporary file.
+#[derive(Debug, Default)]
+pub struct SymCacheConverter {
+    /// Debug identifier of the object file.
+    debug_id: DebugId,
+    /// CPU architecture of the object file.
+    arch: Arch,
+
+    /// The minimum addr for all the ranges in the debug file.
+    /// This is used to ignore ranges that are below this threshold, as linkers leave range data
+    /// intact, but rather set removed ranges to 0 (or below this threshold).
+    /// Also, this is used as an offset for the saved ranges, to decrease the likelihood they
+    /// overflow `u32`.
+    ///
+    /// See [`set_range_threshold`](Self::set_range_threshold).
+    range_threshold: u64,
+
+    /// The address range this converter is restricted to, if any.
+    ///
+    /// Only functions and symbols intersecting this range are added to the SymCache.
+    address_range: Option<Range<u64>>,
+
+    /// An optional transformation applied to all function and symbol names.
+    name_transformer: Option<NameTransformer>,
+
+    /// An optional transformation applied to the file and line of all source locations.
+    line_transformer: Option<LineTransformer>,
+
+    /// Prefixes removed from all paths before they are added.
+    ///
+    /// See [`strip_path_prefix`](Self::strip_path_prefix).
+    path_prefixes: Vec<String>,
+
+    /// An optional function supplying the contents of source files to embed.
+    source_provider: Option<SourceProvider>,
+
+    /// The maximum total size of embedded sources, if it differs from the default.
+    ///
+    /// See [`set_source_size_limit`](Self::set_source_size_limit).
+    source_size_limit: Option<u32>,
+
+    /// Whether only the names of top-level functions are written.
+    ///
+    /// See [`set_names_only`](Self::set_names_only).
+    names_only: bool,
+
+    /// Whether inlined functions are omitted.
+    ///
+    /// See [`set_skip_inlinees`](Self::set_skip_inlinees).
+    skip_inlinees: bool,
+
+    /// Whether symbols from the symbol table are skipped when processing objects.
+    ///
+    /// See [`set_debug_info_only`](Self::set_debug_info_only).
+    debug_info_only: bool,
+
+    /// Whether an index for looking up functions by name is written.
+    ///
+    /// See [`set_name_index`](Self::set_name_index).
+    name_index: bool,
+
+    /// The zstd compression level for string data, if it is compressed.
+    ///
+    /// See [`set_compression_level`](Self::set_compression_level).
+    #[cfg(feature = "compression")]
+    compression_level: Option<i32>,
+
+    /// Additional flags written to the header, which are not derived from the contents.
+    ///
+    /// See [`set_feature_flags`](Self::set_feature_flags).
+    feature_flags: u32,
+
+    /// All strings that have been added to this `Converter`, including their bytes.
+    strings: StringTable,
+    /// The number of strings that have been added, including duplicates.
+    strings_total: usize,
+    /// The set of all [`raw::File`]s that have been added to this `Converter`.
+    files: IndexSet<raw::File>,
+    /// The set of all [`raw::Function`]s that have been added to this `Converter`.
+    functions: IndexSet<raw::Function>,
+    /// The number of functions that have been added, including duplicates.
+    functions_total: usize,
+    /// The number of functions, symbols and line records that have been skipped.
+    ///
+    /// See [`WriteStats::records_skipped`].
+    records_skipped: usize,
+    /// The set of all [`SourceLocationRecord`]s that have been added to this `Converter` and that
+    /// aren't directly associated with a code range.
+    ///
+    /// These are the callers of inlined functions. Identical inline chains share a single entry,
+    /// and each entry is inserted after its own caller, so `inlined_into_idx` always refers to a
+    /// lower index.
+    source_locations: IndexSet<SourceLocationRecord>,
+    /// The number of source locations that were already present in `source_locations`.
+    shared_source_locations: usize,
+    /// A map from code ranges to the [`SourceLocationRecord`]s they correspond to.
+    ///
+    /// Only the starting address of a range is saved, the end address is given implicitly
+    /// by the start address of the next range. Ranges without code map to a
+    /// [gap](raw::SourceLocation::GAP).
+    ranges: BTreeMap<u64, SourceLocationRecord>,
+}
+
+impl SymCacheConverter {
+    /// Creates a new Converter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the CPU architecture of this SymCache.
+    pub fn set_arch(&mut self, arch: Arch) {
+        self.arch = arch;
+    }
+
+    /// Limits the memory used for string data to roughly `budget` bytes.
+    ///
+    /// Once the bytes of all interned strings exceed the budget, they are moved to a temporary
+    /// file, which is created in the [temp dir](Self::set_temp_dir) or the system's default
+    /// temporary directory. The output is identical to converting entirely in memory.
+    pub fn set_memory_budget(&mut self, budget: usize) {
+        self.strings.set_memory_budget(budget);
+    }
+
+    /// Sets the directory in which temporary files are created.
+    ///
+    /// This has no effect unless a [memory budget](Self::set_memory_budget) is set.
+    pub fn set_temp_dir(&mut self, temp_dir: &Path) {
+        self.strings.set_temp_dir(temp_dir);
+    }
+
+    /// Sets the debug identifier of this SymCache.
+    pub fn set_debug_id(&mut self, debug_id: DebugId) {
+        self.debug_id = debug_id;
+    }
+
+    /// Sets the minimum address of all ranges in this SymCache. Defaults to `0`.
+    ///
+    /// Line records and symbols below the threshold are dropped, which compacts the range table
+    /// by removing the ranges of code that the linker discarded and relocated to address `0`.
+    /// All other ranges are stored relative to the threshold, which keeps them within 32 bits for
+    /// images that span less than 4GiB above the threshold. Larger images are stored with
+    /// [wide ranges](super::Features::has_wide_ranges), which doubles the size of the range table.
+    /// Lookups of addresses below the threshold yield no results.
+    pub fn set_range_threshold(&mut self, threshold: u64) {
+        self.range_threshold = threshold;
+    }
+
+    /// Restricts this SymCache to the given address range.
+    ///
+    /// Only functions and symbols that intersect `range` are added, along with the strings and
+    /// files they reference. Functions straddling the range boundaries are included whole, so that
+    /// lookups close to the edges resolve the same as in an unrestricted SymCache. Lookups for
+    /// addresses outside of `range` never yield any results.
+    pub fn set_address_range(&mut self, range: Range<u64>) {
+        self.address_range = Some(range);
+    }
+
+    /// Only writes the names of top-level functions and symbols.
+    ///
+    /// This produces a much smaller SymCache for consumers that do not need source information.
+    /// Files, line numbers, inlined functions, compilation directories, producers and compilation
+    /// units are omitted, and adjacent ranges that resolve to the same function are merged.
+    /// Lookups still resolve the name of the outermost function covering an address. The
+    /// resulting SymCache is marked with
+    /// [`Features::is_names_only`](super::Features::is_names_only).
+    pub fn set_names_only(&mut self, names_only: bool) {
+        self.names_only = names_only;
+    }
+
+    /// Omits inlined functions.
+    ///
+    /// Lookups resolve to the outermost function only, at the line of the call site for addresses
+    /// in inlined code. Unlike [`set_names_only`](Self::set_names_only), files and line numbers
+    /// are kept. Strings that are only referenced by inlined functions are not written.
+    pub fn set_skip_inlinees(&mut self, skip_inlinees: bool) {
+        self.skip_inlinees = skip_inlinees;
+    }
+
+    /// Only writes functions from debug information when processing objects.
+    ///
+    /// By default, the symbol table of an object is processed after its debug information. This
+    /// fills in the names of functions that are not covered by debug information, such as in
+    /// objects with DWARF sections that only describe types. Like Breakpad `PUBLIC` records, these
+    /// functions have no files or line numbers. Enable this to skip the symbol table entirely.
+    pub fn set_debug_info_only(&mut self, debug_info_only: bool) {
+        self.debug_info_only = debug_info_only;
+    }
+
+    /// Writes an index for looking up functions by name.
+    ///
+    /// The index contains four bytes per function, and allows
+    /// [`SymCache::lookup_by_name`](super::SymCache::lookup_by_name) to perform a binary search
+    /// instead of scanning all functions.
+    pub fn set_name_index(&mut self, name_index: bool) {
+        self.name_index = name_index;
+    }
+
+    /// Compresses the string data of the SymCache with zstd at the given level.
+    ///
+    /// String data accounts for most of the size of SymCaches of symbol-heavy modules, and
+    /// compresses well. It is decompressed once by [`SymCache::parse`](super::SymCache::parse).
+    /// String data that compresses better than
+    /// [`MAX_COMPRESSION_RATIO`](super::raw::MAX_COMPRESSION_RATIO) is written uncompressed. The
+    /// string data is held in memory during [`serialize`](Self::serialize).
+    #[cfg(feature = "compression")]
+    pub fn set_compression_level(&mut self, level: i32) {
+        self.compression_level = Some(level);
+    }
+
+    /// Sets additional feature flags in the header of the SymCache.
+    ///
+    /// This marks format extensions whose data is added outside of this converter. Flags in the
+    /// lower 16 bits are optional, and readers that do not know them ignore them. Flags in the
+    /// upper 16 bits are required, and readers that do not know them reject the SymCache with
+    /// [`Error::UnsupportedFeature`]. Flags that the converter derives from the contents, such as
+    /// whether there are inlined functions, cannot be set and are ignored.
+    pub fn set_feature_flags(&mut self, flags: u32) {
+        self.feature_flags = flags & !raw::KNOWN_FLAGS;
+    }
+
+    /// Sets a function that transforms function and symbol names before they are added.
+    ///
+    /// The transformer receives the original name and the language of the function, and returns
+    /// the new name, or `None` to keep the original. Names are deduplicated after transformation,
+    /// so names that map to the same output are only stored once. Both the full name and the
+    /// plain name of functions are transformed.
+    pub fn set_name_transformer<F>(&mut self, transformer: F)
+    where
+        F: FnMut(&str, Language) -> Option<Cow<'_, str>> + 'static,
+    {
+        self.name_transformer = Some(NameTransformer(Box::new(transformer)));
+    }
+
+    /// Applies the [`NameTransformer`] to the given name, if one is set.
+    fn transform_name<'s>(&mut self, name: &'s str, language: Language) -> Cow<'s, str> {
+        let transformed = match self.name_transformer {
+            Some(ref mut transformer) => (transformer.0)(name, language),
+            None => None,
+        };
+        transformed.unwrap_or(Cow::Borrowed(name))
+    }
+
+    /// Transforms and inserts the name of a function or symbol.
+    ///
+    /// Stripped objects may contain functions without a name. Empty names are not stored, and the
+    /// returned index is `u32::MAX` instead.
+    fn insert_name(&mut self, name: &str, language: Language) -> u32 {
+        let name = self.transform_name(name, language);
+        if name.is_empty() {
+            return u32::MAX;
+        }
+        self.insert_string(&name)
+    }
+
+    /// Sets a function that transforms the file and line of source locations before they are
+    /// added.
+    ///
+    /// The transformer receives the file and line number of every line record, including the call
+    /// sites of inlined functions. It returns a new full path and line number, or `None` to keep
+    /// the original location. Transformed paths are stored as path name only, without a directory
+    /// or compilation directory.
+    pub fn set_line_transformer<F>(&mut self, transformer: F)
+    where
+        F: FnMut(&File<'_>, u32) -> Option<(String, u32)> + 'static,
+    {
+        self.line_transformer = Some(LineTransformer(Box::new(transformer)));
+    }
+
+    /// Sets a function that supplies the contents of source files to embed into the SymCache.
+    ///
+    /// The provider is called once for every distinct file during [`serialize`](Self::serialize),
+    /// in the order in which the files were added. It receives the path name of the file as
+    /// `name`, and its directory joined to the compilation directory as `dir`, so that
+    /// [`FileInfo::path_str`] yields the same path as [`File::full_path`]. Both are passed after
+    /// [stripping path prefixes](Self::strip_path_prefix). The provider returns the contents of
+    /// the file, or `None` to embed nothing. Embedded contents are read via [`File::source`].
+    ///
+    /// Embedded sources count towards the [size limit](Self::set_source_size_limit). Files whose
+    /// contents would exceed the limit are written without contents.
+    pub fn set_source_provider<F>(&mut self, provider: F)
+    where
+        F: FnMut(&FileInfo<'_>) -> Option<Vec<u8>> + 'static,
+    {
+        self.source_provider = Some(SourceProvider(Box::new(provider)));
+    }
+
+    /// Limits the total size of embedded sources to `limit` bytes.
+    ///
+    /// Defaults to 16 MiB. This has no effect unless a
+    /// [source provider](Self::set_source_provider) is set. All embedded sources are held in
+    /// memory during [`serialize`](Self::serialize).
+    pub fn set_source_size_limit(&mut self, limit: u32) {
+        self.source_size_limit = Some(limit);
+    }
+
+    /// Removes a prefix from all paths before they are added.
+    ///
+    /// This applies to the full path of files, which is resolved from their compilation directory,
+    /// directory and path name first, as well as to the compilation directory of functions. Files
+    /// whose path starts with a prefix are stored with the remaining path only, without a
+    /// directory or compilation directory. The prefix only matches entire leading path
+    /// components, so `/builds/worker` turns `/builds/worker/src` into `src`, but leaves
+    /// `/builds/worker2` unchanged. Paths that do not start with the prefix are added unchanged.
+    ///
+    /// This can be called multiple times to remove one of several prefixes. Only the first
+    /// matching prefix is removed from each path.
+    pub fn strip_path_prefix(&mut self, prefix: &str) {
+        let prefix = prefix.trim_end_matches(|c: char| c == '/' || c == '\\');
+        self.path_prefixes.push(prefix.to_owned());
+    }
+
+    /// Returns the settings that determine how records are converted.
+    fn options(&self) -> ConvertOptions<'_> {
+        ConvertOptions {
+            arch: self.arch,
+            range_threshold: self.range_threshold,
+            address_range: self.address_range.as_ref(),
+            names_only: self.names_only,
+            skip_inlinees: self.skip_inlinees,
+            path_prefixes: &self.path_prefixes,
+        }
+    }
+
+    /// Whether any range starts more than 4GiB above the `range_threshold`.
+    fn has_wide_ranges(&self) -> bool {
+        self.ranges
+            .keys()
+            .next_back()
+            .map_or(false, |&addr| addr > u64::from(u32::MAX))
+    }
+
+    /// Whether any source location has a column.
+    fn has_columns(&self) -> bool {
+        self.source_locations
+            .iter()
+            .chain(self.ranges.values())
+            .any(|sl| sl.column != 0)
+    }
+
+    /// Insert a string into this converter.
+    ///
+    /// If the string was already present, it is not added again. The returned `u32`
+    /// is the string's index in insertion order.
+    fn insert_string(&mut self, s: &str) -> u32 {
+        self.strings_total += 1;
+        self.strings.insert(s)
+    }
+
+    /// Insert a [`SourceLocationRecord`] into this converter.
+    ///
+    /// If the `SourceLocation` was already present, it is not added again. The returned `u32`
+    /// is the `SourceLocation`'s index in insertion order.
+    fn insert_source_location(&mut self, source_location: SourceLocationRecord) -> u32 {
+        let (source_location_idx, inserted) = self.source_locations.insert_full(source_location);
+        if !inserted {
+            self.shared_source_locations += 1;
+        }
+        to_index(source_location_idx)
+    }
+
+    /// Insert a file into this converter.
+    ///
+    /// If the file was already present, it is not added again. The returned `u32`
+    /// is the file's index in insertion order.
+    pub(crate) fn insert_file(
+        &mut self,
+        path_name: &str,
+        directory: Option<&str>,
+        comp_dir: Option<&str>,
+    ) -> u32 {
+        let stripped = self
+            .options()
+            .strip_file_path(path_name, directory, comp_dir);
+        let (path_name, directory, comp_dir) = match stripped {
+            Some(ref path) => (path.as_str(), None, None),
+            None => (path_name, directory, comp_dir),
+        };
+
+        let path_name_idx = self.insert_string(path_name);
+        let directory_idx = directory.map_or(u32::MAX, |d| self.insert_string(d));
+        let comp_dir_idx = comp_dir.map_or(u32::MAX, |cd| self.insert_string(cd));
+
+        let (file_idx, _) = self.files.insert_full(raw::File {
+            path_name_idx,
+            directory_idx,
+            comp_dir_idx,
+        });
+
+        to_index(file_idx)
+    }
+
+    /// Inserts the file of a line record into this converter, applying the [`LineTransformer`].
+    ///
+    /// Returns the index of the file and the line number, both of which may have been transformed.
+    fn insert_line(
+        &mut self,
+        path_name: &str,
+        directory: Option<&str>,
+        comp_dir: Option<&str>,
+        line: u32,
+    ) -> (u32, u32) {
+        if let Some(ref mut transformer) = self.line_transformer {
+            let file = File {
+                comp_dir,
+                directory,
+                path_name,
+                source: None,
+            };
+            if let Some((path, line)) = (transformer.0)(&file, line) {
+                return (self.insert_file(&path, None, None), line);
+            }
+        }
+
+        (self.insert_file(path_name, directory, comp_dir), line)
+    }
+
+    /// Insert a function into this converter.
+    ///
+    /// If the function was already present, it is not added again. The returned `u32`
+    /// is the function's index in insertion order.
+    fn insert_function(
+        &mut self,
+        name: &str,
+        plain_name: Option<&str>,
+        comp_dir: Option<&str>,
+        producer: Option<&str>,
+        unit: Option<&str>,
+        entry_pc: u32,
+        lang: Language,
+    ) -> u32 {
+        let name_idx = self.insert_name(name, lang);
+        let plain_name_idx = plain_name
+            .map(|plain_name| self.insert_name(plain_name, lang))
+            .unwrap_or(u32::MAX);
+        let comp_dir_idx = comp_dir
+            .map(|comp_dir| self.options().strip_path(comp_dir))
+            .map(|comp_dir| self.insert_string(comp_dir))
+            .unwrap_or(u32::MAX);
+        let producer_idx = producer
+            .map(|producer| self.insert_string(producer))
+            .unwrap_or(u32::MAX);
+        let unit_idx = unit
+            .map(|unit| self.insert_string(unit))
+            .unwrap_or(u32::MAX);
+        let lang = lang as u32;
+        self.functions_total += 1;
+        let (fun_idx, _) = self.functions.insert_full(raw::Function {
+            name_idx,
+            plain_name_idx,
+            comp_dir_idx,
+            entry_pc,
+            lang,
+            producer_idx,
+            unit_idx,
+        });
+        to_index(fun_idx)
+    }
+
+    // Methods processing symbolic-debuginfo [`ObjectLike`] below:
+    // Feel free to move these to a separate file.
+
+    /// This processes the given [`ObjectLike`] object, collecting all its functions and line
+    /// information into the converter.
+    ///
+    /// Compilation units and functions that cannot be read are skipped, and a warning for each of
+    /// them is added to the returned [`WriterReport`]. An error is only returned if the debug
+    /// information cannot be read at all, or if all of it had to be skipped.
+    pub fn process_object<'d, 'o, O>(
+        &mut self,
+        object: &'o O,
+    ) -> Result<WriterReport, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        self.process_object_with_progress(object, |_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`process_object`](Self::process_object), but reports [`Progress`] after each
+    /// top-level function.
+    ///
+    /// If the `progress` callback returns [`ControlFlow::Break`], processing stops immediately and
+    /// an error of kind [`SymCacheErrorKind::Cancelled`] is returned.
+    pub fn process_object_with_progress<'d, 'o, O, F>(
+        &mut self,
+        object: &'o O,
+        progress: F,
+    ) -> Result<WriterReport, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        let session = object
+            .debug_session()
+            .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+
+        self.process_session(&session, object.symbol_map(), 1, progress)
+    }
+
+    /// Like [`process_object`](Self::process_object), but for binaries built with split DWARF.
+    ///
+    /// Such binaries only contain skeleton units, while the line and inlinee information is stored
+    /// in the `.dwo` object or `.dwp` package given as `supplement`. See
+    /// [`Object::debug_session_with_split_dwarf`] for more information.
+    pub fn process_object_with_dwarf_supplement<'d>(
+        &mut self,
+        object: &Object<'d>,
+        supplement: &Object<'d>,
+    ) -> Result<WriterReport, SymCacheError> {
+        let session = object
+            .debug_session_with_split_dwarf(supplement)
+            .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+
+        self.process_session(&session, object.symbol_map(), 1, |_| {
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Like [`process_object`](Self::process_object), but prepares functions in parallel.
+    ///
+    /// The output is identical to [`process_object`](Self::process_object). Functions are still
+    /// read from the debug information sequentially, but their strings, files and line records are
+    /// prepared in parallel batches on the global `rayon` thread pool. Name and line transformers
+    /// cannot be called in parallel, so functions are prepared sequentially if either is set.
+    #[cfg(feature = "rayon")]
+    pub fn process_object_parallel<'d, 'o, O>(
+        &mut self,
+        object: &'o O,
+    ) -> Result<WriterReport, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        self.process_object_parallel_with_progress(object, |_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`process_object_parallel`](Self::process_object_parallel), but reports [`Progress`]
+    /// after each batch of top-level functions.
+    ///
+    /// If the `progress` callback returns [`ControlFlow::Break`], processing stops after the
+    /// current batch and an error of kind [`SymCacheErrorKind::Cancelled`] is returned.
+    #[cfg(feature = "rayon")]
+    pub fn process_object_parallel_with_progress<'d, 'o, O, F>(
+        &mut self,
+        object: &'o O,
+        progress: F,
+    ) -> Result<WriterReport, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        let session = object
+            .debug_session()
+            .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+
+        self.process_session(&session, object.symbol_map(), PARALLEL_BATCH_SIZE, progress)
+    }
+
+    /// Processes a batch of functions like
+    /// [`process_symbolic_function`](Self::process_symbolic_function).
+    ///
+    /// With the `rayon` feature, batches of more than one function are prepared in parallel. The
+    /// functions are inserted in the order of the batch, so the output is identical to processing
+    /// them one by one. Name and line transformers are not thread-safe, so they force sequential
+    /// processing.
+    fn process_functions(&mut self, functions: &[Function<'_>]) {
+        #[cfg(feature = "rayon")]
+        {
+            let transformed = self.name_transformer.is_some() || self.line_transformer.is_some();
+            if functions.len() > 1 && !transformed {
+                self.process_functions_parallel(functions);
+                return;
+            }
+        }
+
+        for function in functions {
+            self.process_symbolic_function(function);
+        }
+    }
+
+    /// Prepares a batch of functions in parallel and inserts them in order, see
+    /// [`process_functions`](Self::process_functions).
+    #[cfg(feature = "rayon")]
+    fn process_functions_parallel(&mut self, functions: &[Function<'_>]) {
+        use rayon::prelude::*;
+
+        let options = self.options();
+        let prepared: Vec<_> = functions
+            .par_iter()
+            .map(|function| {
+                if options.intersects_address_range(function.address, function.end_address()) {
+                    Some(Preparer::new(options).prepare(function))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for function in &prepared {
+            match function {
+                Some(function) => self.insert_prepared(function),
+                None => self.records_skipped += 1,
+            }
+        }
+    }
+
+    /// Processes all functions of a debug session, followed by the symbols in `symbols`.
+    ///
+    /// Functions are processed in batches of `batch_size`, see
+    /// [`process_functions`](Self::process_functions), and progress is reported after each batch.
+    fn process_session<S, E, F>(
+        &mut self,
+        session: &S,
+        symbols: SymbolMap<'_>,
+        batch_size: usize,
+        mut progress: F,
+    ) -> Result<WriterReport, SymCacheError>
+    where
+        S: for<'s> DebugSession<'s, Error = E>,
+        E: std::error::Error + Send + Sync + 'static,
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        let mut report = WriterReport::default();
+        let shared_source_locations = self.shared_source_locations;
+        let mut functions_processed = 0;
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut functions = session.functions();
+        loop {
+            let finished = match functions.next() {
+                Some(Ok(function)) => {
+                    batch.push(function);
+                    false
+                }
+                Some(Err(e)) => {
+                    report.warnings.push(WriterWarning {
+                        unit_offset: unit_offset(&e),
+                        error: SymCacheError::new(SymCacheErrorKind::BadDebugFile, e),
+                    });
+                    continue;
+                }
+                None => true,
+            };
+
+            if !batch.is_empty() && (finished || batch.len() >= batch_size) {
+                self.process_functions(&batch);
+                functions_processed += batch.len();
+                batch.clear();
+
+                let current = Progress {
+                    functions_processed,
+                    functions_total: None,
+                };
+                if let ControlFlow::Break(()) = progress(current) {
+                    return Err(SymCacheErrorKind::Cancelled.into());
+                }
+            }
+
+            if finished {
+                break;
+            }
+        }
+
+        self.finish_processing(symbols, report, shared_source_locations)
+    }
+
+    /// Processes a Breakpad object, producing the same output as
+    /// [`process_object`](Self::process_object).
+    ///
+    /// This reads `FUNC` and line records directly instead of converting them into [`Function`]s
+    /// first, and resolves the path of every `FILE` record only once. Functions with `INLINE`
+    /// records are converted like in [`process_object`](Self::process_object).
+    pub fn process_breakpad(
+        &mut self,
+        object: &BreakpadObject<'_>,
+    ) -> Result<WriterReport, SymCacheError> {
+        let mut report = WriterReport::default();
+        let shared_source_locations = self.shared_source_locations;
+
+        let file_map = object.file_map();
+        let mut session = None;
+
+        for record in object.func_records() {
+            let result = match record {
+                Ok(record) if record.inlinees().next().is_none() => {
+                    self.process_breakpad_function(&record, &file_map)
+                }
+                Ok(record) => {
+                    let session: &BreakpadDebugSession<'_> =
+                        match session {
+                            Some(ref session) => session,
+                            None => session.get_or_insert(object.debug_session().map_err(|e| {
+                                SymCacheError::new(SymCacheErrorKind::BadDebugFile, e)
+                            })?),
+                        };
+                    session
+                        .function(record)
+                        .map(|function| self.process_symbolic_function(&function))
+                }
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                report.warnings.push(WriterWarning {
+                    unit_offset: None,
+                    error: SymCacheError::new(SymCacheErrorKind::BadDebugFile, e),
+                });
+            }
+        }
+
+        self.finish_processing(object.symbol_map(), report, shared_source_locations)
+    }
+
+    /// Processes a Breakpad function without inlinees.
+    ///
+    /// This is equivalent to [`process_symbolic_function`](Self::process_symbolic_function) with
+    /// the converted record.
+    fn process_breakpad_function(
+        &mut self,
+        record: &BreakpadFuncRecord<'_>,
+        file_map: &BreakpadFileMap<'_>,
+    ) -> Result<(), BreakpadError> {
+        // Read all lines first, so that a malformed record is skipped entirely.
+        let lines = record.lines().collect::<Result<Vec<_>, _>>()?;
+
+        let end = record.address.saturating_add(record.size);
+        if !self.options().intersects_address_range(record.address, end) {
+            self.records_skipped += 1;
+            return Ok(());
+        }
+
+        let prepared = self.preparer().prepare_breakpad(record, &lines, file_map);
+        self.insert_prepared(&prepared);
+        Ok(())
+    }
+
+    /// Processes all functions of a SymCache in the legacy format.
+    ///
+    /// Legacy SymCaches do not retain the debug information they were written from, so the ranges
+    /// of the new SymCache are reconstructed from lookups instead. Every address at which a legacy
+    /// lookup can change, which are the start and end addresses of functions and the addresses of
+    /// line records, starts a range covering the result of the lookup at that address. This
+    /// preserves function names, addresses, files and line numbers of all lookups exactly.
+    ///
+    /// Legacy SymCaches do not contain line addresses, producers or compilation units, and older
+    /// versions may lack compilation directories and inlinees.
+    pub fn process_legacy_symcache(
+        &mut self,
+        symcache: &LegacySymCache<'_>,
+    ) -> Result<(), SymCacheError> {
+        let mut addresses = Vec::new();
+        for function in symcache.functions() {
+            let function = function?;
+            let start = function.address();
+            addresses.push(start);
+            addresses.extend(Some(function.end_address()).filter(|&end| end != u64::MAX));
+            // Line addresses are relative to the start of their function.
+            for line in function.lines() {
+                addresses.push(start.saturating_add(line?.address()));
+            }
+        }
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        let mut previous = None;
+        for addr in addresses {
+            // Lookups yield the innermost frame first, but callers are inserted first.
+            let mut frames = symcache.lookup(addr)?.collect::<Vec<_>>()?;
+            frames.reverse();
+
+            let mut source_location = SourceLocationRecord::GAP;
+            for frame in frames {
+                let comp_dir = Some(frame.compilation_dir()).filter(|dir| !dir.is_empty());
+                let entry_pc = if frame.is_inlined() {
+                    u32::MAX
+                } else {
+                    self.options().entry_pc(frame.function_address())
+                };
+                let function_idx = self.insert_function(
+                    frame.symbol(),
+                    None,
+                    comp_dir,
+                    None,
+                    None,
+                    entry_pc,
+                    frame.language(),
+                );
+
+                let (file_idx, line) = if self.names_only {
+                    (u32::MAX, 0)
+                } else if frame.filename().is_empty() {
+                    (u32::MAX, frame.line())
+                } else {
+                    self.insert_line(
+                        frame.filename(),
+                        Some(frame.base_dir()),
+                        comp_dir,
+                        frame.line(),
+                    )
+                };
+
+                let inlined_into_idx = if source_location.is_gap() {
+                    u32::MAX
+                } else {
+                    self.insert_source_location(source_location)
+                };
+                source_location = SourceLocationRecord {
+                    file_idx,
+                    line,
+                    function_idx,
+                    inlined_into_idx,
+                    column: 0,
+                };
+
+                if self.names_only || self.skip_inlinees {
+                    break;
+                }
+            }
+
+            // Consecutive lookups with the same result share a range.
+            if previous.as_ref() == Some(&source_location) {
+                continue;
+            }
+            if let Some(offset_addr) = self.options().offset_addr(addr) {
+                self.ranges.insert(offset_addr, source_location.clone());
+            }
+            previous = Some(source_location);
+        }
+
+        Ok(())
+    }
+
+    /// Processes an object on top of all records of an `existing` SymCache.
+    ///
+    /// All strings, files, functions and inline chains of `existing` are copied into this
+    /// converter, and the records of `object` are interned against them. The ranges of both are
+    /// merged into a single range table.
+    ///
+    /// Where functions of `object` overlap with code of `existing`, the object takes precedence:
+    /// ranges of `existing` within such functions are dropped, and lookups past the end of such a
+    /// function resolve to the code of `existing` again. Each contiguous run of overlapping code is
+    /// reported as a warning in the returned [`WriterReport`].
+    ///
+    /// Addresses of `existing` are rebased onto the [range
+    /// threshold](Self::set_range_threshold) of this converter, which should therefore not be above
+    /// the range offset of `existing`. Embedded sources of `existing` are not copied.
+    pub fn extend_object<'d, 'o, O>(
+        &mut self,
+        existing: &SymCache<'_>,
+        object: &'o O,
+    ) -> Result<WriterReport, SymCacheError>
+    where
+        O: ObjectLike<'d, 'o>,
+        O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let existing_ranges = self.insert_symcache(existing)?;
+        let mut report = self.process_object(object)?;
+
+        let new_ranges = std::mem::take(&mut self.ranges);
+        let mut new_ranges_iter = new_ranges.iter().peekable();
+        let mut in_collision = false;
+        while let Some((&addr, source_location)) = new_ranges_iter.next() {
+            if source_location.is_gap() {
+                // Resume the existing code that was covered by the new function.
+                let resumed = existing_ranges.range(..=addr).next_back();
+                let resumed = resumed.map_or(SourceLocationRecord::GAP, |(_, sl)| sl.clone());
+                self.ranges.insert(addr, resumed);
+                in_collision = false;
+                continue;
+            }
+
+            let end = new_ranges_iter
+                .peek()
+                .map_or(Bound::Unbounded, |&(&end, _)| Bound::Excluded(end));
+            let collides = existing_ranges
+                .range(..=addr)
+                .next_back()
+                .into_iter()
+                .chain(existing_ranges.range((Bound::Excluded(addr), end)))
+                .any(|(_, sl)| !sl.is_gap());
+
+            // Report each contiguous run of new code only once.
+            if collides && !in_collision {
+                report.warnings.push(WriterWarning {
+                    unit_offset: None,
+                    error: Error::AddressCollision(self.range_threshold + addr).into(),
+                });
+            }
+            in_collision = collides;
+            self.ranges.insert(addr, source_location.clone());
+        }
+
+        // Existing ranges within new functions are shadowed by them.
+        for (addr, source_location) in existing_ranges {
+            let shadowed = new_ranges
+                .range(..=addr)
+                .next_back()
+                .map_or(false, |(_, sl)| !sl.is_gap());
+            if !shadowed && !new_ranges.contains_key(&addr) {
+                self.ranges.insert(addr, source_location);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Copies all records of a SymCache into this converter, except for its ranges.
+    ///
+    /// Returns the ranges of the SymCache relative to the range threshold of this converter, with
+    /// their source locations referring to the copied records.
+    fn insert_symcache(
+        &mut self,
+        symcache: &SymCache<'_>,
+    ) -> Result<BTreeMap<u64, SourceLocationRecord>, SymCacheError> {
+        // All references are resolved by indexing below, which requires them to be in bounds.
+        symcache.validate()?;
+
+        let strings = (0..symcache.strings.len())
+            .map(|string_idx| Ok(self.insert_string(symcache.string(to_index(string_idx))?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let string_idx = |idx: Option<u32>| idx.map_or(raw::NONE, |idx| strings[idx as usize]);
+
+        let mut files = Vec::with_capacity(symcache.files.len());
+        for file in symcache.files.iter() {
+            let (file_idx, _) = self.files.insert_full(raw::File {
+                comp_dir_idx: string_idx(file.comp_dir_idx()),
+                directory_idx: string_idx(file.directory_idx()),
+                path_name_idx: strings[file.path_name_idx as usize],
+            });
+            files.push(to_index(file_idx));
+        }
+
+        let range_offset = symcache.header.range_offset;
+        let mut functions = Vec::with_capacity(symcache.functions.len());
+        for function in symcache.functions.iter() {
+            let entry_pc = function.entry_pc().map_or(raw::NONE, |entry_pc| {
+                self.options()
+                    .entry_pc(range_offset.saturating_add(u64::from(entry_pc)))
+            });
+            self.functions_total += 1;
+            let (function_idx, _) = self.functions.insert_full(raw::Function {
+                name_idx: string_idx(function.name_idx()),
+                plain_name_idx: string_idx(function.plain_name_idx()),
+                comp_dir_idx: string_idx(function.comp_dir_idx()),
+                entry_pc,
+                lang: function.lang,
+                producer_idx: string_idx(function.producer_idx()),
+                unit_idx: string_idx(function.unit_idx()),
+            });
+            functions.push(to_index(function_idx));
+        }
+
+        // Callers precede the source locations of ranges, and always precede their own callers.
+        let num_callers = symcache.source_locations.len() - symcache.ranges.len();
+        let convert = |source_location_idx: usize, callers: &[u32]| {
+            let sl = &symcache.source_locations[source_location_idx];
+            if sl.is_gap() {
+                return SourceLocationRecord::GAP;
+            }
+            SourceLocationRecord {
+                file_idx: sl.file_idx().map_or(raw::NONE, |idx| files[idx as usize]),
+                line: sl.line,
+                function_idx: functions[sl.function_idx as usize],
+                inlined_into_idx: sl
+                    .inlined_into_idx()
+                    .map_or(raw::NONE, |idx| callers[idx as usize]),
+                column: symcache.get_column(source_location_idx as u32),
+            }
+        };
+
+        let mut callers = Vec::with_capacity(num_callers);
+        for source_location_idx in 0..num_callers {
+            let source_location = convert(source_location_idx, &callers);
+            callers.push(self.insert_source_location(source_location));
+        }
+
+        let mut ranges = BTreeMap::new();
+        for (addr, source_location_idx) in symcache.ranges.iter().zip(num_callers..) {
+            match self
+                .options()
+                .offset_addr(range_offset.saturating_add(addr))
+            {
+                Some(addr) => {
+                    ranges.insert(addr, convert(source_location_idx, &callers));
+                }
+                None => self.records_skipped += 1,
+            }
+        }
+
+        Ok(ranges)
+    }
+
+    /// Processes the symbols in `symbols` after all functions, and completes the `report`.
+    ///
+    /// `shared_source_locations` is the number of shared source locations before processing.
+    fn finish_processing(
+        &mut self,
+        symbols: SymbolMap<'_>,
+        mut report: WriterReport,
+        shared_source_locations: usize,
+    ) -> Result<WriterReport, SymCacheError> {
+        // The symbol map fills in symbol sizes, which is needed to tell whether a symbol intersects
+        // the address range.
+        if !self.debug_info_only {
+            for symbol in symbols {
+                self.process_symbolic_symbol(&symbol);
+            }
+        }
+
+        if self.ranges.is_empty() && !report.warnings.is_empty() {
+            return Err(report.warnings.swap_remove(0).error);
+        }
+
+        report.shared_source_locations = self.shared_source_locations - shared_source_locations;
+        Ok(report)
+    }
+
+    /// Processes a single top-level function, including all of its inlinees.
+    ///
+    /// Functions that do not intersect the [address range](Self::set_address_range) are skipped.
+    pub fn process_symbolic_function(&mut self, function: &Function<'_>) {
+        if !self
+            .options()
+            .intersects_address_range(function.address, function.end_address())
+        {
+            self.records_skipped += 1;
+            return;
+        }
+
+        self.insert_symbolic_function(function);
+    }
+
+    /// Inserts a top-level function and all of its inlinees.
+    fn insert_symbolic_function(&mut self, function: &Function<'_>) {
+        let prepared = self.preparer().prepare(function);
+        self.insert_prepared(&prepared);
+    }
+
+    /// Returns a [`Preparer`] with the settings and transformers of this converter.
+    fn preparer(&mut self) -> Preparer<'_> {
+        Preparer {
+            options: ConvertOptions {
+                arch: self.arch,
+                range_threshold: self.range_threshold,
+                address_range: self.address_range.as_ref(),
+                names_only: self.names_only,
+                skip_inlinees: self.skip_inlinees,
+                path_prefixes: &self.path_prefixes,
+            },
+            name_transformer: self.name_transformer.as_mut(),
+            line_transformer: self.line_transformer.as_mut(),
+        }
+    }
+
+    /// Inserts a [`PreparedFunction`], remapping its local indexes to the tables of this converter.
+    fn insert_prepared(&mut self, prepared: &PreparedFunction<'_>) {
+        let strings = prepared
+            .strings
+            .iter()
+            .map(|s| self.strings.insert(s))
+            .collect::<Vec<_>>();
+        self.strings_total += prepared.strings_total;
+        let string_idx = |idx: u32| strings.get(idx as usize).copied().unwrap_or(u32::MAX);
+
+        let files = prepared
+            .files
+            .iter()
+            .map(|file| {
+                let (file_idx, _) = self.files.insert_full(raw::File {
+                    path_name_idx: string_idx(file.path_name_idx),
+                    directory_idx: string_idx(file.directory_idx),
+                    comp_dir_idx: string_idx(file.comp_dir_idx),
+                });
+                to_index(file_idx)
+            })
+            .collect::<Vec<_>>();
+
+        let functions = prepared
+            .functions
+            .iter()
+            .map(|function| {
+                let (function_idx, _) = self.functions.insert_full(raw::Function {
+                    name_idx: string_idx(function.name_idx),
+                    plain_name_idx: string_idx(function.plain_name_idx),
+                    comp_dir_idx: string_idx(function.comp_dir_idx),
+                    producer_idx: string_idx(function.producer_idx),
+                    unit_idx: string_idx(function.unit_idx),
+                    ..function.clone()
+                });
+                to_index(function_idx)
+            })
+            .collect::<Vec<_>>();
+        self.functions_total += prepared.functions_total;
+        self.records_skipped += prepared.records_skipped;
+
+        let remap = |source_location: &SourceLocationRecord| SourceLocationRecord {
+            file_idx: files
+                .get(source_location.file_idx as usize)
+                .copied()
+                .unwrap_or(u32::MAX),
+            function_idx: functions[source_location.function_idx as usize],
+            ..source_location.clone()
+        };
+
+        for range in &prepared.ranges {
+            match *range {
+                PreparedRange::Line {
+                    addr,
+                    ref source_location,
+                } => {
+                    self.ranges.insert(addr, remap(source_location));
+                }
+                PreparedRange::Inlined {
+                    addr,
+                    ref source_location,
+                    caller_idx,
+                } => {
+                    // Some formats, such as PDB, do not define a line record in the caller at the
+                    // start of every inlinee. In that case, the caller's location is given by the
+                    // preceding range.
+                    let caller_source_location = caller_idx
+                        .map(|caller_idx| functions[caller_idx as usize])
+                        .and_then(|caller_idx| self.caller_source_location(addr, caller_idx))
+                        .or_else(|| self.ranges.get(&addr).filter(|sl| !sl.is_gap()).cloned());
+
+                    let mut callee_source_location = remap(source_location);
+                    if let Some(caller_source_location) = caller_source_location {
+                        callee_source_location.inlined_into_idx =
+                            self.insert_source_location(caller_source_location);
+                    }
+                    self.ranges.insert(addr, callee_source_location);
+                }
+                PreparedRange::Gap { addr } => {
+                    // Gaps never replace a range, regardless of the order of insertion.
+                    self.ranges.entry(addr).or_insert(SourceLocationRecord::GAP);
+                }
+            }
+        }
+    }
+
+    /// Returns the source location of the function at `caller_idx` that covers `addr`.
+    ///
+    /// This walks up the inline chain of the range containing `addr`, which has been populated by
+    /// the caller and its previous inlinees already.
+    fn caller_source_location(&self, addr: u64, caller_idx: u32) -> Option<SourceLocationRecord> {
+        let (_, mut source_location) = self.ranges.range(..=addr).next_back()?;
+        while source_location.function_idx != caller_idx {
+            let inlined_into_idx = source_location.inlined_into_idx as usize;
+            source_location = self.source_locations.get_index(inlined_into_idx)?;
+        }
+        Some(source_location.clone())
+    }
+
+    /// Processes a symbol from the symbol table.
+    ///
+    /// Symbols only fill in addresses that are not covered by any function yet, so all functions
+    /// should be processed first.
+    pub fn process_symbolic_symbol(&mut self, symbol: &Symbol<'_>) {
+        let name = match symbol.name {
+            Some(ref name) => name.as_ref(),
+            None => return,
+        };
+
+        // Symbols without a size extend up to the next symbol, which might be anywhere.
+        let end = match symbol.size {
+            0 => u64::MAX,
+            size => symbol.address.saturating_add(size),
+        };
+        if !self.options().intersects_address_range(symbol.address, end) {
+            self.records_skipped += 1;
+            return;
+        }
+        let addr = match self.options().offset_addr(symbol.address) {
+            Some(addr) => addr,
+            None => {
+                self.records_skipped += 1;
+                return;
+            }
+        };
+
+        let name_idx = self.insert_name(name, Language::Unknown);
+
+        if self.ranges.get(&addr).map_or(false, |sl| !sl.is_gap()) {
+            // ASSUMPTION:
+            // the `functions` iterator has already filled in this addr via debug session.
+            // we could trace the caller hierarchy up to the root, and assert that it is
+            // indeed the same function, and maybe update its `entry_pc`, but we don’t do
+            // that for now.
+            return;
+        }
+
+        // Symbols replace gaps, since the symbol table may cover code without debug information.
+        let function = raw::Function {
+            name_idx,
+            plain_name_idx: u32::MAX,
+            comp_dir_idx: u32::MAX,
+            entry_pc: self.options().entry_pc(symbol.address),
+            lang: u32::MAX,
+            producer_idx: u32::MAX,
+            unit_idx: u32::MAX,
+        };
+        self.functions_total += 1;
+        let function_idx = to_index(self.functions.insert_full(function).0);
+
+        self.ranges.insert(
+            addr,
+            SourceLocationRecord {
+                file_idx: u32::MAX,
+                line: 0,
+                function_idx,
+                inlined_into_idx: u32::MAX,
+                column: 0,
+            },
+        );
+    }
+
+    // Methods for serializing to a [`Write`] below:
+    // Feel free to move these to a separate file.
+
+    /// Returns an upper bound for the number of bytes written by [`serialize`](Self::serialize).
+    ///
+    /// This is exact, unless [`set_names_only`](Self::set_names_only) is enabled or strings are
+    /// not referenced by any record, since redundant ranges and unreferenced strings are only
+    /// dropped during serialization. If string data is compressed, this
+    /// assumes that it does not grow, which only holds for compressible data. If a
+    /// [source provider](Self::set_source_provider) is set, this includes the full
+    /// [size limit](Self::set_source_size_limit) for embedded sources, since they are only read
+    /// during serialization.
+    pub fn serialized_size_hint(&self) -> u64 {
+        let num_source_locations = self.source_locations.len() + self.ranges.len();
+        let sources_size = if self.source_provider.is_some() {
+            let limit = self.source_size_limit.unwrap_or(DEFAULT_SOURCE_SIZE_LIMIT) as usize;
+            table_size::<raw::Section>(2)
+                + table_size::<u8>(limit)
+                + table_size::<raw::FileSource>(self.files.len())
+        } else {
+            0
+        };
+        let size = table_size::<raw::Header>(1)
+            + table_size::<raw::SectionTable>(1)
+            + table_size::<raw::Section>(NUM_SECTIONS)
+            + table_size::<raw::String>(self.strings.len())
+            + table_size::<raw::File>(self.files.len())
+            + table_size::<raw::Function>(self.functions.len())
+            + table_size::<raw::SourceLocation>(num_source_locations)
+            + if self.has_columns() {
+                table_size::<raw::Section>(1) + table_size::<u16>(num_source_locations)
+            } else {
+                0
+            }
+            + if self.has_wide_ranges() {
+                table_size::<raw::WideRange>(self.ranges.len())
+            } else {
+                table_size::<raw::Range>(self.ranges.len())
+            }
+            + table_size::<u32>(if self.name_index {
+                self.functions.len()
+            } else {
+                0
+            })
+            + table_size::<u8>(self.strings.bytes_len() as usize)
+            + sources_size
+            + table_size::<raw::Trailer>(1);
+
+        size as u64
+    }
+
+    /// Checks that all tables can be referenced by the `u32` indexes of the format.
+    ///
+    /// This is also checked by [`serialize`](Self::serialize), which returns the error wrapped in
+    /// an [`io::Error`]. Call this first to handle the typed [`Error`] instead.
+    pub fn check_limits(&self) -> Result<(), Error> {
+        check_limits(TableSizes {
+            strings: self.strings.len(),
+            string_bytes: self.strings.bytes_len(),
+            files: self.files.len(),
+            functions: self.functions.len(),
+            // Ranges are indexed after all other source locations.
+            source_locations: self.source_locations.len() + self.ranges.len(),
+        })
+    }
+
+    /// Reads the contents of all files from the [`SourceProvider`], if one is set.
+    ///
+    /// Returns the location of the contents of every file, along with the concatenated contents.
+    /// Files without contents, or whose contents would exceed the remaining
+    /// [size limit](Self::set_source_size_limit), have no offset. If no file has contents, the
+    /// locations are empty.
+    fn read_sources(&mut self) -> io::Result<(Vec<raw::FileSource>, Vec<u8>)> {
+        let mut sources = vec![raw::FileSource::NONE; self.files.len()];
+        let mut source_bytes = Vec::new();
+        let provider = match self.source_provider {
+            Some(ref mut provider) => provider,
+            None => return Ok((Vec::new(), source_bytes)),
+        };
+        let limit = self.source_size_limit.unwrap_or(DEFAULT_SOURCE_SIZE_LIMIT) as usize;
+
+        let strings = &self.strings;
+        let optional_string = |string_idx: u32| -> io::Result<String> {
+            match string_idx {
+                u32::MAX => Ok(String::new()),
+                string_idx => Ok(String::from_utf8_lossy(&strings.get_bytes(string_idx)?).into()),
+            }
+        };
+
+        for (file, source) in self.files.iter().zip(&mut sources) {
+            let comp_dir = optional_string(file.comp_dir_idx)?;
+            let directory = optional_string(file.directory_idx)?;
+            let dir = symbolic_common::join_path(&comp_dir, &directory);
+            let path_name = strings.get_bytes(file.path_name_idx)?;
+            let info = FileInfo {
+                name: &path_name,
+                dir: dir.as_bytes(),
+            };
+
+            let contents = match (provider.0)(&info) {
+                Some(contents) => contents,
+                None => continue,
+            };
+            if source_bytes.len() + contents.len() > limit {
+                continue;
+            }
+            *source = raw::FileSource {
+                offset: source_bytes.len() as u32,
+                len: contents.len() as u32,
+            };
+            source_bytes.extend_from_slice(&contents);
+        }
+
+        if sources.iter().all(|source| source.offset().is_none()) {
+            sources.clear();
+        }
+        Ok((sources, source_bytes))
+    }
+
+    /// Marks all strings that are referenced by a file or function record.
+    fn referenced_strings(&self) -> Vec<bool> {
+        let mut referenced = vec![false; self.strings.len()];
+        let mut mark = |string_idx: u32| {
+            if let Some(referenced) = referenced.get_mut(string_idx as usize) {
+                *referenced = true;
+            }
+        };
+
+        for file in &self.files {
+            mark(file.comp_dir_idx);
+            mark(file.directory_idx);
+            mark(file.path_name_idx);
+        }
+        for function in &self.functions {
+            mark(function.name_idx);
+            mark(function.plain_name_idx);
+            mark(function.comp_dir_idx);
+            mark(function.producer_idx);
+            mark(function.unit_idx);
+        }
+
+        referenced
+    }
+
+    /// Serialize the converted data.
+    ///
+    /// This writes the SymCache binary format into the given [`Write`]. The output is fully
+    /// deterministic, and all padding bytes are written as zeros. Returns [`WriteStats`] on the
+    /// size of all tables, and how many of the added records were duplicates or skipped.
+    ///
+    /// The string table is sorted by content, see [`SymCache::find_string`](super::SymCache::find_string).
+    /// Strings that are not referenced by any file or function are dropped along with their bytes.
+    /// If a [source provider](Self::set_source_provider) is set, it is called for every file.
+    /// The output ends with a [`raw::Trailer`] containing a CRC32 checksum of all preceding bytes,
+    /// which is verified by [`SymCache::parse`](super::SymCache::parse).
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if [`check_limits`](Self::check_limits) fails.
+    pub fn serialize<W: Write>(mut self, writer: &mut W) -> std::io::Result<WriteStats> {
+        self.check_limits()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        let mut writer = WriteWrapper::new(writer);
+
+        if self.names_only {
+            // Without files and lines, adjacent ranges often point to the same function. Since a
+            // range extends up to the next one, dropping the repetitions does not change lookups.
+            let mut previous = None;
+            self.ranges.retain(|_, source_location| {
+                let keep = previous.as_ref() != Some(source_location);
+                previous = Some(source_location.clone());
+                keep
+            });
+        }
+
+        // The source provider receives the paths of files, so it is called before the string table
+        // is consumed.
+        let (file_sources, source_bytes) = self.read_sources()?;
+
+        // Columns are optional, so their table is omitted if no source location has a column.
+        let mut columns = Vec::new();
+        if self.has_columns() {
+            columns.extend(
+                self.source_locations
+                    .iter()
+                    .chain(self.ranges.values())
+                    .map(|sl| sl.column),
+            );
+        }
+
+        // Strings are sorted by content to allow binary searches. Strings that are not referenced
+        // by any record, such as those of dropped records, are not written at all. The string
+        // bytes keep their insertion order, but all records referencing strings must be remapped.
+        // Wide ranges double the size of the range table, so they are only used when needed.
+        // This is decided before the string table is taken apart below.
+        let wide_ranges = self.has_wide_ranges();
+        let retain = self.referenced_strings();
+        let mut sorted_order = self.strings.sorted_order()?;
+        sorted_order.retain(|&string_idx| retain[string_idx as usize]);
+        let mut new_indexes = vec![u32::MAX; retain.len()];
+        for (new_idx, &string_idx) in sorted_order.iter().enumerate() {
+            new_indexes[string_idx as usize] = new_idx as u32;
+        }
+        let remap = |string_idx: u32| match string_idx {
+            u32::MAX => u32::MAX,
+            string_idx => new_indexes[string_idx as usize],
+        };
+        let (strings, string_bytes, string_data) = self.strings.into_parts(&retain)?;
+
+        // All counts have been validated by `check_limits` above.
+        let num_strings = sorted_order.len() as u32;
+        let num_files = self.files.len() as u32;
+        let num_functions = self.functions.len() as u32;
+        let num_source_locations = (self.source_locations.len() + self.ranges.len()) as u32;
+        let num_ranges = self.ranges.len() as u32;
+        let string_bytes = string_bytes as u32;
+        let address_range = self.address_range.clone().unwrap_or(0..u64::MAX);
+
+        let mut stats = WriteStats {
+            strings_total: self.strings_total,
+            strings_unique: num_strings as usize,
+            functions_total: self.functions_total,
+            functions_unique: num_functions as usize,
+            files: num_files as usize,
+            source_locations: num_source_locations as usize,
+            ranges: num_ranges as usize,
+            string_bytes: string_bytes as usize,
+            source_bytes: source_bytes.len(),
+            records_skipped: self.records_skipped,
+            total_bytes: 0,
+        };
+
+        let mut flags = self.feature_flags;
+        let has_inline = self
+            .source_locations
+            .iter()
+            .chain(self.ranges.values())
+            .any(|sl| sl.inlined_into_idx != u32::MAX);
+        if has_inline {
+            flags |= raw::FLAG_HAS_INLINE;
+        }
+        if !columns.is_empty() {
+            flags |= raw::FLAG_HAS_COLUMNS;
+        }
+        if !self.files.is_empty() {
+            flags |= raw::FLAG_HAS_SOURCE;
+        }
+        if self.names_only {
+            flags |= raw::FLAG_NAMES_ONLY;
+        }
+        if !file_sources.is_empty() {
+            flags |= raw::FLAG_HAS_EMBEDDED_SOURCES;
+        }
+        if self.name_index {
+            flags |= raw::FLAG_HAS_NAME_INDEX;
+        }
+        if wide_ranges {
+            flags |= raw::FLAG_WIDE_RANGES;
+        }
+        flags |= raw::FLAG_HAS_CHECKSUM | raw::FLAG_SORTED_STRINGS;
+
+        // Since strings are sorted, ordering by string index orders functions by name.
+        let mut name_index = Vec::new();
+        if self.name_index {
+            name_index.extend(0..num_functions);
+            let functions = &self.functions;
+            let key = |&function_idx: &u32| {
+                (
+                    remap(functions[function_idx as usize].name_idx),
+                    function_idx,
+                )
+            };
+            #[cfg(feature = "rayon")]
+            {
+                use rayon::slice::ParallelSliceMut;
+                name_index.par_sort_unstable_by_key(key);
+            }
+            #[cfg(not(feature = "rayon"))]
+            name_index.sort_unstable_by_key(key);
+        }
+
+        // String data that does not compress within the maximum ratio is written from memory.
+        let mut string_data: Box<dyn Read> = Box::new(string_data);
+
+        // Compressed string data is prefixed with its compressed and uncompressed size. The header
+        // records the size of the entire section.
+        #[cfg(feature = "compression")]
+        let compressed = match self.compression_level {
+            Some(level) => {
+                let mut uncompressed = Vec::new();
+                string_data.read_to_end(&mut uncompressed)?;
+                let compressed = zstd::block::compress(&uncompressed, level)?;
+                // Readers reject string data that expands beyond the maximum ratio, so it is
+                // written uncompressed instead.
+                let max_len = (compressed.len() as u64) * u64::from(raw::MAX_COMPRESSION_RATIO);
+                if uncompressed.len() as u64 > max_len {
+                    string_data = Box::new(io::Cursor::new(uncompressed));
+                    None
+                } else {
+                    Some(compressed)
+                }
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "compression"))]
+        let compressed: Option<Vec<u8>> = None;
+
+        let section_bytes = match compressed {
+            Some(ref compressed) => {
+                let section_size =
+                    std::mem::size_of::<raw::CompressedStringBytes>() + compressed.len();
+                u32::try_from(section_size).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, Error::TooManyStringBytes)
+                })?
+            }
+            None => string_bytes,
+        };
+        if compressed.is_some() {
+            flags |= raw::FLAG_COMPRESSED;
+        }
+
+        let header = raw::Header {
+            magic: raw::SYMCACHE_MAGIC,
+            version: raw::SYMCACHE_VERSION,
+
+            debug_id: self.debug_id,
+            arch: self.arch as u32,
+            flags,
+
+            range_offset: self.range_threshold,
+            address_range_start: address_range.start,
+            address_range_end: address_range.end,
+
+            num_strings,
+            num_files,
+            num_functions,
+            num_source_locations,
+            num_ranges,
+            string_bytes: section_bytes,
+        };
+
+        // Sections are written in the order of their ids, so their offsets are known upfront.
+        let mut section_lens = vec![
+            (
+                raw::SECTION_STRINGS,
+                std::mem::size_of::<raw::String>() * sorted_order.len(),
+            ),
+            (
+                raw::SECTION_FILES,
+                std::mem::size_of::<raw::File>() * self.files.len(),
+            ),
+            (
+                raw::SECTION_FUNCTIONS,
+                std::mem::size_of::<raw::Function>() * self.functions.len(),
+            ),
+            (
+                raw::SECTION_SOURCE_LOCATIONS,
+                std::mem::size_of::<raw::SourceLocation>() * num_source_locations as usize,
+            ),
+            (
+                raw::SECTION_RANGES,
+                if wide_ranges {
+                    std::mem::size_of::<raw::WideRange>() * self.ranges.len()
+                } else {
+                    std::mem::size_of::<raw::Range>() * self.ranges.len()
+                },
+            ),
+            (
+                raw::SECTION_NAME_INDEX,
+                std::mem::size_of::<u32>() * name_index.len(),
+            ),
+            (raw::SECTION_STRING_BYTES, section_bytes as usize),
+        ];
+        // Embedded sources are optional, so their sections are omitted if none were embedded.
+        if !source_bytes.is_empty() {
+            section_lens.push((raw::SECTION_SOURCE_BYTES, source_bytes.len()));
+        }
+        if !file_sources.is_empty() {
+            section_lens.push((
+                raw::SECTION_FILE_SOURCES,
+                std::mem::size_of::<raw::FileSource>() * file_sources.len(),
+            ));
+        }
+        if !columns.is_empty() {
+            section_lens.push((
+                raw::SECTION_COLUMNS,
+                std::mem::size_of::<u16>() * columns.len(),
+            ));
+        }
+        let mut offset = table_size::<raw::Header>(1)
+            + table_size::<raw::SectionTable>(1)
+            + table_size::<raw::Section>(section_lens.len());
+        let sections: Vec<_> = section_lens
+            .iter()
+            .map(|&(id, len)| {
+                let section = raw::Section {
+                    id,
+                    reserved: 0,
+                    offset: offset as u64,
+                    len: len as u64,
+                };
+                offset += table_size::<u8>(len);
+                section
+            })
+            .collect();
+
+        writer.write(&[header])?;
+        writer.align()?;
+
+        writer.write(&[raw::SectionTable {
+            num_sections: sections.len() as u32,
+            reserved: 0,
+        }])?;
+        writer.write(&sections)?;
+        writer.align()?;
+
+        for &string_idx in &sorted_order {
+            writer.write(std::slice::from_ref(&strings[string_idx as usize]))?;
+        }
+        writer.align()?;
+
+        for f in self.files {
+            writer.write(&[raw::File {
+                comp_dir_idx: remap(f.comp_dir_idx),
+                directory_idx: remap(f.directory_idx),
+                path_name_idx: remap(f.path_name_idx),
+            }])?;
+        }
+        writer.align()?;
+
+        for f in self.functions {
+            writer.write(&[raw::Function {
+                name_idx: remap(f.name_idx),
+                plain_name_idx: remap(f.plain_name_idx),
+                comp_dir_idx: remap(f.comp_dir_idx),
+                producer_idx: remap(f.producer_idx),
+                unit_idx: remap(f.unit_idx),
+                ..f
+            }])?;
+        }
+        writer.align()?;
+
+        for s in self.source_locations.iter().chain(self.ranges.values()) {
+            writer.write(&[s.to_raw()])?;
+        }
+        writer.align()?;
+
+        for &r in self.ranges.keys() {
+            if wide_ranges {
+                writer.write(&[raw::WideRange(r)])?;
+            } else {
+                writer.write(&[raw::Range(r as u32)])?;
+            }
+        }
+        writer.align()?;
+
+        writer.write(&name_index)?;
+        writer.align()?;
+
+        match compressed {
+            Some(compressed) => {
+                writer.write(&[raw::CompressedStringBytes {
+                    compressed_len: compressed.len() as u32,
+                    uncompressed_len: string_bytes,
+                }])?;
+                writer.write(&compressed)?;
+            }
+            None => {
+                writer.write_from(&mut string_data)?;
+            }
+        }
+        writer.align()?;
+
+        writer.write(&source_bytes)?;
+        writer.align()?;
+
+        writer.write(&file_sources)?;
+        writer.align()?;
+
+        writer.write(&columns)?;
+        writer.align()?;
+
+        let trailer = raw::Trailer {
+            magic: raw::TRAILER_MAGIC,
+            checksum: writer.checksum.clone().finalize(),
+        };
+        writer.write(&[trailer])?;
+
+        stats.total_bytes = writer.position as u64;
+        Ok(stats)
+    }
+}
+
+/// The number of sections written by [`SymCacheConverter::serialize`], unless sources are
+/// embedded, which adds up to two more, or source locations have columns, which adds one more.
+const NUM_SECTIONS: usize = 7;
+
+/// Returns the size of a table of `len` records of type `T`, including padding.
+fn table_size<T>(len: usize) -> usize {
+    let size = std::mem::size_of::<T>() * len;
+    size + raw::align_to_eight(size)
+}
+
+struct WriteWrapper<W> {
+    writer: W,
+    position: usize,
+    /// The CRC32 checksum of all bytes written so far.
+    checksum: crc32fast::Hasher,
+}
+
+impl<W: Write> WriteWrapper<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            position: 0,
+            checksum: crc32fast::Hasher::new(),
+        }
+    }
+
+    fn write<T>(&mut self, data: &[T]) -> std::io::Result<usize> {
+        let pointer = data.as_ptr() as *const u8;
+        let len = std::mem::size_of_val(data);
+        // SAFETY: both pointer and len are derived directly from data/T and are valid.
+        let buf = unsafe { std::slice::from_raw_parts(pointer, len) };
+        self.writer.write_all(buf)?;
+        self.checksum.update(buf);
+        self.position += len;
+        Ok(len)
+    }
+
+    fn write_from<R: Read>(&mut self, reader: &mut R) -> std::io::Result<u64> {
+        let mut buf = [0u8; 8192];
+        let mut total = 0;
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => return Ok(total),
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.write(&buf[..len])?;
+            total += len as u64;
+        }
+    }
+
+    fn align(&mut self) -> std::io::Result<usize> {
+        let buf = &[0u8; 7];
+        let len = raw::align_to_eight(self.position);
+        self.write(&buf[0..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbolic_common::{ByteView, Name, NameMangling};
+    use symbolic_debuginfo::{FileInfo, LineInfo, Object};
+    use symbolic_testutils::fixture;
+
+    use super::super::SymCache;
+    use super::*;
+
+    fn lookup_names(symcache: &SymCache<'_>, addr: u64) -> Vec<(Option<String>, u32)> {
+        symcache
+            .lookup(addr)
+            .map(|sl| {
+                (
+                    sl.function().and_then(|f| f.name().map(String::from)),
+                    sl.line().unwrap_or(0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_memory_budget() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut expected = Vec::new();
+        converter.serialize(&mut expected)?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let mut converter = SymCacheConverter::new();
+        converter.set_memory_budget(1024);
+        converter.set_temp_dir(temp_dir.path());
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+
+        assert!(buffer == expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreferenced_strings() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        for &memory_budget in &[None, Some(1024)] {
+            let expected = write_fixture("linux/crash.debug", memory_budget);
+
+            // Strings of dropped records are interleaved with the strings that are written.
+            let mut converter = SymCacheConverter::new();
+            if let Some(memory_budget) = memory_budget {
+                converter.set_memory_budget(memory_budget);
+            }
+            converter.set_arch(object.arch());
+            converter.set_debug_id(object.debug_id());
+            converter.insert_string("unreferenced before");
+            converter.process_object(&object)?;
+            converter.insert_string("unreferenced after");
+            let mut buffer = Vec::new();
+            let stats = converter.serialize(&mut buffer)?;
+
+            assert!(buffer == expected);
+            let symcache = SymCache::parse(&buffer)?;
+            assert_eq!(stats.strings_unique, symcache.header.num_strings as usize);
+            assert_eq!(symcache.find_string("unreferenced before"), None);
+            assert_eq!(symcache.find_string("unreferenced after"), None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_inlinees() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut full_buffer = Vec::new();
+        converter.serialize(&mut full_buffer)?;
+        let full = SymCache::parse(&full_buffer)?;
+        assert!(full.features().has_inline());
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_skip_inlinees(true);
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        symcache.validate()?;
+
+        assert!(!symcache.features().has_inline());
+        assert!(symcache.features().has_source());
+        assert!(symcache.header.num_strings < full.header.num_strings);
+        assert!(symcache.header.string_bytes < full.header.string_bytes);
+
+        for range in full.ranges.iter() {
+            let addr = full.header.range_offset + range;
+            let expected = full.lookup(addr).last().and_then(|sl| sl.function());
+            let mut source_locations = symcache.lookup(addr);
+            let actual = source_locations.next().and_then(|sl| sl.function());
+            assert!(source_locations.next().is_none());
+            assert_eq!(
+                actual.and_then(|f| f.name()),
+                expected.and_then(|f| f.name()),
+                "{:#x}",
+                addr
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_provider() -> Result<(), Box<dyn std::error::Error>> {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+        let source = std::fs::read(fixture("linux/dwarf5/generate_dwarf5.sh"))?;
+
+        let paths = Rc::new(RefCell::new(Vec::new()));
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let (contents, provided_paths) = (source.clone(), paths.clone());
+        converter.set_source_provider(move |info| {
+            provided_paths.borrow_mut().push(info.path_str());
+            if info.name_str().ends_with("main.cpp") {
+                Some(contents.clone())
+            } else {
+                None
+            }
+        });
+        let size_hint = converter.serialized_size_hint();
+        let mut buffer = Vec::new();
+        let stats = converter.serialize(&mut buffer)?;
+        assert!(buffer.len() as u64 <= size_hint);
+        let symcache = SymCache::parse(&buffer)?;
+        symcache.validate()?;
+        assert!(symcache.features().has_embedded_sources());
+        assert_eq!(stats.source_bytes, source.len());
+        // File records keep their size, the locations of their contents are stored separately.
+        assert_eq!(symcache.file_sources.len(), symcache.files.len());
+
+        // The provider is called once per file, with the same path that the reader resolves.
+        let full_paths = (0..symcache.header.num_files)
+            .map(|file_idx| symcache.full_path(file_idx))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(*paths.borrow(), full_paths);
+
+        let mut embedded = 0;
+        for range in symcache.ranges.iter() {
+            let addr = symcache.header.range_offset + range;
+            for file in symcache.lookup(addr).filter_map(|sl| sl.file()) {
+                if file.path_name().ends_with("main.cpp") {
+                    assert_eq!(file.source(), Some(&source[..]));
+                    embedded += 1;
+                } else {
+                    assert_eq!(file.source(), None);
+                }
+            }
+        }
+        assert!(embedded > 0);
 
-        for f in self.files {
-            writer.write(&[f])?;
+        // Sources exceeding the size limit are not embedded.
+        let mut converter = SymCacheConverter::new();
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+        converter.process_object(&object)?;
+        let contents = source.clone();
+        converter.set_source_provider(move |_| Some(contents.clone()));
+        converter.set_source_size_limit(source.len() as u32 - 1);
+        let mut buffer = Vec::new();
+        let stats = converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(!symcache.features().has_embedded_sources());
+        assert_eq!(stats.source_bytes, 0);
+        assert!(buffer == write_fixture("linux/crash.debug", None));
+
+        Ok(())
+    }
+
+    fn write_fixture(path: &str, memory_budget: Option<usize>) -> Vec<u8> {
+        let buffer = ByteView::open(fixture(path)).unwrap();
+        let object = Object::parse(&buffer).unwrap();
+
+        let mut converter = SymCacheConverter::new();
+        if let Some(memory_budget) = memory_budget {
+            converter.set_memory_budget(memory_budget);
         }
-        writer.align()?;
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+        converter.process_object(&object).unwrap();
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer).unwrap();
+        buffer
+    }
 
-        for f in self.functions {
-            writer.write(&[f])?;
+    #[test]
+    fn test_deterministic_output() {
+        let paths = [
+            "linux/crash.debug",
+            "macos/crash.dSYM/Contents/Resources/DWARF/crash",
+        ];
+
+        for &path in &paths {
+            // Every converter creates its interners with freshly seeded hashers. Writing on another
+            // thread additionally uses different thread-local hasher keys.
+            let first = write_fixture(path, None);
+            let second = write_fixture(path, None);
+            let third = std::thread::spawn(move || write_fixture(path, None))
+                .join()
+                .unwrap();
+            // Strings moved to disk are interned through a different code path.
+            let spilled = write_fixture(path, Some(0));
+
+            assert!(first == second, "{} differs between runs", path);
+            assert!(first == third, "{} differs between threads", path);
+            assert!(first == spilled, "{} differs with a memory budget", path);
         }
-        writer.align()?;
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_parallel_output() {
+        let paths = [
+            "linux/crash.debug",
+            "macos/crash.dSYM/Contents/Resources/DWARF/crash",
+        ];
+
+        for &path in &paths {
+            let buffer = ByteView::open(fixture(path)).unwrap();
+            let object = Object::parse(&buffer).unwrap();
 
-        for s in self.source_locations {
-            writer.write(&[s])?;
+            for &names_only in &[false, true] {
+                let write = |parallel: bool| {
+                    let mut converter = SymCacheConverter::new();
+                    converter.set_arch(object.arch());
+                    converter.set_debug_id(object.debug_id());
+                    converter.set_names_only(names_only);
+                    converter.strip_path_prefix("/Users");
+                    if parallel {
+                        converter.process_object_parallel(&object).unwrap();
+                    } else {
+                        converter.process_object(&object).unwrap();
+                    }
+                    let mut buffer = Vec::new();
+                    converter.serialize(&mut buffer).unwrap();
+                    buffer
+                };
+
+                assert!(
+                    write(false) == write(true),
+                    "{} differs in parallel (names_only: {})",
+                    path,
+                    names_only
+                );
+            }
         }
-        for s in self.ranges.values() {
-            writer.write(std::slice::from_ref(s))?;
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_parallel_progress() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut serial = Vec::new();
+        SymCacheConverter::new().process_object_with_progress(&object, |p| {
+            serial.push(p.functions_processed);
+            ControlFlow::Continue(())
+        })?;
+
+        // Progress is reported once per batch, and ends with the same count.
+        let mut parallel = Vec::new();
+        SymCacheConverter::new().process_object_parallel_with_progress(&object, |p| {
+            parallel.push(p.functions_processed);
+            ControlFlow::Continue(())
+        })?;
+        let expected_calls = (serial.len() + PARALLEL_BATCH_SIZE - 1) / PARALLEL_BATCH_SIZE;
+        assert_eq!(parallel.len(), expected_calls);
+        assert_eq!(parallel.last(), serial.last());
+
+        let result = SymCacheConverter::new()
+            .process_object_parallel_with_progress(&object, |_| ControlFlow::Break(()));
+        let error = result.err().expect("processing should be cancelled");
+        assert_eq!(error.kind(), SymCacheErrorKind::Cancelled);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_transformer() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut original_buffer = Vec::new();
+        converter.serialize(&mut original_buffer)?;
+        let original = SymCache::parse(&original_buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_name_transformer(|name, _| Some(name.to_uppercase().into()));
+        converter.process_object(&object)?;
+        let mut transformed_buffer = Vec::new();
+        converter.serialize(&mut transformed_buffer)?;
+        let transformed = SymCache::parse(&transformed_buffer)?;
+
+        assert_eq!(transformed.functions.len(), original.functions.len());
+        assert_eq!(transformed.ranges.len(), original.ranges.len());
+
+        for (original, transformed) in original.functions().zip(transformed.functions()) {
+            let expected = original.name().map(str::to_uppercase);
+            assert_eq!(transformed.name().map(String::from), expected);
+            let expected = original.plain_name().map(str::to_uppercase);
+            assert_eq!(transformed.plain_name().map(String::from), expected);
         }
-        writer.align()?;
 
-        for r in self.ranges.keys() {
-            writer.write(&[raw::Range(*r)])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_path_prefix() -> Result<(), Box<dyn std::error::Error>> {
+        let data = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 libxul.so
+FILE 0 /builds/worker/checkouts/gecko/netwerk/protocol/http/HttpChannelChild.cpp
+FILE 1 /builds/worker/checkouts2/gecko/dom/base/Element.cpp
+FILE 2 /usr/include/c++/vector
+FUNC 1000 30 0 HttpChannelChild::OnStart
+1000 10 411 0
+1010 10 17 1
+1020 10 86 2
+";
+        let object = Object::parse(data)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.strip_path_prefix("/builds/worker/checkouts/");
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let location = |addr| {
+            let sl = symcache.lookup(addr).next().unwrap();
+            sl.file().unwrap().full_path()
+        };
+        assert_eq!(
+            location(0x1000),
+            "gecko/netwerk/protocol/http/HttpChannelChild.cpp"
+        );
+        assert_eq!(
+            location(0x1010),
+            "/builds/worker/checkouts2/gecko/dom/base/Element.cpp"
+        );
+        assert_eq!(location(0x1020), "/usr/include/c++/vector");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_path_prefix_components() -> Result<(), Box<dyn std::error::Error>> {
+        let mut converter = SymCacheConverter::new();
+        converter.strip_path_prefix("/builds/worker");
+        let files = [
+            // The prefix spans the compilation directory.
+            converter.insert_file(
+                "Element.cpp",
+                Some("dom/base"),
+                Some("/builds/worker/gecko"),
+            ),
+            // The directory is absolute and overrides the compilation directory.
+            converter.insert_file(
+                "nsHttp.cpp",
+                Some("/builds/worker/gecko/netwerk"),
+                Some("/src"),
+            ),
+            // The directory overrides the stripped compilation directory.
+            converter.insert_file("vector", Some("/usr/include/c++"), Some("/builds/worker")),
+        ];
+
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let paths = files
+            .iter()
+            .map(|&file_idx| symcache.full_path(file_idx))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            paths,
+            [
+                "gecko/dom/base/Element.cpp",
+                "gecko/netwerk/nsHttp.cpp",
+                "/usr/include/c++/vector",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_range() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let write = |range: Option<Range<u64>>| -> Result<Vec<u8>, SymCacheError> {
+            let mut converter = SymCacheConverter::new();
+            if let Some(range) = range {
+                converter.set_address_range(range);
+            }
+            converter.process_object(&object)?;
+            let mut buffer = Vec::new();
+            converter
+                .serialize(&mut buffer)
+                .map_err(|e| SymCacheError::new(SymCacheErrorKind::WriteFailed, e))?;
+            Ok(buffer)
+        };
+
+        let full_buffer = write(None)?;
+        let full = SymCache::parse(&full_buffer)?;
+
+        let offset = full.header.range_offset;
+        let addrs: Vec<u64> = full.ranges.iter().map(|r| offset + r).collect();
+        // Split at the start of a function rather than at a gap, which belongs to the function
+        // before it.
+        let mid = addrs[addrs.len() / 2..]
+            .iter()
+            .copied()
+            .find(|&addr| full.covers(addr))
+            .unwrap();
+
+        let lower_buffer = write(Some(0..mid))?;
+        let lower = SymCache::parse(&lower_buffer)?;
+        let upper_buffer = write(Some(mid..u64::MAX))?;
+        let upper = SymCache::parse(&upper_buffer)?;
+
+        assert!(lower.functions.len() < full.functions.len());
+        assert!(upper.functions.len() < full.functions.len());
+
+        for addr in addrs {
+            let expected = lookup_names(&full, addr);
+            let from_lower = lookup_names(&lower, addr);
+            let from_upper = lookup_names(&upper, addr);
+
+            if addr < mid {
+                assert!(from_upper.is_empty(), "{:#x}", addr);
+                assert_eq!(from_lower, expected, "{:#x}", addr);
+            } else {
+                assert!(from_lower.is_empty(), "{:#x}", addr);
+                assert_eq!(from_upper, expected, "{:#x}", addr);
+            }
         }
-        writer.align()?;
 
-        writer.write(&self.string_bytes)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_threshold() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let write = |threshold: u64| -> Result<Vec<u8>, SymCacheError> {
+            let mut converter = SymCacheConverter::new();
+            converter.set_range_threshold(threshold);
+            converter.process_object(&object)?;
+            let mut buffer = Vec::new();
+            converter
+                .serialize(&mut buffer)
+                .map_err(|e| SymCacheError::new(SymCacheErrorKind::WriteFailed, e))?;
+            Ok(buffer)
+        };
+
+        let full_buffer = write(0)?;
+        let full = SymCache::parse(&full_buffer)?;
+
+        let addrs: Vec<u64> = full.ranges.iter().collect();
+        let threshold = addrs[addrs.len() / 2];
+
+        let compact_buffer = write(threshold)?;
+        let compact = SymCache::parse(&compact_buffer)?;
+
+        let num_below = addrs.iter().filter(|&&addr| addr < threshold).count();
+        assert_eq!(compact.header.range_offset, threshold);
+        assert_eq!(
+            compact.header.num_ranges as usize,
+            full.header.num_ranges as usize - num_below
+        );
+
+        for addr in addrs {
+            let from_compact = lookup_names(&compact, addr);
+            if addr < threshold {
+                assert!(from_compact.is_empty(), "{:#x}", addr);
+            } else {
+                assert_eq!(from_compact, lookup_names(&full, addr), "{:#x}", addr);
+            }
+        }
 
         Ok(())
     }
-}
 
-struct WriteWrapper<W> {
-    writer: W,
-    position: usize,
-}
+    #[test]
+    fn test_range_threshold_boundary() -> Result<(), Box<dyn std::error::Error>> {
+        let threshold = 0x1_0000_0000;
 
-impl<W: Write> WriteWrapper<W> {
-    fn new(writer: W) -> Self {
-        Self {
-            writer,
-            position: 0,
+        let mut converter = SymCacheConverter::new();
+        converter.set_range_threshold(threshold);
+        for &(name, address) in &[("discarded", threshold - 0x10), ("first", threshold)] {
+            converter.process_symbolic_symbol(&Symbol {
+                name: Some(name.into()),
+                address,
+                size: 0x10,
+            });
         }
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert_eq!(symcache.header.num_ranges, 1);
+        assert!(lookup_names(&symcache, threshold - 1).is_empty());
+        assert_eq!(
+            lookup_names(&symcache, threshold),
+            [(Some("first".to_owned()), 0)]
+        );
+        assert!(!symcache.covers(threshold - 1));
+        assert!(symcache.covers(threshold));
+
+        // The threshold does not bound the last range, which extends up to the next range or gap.
+        assert_eq!(
+            lookup_names(&symcache, threshold + 0x10),
+            [(Some("first".to_owned()), 0)]
+        );
+
+        // There is no default threshold per architecture.
+        for &arch in &[Arch::Amd64, Arch::Arm64, Arch::Arm, Arch::Wasm32] {
+            let mut converter = SymCacheConverter::new();
+            converter.set_arch(arch);
+            let mut buffer = Vec::new();
+            converter.serialize(&mut buffer)?;
+            assert_eq!(SymCache::parse(&buffer)?.header.range_offset, 0);
+        }
+
+        Ok(())
     }
 
-    fn write<T>(&mut self, data: &[T]) -> std::io::Result<usize> {
-        let pointer = data.as_ptr() as *const u8;
-        let len = std::mem::size_of_val(data);
-        // SAFETY: both pointer and len are derived directly from data/T and are valid.
-        let buf = unsafe { std::slice::from_raw_parts(pointer, len) };
-        self.writer.write_all(buf)?;
-        self.position += len;
-        Ok(len)
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compression_ratio() -> Result<(), Box<dyn std::error::Error>> {
+        // A long run of a single character compresses far beyond the maximum ratio.
+        let name = "a".repeat(1 << 20);
+        let mut converter = SymCacheConverter::new();
+        converter.set_compression_level(3);
+        converter.process_symbolic_symbol(&Symbol {
+            name: Some(name.clone().into()),
+            address: 0x1000,
+            size: 0x10,
+        });
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(!symcache.features().is_compressed());
+        assert_eq!(lookup_names(&symcache, 0x1008), [(Some(name), 0)]);
+
+        Ok(())
     }
 
-    fn align(&mut self) -> std::io::Result<usize> {
-        let buf = &[0u8; 7];
-        let len = raw::align_to_eight(self.position);
-        self.write(&buf[0..len])
+    #[test]
+    fn test_wide_ranges() -> Result<(), Box<dyn std::error::Error>> {
+        let write = |addresses: &[u64]| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            let mut converter = SymCacheConverter::new();
+            for (index, &address) in addresses.iter().enumerate() {
+                converter.process_symbolic_symbol(&Symbol {
+                    name: Some(format!("symbol{}", index).into()),
+                    address,
+                    size: 0x10,
+                });
+            }
+            let mut buffer = Vec::new();
+            converter.serialize(&mut buffer)?;
+            Ok(buffer)
+        };
+
+        // Caches that fit into 32 bits keep the narrow encoding.
+        let buffer = write(&[0x1000, 0xffff_f000])?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert!(!symcache.features().has_wide_ranges());
+        assert!(lookup_names(&symcache, 0x1_0000_1000).is_empty());
+
+        let buffer = write(&[0x1000, 0x1_0000_1000])?;
+        let symcache = SymCache::parse(&buffer)?;
+        symcache.validate()?;
+        assert!(symcache.features().has_wide_ranges());
+        assert_eq!(
+            lookup_names(&symcache, 0x1008),
+            [(Some("symbol0".to_owned()), 0)]
+        );
+        assert_eq!(
+            lookup_names(&symcache, 0x1_0000_1008),
+            [(Some("symbol1".to_owned()), 0)]
+        );
+        assert_eq!(
+            lookup_names(&symcache, u64::MAX - 1),
+            [(Some("symbol1".to_owned()), 0)]
+        );
+        // The address range is half-open, so its end is not covered.
+        assert!(lookup_names(&symcache, u64::MAX).is_empty());
+        assert_eq!(symcache.address_range(), Some(0x1000..u64::MAX));
+
+        // Entry pcs remain 32-bit and are omitted for functions that do not fit.
+        let entry_pcs: Vec<_> = symcache.functions().map(|f| f.entry_pc()).collect();
+        assert_eq!(entry_pcs, [0x1000, u32::MAX]);
+
+        // Functions without an entry pc are still listed by address.
+        let names: Vec<_> = symcache
+            .functions_by_address()
+            .map(|f| f.name().map(str::to_owned))
+            .collect();
+        assert_eq!(
+            names,
+            [Some("symbol0".to_owned()), Some("symbol1".to_owned())]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_inline_chains() -> Result<(), Box<dyn std::error::Error>> {
+        let file = FileInfo {
+            name: b"main.cpp",
+            dir: b"/src",
+        };
+        let line = |address, line| LineInfo {
+            address,
+            size: Some(0x10),
+            file: file.clone(),
+            line,
+            column: 0,
+        };
+
+        // The inlinee covers two line records, both of which are called from line 11.
+        let function = Function {
+            address: 0x1000,
+            size: 0x30,
+            entry_address: None,
+            name: Name::new("outer", NameMangling::Unmangled, Language::Cpp),
+            plain_name: None,
+            compilation_dir: b"",
+            producer: b"",
+            compilation_unit: b"",
+            lines: vec![line(0x1000, 10), line(0x1010, 11), line(0x1020, 11)],
+            inlinees: vec![Function {
+                address: 0x1010,
+                size: 0x20,
+                entry_address: None,
+                name: Name::new("inlined", NameMangling::Unmangled, Language::Cpp),
+                plain_name: None,
+                compilation_dir: b"",
+                producer: b"",
+                compilation_unit: b"",
+                lines: vec![line(0x1010, 3), line(0x1020, 4)],
+                inlinees: vec![],
+                inline: true,
+            }],
+            inline: false,
+        };
+
+        let mut converter = SymCacheConverter::new();
+        converter.insert_symbolic_function(&function);
+        assert_eq!(converter.shared_source_locations, 1);
+
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        symcache.validate()?;
+
+        // One caller record is shared by both inlined ranges. The last range is the gap after the
+        // function.
+        assert_eq!(symcache.header.num_ranges, 4);
+        assert_eq!(symcache.header.num_source_locations, 5);
+        for &addr in &[0x1010, 0x1020] {
+            let names: Vec<_> = lookup_names(&symcache, addr)
+                .into_iter()
+                .map(|(name, _)| name.unwrap_or_default())
+                .collect();
+            assert_eq!(names, ["inlined", "outer"]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gaps() -> Result<(), Box<dyn std::error::Error>> {
+        let function = |name: &'static str, address| Function {
+            address,
+            size: 0x10,
+            entry_address: None,
+            name: Name::new(name, NameMangling::Unmangled, Language::C),
+            plain_name: None,
+            compilation_dir: b"",
+            producer: b"",
+            compilation_unit: b"",
+            lines: vec![LineInfo {
+                address,
+                size: Some(0x10),
+                file: FileInfo {
+                    name: b"main.c",
+                    dir: b"/src",
+                },
+                line: 1,
+                column: 0,
+            }],
+            inlinees: vec![],
+            inline: false,
+        };
+
+        // The functions are padded to 0x20 bytes, and the range threshold must not matter.
+        for &threshold in &[0, 0x800] {
+            let mut converter = SymCacheConverter::new();
+            converter.set_range_threshold(threshold);
+            converter.insert_symbolic_function(&function("first", 0x1000));
+            converter.insert_symbolic_function(&function("second", 0x1020));
+            let mut buffer = Vec::new();
+            converter.serialize(&mut buffer)?;
+            let symcache = SymCache::parse(&buffer)?;
+            symcache.validate()?;
+
+            let frame = |name: &str| vec![(Some(name.to_owned()), 1)];
+            assert_eq!(lookup_names(&symcache, 0x100f), frame("first"));
+            assert!(lookup_names(&symcache, 0x1010).is_empty());
+            assert!(lookup_names(&symcache, 0x101f).is_empty());
+            assert_eq!(lookup_names(&symcache, 0x1020), frame("second"));
+            assert!(lookup_names(&symcache, 0x1030).is_empty());
+            assert!(!symcache.covers(0x1010));
+            assert_eq!(symcache.address_range(), Some(0x1000..0x1030));
+
+            let mut sequential = symcache.sequential_lookup();
+            assert!(sequential.next(0x1000).next().is_some());
+            assert!(sequential.next(0x1018).next().is_none());
+            assert!(sequential.next(0x1028).next().is_some());
+
+            let first = symcache.functions().next().unwrap();
+            let ranges: Vec<_> = symcache.function_ranges(&first).collect();
+            let start = 0x1000 - threshold;
+            assert_eq!(ranges, [(start, start + 0x10)]);
+        }
+
+        // Symbols cover code without debug information and replace the gap.
+        let mut converter = SymCacheConverter::new();
+        converter.insert_symbolic_function(&function("first", 0x1000));
+        converter.process_symbolic_symbol(&Symbol {
+            name: Some("padding".into()),
+            address: 0x1010,
+            size: 0x10,
+        });
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert_eq!(
+            lookup_names(&symcache, 0x1018),
+            [(Some("padding".to_owned()), 0)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gaps_padding() -> Result<(), Box<dyn std::error::Error>> {
+        // `ConvertUTF32toUTF8` ends at 0x14918, and is padded up to the next function at 0x14920.
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        symcache.validate()?;
+
+        let function = |addr| {
+            let frames = lookup_names(&symcache, addr);
+            frames.last().and_then(|(name, _)| name.clone())
+        };
+        assert_eq!(function(0x14917).as_deref(), Some("ConvertUTF32toUTF8"));
+        assert_eq!(function(0x14918), None);
+        assert_eq!(function(0x1491f), None);
+        assert_eq!(function(0x14920).as_deref(), Some("ConvertUTF8toUTF32"));
+
+        // Every gap directly follows the code of a function.
+        let gaps = symcache
+            .ranges
+            .iter()
+            .enumerate()
+            .filter(|&(range_idx, _)| symcache.is_gap(range_idx))
+            .map(|(_, range)| range)
+            .collect::<Vec<_>>();
+        assert!(!gaps.is_empty());
+        for addr in gaps {
+            assert!(lookup_names(&symcache, addr).is_empty(), "{:#x}", addr);
+            assert!(!lookup_names(&symcache, addr - 1).is_empty(), "{:#x}", addr);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_shared_source_locations() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        let report = converter.process_object(&object)?;
+        assert_eq!(
+            report.shared_source_locations,
+            converter.shared_source_locations
+        );
+
+        let num_callers = converter.source_locations.len();
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert_eq!(
+            (symcache.header.num_source_locations - symcache.header.num_ranges) as usize,
+            num_callers
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_stats() -> Result<(), Box<dyn std::error::Error>> {
+        let data = b"MODULE Linux x86_64 C0BCC3F19827FE653058404B2831D9E60 dedup
+FILE 0 /src/dedup.c
+FUNC 1000 10 0 helper
+1000 10 3 0
+FUNC 1010 10 0 helper
+1010 10 3 0
+FUNC 1020 10 0 main
+1020 10 8 0
+";
+        let object = Object::parse(data)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        let stats = converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        // `helper` is added twice, but only written once.
+        assert!(stats.strings_unique < stats.strings_total);
+        assert!(stats.functions_unique <= stats.functions_total);
+        assert_eq!(stats.strings_unique, symcache.header.num_strings as usize);
+        assert_eq!(
+            stats.functions_unique,
+            symcache.header.num_functions as usize
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cold_fragments() -> Result<(), Box<dyn std::error::Error>> {
+        // `process` is split into a hot part at 0x11a0 and a cold part at 0x107a.
+        let buffer = ByteView::open(fixture("linux/cold/gen/cold"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let function = |addr| symcache.lookup(addr).last().and_then(|sl| sl.function());
+        let hot = function(0x11a5).unwrap();
+        let cold = function(0x1080).unwrap();
+        assert_eq!(hot.name(), Some("process"));
+        assert_eq!(cold.name(), Some("process"));
+        assert_eq!(cold.entry_pc(), 0x11a0);
+
+        // Both parts resolve to the same function record.
+        let starts: Vec<_> = symcache
+            .function_ranges(&hot)
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(starts.first(), Some(&0x107a));
+        assert!(starts.contains(&0x11a0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_linkage_names() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let handle_signal = symcache
+            .functions()
+            .find(|function| {
+                function.mangled_name().map_or(false, |name| {
+                    name.starts_with("_ZN15google_breakpad16ExceptionHandler12HandleSignal")
+                })
+            })
+            .unwrap();
+        assert_eq!(handle_signal.name(), handle_signal.mangled_name());
+        assert_eq!(handle_signal.plain_name(), Some("HandleSignal"));
+
+        // C functions only have a plain name.
+        let main = symcache
+            .functions()
+            .find(|function| function.name() == Some("main"))
+            .unwrap();
+        assert_eq!(main.mangled_name(), None);
+        assert_eq!(main.plain_name(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_symbol_fallback() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("linux/types-only.so"))?;
+        let object = Object::parse(&buffer)?;
+        assert!(object.has_debug_info());
+
+        let symbol_map = object.symbol_map();
+        let symbol = symbol_map
+            .iter()
+            .find(|symbol| symbol.name() == Some("types_only_compute"))
+            .unwrap();
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let num_symbols = symbol_map.iter().filter(|s| s.name.is_some()).count();
+        assert_eq!(symcache.functions.len(), num_symbols);
+        assert!(!symcache.features().has_source());
+        assert_eq!(
+            lookup_names(&symcache, symbol.address),
+            [(Some("types_only_compute".to_owned()), 0)]
+        );
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_debug_info_only(true);
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert!(symcache.functions.is_empty());
+        assert!(lookup_names(&symcache, symbol.address).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumb_addresses() -> Result<(), Box<dyn std::error::Error>> {
+        let write = |arch: Arch| -> Result<Vec<u8>, std::io::Error> {
+            let mut converter = SymCacheConverter::new();
+            converter.set_arch(arch);
+            converter.process_symbolic_symbol(&Symbol {
+                name: Some("thumb_function".into()),
+                address: 0x1001,
+                size: 0x20,
+            });
+            let mut buffer = Vec::new();
+            converter.serialize(&mut buffer)?;
+            Ok(buffer)
+        };
+
+        let buffer = write(Arch::ArmV7)?;
+        let symcache = SymCache::parse(&buffer)?;
+        let function = symcache.lookup(0x1000).next().and_then(|sl| sl.function());
+        assert_eq!(
+            function.as_ref().and_then(|f| f.name()),
+            Some("thumb_function")
+        );
+        assert_eq!(function.as_ref().map(|f| f.entry_pc()), Some(0x1000));
+
+        // Other architectures keep their addresses untouched.
+        for &arch in &[Arch::Arm64, Arch::Amd64] {
+            let buffer = write(arch)?;
+            let symcache = SymCache::parse(&buffer)?;
+            assert!(symcache.lookup(0x1000).next().is_none());
+            assert!(symcache.lookup(0x1001).next().is_some());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pdb() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("windows/crash.pdb"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert_eq!(symcache.arch(), Arch::X86);
+        assert_eq!(symcache.debug_id(), object.debug_id());
+        assert!(symcache.features().has_inline());
+        assert!(symcache.features().has_source());
+        assert!(symcache.header.num_files > 0);
+        assert!(symcache.header.num_functions > 0);
+        assert!(symcache.header.num_source_locations > symcache.header.num_ranges);
+
+        let frame = |name: &str, line| (Some(name.to_owned()), line);
+        assert_eq!(
+            lookup_names(&symcache, 0x1000),
+            [frame(
+                "google_breakpad::CrashGenerationClient::RequestDump",
+                323
+            )]
+        );
+        assert_eq!(
+            lookup_names(&symcache, 0x1011),
+            [
+                frame("google_breakpad::CrashGenerationClient::IsRegistered", 319),
+                frame("google_breakpad::CrashGenerationClient::RequestDump", 324),
+            ]
+        );
+
+        // `std::_Deallocate` starts at an address where none of its callers has a line record.
+        let tidy_deallocate =
+            "std::basic_string<wchar_t,std::char_traits<wchar_t>,std::allocator<wchar_t> >::_Tidy_deallocate";
+        let destructor = "std::basic_string<wchar_t,std::char_traits<wchar_t>,std::allocator<wchar_t> >::~basic_string<wchar_t,std::char_traits<wchar_t>,std::allocator<wchar_t> >";
+        assert_eq!(
+            lookup_names(&symcache, 0x1134),
+            [
+                frame("std::_Deallocate", 211),
+                frame("std::allocator<wchar_t>::deallocate", 1030),
+                frame(tidy_deallocate, 3907),
+                frame(destructor, 2425),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pe_symbols_only() -> Result<(), Box<dyn std::error::Error>> {
+        // The executable does not contain debug information or exports.
+        let buffer = ByteView::open(fixture("windows/crash.exe"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert_eq!(symcache.debug_id(), object.debug_id());
+        assert_eq!(symcache.header.num_ranges, 0);
+        assert!(lookup_names(&symcache, 0x1000).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm() -> Result<(), Box<dyn std::error::Error>> {
+        let buffer = ByteView::open(fixture("wasm/simple.wasm"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.set_arch(object.arch());
+        converter.set_debug_id(object.debug_id());
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        assert_eq!(symcache.arch(), Arch::Wasm32);
+        assert_eq!(symcache.debug_id(), object.debug_id());
+        assert!(symcache.features().has_source());
+
+        // Addresses are offsets into the module, just like in the DWARF debug session.
+        let session = object.debug_session()?;
+        let function = session
+            .functions()
+            .filter_map(Result::ok)
+            .find(|f| f.address == 0x8b)
+            .expect("internal_func at 0x8b");
+        for line in &function.lines {
+            let frames = lookup_names(&symcache, line.address);
+            let (name, _) = frames.last().expect("no frames for line");
+            assert_eq!(name.as_deref(), Some("internal_func"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpad_inlinees() -> Result<(), Box<dyn std::error::Error>> {
+        let data = b"MODULE Linux x86_64 492E2DD23CC306CA9C494EEF1533A3810 crash
+FILE 0 main.cpp
+FILE 1 util.h
+INLINE_ORIGIN 0 outer()
+FUNC 1000 30 0 main
+INLINE 0 10 0 0 1010 10
+INLINE 1 20 1 1 1018 8
+1000 10 5 0
+1010 20 30 1
+INLINE_ORIGIN 1 inner()
+";
+        let object = Object::parse(data)?;
+
+        let mut converter = SymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = SymCache::parse(&buffer)?;
+
+        let frame = |name: &str, line| (Some(name.to_owned()), line);
+        assert_eq!(lookup_names(&symcache, 0x1008), [frame("main", 5)]);
+        assert_eq!(
+            lookup_names(&symcache, 0x1010),
+            [frame("outer()", 30), frame("main", 10)]
+        );
+        assert_eq!(
+            lookup_names(&symcache, 0x101c),
+            [
+                frame("inner()", 30),
+                frame("outer()", 20),
+                frame("main", 10)
+            ]
+        );
+        assert_eq!(lookup_names(&symcache, 0x1020), [frame("main", 30)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_limits() {
+        let max = TableSizes {
+            strings: u32::MAX as usize,
+            string_bytes: u32::MAX.into(),
+            files: u32::MAX as usize,
+            functions: u32::MAX as usize,
+            source_locations: u32::MAX as usize,
+        };
+        assert!(check_limits(max).is_ok());
+
+        let result = check_limits(TableSizes {
+            strings: usize::MAX,
+            ..max
+        });
+        assert!(matches!(result, Err(Error::TooManyStrings)));
+
+        let result = check_limits(TableSizes {
+            string_bytes: u64::from(u32::MAX) + 1,
+            ..max
+        });
+        assert!(matches!(result, Err(Error::TooManyStringBytes)));
+
+        let result = check_limits(TableSizes {
+            files: usize::MAX,
+            ..max
+        });
+        assert!(matches!(result, Err(Error::TooManyFiles)));
+
+        let result = check_limits(TableSizes {
+            functions: usize::MAX,
+            ..max
+        });
+        assert!(matches!(result, Err(Error::TooManyFunctions)));
+
+        let result = check_limits(TableSizes {
+            source_locations: usize::MAX,
+            ..max
+        });
+        assert!(matches!(result, Err(Error::TooManySourceLocations)));
+    }
+
+    #[test]
+    fn test_saturating_conversions() {
+        assert_eq!(to_index(42), 42);
+        assert_eq!(to_index(usize::MAX), u32::MAX);
+        assert_eq!(to_line_number(42), 42);
+        assert_eq!(to_line_number(u64::from(u32::MAX) + 1), u32::MAX);
     }
 }
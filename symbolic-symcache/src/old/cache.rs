@@ -1,9 +1,13 @@
 use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
 
-use symbolic_common::{Arch, AsSelf, DebugId, Language, Name, NameMangling};
+use sha2::{Digest, Sha256};
+use symbolic_common::{
+    join_path, Arch, AsSelf, ByteView, DebugId, Language, Name, NameMangling, SelfCell,
+};
 
-use crate::format;
-use crate::SymCacheError;
+use crate::format::{self, get_checked};
+use crate::{SymCacheError, SymCacheErrorKind, ValueKind};
 
 /// A platform independent symbolication cache.
 ///
@@ -48,7 +52,7 @@ impl<'a> SymCache<'a> {
         Arch::from_u32(self.header.arch)
     }
 
-    /// The debuig identifier of the cache file.
+    /// The debug identifier of the cache file.
     pub fn debug_id(&self) -> DebugId {
         self.header.debug_id
     }
@@ -64,6 +68,31 @@ impl<'a> SymCache<'a> {
         self.has_line_info()
     }
 
+    /// Computes a SHA-256 hash over the contents of this SymCache.
+    ///
+    /// The hash covers the format version, debug identifier, architecture and all data following
+    /// the header. Deprecated header fields and the offsets of segments are not included. This can
+    /// be used to identify SymCaches independently of where they are stored.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let body = match self.version() {
+            1 => self.data,
+            _ => self
+                .data
+                .get(std::mem::size_of::<format::HeaderV2>()..)
+                .unwrap_or_default(),
+        };
+
+        let debug_id = self.debug_id();
+        let mut hasher = Sha256::new();
+        hasher.update(&self.version().to_le_bytes());
+        hasher.update(debug_id.uuid().as_bytes());
+        hasher.update(&debug_id.appendix().to_le_bytes());
+        hasher.update(&self.header.arch.to_le_bytes());
+        hasher.update(&[self.header.has_line_records]);
+        hasher.update(body);
+        hasher.finalize().into()
+    }
+
     /// Returns an iterator over all functions.
     pub fn functions(&self) -> Functions<'a> {
         Functions {
@@ -71,16 +100,56 @@ impl<'a> SymCache<'a> {
             symbols: self.header.symbols,
             files: self.header.files,
             data: self.data,
+            version: self.version(),
             index: 0,
         }
     }
 
+    /// Writes a listing of the selected `sections` of this SymCache to `w`.
+    ///
+    /// The listing is line-oriented and deterministic, which makes it suitable for comparing
+    /// SymCaches with a diff. Each section starts with a `# name` line, followed by one line per
+    /// record, and sections are separated by an empty line. Records that cannot be read are listed
+    /// as `error: ...` lines. See [`DumpSections`] for the contents of each section.
+    ///
+    /// The format is meant for humans and may change between releases.
+    pub fn debug_dump<W: fmt::Write>(&self, mut w: W, sections: DumpSections) -> fmt::Result {
+        type Dump<'a> = fn(&SymCache<'a>, &mut dyn fmt::Write) -> fmt::Result;
+        let dumps: [(DumpSections, &str, Dump<'a>); 5] = [
+            (DumpSections::HEADER, "header", Self::dump_header),
+            (DumpSections::FILES, "files", Self::dump_files),
+            (DumpSections::FUNCTIONS, "functions", Self::dump_functions),
+            (DumpSections::RANGES, "ranges", Self::dump_ranges),
+            (DumpSections::STRINGS, "strings", Self::dump_strings),
+        ];
+
+        let mut first = true;
+        for &(section, name, dump) in &dumps {
+            if !sections.contains(section) {
+                continue;
+            }
+            if !first {
+                writeln!(w)?;
+            }
+            first = false;
+
+            writeln!(w, "# {}", name)?;
+            dump(self, &mut w)?;
+        }
+
+        Ok(())
+    }
+
     /// Given an address this looks up the symbol at that point.
     ///
     /// Because of inline information this returns a vector of zero or
     /// more symbols.  If nothing is found then the return value will be
     /// an empty vector.
+    ///
+    /// The address is truncated to the pointer width of the SymCache's architecture first, see
+    /// [`Arch::truncate_address`].
     pub fn lookup(&self, addr: u64) -> Result<Lookup<'a, '_>, SymCacheError> {
+        let addr = self.arch().truncate_address(addr);
         let funcs = self.function_records()?;
 
         // Functions in the function segment are ordered by start address
@@ -94,8 +163,9 @@ impl<'a> SymCache<'a> {
         };
 
         // Seek forward to the deepest inlined function at the same start address.
-        while let Some(current_fn) = funcs.get(current_id + 1) {
-            if current_fn.addr_start() != funcs[current_id].addr_start() {
+        let start_addr = get_checked(funcs, ValueKind::Function, current_id as u64)?.addr_start();
+        while let Some(next_fn) = funcs.get(current_id + 1) {
+            if next_fn.addr_start() != start_addr {
                 break;
             }
             current_id += 1;
@@ -123,7 +193,7 @@ impl<'a> SymCache<'a> {
         // FOR OVERLAPS IN INLINE FUNCTIONS.
         let mut last_id = current_id;
         loop {
-            let current_fn = &funcs[current_id];
+            let current_fn = get_checked(funcs, ValueKind::Function, current_id as u64)?;
 
             // If the current function covers the address, resolve the closest line record before
             // the search address. If it is closer than what we've seen before, this is a better
@@ -143,7 +213,7 @@ impl<'a> SymCache<'a> {
             // We are currently looking at an inline function. Since we're scanning linearly, ensure
             // that we're also including its parent. This might be from a completely different
             // inlining branch, so honor the existing `last_id` value as it might be lower.
-            if let Some(parent_id) = current_fn.parent(current_id) {
+            if let Some((parent_id, _)) = read_parent(funcs, current_fn, current_id)? {
                 last_id = parent_id.min(last_id);
             }
 
@@ -171,11 +241,120 @@ impl<'a> SymCache<'a> {
         })
     }
 
+    /// Looks up the innermost source location at the given address.
+    ///
+    /// This returns the first item of [`lookup`](Self::lookup), which is the most deeply inlined
+    /// function covering the address, or `None` if no function covers it. The functions it was
+    /// inlined into are not resolved, which makes this cheaper than collecting the entire lookup
+    /// if only the innermost frame is needed.
+    pub fn lookup_innermost(&self, addr: u64) -> Result<Option<LineInfo<'a>>, SymCacheError> {
+        match self.lookup(addr)?.current {
+            Some((addr, _, fun)) => self.build_line_info(fun, addr, None).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Resolves the raw list of `FuncRecords` from the funcs segment.
     fn function_records(&self) -> Result<&'a [format::FuncRecord], SymCacheError> {
         self.header.functions.read(self.data)
     }
 
+    /// Returns all functions sorted by address, keeping inlinees after their parents.
+    fn sorted_functions(&self) -> Result<Vec<Function<'a>>, SymCacheError> {
+        let mut functions = self.functions().collect::<Result<Vec<_>, _>>()?;
+        functions.sort_by_key(Function::address);
+        Ok(functions)
+    }
+
+    /// Writes the [`DumpSections::HEADER`] section of a [`debug_dump`](Self::debug_dump).
+    fn dump_header(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        writeln!(w, "version: {}", self.version())?;
+        writeln!(w, "debug_id: {}", self.debug_id())?;
+        writeln!(w, "arch: {}", self.arch())?;
+        writeln!(w, "has_line_info: {}", self.has_line_info())?;
+        writeln!(w, "strings: {}", { self.header.symbols.len })?;
+        writeln!(w, "files: {}", { self.header.files.len })?;
+        writeln!(w, "functions: {}", { self.header.functions.len })
+    }
+
+    /// Writes the [`DumpSections::FILES`] section of a [`debug_dump`](Self::debug_dump).
+    fn dump_files(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        let files = match self.header.files.read(self.data) {
+            Ok(files) => files,
+            Err(error) => return writeln!(w, "error: {}", error),
+        };
+
+        for (index, file) in files.iter().enumerate() {
+            let path = file.base_dir.read_str(self.data).and_then(|dir| {
+                let name = file.filename.read_str(self.data)?;
+                Ok(join_path(dir, name))
+            });
+            match path {
+                Ok(path) => writeln!(w, "{:>6} {}", index, path)?,
+                Err(error) => writeln!(w, "{:>6} error: {}", index, error)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the [`DumpSections::FUNCTIONS`] section of a [`debug_dump`](Self::debug_dump).
+    fn dump_functions(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        let functions = match self.sorted_functions() {
+            Ok(functions) => functions,
+            Err(error) => return writeln!(w, "error: {}", error),
+        };
+
+        for function in &functions {
+            writeln!(w, "{:>16x} {}", function.address(), function.name())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the [`DumpSections::RANGES`] section of a [`debug_dump`](Self::debug_dump).
+    fn dump_ranges(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        let functions = match self.sorted_functions() {
+            Ok(functions) => functions,
+            Err(error) => return writeln!(w, "error: {}", error),
+        };
+
+        for function in &functions {
+            for line in function.lines() {
+                match line {
+                    Ok(line) => writeln!(
+                        w,
+                        "{:>16x} {:>6} {:>6} {}",
+                        function.address() + line.address(),
+                        function.id(),
+                        { line.record.file_id },
+                        line.line(),
+                    )?,
+                    Err(error) => writeln!(w, "error: {}", error)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the [`DumpSections::STRINGS`] section of a [`debug_dump`](Self::debug_dump).
+    fn dump_strings(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        let symbols = match self.header.symbols.read(self.data) {
+            Ok(symbols) => symbols,
+            Err(error) => return writeln!(w, "error: {}", error),
+        };
+
+        for (index, symbol) in symbols.iter().enumerate() {
+            match symbol.read_str(self.data) {
+                Ok(symbol) => writeln!(w, "{:>6} {}", index, symbol)?,
+                Err(error) => writeln!(w, "{:>6} error: {}", index, error)?,
+            }
+        }
+
+        Ok(())
+    }
+
     /// Locates the source line record for an instruction address within a function.
     ///
     /// This function runs through all line records of the given function and
@@ -194,7 +373,7 @@ impl<'a> SymCache<'a> {
         fun: &format::FuncRecord,
         addr: u64,
     ) -> Result<Option<(u64, u16, u32)>, SymCacheError> {
-        let records = fun.line_records.read(self.data)?;
+        let records = fun.line_records.read_lines(self.data, self.version())?;
         if records.is_empty() {
             // A non-empty function without line records can happen in a couple
             // of cases:
@@ -217,11 +396,11 @@ impl<'a> SymCache<'a> {
         // the record.  Because of that we pick in any case the first
         // record as fallback.
         let mut file_id = records[0].file_id;
-        let mut line = u32::from(records[0].line);
+        let mut line = records[0].line;
         let mut running_addr = fun.addr_start();
         let mut line_addr = running_addr;
 
-        for rec in records {
+        for rec in records.iter() {
             // Keep running until we exceed the search address
             running_addr += u64::from(rec.addr_off);
             if running_addr > addr {
@@ -231,11 +410,11 @@ impl<'a> SymCache<'a> {
             // Remember the starting address of the current line. There might be
             // multiple line records for a single line if `addr_off` overflows.
             // So only update `line_addr` if we actually hit a new line.
-            if u32::from(rec.line) != line {
+            if { rec.line } != line {
                 line_addr = running_addr;
             }
 
-            line = u32::from(rec.line);
+            line = rec.line;
             file_id = rec.file_id;
         }
 
@@ -265,8 +444,7 @@ impl<'a> SymCache<'a> {
         let (line, line_addr, filename, base_dir) = if let Some((line_addr, file_id, line)) =
             self.run_to_line(fun, addr)?
         {
-            // A missing file record indicates a bad symcache or too many files, which we handle
-            // gracefully here.
+            // A missing file record indicates too many files, which we handle gracefully here.
             if let Some(file_record) = read_file_record(self.data, self.header.files, file_id)? {
                 // The address was found in the function's line records, so use
                 // it directly. This should is the default case for all valid
@@ -305,11 +483,12 @@ impl<'a> SymCache<'a> {
             line_addr,
             instr_addr: addr,
             line,
-            lang: Language::from_u32(fun.lang.into()),
+            lang: Language::from_u8(fun.lang),
             symbol: read_symbol(self.data, self.header.symbols, fun.symbol_id())?,
             filename,
             base_dir,
             comp_dir: fun.comp_dir.read_str(self.data)?,
+            inlined: fun.parent_offset != !0,
         })
     }
 }
@@ -334,6 +513,94 @@ impl fmt::Debug for SymCache<'_> {
     }
 }
 
+/// A [`SymCache`] that owns the buffer it was parsed from.
+///
+/// The parsed SymCache borrows from its buffer, which otherwise has to be kept alive separately.
+/// This bundles both, so that it can be stored in structs or returned from functions. All
+/// accessors of the SymCache are available via [`get`](Self::get).
+pub struct OwnedSymCache(SelfCell<ByteView<'static>, SymCache<'static>>);
+
+impl OwnedSymCache {
+    /// Parses a SymCache from a buffer, taking ownership of the buffer.
+    pub fn parse(byteview: ByteView<'static>) -> Result<Self, SymCacheError> {
+        let cell = SelfCell::try_new(byteview, |data| SymCache::parse(unsafe { &*data }))?;
+        Ok(OwnedSymCache(cell))
+    }
+
+    /// Returns the parsed SymCache, borrowing from the owned buffer.
+    pub fn get(&self) -> &SymCache<'_> {
+        self.0.get()
+    }
+
+    /// Returns the buffer of this SymCache.
+    pub fn byteview(&self) -> &ByteView<'static> {
+        self.0.owner()
+    }
+}
+
+impl fmt::Debug for OwnedSymCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedSymCache").field(self.get()).finish()
+    }
+}
+
+/// A set of sections to include in a [`SymCache::debug_dump`].
+///
+/// Sections are combined with `|`, for example `DumpSections::FILES | DumpSections::FUNCTIONS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DumpSections(u32);
+
+impl DumpSections {
+    /// The format version, debug identifier, architecture and the size of each table.
+    pub const HEADER: Self = DumpSections(1 << 0);
+    /// The index and full path of each file.
+    pub const FILES: Self = DumpSections(1 << 1);
+    /// The address and name of each function, inlinee and symbol, sorted by address.
+    pub const FUNCTIONS: Self = DumpSections(1 << 2);
+    /// The address, function index, file index and line number of each line record, sorted by
+    /// address of the function.
+    pub const RANGES: Self = DumpSections(1 << 3);
+    /// The index and value of each entry in the table of function and symbol names.
+    pub const STRINGS: Self = DumpSections(1 << 4);
+    /// All sections.
+    pub const ALL: Self = DumpSections((1 << 5) - 1);
+
+    /// Returns an empty set of sections.
+    pub fn empty() -> Self {
+        DumpSections(0)
+    }
+
+    /// Returns the raw bitfield of sections.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether all sections in `other` are included in this set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for DumpSections {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitOr for DumpSections {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        DumpSections(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for DumpSections {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
 /// An iterator over line matches for an address lookup.
 #[derive(Clone)]
 pub struct Lookup<'a, 'c> {
@@ -366,12 +633,16 @@ impl<'a, 'c> Iterator for Lookup<'a, 'c> {
     type Item = Result<LineInfo<'a>, SymCacheError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (addr, id, fun) = self.current?;
+        let (addr, id, fun) = self.current.take()?;
         let line_result = self.cache.build_line_info(fun, addr, None);
 
-        self.current = fun
-            .parent(id)
-            .map(|parent_id| (addr, parent_id, &self.funcs[parent_id]));
+        // If the inlining hierarchy is broken, there is no way to continue with the parent.
+        match read_parent(self.funcs, fun, id) {
+            Ok(parent) => {
+                self.current = parent.map(|(parent_id, parent_fn)| (addr, parent_id, parent_fn))
+            }
+            Err(error) => return Some(Err(error)),
+        }
 
         if let Ok(ref line_info) = line_result {
             self.inner = Some((
@@ -417,6 +688,7 @@ pub struct LineInfo<'a> {
     filename: &'a str,
     base_dir: &'a str,
     comp_dir: &'a str,
+    inlined: bool,
 }
 
 impl<'a> LineInfo<'a> {
@@ -494,6 +766,14 @@ impl<'a> LineInfo<'a> {
     pub fn function_name(&self) -> Name<'a> {
         Name::new(self.symbol(), NameMangling::Unknown, self.language())
     }
+
+    /// Whether this frame was inlined into the next frame of the lookup.
+    ///
+    /// The outermost frame of a lookup, which corresponds to a real function on the stack, is never
+    /// inlined.
+    pub fn is_inlined(&self) -> bool {
+        self.inlined
+    }
 }
 
 impl fmt::Display for LineInfo<'_> {
@@ -527,6 +807,7 @@ pub struct Functions<'a> {
     symbols: format::Seg<format::Seg<u8, u16>>,
     files: format::Seg<format::FileRecord, u16>,
     data: &'a [u8],
+    version: u32,
     index: u32,
 }
 
@@ -545,6 +826,7 @@ impl<'a> Iterator for Functions<'a> {
             symbols: self.symbols,
             files: self.files,
             data: self.data,
+            version: self.version,
             index: self.index,
         }));
 
@@ -562,6 +844,7 @@ pub struct Function<'a> {
     symbols: format::Seg<format::Seg<u8, u16>>,
     files: format::Seg<format::FileRecord, u16>,
     data: &'a [u8],
+    version: u32,
     index: u32,
 }
 
@@ -581,6 +864,11 @@ impl<'a> Function<'a> {
         self.record.addr_start()
     }
 
+    /// The instruction address _after_ the end of the function, or `u64::MAX` if unknown.
+    pub(crate) fn end_address(&self) -> u64 {
+        self.record.addr_end()
+    }
+
     /// The raw name of the function.
     pub fn symbol(&self) -> &'a str {
         read_symbol(self.data, self.symbols, self.record.symbol_id())
@@ -590,7 +878,7 @@ impl<'a> Function<'a> {
 
     /// The language of the function.
     pub fn language(&self) -> Language {
-        Language::from_u32(self.record.lang.into())
+        Language::from_u8(self.record.lang)
     }
 
     /// The name of the function suitable for demangling.
@@ -611,6 +899,7 @@ impl<'a> Function<'a> {
             lines: self.record.line_records,
             files: self.files,
             data: self.data,
+            version: self.version,
             address: 0,
             index: 0,
         }
@@ -657,6 +946,7 @@ pub struct Lines<'a> {
     lines: format::Seg<format::LineRecord, u16>,
     files: format::Seg<format::FileRecord, u16>,
     data: &'a [u8],
+    version: u32,
     address: u64,
     index: u16,
 }
@@ -665,7 +955,7 @@ impl<'a> Iterator for Lines<'a> {
     type Item = Result<Line<'a>, SymCacheError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let record = match self.lines.get(self.data, self.index) {
+        let record = match self.lines.get_line(self.data, self.version, self.index) {
             Ok(Some(record)) => record,
             Ok(None) => return None,
             Err(error) => return Some(Err(error)),
@@ -674,9 +964,14 @@ impl<'a> Iterator for Lines<'a> {
         self.address += u64::from(record.addr_off);
         self.index += 1;
 
+        let file = match read_file_record(self.data, self.files, record.file_id) {
+            Ok(file) => file,
+            Err(error) => return Some(Err(error)),
+        };
+
         Some(Ok(Line {
             record,
-            file: read_file_record(self.data, self.files, record.file_id).unwrap_or(None),
+            file,
             address: self.address,
             data: self.data,
         }))
@@ -685,7 +980,7 @@ impl<'a> Iterator for Lines<'a> {
 
 /// A line covered by a [`Function`](symbolic_debuginfo::Function).
 pub struct Line<'a> {
-    record: &'a format::LineRecord,
+    record: format::LineRecord,
     file: Option<&'a format::FileRecord>,
     data: &'a [u8],
     address: u64,
@@ -698,7 +993,7 @@ impl<'a> Line<'a> {
     }
 
     /// The line number of the line.
-    pub fn line(&self) -> u16 {
+    pub fn line(&self) -> u32 {
         self.record.line
     }
 
@@ -738,10 +1033,9 @@ fn read_symbol(
 ) -> Result<Option<&str>, SymCacheError> {
     if index == u32::MAX {
         Ok(None)
-    } else if let Some(symbol) = symbols.get(data, index)? {
-        symbol.read_str(data).map(Some)
     } else {
-        Ok(None)
+        let symbol = symbols.get_checked(data, ValueKind::Symbol, index)?;
+        symbol.read_str(data).map(Some)
     }
 }
 
@@ -754,6 +1048,31 @@ fn read_file_record(
     if index == u16::MAX {
         Ok(None)
     } else {
-        files.get(data, index)
+        files.get_checked(data, ValueKind::File, index).map(Some)
+    }
+}
+
+/// Look up the parent of an inlined function record at `index`.
+///
+/// The parent must precede the function in the functions segment.
+fn read_parent<'a>(
+    funcs: &'a [format::FuncRecord],
+    record: &format::FuncRecord,
+    index: usize,
+) -> Result<Option<(usize, &'a format::FuncRecord)>, SymCacheError> {
+    if record.parent_offset == !0 {
+        return Ok(None);
+    }
+
+    match record.parent(index) {
+        Some(parent_id) if parent_id < index => {
+            let parent = get_checked(funcs, ValueKind::Function, parent_id as u64)?;
+            Ok(Some((parent_id, parent)))
+        }
+        _ => Err(SymCacheErrorKind::OutOfBounds {
+            table: ValueKind::ParentOffset,
+            index: index as u64,
+        }
+        .into()),
     }
 }
@@ -66,6 +66,17 @@ pub enum SymCacheErrorKind {
 
     /// Generic error when writing a symcache, most likely IO.
     WriteFailed,
+
+    /// Writing the symcache was cancelled by the caller.
+    Cancelled,
+
+    /// A record references an index outside of its table, indicating a malformed symcache.
+    OutOfBounds {
+        /// The table that was accessed.
+        table: ValueKind,
+        /// The offending index into the table.
+        index: u64,
+    },
 }
 
 impl fmt::Display for SymCacheErrorKind {
@@ -83,6 +94,10 @@ impl fmt::Display for SymCacheErrorKind {
             Self::ValueTooLarge(kind) => write!(f, "{} too large for symcache file format", kind),
             Self::TooManyValues(kind) => write!(f, "too many {}s for symcache", kind),
             Self::WriteFailed => write!(f, "failed to write symcache"),
+            Self::Cancelled => write!(f, "symcache writing was cancelled"),
+            Self::OutOfBounds { table, index } => {
+                write!(f, "{} index {} out of bounds", table, index)
+            }
         }
     }
 }
@@ -1,6 +1,8 @@
 //! Definition of the binary format for SymCaches.
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::fmt;
 use std::io;
 use std::marker::PhantomData;
@@ -8,13 +10,16 @@ use std::num::NonZeroU16;
 
 use symbolic_common::{DebugId, Uuid};
 
-use crate::{SymCacheError, SymCacheErrorKind};
+use crate::{SymCacheError, SymCacheErrorKind, ValueKind};
 
 /// The magic file preamble to identify symcache files.
 pub const SYMCACHE_MAGIC: [u8; 4] = *b"SYMC";
 
 /// The latest version of the file format.
-pub const SYMCACHE_VERSION: u32 = 6;
+pub const SYMCACHE_VERSION: u32 = 7;
+
+/// The last version of the file format that stores [`NarrowLineRecord`]s.
+pub const SYMCACHE_VERSION_NARROW_LINES: u32 = 6;
 
 // Version history:
 //
@@ -24,6 +29,7 @@ pub const SYMCACHE_VERSION: u32 = 6;
 // 4: PR #155: Functions with more than 65k line records
 // 5: PR #221: Invalid inlinee nesting leading to wrong stack traces
 // 6: PR #319: Correct line offsets and spacer line records
+// 7: 32-bit line numbers in line records
 
 /// Loads binary data from a segment.
 pub(crate) fn get_slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], io::Error> {
@@ -42,6 +48,20 @@ pub(crate) fn get_record<T>(data: &[u8], offset: usize) -> Result<&T, io::Error>
     Ok(unsafe { &*(record.as_ptr() as *const T) })
 }
 
+/// Returns the item at `index` in a table that was read from a segment.
+///
+/// Returns [`SymCacheErrorKind::OutOfBounds`] if the index is not within the table.
+pub(crate) fn get_checked<T>(
+    table: &[T],
+    kind: ValueKind,
+    index: u64,
+) -> Result<&T, SymCacheError> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| table.get(index))
+        .ok_or_else(|| SymCacheErrorKind::OutOfBounds { table: kind, index }.into())
+}
+
 /// Loads a slice of typed objects from a binary slice.
 #[inline(always)]
 pub(crate) fn as_slice<T>(data: &T) -> &[u8] {
@@ -89,8 +109,10 @@ where
     pub fn read<'a>(&self, data: &'a [u8]) -> Result<&'a [T], SymCacheError> {
         let offset = self.offset as usize;
         let len = self.len.into() as usize;
-        let size = std::mem::size_of::<T>() * len;
-        let slice = get_slice(data, offset, size)
+        let slice = std::mem::size_of::<T>()
+            .checked_mul(len)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "out of range"))
+            .and_then(|size| get_slice(data, offset, size))
             .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadSegment, e))?;
         Ok(unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const T, len) })
     }
@@ -102,6 +124,22 @@ where
     {
         Ok(self.read(data)?.get(index.into() as usize))
     }
+
+    /// Reads a single element within a segment from the SymCache buffer.
+    ///
+    /// Unlike [`get`](Self::get), this returns [`SymCacheErrorKind::OutOfBounds`] if the index is
+    /// not within the segment.
+    pub fn get_checked<'a, U>(
+        &self,
+        data: &'a [u8],
+        kind: ValueKind,
+        index: U,
+    ) -> Result<&'a T, SymCacheError>
+    where
+        U: Into<u64>,
+    {
+        get_checked(self.read(data)?, kind, index.into())
+    }
 }
 
 impl<L> Seg<u8, L>
@@ -248,11 +286,13 @@ impl FuncRecord {
 
     /// Resolves the index of the parent function in the [`functions`](Header::functions)
     /// segment, if this is an inlined function.
+    ///
+    /// Also returns `None` if the parent offset points before the start of the segment.
     pub fn parent(&self, func_id: usize) -> Option<usize> {
         if self.parent_offset == !0 {
             None
         } else {
-            Some(func_id - (self.parent_offset as usize))
+            func_id.checked_sub(self.parent_offset as usize)
         }
     }
 }
@@ -268,10 +308,71 @@ pub struct LineRecord {
     /// Index of the file record in the [`files`](Header::files) segment.
     pub file_id: u16,
 
+    /// The line number of the line record.
+    pub line: u32,
+}
+
+/// A line record with a 16-bit line number, used by SymCaches up to
+/// [`SYMCACHE_VERSION_NARROW_LINES`].
+#[repr(C, packed)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct NarrowLineRecord {
+    /// Offset to the previous line record in the same function, or to the [function address](FuncRecord::addr_start)
+    /// if this is the first line.
+    pub addr_off: u8,
+
+    /// Index of the file record in the [`files`](Header::files) segment.
+    pub file_id: u16,
+
     /// The line number of the line record.
     pub line: u16,
 }
 
+impl From<&'_ NarrowLineRecord> for LineRecord {
+    fn from(record: &NarrowLineRecord) -> Self {
+        LineRecord {
+            addr_off: record.addr_off,
+            file_id: record.file_id,
+            line: u32::from(record.line),
+        }
+    }
+}
+
+impl Seg<LineRecord, u16> {
+    /// Reads the line records of a function from a SymCache with the given format `version`.
+    ///
+    /// Up to [`SYMCACHE_VERSION_NARROW_LINES`], the segment contains [`NarrowLineRecord`]s, which
+    /// are converted into line records.
+    pub fn read_lines<'a>(
+        &self,
+        data: &'a [u8],
+        version: u32,
+    ) -> Result<Cow<'a, [LineRecord]>, SymCacheError> {
+        if version > SYMCACHE_VERSION_NARROW_LINES {
+            return self.read(data).map(Cow::Borrowed);
+        }
+
+        let narrow = Seg::<NarrowLineRecord, u16>::new(self.offset, self.len);
+        let records = narrow.read(data)?.iter().map(LineRecord::from).collect();
+        Ok(Cow::Owned(records))
+    }
+
+    /// Reads a single line record like [`read_lines`](Self::read_lines).
+    pub fn get_line(
+        &self,
+        data: &[u8],
+        version: u32,
+        index: u16,
+    ) -> Result<Option<LineRecord>, SymCacheError> {
+        if version > SYMCACHE_VERSION_NARROW_LINES {
+            return Ok(self.get(data, index)?.copied());
+        }
+
+        let narrow = Seg::<NarrowLineRecord, u16>::new(self.offset, self.len);
+        Ok(narrow.get(data, index)?.map(LineRecord::from))
+    }
+}
+
 /// The start of a SymCache file.
 #[repr(C, packed)]
 #[derive(Default, Copy, Clone, Debug)]
@@ -1,13 +1,21 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::hash::Hasher;
 use std::io::{self, Seek, Write};
 use std::num::NonZeroU16;
 
-use fnv::{FnvHashMap, FnvHashSet};
+use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 
 use symbolic_common::{Arch, DebugId, Language};
-use symbolic_debuginfo::{DebugSession, FileInfo, Function, LineInfo, ObjectLike, Symbol};
+#[cfg(feature = "rayon")]
+use symbolic_debuginfo::{
+    dwarf::{DwarfDebugSession, DwarfSeenFunctions},
+    Object, ObjectDebugSession,
+};
+use symbolic_debuginfo::{
+    DebugSession, FileInfo, Function, LineInfo, ObjectLike, Symbol, SymbolMap,
+};
 
 use crate::format;
 use crate::{SymCacheError, SymCacheErrorKind, ValueKind};
@@ -17,6 +25,31 @@ fn is_empty_function(function: &Function<'_>) -> bool {
     function.size == 0
 }
 
+/// Truncates a name to at most `max_len` bytes if it is longer.
+///
+/// The name is cut at a character boundary and suffixed with `…` and a hash of the full name, so
+/// that distinct names remain distinct after truncation. If `max_len` is too small to fit the
+/// suffix, the name is only cut.
+fn truncate_name(name: Cow<'_, str>, max_len: usize) -> Cow<'_, str> {
+    if name.len() <= max_len {
+        return name;
+    }
+
+    let mut hasher = FnvHasher::default();
+    hasher.write(name.as_bytes());
+    let mut suffix = format!("\u{2026}{:08x}", hasher.finish() as u32);
+    if suffix.len() > max_len {
+        suffix.clear();
+    }
+
+    let mut len = max_len - suffix.len();
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    Cow::Owned(format!("{}{}", &name[..len], suffix))
+}
+
 /// Recursively cleans a tree of functions that does not cover any lines.
 ///
 ///  - Removes all redundant line records
@@ -159,17 +192,211 @@ struct FuncHandle {
 
     /// Data of this record.
     pub record: format::FuncRecord,
+
+    /// Whether this is a symbol without an explicit size, which extends up to the next function.
+    pub implicit_size: bool,
 }
 
 /// A cache for line record deduplication across inline functions.
 type LineCache = FnvHashSet<(u64, u64)>;
 
+/// The number of functions that are prepared in parallel before they are inserted.
+#[cfg(feature = "rayon")]
+const PARALLEL_BATCH_SIZE: usize = 4096;
+
+/// The number of DWARF compilation units that are read in parallel before they are merged.
+#[cfg(feature = "rayon")]
+const PARALLEL_UNIT_BATCH_SIZE: usize = 256;
+
+/// A function whose records have been computed, but not yet inserted into a writer.
+///
+/// Preparing a function does not depend on the state of the writer, which allows to prepare
+/// functions in parallel. Inserting them in order afterwards produces the same output as inserting
+/// them directly.
+struct PreparedFunction<'a> {
+    name: &'a str,
+    comp_dir: &'a [u8],
+    lang: u8,
+    records: Vec<PreparedRecord<'a>>,
+}
+
+/// A function record of a [`PreparedFunction`], along with its line records and inlinees.
+struct PreparedRecord<'a> {
+    address: u64,
+    end_address: u64,
+    /// Files in the order in which they are first referenced.
+    files: Vec<&'a FileInfo<'a>>,
+    /// Line records whose `file_id` is an index into `files`.
+    lines: Vec<format::LineRecord>,
+    inlinees: Vec<PreparedFunction<'a>>,
+}
+
+/// Prepares a function and its inlinees for insertion.
+///
+/// This may produce multiple [`PreparedRecord`]s for one [`Function`] under two conditions:
+///
+///  1. Its address range exceeds 2^16 bytes. This makes it too large for the `len` field in
+///     the function record.
+///  2. There are more than 2^16 line records. This is larger than the index used for the line
+///     segment.
+///
+/// Returns an error of kind [`ValueTooLarge`](SymCacheErrorKind::ValueTooLarge) if a line number
+/// exceeds the 32 bits of a line record.
+fn prepare_function<'a>(function: &'a Function<'a>) -> Result<PreparedFunction<'a>, SymCacheError> {
+    let mut records = Vec::new();
+    let mut current_start_address = function.address;
+    let mut lines = function.lines.iter().peekable();
+
+    while current_start_address < function.end_address() {
+        // Create line records for a part of the function.
+        // - The first return value is the vector of created line records.
+        // - If all line records were created, the second return value is equal to `function.end_address()`
+        //   and the loop terminates. Otherwise it is the address of the first line record
+        // that couldn't be created, which is where we have to start the next iteration.
+        let mut files = Vec::new();
+        let (lines, next_start_address) = take_lines(
+            &mut lines,
+            &mut files,
+            current_start_address,
+            function.end_address(),
+        )?;
+
+        let inlinees = function
+            .inlinees
+            .iter()
+            .filter(|inlinee| {
+                inlinee.address >= current_start_address
+                    && inlinee.end_address() <= next_start_address
+            })
+            .map(prepare_function)
+            .collect::<Result<_, _>>()?;
+
+        records.push(PreparedRecord {
+            address: current_start_address,
+            end_address: next_start_address,
+            files,
+            lines,
+            inlinees,
+        });
+
+        // An empty record is not written and stops the function, see `insert_prepared`.
+        if next_start_address == current_start_address {
+            break;
+        }
+
+        current_start_address = next_start_address;
+    }
+
+    Ok(PreparedFunction {
+        name: function.name.as_str(),
+        comp_dir: function.compilation_dir,
+        lang: function.name.language().as_u8(),
+        records,
+    })
+}
+
+/// Takes an iterator of [`LineInfo`]s and returns a vector containing [`LineRecord`](format::LineRecord)s
+/// for those lines whose address is between `start_address` and `end_address`.
+///
+/// - The `file_id` of line records is an index into `files`, to which all files are added in the
+/// order in which they are first referenced.
+///
+/// - If the difference between the addresses of two consecutive
+/// lines `L1` and `L2` is greater than 255, dummy line records with the same file and line
+/// information as L1 will be inserted between the two.
+///
+/// - One call of this function will
+/// produce a maximum of 2^16 line records and will not produce line records with an address more than
+/// 2^16 bytes after the start address. If either of these limits is exceeded, the function will return
+/// early with the address of the first line that could not be processed; it is then up to
+/// the caller to call it again with that address as the new start address.
+///
+/// - Line numbers that do not fit into the 32 bits of a line record result in an error of kind
+/// [`ValueTooLarge`](SymCacheErrorKind::ValueTooLarge).
+fn take_lines<'a>(
+    lines: &mut std::iter::Peekable<std::slice::Iter<'a, LineInfo<'a>>>,
+    files: &mut Vec<&'a FileInfo<'a>>,
+    start_address: u64,
+    end_address: u64,
+) -> Result<(Vec<format::LineRecord>, u64), SymCacheError> {
+    let mut line_records = vec![];
+    let mut last_address = start_address;
+    let mut last_file = 0;
+    let mut last_line = 0;
+
+    while let Some(&line) = lines.peek() {
+        let file_id = match files.iter().position(|file| **file == line.file) {
+            Some(index) => index,
+            None => {
+                files.push(&line.file);
+                files.len() - 1
+            }
+        };
+
+        // We have seen that swift can generate line records that lie outside of the function
+        // start.  Why this happens is unclear but it happens with highly inlined function
+        // calls.  Instead of panicking we want to just assume there is a single record at the
+        // address of the function and in case there are more the offsets are just slightly off.
+        let mut remaining_offset = Some(line.address.saturating_sub(last_address));
+
+        // Line records store offsets relative to the previous line's address. If that offset
+        // exceeds 255 (max u8 value), we write multiple line records to fill the gap.
+        while let Some(offset) = remaining_offset {
+            let (current_offset, rest) = if offset > 0xff {
+                (0xff, Some(offset - 0xff))
+            } else {
+                (offset, None)
+            };
+
+            remaining_offset = rest;
+            last_address += current_offset;
+
+            // If there is a rest offset, then the current line record is just a filler. This
+            // record still falls into the previous record's range, so we need to use the
+            // previous record's information. Only if there is no rest, use the new information.
+            if rest.is_none() {
+                last_file = file_id as u16;
+                last_line = u32::try_from(line.line).map_err(|e| {
+                    SymCacheError::new(SymCacheErrorKind::ValueTooLarge(ValueKind::Line), e)
+                })?;
+            }
+
+            // Check if we can still add a line record to this function without exceeding limits
+            // of the physical format. Otherwise, do an early exit and let the caller iterate.
+            let should_split_function = last_address - start_address > std::u16::MAX.into()
+                || line_records.len() >= std::u16::MAX.into();
+
+            if should_split_function {
+                return Ok((line_records, last_address));
+            }
+
+            line_records.push(format::LineRecord {
+                addr_off: current_offset as u8,
+                file_id: last_file,
+                line: last_line,
+            });
+        }
+
+        lines.next();
+    }
+
+    Ok((line_records, end_address))
+}
+
 /// A high level writer that can construct SymCaches.
 ///
 /// When using this writer directly, make sure to call [`finish`](SymCacheWriter::finish)
 /// at the end, so that all segments are
 /// written to the underlying writer and the header is fixed up with the references. Since segments
 /// are consecutive chunks of memory, this can only be done once at the end of the writing process.
+///
+/// # Determinism
+///
+/// The output only depends on the functions and symbols that were added, so writing the same
+/// object twice produces identical bytes. Files, symbols and line records are written in the
+/// order in which they are first added, and functions are sorted by address. Hash maps are only
+/// used to look up records that were written already, so their iteration order never affects the
+/// output.
 pub struct SymCacheWriter<W> {
     writer: FormatWriter<W>,
     header: format::HeaderV2,
@@ -179,6 +406,7 @@ pub struct SymCacheWriter<W> {
     path_cache: HashMap<Vec<u8>, format::Seg<u8, u8>>,
     file_cache: FnvHashMap<format::FileRecord, u16>,
     symbol_cache: HashMap<String, u32>,
+    max_name_length: Option<usize>,
     sorted: bool,
 }
 
@@ -195,32 +423,200 @@ where
     where
         O: ObjectLike<'d, 'o>,
         O::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let session = object
+            .debug_session()
+            .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+
+        Self::write_session(
+            &session,
+            object.arch(),
+            object.debug_id(),
+            object.symbol_map(),
+            target,
+        )
+    }
+
+    /// Converts an existing debug session into a SymCache.
+    ///
+    /// This is equivalent to [`write_object`](Self::write_object), but allows to reuse a debug
+    /// session that has already been created for other purposes, such as extracting sources.
+    /// The caller supplies the architecture, debug identifier and symbol table of the object the
+    /// session belongs to. This already implicitly calls [`SymCacheWriter::finish`], thus
+    /// consuming the writer.
+    pub fn write_session<S, E>(
+        session: &S,
+        arch: Arch,
+        debug_id: DebugId,
+        symbols: SymbolMap<'_>,
+        target: W,
+    ) -> Result<W, SymCacheError>
+    where
+        S: for<'s> DebugSession<'s, Error = E>,
+        E: std::error::Error + Send + Sync + 'static,
     {
         let mut writer = SymCacheWriter::new(target)?;
 
-        writer.set_arch(object.arch());
-        writer.set_debug_id(object.debug_id());
+        writer.set_arch(arch);
+        writer.set_debug_id(debug_id);
+
+        for function in session.functions() {
+            let function =
+                function.map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+            writer.add_function(function)?;
+        }
+
+        writer.add_symbol_map(symbols)?;
+        writer.finish()
+    }
 
+    /// Converts an entire object into a SymCache, processing functions in parallel.
+    ///
+    /// The output is identical to [`write_object`](Self::write_object). For objects with DWARF
+    /// debug information, compilation units are read in parallel and merged in unit order. For
+    /// all other objects, functions are read sequentially. In both cases, line records are
+    /// computed in parallel batches on the global `rayon` thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn write_object_parallel(object: &Object<'_>, target: W) -> Result<W, SymCacheError> {
         let session = object
             .debug_session()
             .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
 
+        match session {
+            ObjectDebugSession::Dwarf(ref session) => Self::write_dwarf_session_parallel(
+                session,
+                object.arch(),
+                object.debug_id(),
+                object.symbol_map(),
+                target,
+            ),
+            _ => Self::write_session_parallel(
+                &session,
+                object.arch(),
+                object.debug_id(),
+                object.symbol_map(),
+                target,
+            ),
+        }
+    }
+
+    /// Converts a DWARF debug session into a SymCache, reading compilation units in parallel.
+    ///
+    /// Each unit is first read on its own, which only skips functions duplicated within that unit.
+    /// The units are then merged in order: If a unit shares no top-level function with the units
+    /// before it, its functions are identical to a sequential read and are used as is. Otherwise,
+    /// the unit is read again with all previously seen functions, exactly like
+    /// [`DwarfDebugSession::functions`] does. This keeps the output identical to
+    /// [`write_session`](Self::write_session).
+    #[cfg(feature = "rayon")]
+    fn write_dwarf_session_parallel(
+        session: &DwarfDebugSession<'_>,
+        arch: Arch,
+        debug_id: DebugId,
+        symbols: SymbolMap<'_>,
+        target: W,
+    ) -> Result<W, SymCacheError> {
+        use rayon::prelude::*;
+
+        let mut writer = SymCacheWriter::new(target)?;
+
+        writer.set_arch(arch);
+        writer.set_debug_id(debug_id);
+
+        let mut seen = DwarfSeenFunctions::new();
+        let unit_count = session.unit_count();
+
+        for start in (0..unit_count).step_by(PARALLEL_UNIT_BATCH_SIZE) {
+            let end = std::cmp::min(start + PARALLEL_UNIT_BATCH_SIZE, unit_count);
+
+            let units: Vec<_> = (start..end)
+                .into_par_iter()
+                .map(|index| {
+                    let mut unit_seen = DwarfSeenFunctions::new();
+                    let functions = session.unit_functions(index, &mut unit_seen);
+                    (functions, unit_seen)
+                })
+                .collect();
+
+            let mut functions = Vec::new();
+            for (index, (unit_functions, unit_seen)) in (start..end).zip(units) {
+                match unit_functions {
+                    Ok(unit_functions) if unit_seen.is_disjoint(&seen) => {
+                        seen.extend(unit_seen);
+                        functions.extend(unit_functions);
+                    }
+                    // Either the unit shares functions with previous units, or it failed to read.
+                    // Skipping shared functions can change both, so read it again in sequence.
+                    _ => {
+                        let unit_functions = session
+                            .unit_functions(index, &mut seen)
+                            .map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
+                        functions.extend(unit_functions);
+                    }
+                }
+            }
+
+            for batch in functions.chunks_mut(PARALLEL_BATCH_SIZE) {
+                writer.add_functions_parallel(batch)?;
+            }
+        }
+
+        writer.add_symbol_map(symbols)?;
+        writer.finish()
+    }
+
+    /// Converts an existing debug session into a SymCache, processing functions in parallel.
+    ///
+    /// The output is identical to [`write_session`](Self::write_session). See
+    /// [`write_object_parallel`](Self::write_object_parallel) for more information.
+    #[cfg(feature = "rayon")]
+    pub fn write_session_parallel<S, E>(
+        session: &S,
+        arch: Arch,
+        debug_id: DebugId,
+        symbols: SymbolMap<'_>,
+        target: W,
+    ) -> Result<W, SymCacheError>
+    where
+        S: for<'s> DebugSession<'s, Error = E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let mut writer = SymCacheWriter::new(target)?;
+
+        writer.set_arch(arch);
+        writer.set_debug_id(debug_id);
+
+        let mut batch = Vec::with_capacity(PARALLEL_BATCH_SIZE);
         for function in session.functions() {
             let function =
                 function.map_err(|e| SymCacheError::new(SymCacheErrorKind::BadDebugFile, e))?;
-            writer.add_function(function)?;
+            batch.push(function);
+
+            if batch.len() == PARALLEL_BATCH_SIZE {
+                writer.add_functions_parallel(&mut batch)?;
+                batch.clear();
+            }
         }
+        writer.add_functions_parallel(&mut batch)?;
 
+        writer.add_symbol_map(symbols)?;
+        writer.finish()
+    }
+
+    /// Adds symbols from the symbol table that are not covered by any function.
+    ///
+    /// All functions must have been added before calling this.
+    fn add_symbol_map(&mut self, symbols: SymbolMap<'_>) -> Result<(), SymCacheError> {
         // Sort the files to efficiently add symbols from the symbol table in linear time
         // complexity. When the writer finishes, it will sort again with the added symbols.
-        writer.ensure_sorted();
+        self.ensure_sorted();
 
-        let mut symbols = object.symbol_map().into_iter().peekable();
+        let mut symbols = symbols.into_iter().peekable();
 
         // Add symbols from the symbol table. Since `add_symbol` mutates the internal `functions`
         // list, remember the current range to avoid handling a function twice.
-        for index in 0..writer.functions.len() {
-            if let Some(function) = writer.functions.get(index) {
+        for index in 0..self.functions.len() {
+            if let Some(function) = self.functions.get(index) {
                 let address = function.original.addr;
                 let end = address + function.record.len.get() as u64;
 
@@ -229,17 +625,17 @@ where
                 while symbols.peek().map_or(false, |s| s.address < end) {
                     let symbol = symbols.next().unwrap();
                     if symbol.address < address {
-                        writer.add_symbol(symbol)?;
+                        self.add_symbol(symbol)?;
                     }
                 }
             }
         }
 
         for symbol in symbols {
-            writer.add_symbol(symbol)?;
+            self.add_symbol(symbol)?;
         }
 
-        writer.finish()
+        Ok(())
     }
 
     /// Constructs a new `SymCacheWriter` and writes the preamble.
@@ -260,6 +656,7 @@ where
             path_cache: HashMap::new(),
             file_cache: FnvHashMap::default(),
             symbol_cache: HashMap::new(),
+            max_name_length: None,
             sorted: true,
         })
     }
@@ -274,6 +671,16 @@ where
         self.header.debug_id = debug_id;
     }
 
+    /// Limits the length of function and symbol names to `max_name_length` bytes.
+    ///
+    /// Longer names are cut at a character boundary and suffixed with `…` and a short hash of the
+    /// full name, so that distinct names do not collide after truncation. The suffix counts
+    /// towards the limit, so limits below 11 bytes cut names without a suffix. This only applies
+    /// to names added after calling this method.
+    pub fn set_max_name_length(&mut self, max_name_length: usize) {
+        self.max_name_length = Some(max_name_length);
+    }
+
     /// Adds a new symbol to this SymCache.
     ///
     /// Symbols **must** be added in ascending order using this method. This will emit a function
@@ -288,8 +695,9 @@ where
 
         // NB: SymbolMap usually fills in sizes of consecutive symbols already. This is not done if
         // there is only one symbol and for the last symbol. `FuncRecord::addr_in_range` always
-        // requires some address range. Since we can't possibly know the actual size, just assume
-        // that the symbol is VERY large.
+        // requires some address range. Since we can't possibly know the actual size yet, assume
+        // that the symbol is VERY large. It is clamped to the next function in `write_functions`.
+        let implicit_size = symbol.size == 0;
         let len = match symbol.size {
             0 => u16::MAX,
             s => std::cmp::min(s, 0xffff) as u16,
@@ -307,10 +715,10 @@ where
             line_records: format::Seg::default(),
             parent_offset: !0, // amended during write_functions
             comp_dir: format::Seg::default(),
-            lang: Language::Unknown as u8,
+            lang: Language::Unknown.as_u8(),
         };
 
-        self.push_function(record, FuncRef::none())?;
+        self.push_function(record, FuncRef::none(), implicit_size)?;
         Ok(())
     }
 
@@ -328,7 +736,36 @@ where
             return Ok(());
         }
         clean_function(&mut function, &mut LineCache::default());
-        self.insert_function(&function, FuncRef::none())
+        self.insert_prepared(&prepare_function(&function)?, FuncRef::none())
+    }
+
+    /// Adds a batch of functions like [`add_function`](Self::add_function), preparing them in
+    /// parallel.
+    ///
+    /// The functions are inserted in the order of the batch, so the output is identical to adding
+    /// them one by one.
+    #[cfg(feature = "rayon")]
+    fn add_functions_parallel(
+        &mut self,
+        functions: &mut [Function<'_>],
+    ) -> Result<(), SymCacheError> {
+        use rayon::prelude::*;
+
+        let prepared: Vec<_> = functions
+            .par_iter_mut()
+            .filter(|function| !is_empty_function(function))
+            .map(|function| {
+                clean_function(function, &mut LineCache::default());
+                let function: &Function<'_> = function;
+                prepare_function(function)
+            })
+            .collect::<Result<_, _>>()?;
+
+        for function in &prepared {
+            self.insert_prepared(function, FuncRef::none())?;
+        }
+
+        Ok(())
     }
 
     /// Persists all open segments to the writer and fixes up the header.
@@ -397,8 +834,15 @@ where
     ///
     /// This writes a segment containing the symbol's name. The returned `index`
     /// is that segment's index in the [`symbols`](Self::symbols) vector. Names longer than 2^16
-    /// bytes will be truncated.
+    /// bytes will be truncated, as will names longer than the
+    /// [maximum name length](Self::set_max_name_length).
     fn insert_symbol(&mut self, name: Cow<'_, str>) -> Result<u32, SymCacheError> {
+        // Truncate before looking up the cache, so that truncated names are deduplicated.
+        let name = match self.max_name_length {
+            Some(max_len) => truncate_name(name, max_len),
+            None => name,
+        };
+
         let mut len = std::cmp::min(name.len(), std::u16::MAX.into());
         if len < name.len() {
             len = match std::str::from_utf8(name[..len].as_bytes()) {
@@ -429,117 +873,38 @@ where
         Ok(index)
     }
 
-    /// Takes an iterator of [`LineInfo`]s and returns a vector containing [`LineRecord`](format::LineRecord)s
-    /// for those lines whose address is between `start_address` and `end_address`.
-    ///
-    /// - If the difference between the addresses of two consecutive
-    /// lines `L1` and `L2` is greater than 255, dummy line records with the same file and line
-    /// information as L1 will be inserted between the two.
+    /// Inserts a prepared function into the writer and writes its line records.
     ///
-    /// - One call of this function will
-    /// produce a maximum of 2^16 line records and will not produce line records with an address more than
-    /// 2^16 bytes after the start address. If either of these limits is exceeded, the function will return
-    /// early with the address of the first line that could not be processed; it is then up to
-    /// the caller to call it again with that address as the new start address.
-    fn take_lines(
+    /// See [`prepare_function`] for how a [`Function`] is split into function records.
+    fn insert_prepared(
         &mut self,
-        lines: &mut std::iter::Peekable<std::slice::Iter<'_, LineInfo<'_>>>,
-        start_address: u64,
-        end_address: u64,
-    ) -> Result<(Vec<format::LineRecord>, u64), SymCacheError> {
-        let mut line_records = vec![];
-        let mut last_address = start_address;
-        let mut last_file = 0;
-        let mut last_line = 0;
-
-        while let Some(line) = lines.peek() {
-            let file_id = self.insert_file(&line.file)?;
-
-            // We have seen that swift can generate line records that lie outside of the function
-            // start.  Why this happens is unclear but it happens with highly inlined function
-            // calls.  Instead of panicking we want to just assume there is a single record at the
-            // address of the function and in case there are more the offsets are just slightly off.
-            let mut remaining_offset = Some(line.address.saturating_sub(last_address));
-
-            // Line records store offsets relative to the previous line's address. If that offset
-            // exceeds 255 (max u8 value), we write multiple line records to fill the gap.
-            while let Some(offset) = remaining_offset {
-                let (current_offset, rest) = if offset > 0xff {
-                    (0xff, Some(offset - 0xff))
-                } else {
-                    (offset, None)
-                };
-
-                remaining_offset = rest;
-                last_address += current_offset;
-
-                // If there is a rest offset, then the current line record is just a filler. This
-                // record still falls into the previous record's range, so we need to use the
-                // previous record's information. Only if there is no rest, use the new information.
-                if rest.is_none() {
-                    last_file = file_id;
-                    last_line = line.line.min(std::u16::MAX.into()) as u16;
-                }
-
-                // Check if we can still add a line record to this function without exceeding limits
-                // of the physical format. Otherwise, do an early exit and let the caller iterate.
-                let should_split_function = last_address - start_address > std::u16::MAX.into()
-                    || line_records.len() >= std::u16::MAX.into();
-
-                if should_split_function {
-                    return Ok((line_records, last_address));
-                }
+        function: &PreparedFunction<'_>,
+        parent_ref: FuncRef,
+    ) -> Result<(), SymCacheError> {
+        let symbol_id = self.insert_symbol(function.name.into())?;
+        let comp_dir = self.write_path(function.comp_dir)?;
 
-                line_records.push(format::LineRecord {
-                    addr_off: current_offset as u8,
-                    file_id: last_file,
-                    line: last_line,
-                });
+        for prepared in &function.records {
+            let mut file_ids = Vec::with_capacity(prepared.files.len());
+            for file in &prepared.files {
+                file_ids.push(self.insert_file(file)?);
             }
 
-            lines.next();
-        }
-
-        Ok((line_records, end_address))
-    }
-
-    /// Inserts a function into the writer and writes its line records.
-    ///
-    /// This function may produce multiple [`FuncRecord`](format::FuncRecord)s for one [`Function`] under two conditions:
-    ///
-    ///  1. Its address range exceeds 2^16 bytes. This makes it too large for the `len` field in
-    ///     the function record.
-    ///  2. There are more than 2^16 line records. This is larger than the index used for the line
-    ///     segment.
-    fn insert_function(
-        &mut self,
-        function: &Function<'_>,
-        parent_ref: FuncRef,
-    ) -> Result<(), SymCacheError> {
-        let language = function.name.language();
-        let symbol_id = self.insert_symbol(function.name.as_str().into())?;
-        let comp_dir = self.write_path(function.compilation_dir)?;
-        let lang = u8::try_from(language as u32)
-            .map_err(|_| SymCacheErrorKind::ValueTooLarge(ValueKind::Language))?;
-
-        let mut current_start_address = function.address;
-        let mut lines = function.lines.iter().peekable();
-
-        while current_start_address < function.end_address() {
-            // Create line records for a part of the function.
-            // - The first return value is the vector of created line records.
-            // - If all line records were created, the second return value is equal to `function.end_address()`
-            //   and the loop terminates. Otherwise it is the address of the first line record
-            // that couldn't be created, which is where we have to start the next iteration.
-            let (line_records, next_start_address) =
-                self.take_lines(&mut lines, current_start_address, function.end_address())?;
+            let line_records: Vec<_> = prepared
+                .lines
+                .iter()
+                .map(|line| format::LineRecord {
+                    file_id: file_ids[line.file_id as usize],
+                    ..*line
+                })
+                .collect();
 
             let line_records = self.writer.write_segment(&line_records, ValueKind::Line)?;
             if line_records.len > 0 {
                 self.header.has_line_records = 1;
             }
 
-            let len = std::cmp::min(next_start_address - current_start_address, 0xffff) as u16;
+            let len = std::cmp::min(prepared.end_address - prepared.address, 0xffff) as u16;
             debug_assert_ne!(
                 len, 0,
                 "While adding function {}: length must be positive",
@@ -552,27 +917,21 @@ where
             };
 
             let record = format::FuncRecord {
-                addr_low: (current_start_address & 0xffff_ffff) as u32,
-                addr_high: ((current_start_address >> 32) & 0xffff) as u16,
+                addr_low: (prepared.address & 0xffff_ffff) as u32,
+                addr_high: ((prepared.address >> 32) & 0xffff) as u16,
                 len,
                 symbol_id_low: (symbol_id & 0xffff) as u16,
                 symbol_id_high: ((symbol_id >> 16) & 0xff) as u8,
                 parent_offset: !0,
                 line_records,
                 comp_dir,
-                lang,
+                lang: function.lang,
             };
 
-            let function_ref = self.push_function(record, parent_ref)?;
-            for inlinee in &function.inlinees {
-                if inlinee.address >= current_start_address
-                    && inlinee.end_address() <= next_start_address
-                {
-                    self.insert_function(inlinee, function_ref)?;
-                }
+            let function_ref = self.push_function(record, parent_ref, false)?;
+            for inlinee in &prepared.inlinees {
+                self.insert_prepared(inlinee, function_ref)?;
             }
-
-            current_start_address = next_start_address;
         }
 
         Ok(())
@@ -583,6 +942,7 @@ where
         &mut self,
         record: format::FuncRecord,
         parent: FuncRef,
+        implicit_size: bool,
     ) -> Result<FuncRef, SymCacheError> {
         let functions = &mut self.functions;
         let addr = record.addr_start();
@@ -609,6 +969,7 @@ where
             original,
             parent,
             record,
+            implicit_size,
         });
 
         Ok(original)
@@ -625,6 +986,47 @@ where
         }
     }
 
+    /// Limits symbols without an explicit size to the start of the next function.
+    ///
+    /// Such symbols that start at the same address as a preceding function do not cover any
+    /// address and are removed. Functions must be sorted before calling this.
+    fn clamp_symbols(&mut self) {
+        let len = self.functions.len();
+        let mut previous_addr = None;
+        self.functions.retain(|handle| {
+            let addr = handle.original.addr;
+            let keep = !handle.implicit_size || previous_addr != Some(addr);
+            previous_addr = Some(addr);
+            keep
+        });
+
+        // Removing functions invalidates their original indexes, so parent references have to be
+        // resolved via binary search.
+        if self.functions.len() != len {
+            self.sorted = false;
+        }
+
+        for index in 0..self.functions.len() {
+            let handle = &self.functions[index];
+            if !handle.implicit_size {
+                continue;
+            }
+
+            let addr = handle.original.addr;
+            let next_addr = self.functions[index + 1..]
+                .iter()
+                .map(|handle| handle.original.addr)
+                .find(|&next_addr| next_addr > addr);
+
+            // The last symbol keeps its maximum size, since there is no way to know its end.
+            if let Some(next_addr) = next_addr {
+                let len = std::cmp::min(next_addr - addr, 0xffff) as u16;
+                // This unwrap cannot fail; the next function starts after this one.
+                self.functions[index].record.len = NonZeroU16::new(len).unwrap();
+            }
+        }
+    }
+
     /// Writes the functions that have been added to this writer.
     fn write_functions(&mut self) -> Result<format::Seg<format::FuncRecord>, SymCacheError> {
         if self.functions.is_empty() {
@@ -634,6 +1036,7 @@ where
         // To compute parent offsets after that, one can simply binary search by the parent_ref
         // handle, allowing for efficient unique lookups.
         self.ensure_sorted();
+        self.clamp_symbols();
 
         let functions = &self.functions;
         let segment = format::Seg::new(self.writer.position as u32, functions.len() as u32);
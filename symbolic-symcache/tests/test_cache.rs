@@ -1,27 +1,9 @@
-use std::fmt;
-
-use symbolic_common::ByteView;
-use symbolic_symcache::SymCache;
+use symbolic_common::{Arch, ByteView, DebugId};
+use symbolic_symcache::{DumpSections, OwnedSymCache, SymCache, SymCacheErrorKind};
 use symbolic_testutils::fixture;
 
 type Error = Box<dyn std::error::Error>;
 
-/// Helper to create neat snapshots for symbol tables.
-struct FunctionsDebug<'a>(&'a SymCache<'a>);
-
-impl fmt::Debug for FunctionsDebug<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for result in self.0.functions() {
-            match result {
-                Ok(function) => writeln!(f, "{:>16x} {}", &function.address(), &function.name())?,
-                Err(error) => writeln!(f, "{:?}", error)?,
-            }
-        }
-
-        Ok(())
-    }
-}
-
 #[test]
 fn test_load_header_linux() -> Result<(), Error> {
     let buffer = ByteView::open(fixture("symcache/current/linux.symc"))?;
@@ -41,11 +23,44 @@ fn test_load_header_linux() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_load_bad_magic() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/linux.symc"))?;
+    let mut data = buffer.to_vec();
+    data[..4].copy_from_slice(b"XXXX");
+
+    let error = SymCache::parse(&data).unwrap_err();
+    assert_eq!(error.kind(), SymCacheErrorKind::BadFileMagic);
+    Ok(())
+}
+
+#[test]
+fn test_load_unsupported_version() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/linux.symc"))?;
+    let mut data = buffer.to_vec();
+    data[4..8].copy_from_slice(&u32::MAX.to_ne_bytes());
+
+    let error = SymCache::parse(&data).unwrap_err();
+    assert_eq!(error.kind(), SymCacheErrorKind::UnsupportedVersion);
+    Ok(())
+}
+
+#[test]
+fn test_load_truncated_header() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/linux.symc"))?;
+
+    let error = SymCache::parse(&buffer[..4]).unwrap_err();
+    assert_eq!(error.kind(), SymCacheErrorKind::BadFileHeader);
+    Ok(())
+}
+
 #[test]
 fn test_load_functions_linux() -> Result<(), Error> {
     let buffer = ByteView::open(fixture("symcache/current/linux.symc"))?;
     let symcache = SymCache::parse(&buffer)?;
-    insta::assert_debug_snapshot!("functions_linux", FunctionsDebug(&symcache));
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::FUNCTIONS)?;
+    insta::assert_snapshot!("functions_linux", dump);
     Ok(())
 }
 
@@ -72,7 +87,128 @@ fn test_load_header_macos() -> Result<(), Error> {
 fn test_load_functions_macos() -> Result<(), Error> {
     let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
     let symcache = SymCache::parse(&buffer)?;
-    insta::assert_debug_snapshot!("functions_macos", FunctionsDebug(&symcache));
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::FUNCTIONS)?;
+    insta::assert_snapshot!("functions_macos", dump);
+    Ok(())
+}
+
+/// Parses a SymCache that outlives the buffer created in this function.
+fn parse_owned(path: &str) -> Result<OwnedSymCache, Error> {
+    let buffer = ByteView::from_vec(std::fs::read(fixture(path))?);
+    Ok(OwnedSymCache::parse(buffer)?)
+}
+
+#[test]
+fn test_owned_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<OwnedSymCache>();
+}
+
+#[test]
+fn test_owned() -> Result<(), Error> {
+    let owned = parse_owned("symcache/current/macos.symc")?;
+    let symcache = owned.get();
+
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let expected = SymCache::parse(&buffer)?;
+    assert_eq!(owned.byteview().as_slice(), buffer.as_slice());
+    assert_eq!(symcache.debug_id(), expected.debug_id());
+
+    for function in expected.functions().take(50) {
+        let addr = function?.address();
+        let actual: Vec<_> = symcache.lookup(addr)?.collect()?;
+        assert!(!actual.is_empty());
+        assert_eq!(actual, expected.lookup(addr)?.collect::<Vec<_>>()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_header_macos() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::HEADER)?;
+    insta::assert_snapshot!(dump, @r###"
+    # header
+    version: 2
+    debug_id: 67e9247c-814e-392b-a027-dbde6748fcbf
+    arch: x86_64
+    has_line_info: true
+    strings: 587
+    files: 36
+    functions: 1863
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_dump_files_macos() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::FILES)?;
+    insta::assert_snapshot!("files_macos", dump);
+    Ok(())
+}
+
+#[test]
+fn test_dump_sections() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let mut all = String::new();
+    symcache.debug_dump(&mut all, DumpSections::ALL)?;
+
+    let mut combined = String::new();
+    for &section in &[
+        DumpSections::HEADER,
+        DumpSections::FILES,
+        DumpSections::FUNCTIONS,
+        DumpSections::RANGES,
+        DumpSections::STRINGS,
+    ] {
+        if !combined.is_empty() {
+            combined.push('\n');
+        }
+        symcache.debug_dump(&mut combined, section)?;
+    }
+    assert_eq!(all, combined);
+
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::FILES | DumpSections::STRINGS)?;
+    let titles: Vec<_> = dump.lines().filter(|l| l.starts_with('#')).collect();
+    assert_eq!(titles, ["# files", "# strings"]);
+
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::empty())?;
+    assert!(dump.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_load_arch_and_debug_id() -> Result<(), Error> {
+    let fixtures = [
+        ("linux.symc", "c0bcc3f1-9827-fe65-3058-404b2831d9e6"),
+        ("macos.symc", "67e9247c-814e-392b-a027-dbde6748fcbf"),
+    ];
+
+    for &(name, debug_id) in &fixtures {
+        let buffer = ByteView::open(fixture(format!("symcache/current/{}", name)))?;
+        let symcache = SymCache::parse(&buffer)?;
+        assert_eq!(symcache.arch(), Arch::Amd64, "{}", name);
+        assert_eq!(
+            symcache.debug_id(),
+            debug_id.parse::<DebugId>()?,
+            "{}",
+            name
+        );
+    }
+
     Ok(())
 }
 
@@ -85,3 +221,108 @@ fn test_lookup() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_lookup_inlined() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let symcache = SymCache::parse(&buffer)?;
+    let line_infos: Vec<_> = symcache.lookup(4_458_187_797 - 4_458_131_456)?.collect()?;
+
+    let inlined: Vec<_> = line_infos.iter().map(|info| info.is_inlined()).collect();
+    assert_eq!(inlined, [true, true, false]);
+
+    Ok(())
+}
+
+#[test]
+fn test_lookup_innermost() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let addresses = symcache
+        .functions()
+        .map(|function| Ok(function?.address() + 1))
+        .collect::<Result<Vec<_>, Error>>()?;
+    for &address in addresses.iter().chain(&[0, 4_458_187_797 - 4_458_131_456]) {
+        let line_infos: Vec<_> = symcache.lookup(address)?.collect()?;
+        let innermost = symcache.lookup_innermost(address)?;
+        assert_eq!(innermost.as_ref(), line_infos.first(), "{:#x}", address);
+    }
+
+    let innermost = symcache.lookup_innermost(4_458_187_797 - 4_458_131_456)?;
+    assert!(innermost.map_or(false, |line_info| line_info.is_inlined()));
+
+    Ok(())
+}
+
+/// Runs all accessors of a SymCache, discarding any errors.
+fn resolve_all(data: &[u8], addresses: &[u64]) {
+    let symcache = match SymCache::parse(data) {
+        Ok(symcache) => symcache,
+        Err(_) => return,
+    };
+
+    for function in symcache.functions() {
+        let function = match function {
+            Ok(function) => function,
+            Err(_) => break,
+        };
+
+        let _ = (
+            function.name(),
+            function.compilation_dir(),
+            function.parent_id(),
+        );
+        for line in function.lines() {
+            match line {
+                Ok(line) => {
+                    let _ = (line.base_dir(), line.filename());
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    for &address in addresses {
+        if let Ok(lookup) = symcache.lookup(address) {
+            lookup.for_each(drop);
+        }
+        let _ = symcache.lookup_innermost(address);
+    }
+}
+
+#[test]
+fn test_malformed_no_panic() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("symcache/current/macos.symc"))?;
+    let symcache = SymCache::parse(&buffer)?;
+    let addresses = symcache
+        .functions()
+        .step_by(16)
+        .map(|function| Ok(function?.address() + 1))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // A simple xorshift generator, so that failures are reproducible.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+
+    for _ in 0..64 {
+        let len = random() % buffer.len();
+        resolve_all(&buffer[..len], &addresses);
+    }
+
+    for _ in 0..64 {
+        let mut data = buffer.to_vec();
+        for _ in 0..16 {
+            let index = random() % data.len();
+            data[index] = random() as u8;
+        }
+        resolve_all(&data, &addresses);
+    }
+
+    Ok(())
+}
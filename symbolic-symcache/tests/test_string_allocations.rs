@@ -0,0 +1,74 @@
+//! Checks that string accesses do not allocate.
+//!
+//! This installs a counting global allocator, so it runs as a separate test binary.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use symbolic_common::ByteView;
+use symbolic_debuginfo::Object;
+use symbolic_symcache::{NewSymCache, NewSymCacheConverter};
+use symbolic_testutils::fixture;
+
+type Error = Box<dyn std::error::Error>;
+
+/// Counts the allocations of the current thread, to check that lookups are zero-copy.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+#[test]
+fn test_string_no_allocations() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut converter = NewSymCacheConverter::new();
+    converter.set_name_index(true);
+    converter.process_object(&object)?;
+    let mut buffer = Vec::new();
+    converter.serialize(&mut buffer)?;
+
+    let symcache = NewSymCache::parse(&buffer)?;
+
+    // The string data is validated on the first access, which does not allocate either.
+    let before = allocations();
+    let function = symcache
+        .functions()
+        .find(|function| function.name().is_some())
+        .unwrap();
+    let name = function.name().unwrap();
+    let name_idx = symcache.find_string(name).unwrap();
+    assert_eq!(symcache.string(name_idx)?, name);
+
+    for _ in 0..100 {
+        let found = symcache.lookup_by_name(name).next().unwrap();
+        assert_eq!(found.name(), Some(name));
+
+        let string = symcache.string(name_idx)?;
+        let offset = string.as_ptr() as usize - buffer.as_ptr() as usize;
+        assert!(offset + string.len() <= buffer.len());
+    }
+    assert_eq!(allocations(), before);
+
+    Ok(())
+}
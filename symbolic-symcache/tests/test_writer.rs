@@ -1,29 +1,13 @@
-use std::fmt;
 use std::io::Cursor;
 
 use symbolic_common::ByteView;
+use symbolic_debuginfo::macho::BcSymbolMap;
 use symbolic_debuginfo::Object;
-use symbolic_symcache::{SymCache, SymCacheWriter};
+use symbolic_symcache::{DumpSections, SymCache, SymCacheErrorKind, SymCacheWriter, ValueKind};
 use symbolic_testutils::fixture;
 
 type Error = Box<dyn std::error::Error>;
 
-/// Helper to create neat snapshots for symbol tables.
-struct FunctionsDebug<'a>(&'a SymCache<'a>);
-
-impl fmt::Debug for FunctionsDebug<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for result in self.0.functions() {
-            match result {
-                Ok(function) => writeln!(f, "{:>16x} {}", &function.address(), &function.name())?,
-                Err(error) => writeln!(f, "{:?}", error)?,
-            }
-        }
-
-        Ok(())
-    }
-}
-
 #[test]
 fn test_write_header_linux() -> Result<(), Error> {
     let buffer = ByteView::open(fixture("linux/crash.debug"))?;
@@ -56,7 +40,52 @@ fn test_write_functions_linux() -> Result<(), Error> {
     let mut buffer = Vec::new();
     SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
     let symcache = SymCache::parse(&buffer)?;
-    insta::assert_debug_snapshot!("functions_linux", FunctionsDebug(&symcache));
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::FUNCTIONS)?;
+    insta::assert_snapshot!("functions_linux", dump);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_session_linux() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut expected = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut expected))?;
+
+    let session = object.debug_session()?;
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_session(
+        &session,
+        object.arch(),
+        object.debug_id(),
+        object.symbol_map(),
+        Cursor::new(&mut buffer),
+    )?;
+
+    assert_eq!(buffer, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_compressed_sections() -> Result<(), Error> {
+    let write = |path| -> Result<Vec<u8>, Error> {
+        let buffer = ByteView::open(fixture(path))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut buffer = Vec::new();
+        SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+        Ok(buffer)
+    };
+
+    // Created with `objcopy --compress-debug-sections=<zlib|zlib-gnu|zstd> crash.debug`.
+    let expected = write("linux/crash.debug")?;
+    assert!(write("linux/crash-zlib.debug")? == expected);
+    assert!(write("linux/crash-zdebug.debug")? == expected);
+    assert!(write("linux/crash-zstd.debug")? == expected);
 
     Ok(())
 }
@@ -93,7 +122,67 @@ fn test_write_functions_macos() -> Result<(), Error> {
     let mut buffer = Vec::new();
     SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
     let symcache = SymCache::parse(&buffer)?;
-    insta::assert_debug_snapshot!("functions_macos", FunctionsDebug(&symcache));
+    let mut dump = String::new();
+    symcache.debug_dump(&mut dump, DumpSections::FUNCTIONS)?;
+    insta::assert_snapshot!("functions_macos", dump);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_deterministic() -> Result<(), Error> {
+    let write = || -> Result<Vec<u8>, Error> {
+        let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+        let object = Object::parse(&buffer)?;
+
+        let mut buffer = Vec::new();
+        SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+        Ok(buffer)
+    };
+
+    let first = write()?;
+    let second = write()?;
+    // Hash maps on another thread use different random keys.
+    let third = std::thread::spawn(move || write().map_err(|e| e.to_string()))
+        .join()
+        .unwrap()?;
+
+    assert!(first == second, "output differs between runs");
+    assert!(first == third, "output differs between threads");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_write_parallel_macos() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut expected = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut expected))?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object_parallel(&object, Cursor::new(&mut buffer))?;
+
+    assert_eq!(buffer, expected);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_write_parallel_linux() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut expected = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut expected))?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object_parallel(&object, Cursor::new(&mut buffer))?;
+
+    assert_eq!(buffer, expected);
 
     Ok(())
 }
@@ -110,6 +199,162 @@ fn test_write_large_symbol_names() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_write_max_name_length() -> Result<(), Error> {
+    let prefix = "a".repeat(300);
+    let data = format!(
+        "MODULE Linux x86_64 C0BCC3F19827FE653058404B2831D9E60 long
+PUBLIC 1000 0 {0}_first
+PUBLIC 1010 0 {0}_second
+PUBLIC 1020 0 short
+",
+        prefix
+    );
+    let object = Object::parse(data.as_bytes())?;
+
+    let mut buffer = Vec::new();
+    let mut writer = SymCacheWriter::new(Cursor::new(&mut buffer))?;
+    writer.set_max_name_length(100);
+    for symbol in object.symbols() {
+        writer.add_symbol(symbol)?;
+    }
+    writer.finish()?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let names = symcache
+        .functions()
+        .map(|function| Ok(function?.symbol()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(names.len(), 3);
+
+    // Names that only differ after the cut remain distinct.
+    let (first, second) = (names[0], names[1]);
+    assert_ne!(first, second);
+    for name in &[first, second] {
+        assert!(name.len() <= 100);
+        assert!(name.starts_with("aaaa"));
+        assert!(name.contains('\u{2026}'));
+    }
+    assert_eq!(names[2], "short");
+
+    // Template-heavy C++ names are capped as well.
+    let buffer = ByteView::open(fixture("regression/large_symbol.sym"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut buffer = Vec::new();
+    let mut writer = SymCacheWriter::new(Cursor::new(&mut buffer))?;
+    writer.set_max_name_length(1024);
+    for function in object.debug_session()?.functions() {
+        writer.add_function(function?)?;
+    }
+    writer.finish()?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    for function in symcache.functions() {
+        assert!(function?.symbol().len() <= 1024);
+    }
+
+    // Limits too small for the suffix only cut the name.
+    let object = Object::parse(data.as_bytes())?;
+    let mut buffer = Vec::new();
+    let mut writer = SymCacheWriter::new(Cursor::new(&mut buffer))?;
+    writer.set_max_name_length(4);
+    for symbol in object.symbols() {
+        writer.add_symbol(symbol)?;
+    }
+    writer.finish()?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let names = symcache
+        .functions()
+        .map(|function| Ok(function?.symbol()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(names, ["aaaa", "aaaa", "shor"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_content_hash() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("linux/crash.debug"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut first = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut first))?;
+    let mut second = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut second))?;
+
+    let buffer = ByteView::open(fixture("macos/crash.dSYM/Contents/Resources/DWARF/crash"))?;
+    let object = Object::parse(&buffer)?;
+    let mut other = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut other))?;
+
+    let first = SymCache::parse(&first)?.content_hash();
+    let second = SymCache::parse(&second)?.content_hash();
+    let other = SymCache::parse(&other)?.content_hash();
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_bcsymbolmap() -> Result<(), Error> {
+    // The symbol map must outlive the object it is loaded into.
+    let map_buffer = ByteView::open(fixture(
+        "macos/c8374b6d-6e96-34d8-ae38-efaa5fec424f.bcsymbolmap",
+    ))?;
+    let buffer = ByteView::open(fixture(
+        "macos/2d10c42f-591d-3265-b147-78ba0868073f.dwarf-hidden",
+    ))?;
+    let mut object = Object::parse(&buffer)?;
+    object.load_symbolmap(BcSymbolMap::parse(&map_buffer)?);
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let names = symcache
+        .functions()
+        .map(|function| function.map(|f| f.symbol().to_owned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(names
+        .iter()
+        .any(|name| name == "-[SentryMessage initWithFormatted:]"));
+    assert!(names.iter().all(|name| !name.starts_with("__hidden#")));
+
+    let lines = symcache.lookup(0x5a74)?.collect::<Vec<_>>()?;
+    assert_eq!(
+        lines[0].function_name(),
+        "-[SentryMessage initWithFormatted:]"
+    );
+    assert_eq!(
+        lines[0].abs_path(),
+        "/Users/philipphofmann/git-repos/sentry-cocoa/Sources/Sentry/SentryMessage.m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_write_mini_debug_info() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("linux/minidebuginfo"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let names = symcache
+        .functions()
+        .map(|function| function.map(|f| f.symbol().to_owned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(names.iter().any(|name| name == "minidebuginfo_compute"));
+    assert!(names.iter().any(|name| name == "minidebuginfo_helper"));
+
+    Ok(())
+}
+
 /// This tests the fix for the bug described in
 /// https://github.com/getsentry/symbolic/issues/284#issue-726898083
 #[test]
@@ -132,6 +377,24 @@ fn test_lookup_no_lines() -> Result<(), Error> {
         protocol/http/HttpChannelChild.cpp:411:7',void>::_Do_call()"
     );
 
+    // Breakpad symbols have no columns, so the new format neither writes nor reports any.
+    #[cfg(all(feature = "bench", feature = "fuzzing"))]
+    {
+        use symbolic_symcache::{NewSymCache, NewSymCacheConverter};
+
+        let mut converter = NewSymCacheConverter::new();
+        converter.process_object(&object)?;
+        let mut buffer = Vec::new();
+        converter.serialize(&mut buffer)?;
+        let symcache = NewSymCache::parse(&buffer)?;
+        assert!(!symcache.features().has_columns());
+
+        let source_locations = symcache.lookup(0xc6dd98).collect::<Vec<_>>();
+        assert_eq!(source_locations.len(), 1);
+        assert_eq!(source_locations[0].line(), None);
+        assert_eq!(source_locations[0].column(), None);
+    }
+
     Ok(())
 }
 
@@ -174,3 +437,174 @@ fn test_lookup_modulo_u16() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_lookup_truncated_address() -> Result<(), Error> {
+    let data = b"MODULE Linux x86 C0BCC3F19827FE653058404B2831D9E60 truncated
+PUBLIC 1000 0 first
+PUBLIC 1010 0 second
+";
+    let object = Object::parse(data)?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    // Upper bits are ignored in SymCaches of 32-bit modules.
+    let symbols = symcache
+        .lookup(0xffff_ffff_0000_1010)?
+        .collect::<Vec<_>>()?;
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].symbol(), "second");
+
+    Ok(())
+}
+
+#[test]
+fn test_write_zero_size_symbols() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("zero-size.sym"))?;
+    let object = Object::parse(&buffer)?;
+
+    // Add the symbols directly, since `SymbolMap` would compute their sizes.
+    let mut buffer = Vec::new();
+    let mut writer = SymCacheWriter::new(Cursor::new(&mut buffer))?;
+    for symbol in object.symbols() {
+        assert_eq!(symbol.size, 0);
+        writer.add_symbol(symbol)?;
+    }
+    writer.finish()?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    // The degenerate symbol at the same address as `second` is dropped.
+    let functions = symcache
+        .functions()
+        .map(|function| {
+            let function = function?;
+            Ok((function.address(), function.symbol()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(functions, [(0x1000, "first"), (0x1010, "second")]);
+
+    let lookup = |addr| -> Result<Vec<&str>, Error> {
+        let symbols = symcache.lookup(addr)?.collect::<Vec<_>>()?;
+        Ok(symbols.iter().map(|symbol| symbol.symbol()).collect())
+    };
+    assert_eq!(lookup(0x100f)?, ["first"]);
+    assert_eq!(lookup(0x1010)?, ["second"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_public_only() -> Result<(), Error> {
+    let buffer = ByteView::open(fixture("public-only.sym"))?;
+    let object = Object::parse(&buffer)?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    // The first record at a duplicated address wins.
+    let functions = symcache
+        .functions()
+        .map(|function| {
+            let function = function?;
+            Ok((function.address(), function.symbol()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(
+        functions,
+        [(0x1000, "first"), (0x1040, "second"), (0x1080, "third")]
+    );
+
+    let lookup = |addr| -> Result<Vec<&str>, Error> {
+        let symbols = symcache.lookup(addr)?.collect::<Vec<_>>()?;
+        Ok(symbols.iter().map(|symbol| symbol.symbol()).collect())
+    };
+    assert!(lookup(0xfff)?.is_empty());
+    assert_eq!(lookup(0x1000)?, ["first"]);
+    assert_eq!(lookup(0x103f)?, ["first"]);
+    assert_eq!(lookup(0x1040)?, ["second"]);
+    assert_eq!(lookup(0x1090)?, ["third"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_public_and_func() -> Result<(), Error> {
+    let data = b"MODULE Linux x86_64 C0BCC3F19827FE653058404B2831D9E60 mixed
+FILE 0 /src/mixed.c
+FUNC 1040 20 0 func_second
+1040 20 7 0
+PUBLIC 1000 0 first
+PUBLIC 1040 0 public_second
+PUBLIC 1080 0 third
+";
+    let object = Object::parse(data)?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+    let symcache = SymCache::parse(&buffer)?;
+
+    let lookup = |addr| -> Result<Vec<(&str, u32)>, Error> {
+        let symbols = symcache.lookup(addr)?.collect::<Vec<_>>()?;
+        Ok(symbols.iter().map(|s| (s.symbol(), s.line())).collect())
+    };
+    assert_eq!(lookup(0x1010)?, [("first", 0)]);
+    // The `FUNC` record takes precedence over the `PUBLIC` record at the same address.
+    assert_eq!(lookup(0x1040)?, [("func_second", 7)]);
+    assert_eq!(lookup(0x1090)?, [("third", 0)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_large_line() -> Result<(), Error> {
+    let data = b"MODULE Linux x86_64 C0BCC3F19827FE653058404B2831D9E60 large
+FILE 0 /src/large.c
+FUNC 1000 20 0 large_line
+1000 10 100000 0
+1010 10 65537 0
+";
+    let object = Object::parse(data)?;
+
+    let mut buffer = Vec::new();
+    SymCacheWriter::write_object(&object, Cursor::new(&mut buffer))?;
+    let symcache = SymCache::parse(&buffer)?;
+    assert!(symcache.is_latest());
+
+    let lookup = |addr| -> Result<Vec<u32>, Error> {
+        let symbols = symcache.lookup(addr)?.collect::<Vec<_>>()?;
+        Ok(symbols.iter().map(|s| s.line()).collect())
+    };
+    assert_eq!(lookup(0x1000)?, [100_000]);
+    assert_eq!(lookup(0x1018)?, [65_537]);
+
+    let function = symcache.functions().next().unwrap()?;
+    let lines = function
+        .lines()
+        .map(|line| Ok(line?.line()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(lines, [100_000, 65_537]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_line_too_large() -> Result<(), Error> {
+    let data = b"MODULE Linux x86_64 C0BCC3F19827FE653058404B2831D9E60 large
+FILE 0 /src/large.c
+FUNC 1000 20 0 large_line
+1000 20 4294967296 0
+";
+    let object = Object::parse(data)?;
+
+    let mut buffer = Vec::new();
+    let error = SymCacheWriter::write_object(&object, Cursor::new(&mut buffer)).unwrap_err();
+    assert_eq!(
+        error.kind(),
+        SymCacheErrorKind::ValueTooLarge(ValueKind::Line)
+    );
+
+    Ok(())
+}
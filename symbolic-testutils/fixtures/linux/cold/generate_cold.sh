@@ -0,0 +1,46 @@
+#!/bin/bash
+
+# This script was used to generate the fixtures in the "gen" directory, used to test functions
+# that the compiler splits into a hot and a cold part with discontiguous address ranges.
+
+# Pre-requisites:
+#
+# - gcc
+
+OUTPUT=gen
+
+# 0. Clean and remake output directory, switch to it
+rm -rf $OUTPUT
+mkdir -p $OUTPUT
+cd $OUTPUT
+
+# 1. write a program where the error path of `process` only calls cold functions. GCC moves this
+# path into `process.cold`, which is placed in `.text.unlikely` before the hot part.
+cat > cold.c << EOF
+#include <stdio.h>
+#include <stdlib.h>
+
+__attribute__((cold, noinline)) static void report(const char *message, int value) {
+    fprintf(stderr, "%s: %d\n", message, value);
+}
+
+__attribute__((noinline)) int process(int value) {
+    if (value < 0) {
+        report("negative value", value);
+        report("aborting", value);
+        abort();
+    }
+    return value * 2 + 1;
+}
+
+int main(int argc, char **argv) {
+    (void)argv;
+    return process(argc - 2);
+}
+EOF
+
+# 2. compile with optimizations, which enables hot/cold partitioning. The build directory is
+# remapped so that paths do not depend on where this script runs.
+gcc -O2 -g -fdebug-prefix-map="$PWD=/build/cold" -o cold cold.c
+
+rm cold.c
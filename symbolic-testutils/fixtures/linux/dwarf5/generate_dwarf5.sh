@@ -0,0 +1,57 @@
+#!/bin/bash
+
+# This script was used to generate the fixtures in the "gen" directory, used to test that DWARF 5
+# line programs resolve to the same functions and lines as DWARF 4.
+
+# Pre-requisites:
+#
+# - gcc (12 or later, which emits `.debug_line_str`)
+
+OUTPUT=gen
+
+# 0. Clean and remake output directory, switch to it
+rm -rf $OUTPUT
+mkdir -p $OUTPUT
+cd $OUTPUT
+
+# 1. write a small program with a header and inlined functions, so that the line program
+# references more than the primary source file.
+cat > lines.h << EOF
+static inline int clamp(int value, int min, int max) {
+    if (value < min) {
+        return min;
+    }
+    if (value > max) {
+        return max;
+    }
+    return value;
+}
+EOF
+
+cat > lines.c << EOF
+#include "lines.h"
+
+static inline int square(int value) {
+    return value * value;
+}
+
+int sum_of_squares(int a, int b) {
+    int result = square(a);
+    result += square(b);
+    return result;
+}
+
+int main(int argc, char **argv) {
+    (void)argv;
+    return sum_of_squares(argc, clamp(argc, 0, 10));
+}
+EOF
+
+# 2. compile the same program with DWARF 4 and DWARF 5. The build directory is remapped so that
+# paths do not depend on where this script runs.
+for VERSION in 4 5; do
+    gcc -O1 -g -gdwarf-$VERSION -fdebug-prefix-map="$PWD=/build/lines" \
+        -o lines-dwarf$VERSION lines.c
+done
+
+rm lines.c lines.h
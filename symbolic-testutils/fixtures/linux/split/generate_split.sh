@@ -0,0 +1,62 @@
+#!/bin/bash
+
+# This script was used to generate the fixtures in the "gen" directory, used to test that split
+# DWARF resolves to the same functions and lines as regular DWARF when given the `.dwo` object or
+# the `.dwp` package of a binary.
+
+# Pre-requisites:
+#
+# - gcc
+# - llvm-dwp (binutils `dwp` does not support DWARF 5 packages)
+
+OUTPUT=gen
+
+# 0. Clean and remake output directory, switch to it
+rm -rf $OUTPUT
+mkdir -p $OUTPUT
+cd $OUTPUT
+
+# 1. write a small program with a header and inlined functions, so that the split unit contains
+# inlinees and the line program references more than the primary source file.
+cat > lines.h << EOF
+static inline int clamp(int value, int min, int max) {
+    if (value < min) {
+        return min;
+    }
+    if (value > max) {
+        return max;
+    }
+    return value;
+}
+EOF
+
+cat > lines.c << EOF
+#include "lines.h"
+
+static inline int square(int value) {
+    return value * value;
+}
+
+int sum_of_squares(int a, int b) {
+    int result = square(a);
+    result += square(b);
+    return result;
+}
+
+int main(int argc, char **argv) {
+    (void)argv;
+    return sum_of_squares(argc, clamp(argc, 0, 10));
+}
+EOF
+
+# 2. compile the same program with regular and split DWARF. The build directory is remapped so
+# that paths do not depend on where this script runs.
+gcc -O1 -g -fdebug-prefix-map="$PWD=/build/lines" -o lines lines.c
+gcc -O1 -g -gsplit-dwarf -fdebug-prefix-map="$PWD=/build/lines" -c -o lines-split.o lines.c
+gcc -o lines-split lines-split.o
+
+# 3. package the split unit, as it would be shipped alongside the binary. The `.dwo` file is passed
+# explicitly, since the skeleton unit references it via the remapped build directory.
+llvm-dwp -o lines-split.dwp lines-split.dwo
+
+rm lines.c lines.h lines-split.o